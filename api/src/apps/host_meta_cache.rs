@@ -25,6 +25,7 @@
  */
 
 use crate::apps::Apps;
+use crate::config::HostMetaConfig;
 use crate::infrastructure::HttpForwarder;
 use crate::models::service::{
     Service, ServiceStatus, ServiceWithHostMeta, Services, ServicesWithHostMeta,
@@ -34,7 +35,7 @@ use chrono::{DateTime, Utc};
 use evmap::{ReadHandleFactory, WriteHandle};
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
-use http::header::{HOST, USER_AGENT};
+use http::header::{HeaderMap, HOST, USER_AGENT};
 use rocket::outcome::Outcome;
 use rocket::request::{self, FromRequest, Request};
 use std::collections::{HashMap, HashSet};
@@ -50,6 +51,8 @@ pub struct HostMetaCache {
 pub struct HostMetaCrawler {
     writer: WriteHandle<Key, Arc<Value>>,
     update_watch_tx: Sender<DateTime<Utc>>,
+    ttl: Option<chrono::Duration>,
+    config: HostMetaConfig,
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -64,7 +67,7 @@ struct Value {
     web_host_meta: WebHostMeta,
 }
 
-pub fn new() -> (HostMetaCache, HostMetaCrawler) {
+pub fn new(config: &HostMetaConfig) -> (HostMetaCache, HostMetaCrawler) {
     // TODO: eventually we should replace evmap with the watch channel or with another thread safe
     // alternative..
     let (reader, writer) = evmap::new();
@@ -78,6 +81,10 @@ pub fn new() -> (HostMetaCache, HostMetaCrawler) {
         HostMetaCrawler {
             writer,
             update_watch_tx,
+            ttl: config
+                .ttl()
+                .and_then(|ttl| chrono::Duration::from_std(ttl).ok()),
+            config: config.clone(),
         },
     )
 }
@@ -119,7 +126,7 @@ impl HostMetaCache {
 
             assigned_apps.insert(
                 app_name,
-                ServicesWithHostMeta::from(services_with_host_meta),
+                ServicesWithHostMeta::in_given_order(services_with_host_meta),
             );
         }
 
@@ -196,6 +203,7 @@ impl HostMetaCrawler {
     ) -> Option<DateTime<Utc>> {
         self.clear_stale_web_host_meta(apps);
 
+        let now = Utc::now();
         let running_services_without_host_meta = apps
             .iter()
             .flat_map(|(app_name, services)| {
@@ -211,7 +219,7 @@ impl HostMetaCrawler {
                     })
             })
             .filter(|(_, service)| *service.status() == ServiceStatus::Running)
-            .filter(|(key, _service)| !self.writer.contains_key(key))
+            .filter(|(key, _service)| !self.has_fresh_host_meta(key, now))
             .collect::<Vec<(Key, Service)>>();
 
         if running_services_without_host_meta.is_empty() {
@@ -225,10 +233,10 @@ impl HostMetaCrawler {
                 .map(|(k, service)| format!("({}, {})", k.app_name, service.service_name()))
                 .fold(String::new(), |a, b| a + &b + ", ")
         );
-        let now = Utc::now();
         let duration_prevant_startup = Utc::now().signed_duration_since(since_timestamp);
         let resolved_host_meta_infos = Self::resolve_host_meta(
             http_forwarder,
+            &self.config,
             running_services_without_host_meta,
             duration_prevant_startup,
         )
@@ -241,7 +249,7 @@ impl HostMetaCrawler {
 
             updated_host_meta_info_entries += 1;
 
-            self.writer.insert(
+            self.writer.update(
                 key,
                 Arc::new(Value {
                     last_update_timestamp: now,
@@ -259,6 +267,18 @@ impl HostMetaCrawler {
         }
     }
 
+    /// Checks if there is web host meta for the given key that has not been expired according to
+    /// the configured TTL.
+    fn has_fresh_host_meta(&self, key: &Key, now: DateTime<Utc>) -> bool {
+        match self.writer.get_one(key) {
+            Some(value) => match self.ttl {
+                Some(ttl) => now.signed_duration_since(value.last_update_timestamp) < ttl,
+                None => true,
+            },
+            None => false,
+        }
+    }
+
     fn clear_stale_web_host_meta(&mut self, apps: &HashMap<AppName, Services>) {
         let copy: HashMap<Key, Vec<_>> = self
             .writer
@@ -300,6 +320,7 @@ impl HostMetaCrawler {
 
     async fn resolve_host_meta(
         http_forwarder: Box<dyn HttpForwarder>,
+        config: &HostMetaConfig,
         services_without_host_meta: Vec<(Key, Service)>,
         duration_prevant_startup: chrono::Duration,
     ) -> Vec<(Key, Service, WebHostMeta)> {
@@ -312,9 +333,12 @@ impl HostMetaCrawler {
             .into_iter()
             .map(|(key, service)| {
                 let http_forwarder = dyn_clone::clone_box(&*http_forwarder);
+                let request_headers = config.request_headers(service.service_name());
                 async {
                     Self::resolve_web_host_meta(
                         http_forwarder,
+                        request_headers,
+                        config.request_timeout(),
                         key,
                         service,
                         duration_prevant_startup,
@@ -334,35 +358,47 @@ impl HostMetaCrawler {
 
     async fn resolve_web_host_meta(
         http_forwarder: Box<dyn HttpForwarder + Send>,
+        request_headers: HeaderMap,
+        request_timeout: Option<std::time::Duration>,
         key: Key,
         service: Service,
         duration_prevant_startup: chrono::Duration,
     ) -> (Key, Service, WebHostMeta) {
         let app_name = &key.app_name;
-        let response = http_forwarder
-            .request_web_host_meta(
-                app_name,
-                service.service_name(),
-                http::Request::builder()
-                    // TODO: include real service traefic route, see #169
-                    .header(
-                        USER_AGENT.as_str(),
-                        format!("PREvant/{}", clap::crate_version!()),
-                    )
-                    .method("GET")
-                    .uri("/.well-known/host-meta.json")
-                    .header(HOST, "127.0.0.1")
-                    .header("Connection", "Close")
-                    .header("Forwarded", "host=www.prevant.example.com;proto=http")
-                    .header(
-                        "X-Forwarded-Prefix",
-                        format!("/{app_name}/{}", service.service_name()),
-                    )
-                    .header("Accept", "application/json")
-                    .body(http_body_util::Empty::<bytes::Bytes>::new())
-                    .unwrap(),
+        let mut request = http::Request::builder()
+            // TODO: include real service traefic route, see #169
+            .header(
+                USER_AGENT.as_str(),
+                format!("PREvant/{}", clap::crate_version!()),
             )
-            .await;
+            .method("GET")
+            .uri("/.well-known/host-meta.json")
+            .header(HOST, "127.0.0.1")
+            .header("Forwarded", "host=www.prevant.example.com;proto=http")
+            .header(
+                "X-Forwarded-Prefix",
+                format!("/{app_name}/{}", service.service_name()),
+            )
+            .header("Accept", "application/json")
+            .body(http_body_util::Empty::<bytes::Bytes>::new())
+            .unwrap();
+        for (name, value) in request_headers.iter() {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+
+        let response =
+            http_forwarder.request_web_host_meta(app_name, service.service_name(), request);
+        let response = match request_timeout {
+            Some(request_timeout) => tokio::time::timeout(request_timeout, response)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(anyhow::anyhow!(
+                        "The request did not finish within {}",
+                        humantime::format_duration(request_timeout)
+                    ))
+                }),
+            None => response.await,
+        };
 
         let meta = match response {
             Ok(Some(meta)) => {
@@ -461,6 +497,7 @@ mod tests {
             state: State {
                 status: ServiceStatus::Running,
                 started_at: Some(Utc::now()),
+                replicas: None,
             },
             config: crate::sc!("nginx", "nginx:latest"),
         };
@@ -470,7 +507,7 @@ mod tests {
             Services::from(vec![nginx_service.clone()]),
         )]);
 
-        let (cache, mut crawler) = super::new();
+        let (cache, mut crawler) = super::new(&Default::default());
         crawler.crawl(forwarder, &apps, Utc::now()).await;
 
         let apps = cache.update_meta_data(apps, &RequestInfo::new(base_url.clone()));
@@ -490,6 +527,191 @@ mod tests {
         )
     }
 
+    #[derive(Clone)]
+    struct HangingHttpForwarder {}
+
+    #[async_trait]
+    impl HttpForwarder for HangingHttpForwarder {
+        async fn request_web_host_meta(
+            &self,
+            _app_name: &AppName,
+            _service_name: &str,
+            _request: http::Request<http_body_util::Empty<bytes::Bytes>>,
+        ) -> Result<Option<WebHostMeta>> {
+            tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+            Ok(Some(WebHostMeta::with_version(String::from("1.2.3"))))
+        }
+    }
+
+    #[tokio::test]
+    async fn abort_crawling_host_meta_after_request_timeout() {
+        let nginx_service = Service {
+            id: String::from("nginx"),
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: Some(Utc::now()),
+                replicas: None,
+            },
+            config: crate::sc!("nginx", "nginx:latest"),
+        };
+        let apps = HashMap::from([(AppName::master(), Services::from(vec![nginx_service]))]);
+        let config = toml::from_str::<HostMetaConfig>("requestTimeout = '10ms'").unwrap();
+
+        let (_cache, mut crawler) = super::new(&config);
+        let updated = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            crawler.crawl(Box::new(HangingHttpForwarder {}), &apps, Utc::now()),
+        )
+        .await
+        .expect("crawling should be aborted by the request timeout");
+
+        assert_eq!(updated, None);
+    }
+
+    #[derive(Clone)]
+    struct HostEchoingHttpForwarder {}
+
+    #[async_trait]
+    impl HttpForwarder for HostEchoingHttpForwarder {
+        async fn request_web_host_meta(
+            &self,
+            _app_name: &AppName,
+            _service_name: &str,
+            request: http::Request<http_body_util::Empty<bytes::Bytes>>,
+        ) -> Result<Option<WebHostMeta>> {
+            Ok(Some(WebHostMeta::with_version(
+                request.headers()[HOST].to_str()?.to_string(),
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn crawl_host_meta_with_configured_request_headers() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let nginx_service = Service {
+            id: String::from("nginx"),
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: Some(Utc::now()),
+                replicas: None,
+            },
+            config: crate::sc!("nginx", "nginx:latest"),
+        };
+        let apps = HashMap::from([(
+            AppName::master(),
+            Services::from(vec![nginx_service.clone()]),
+        )]);
+
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            [services.nginx.headers]
+            Host = 'nginx.example.com'
+            "#,
+        )
+        .unwrap();
+        let (cache, mut crawler) = super::new(&config);
+        crawler
+            .crawl(Box::new(HostEchoingHttpForwarder {}), &apps, Utc::now())
+            .await;
+
+        let apps = cache.update_meta_data(apps, &RequestInfo::new(base_url.clone()));
+        assert_eq!(
+            apps,
+            HashMap::from([(
+                AppName::master(),
+                ServicesWithHostMeta::from(vec![
+                    ServiceWithHostMeta::from_service_and_web_host_meta(
+                        nginx_service,
+                        WebHostMeta::with_version(String::from("nginx.example.com")),
+                        base_url,
+                        &AppName::master()
+                    )
+                ]),
+            )])
+        )
+    }
+
+    #[tokio::test]
+    async fn crawl_host_meta_again_after_ttl_expired() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let nginx_service = Service {
+            id: String::from("nginx"),
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: Some(Utc::now()),
+                replicas: None,
+            },
+            config: crate::sc!("nginx", "nginx:latest"),
+        };
+        let apps = HashMap::from([(
+            AppName::master(),
+            Services::from(vec![nginx_service.clone()]),
+        )]);
+
+        let config = toml::from_str::<HostMetaConfig>("ttl = '0s'").unwrap();
+        let (cache, mut crawler) = super::new(&config);
+        crawler.fake_empty_host_meta_info(AppName::master(), String::from("nginx"));
+        crawler
+            .crawl(Box::new(DummyHttpForwarder {}), &apps, Utc::now())
+            .await;
+
+        let apps = cache.update_meta_data(apps, &RequestInfo::new(base_url.clone()));
+        assert_eq!(
+            apps,
+            HashMap::from([(
+                AppName::master(),
+                ServicesWithHostMeta::from(vec![
+                    ServiceWithHostMeta::from_service_and_web_host_meta(
+                        nginx_service,
+                        WebHostMeta::with_version(String::from("1.2.3")),
+                        base_url,
+                        &AppName::master()
+                    )
+                ]),
+            )])
+        )
+    }
+
+    #[tokio::test]
+    async fn keep_host_meta_without_ttl() {
+        let base_url = Url::parse("https://example.com").unwrap();
+        let nginx_service = Service {
+            id: String::from("nginx"),
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: Some(Utc::now()),
+                replicas: None,
+            },
+            config: crate::sc!("nginx", "nginx:latest"),
+        };
+        let apps = HashMap::from([(
+            AppName::master(),
+            Services::from(vec![nginx_service.clone()]),
+        )]);
+
+        let (cache, mut crawler) = super::new(&Default::default());
+        crawler.fake_empty_host_meta_info(AppName::master(), String::from("nginx"));
+        crawler
+            .crawl(Box::new(DummyHttpForwarder {}), &apps, Utc::now())
+            .await;
+
+        let apps = cache.update_meta_data(apps, &RequestInfo::new(base_url.clone()));
+        assert_eq!(
+            apps,
+            HashMap::from([(
+                AppName::master(),
+                ServicesWithHostMeta::from(vec![
+                    ServiceWithHostMeta::from_service_and_web_host_meta(
+                        nginx_service,
+                        WebHostMeta::empty(),
+                        base_url,
+                        &AppName::master()
+                    )
+                ]),
+            )])
+        )
+    }
+
     #[tokio::test]
     async fn crawl_no_host_meta_for_paused_service() {
         let base_url = Url::parse("https://example.com").unwrap();
@@ -498,6 +720,7 @@ mod tests {
             state: State {
                 status: ServiceStatus::Paused,
                 started_at: None,
+                replicas: None,
             },
             config: crate::sc!("nginx", "nginx:latest"),
         };
@@ -508,7 +731,7 @@ mod tests {
             Services::from(vec![nginx_service.clone()]),
         )]);
 
-        let (cache, mut crawler) = super::new();
+        let (cache, mut crawler) = super::new(&Default::default());
         crawler.crawl(forwarder, &apps, Utc::now()).await;
 
         let apps = cache.update_meta_data(apps, &RequestInfo::new(base_url.clone()));
@@ -538,6 +761,7 @@ mod tests {
             state: State {
                 status: ServiceStatus::Running,
                 started_at: Some(Utc::now()),
+                replicas: None,
             },
             config: crate::sc!("nginx", "nginx:latest"),
         };
@@ -548,7 +772,7 @@ mod tests {
             Services::from(vec![nginx_service]),
         )]);
 
-        let (cache, mut crawler) = super::new();
+        let (cache, mut crawler) = super::new(&Default::default());
         crawler.crawl(forwarder, &apps, Utc::now()).await;
 
         // recrawl data for paused nginx
@@ -557,6 +781,7 @@ mod tests {
             state: State {
                 status: ServiceStatus::Paused,
                 started_at: None,
+                replicas: None,
             },
             config: crate::sc!("nginx", "nginx:latest"),
         };
@@ -24,41 +24,143 @@
  * =========================LICENSE_END==================================
  */
 mod host_meta_cache;
+mod notifications;
+mod progress;
 mod routes;
 
 pub use crate::apps::AppsService as Apps;
 pub use crate::apps::AppsServiceError as AppsError;
-use crate::config::{Config, ConfigError};
-use crate::deployment::deployment_unit::DeploymentUnitBuilder;
+use crate::config::{
+    AppLifecycleEvent, Config, ConfigError, OperationLockStrategy, PostDeployHookFailureStrategy,
+    ReplicatedEnvironmentPrecedence, Runtime, ServiceOrder, UnknownPortStrategy,
+};
+use crate::deployment::deployment_unit::{
+    DeploymentStrategy, DeploymentUnit, DeploymentUnitBuilder, SkippedCompanion,
+};
+use crate::deployment::hooks::Hooks;
 use crate::infrastructure::HttpForwarder;
 use crate::infrastructure::Infrastructure;
+use crate::infrastructure::ScalingNotSupported;
 use crate::models::service::Services;
 use crate::models::service::{ContainerType, Service, ServiceStatus};
 use crate::models::user_defined_parameters::UserDefinedParameters;
-use crate::models::{AppName, AppStatusChangeId, LogChunk, ServiceConfig};
+use crate::models::{
+    AppGraph, AppName, AppResourceUsage, AppStatusChangeId, Environment, Image, LogChunk,
+    LogFilter, ServiceConfig,
+};
 use crate::registry::Registry;
-use crate::registry::RegistryError;
-use chrono::{DateTime, FixedOffset};
+use crate::registry::{ImageInfo, RegistryError};
+use chrono::{DateTime, FixedOffset, Utc};
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use handlebars::RenderError;
 pub use host_meta_cache::new as host_meta_crawling;
 pub use host_meta_cache::HostMetaCache;
-pub use routes::{apps_routes, delete_app_sync};
-use std::collections::{HashMap, HashSet};
+use opentelemetry::KeyValue;
+use progress::{progress_stream, service_names, ProgressReporter, StatusChangeProgress};
+pub use routes::{
+    apps_routes, companions_routes, delete_app_sync, graphql_routes, graphql_schema,
+    CreateAppRateLimiter,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::From;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
 use std::sync::{Arc, Condvar, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::watch::Receiver;
 
 pub struct AppsService {
     config: Config,
     infrastructure: Box<dyn Infrastructure>,
     app_guards: Mutex<HashMap<AppName, Arc<AppGuard>>>,
+    progress: ProgressReporter,
 }
 
 type GuardedResult = Result<Services, AppsServiceError>;
 
+/// Which application's services are replicated into an application when it is deployed, see
+/// [`AppsService::create_or_update`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Replication {
+    /// Replicates the services of the default app master.
+    FromDefault,
+    /// Replicates the services of the given application.
+    From(AppName),
+    /// Deploys only the requested services and companions.
+    None,
+}
+
+/// The result of [`AppsService::create_or_update`].
+#[derive(Clone, Debug)]
+pub struct DeployedServices {
+    pub services: Services,
+    pub warnings: Vec<DeploymentWarning>,
+    pub timings: DeploymentTimings,
+}
+
+/// How long the phases of a deployment took, e.g. to spot slow registries or services that are
+/// slow to start.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeploymentTimings {
+    phases: Vec<(&'static str, Duration)>,
+    total: Duration,
+}
+
+impl DeploymentTimings {
+    fn record(&mut self, phase: &'static str, started: Instant) {
+        self.phases.push((phase, started.elapsed()));
+    }
+
+    /// The phases in the order in which they have been run.
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    /// The duration of the whole deployment, including the preparation that is not covered by a
+    /// phase, e.g. the templating.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+/// Hints about a deployment that succeeded but might not behave as the user expects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeploymentWarning {
+    /// A companion that has not been deployed as separate service
+    SkippedCompanion(SkippedCompanion),
+    /// A service whose image tag might point to a different image in the future
+    MutableImageTag { service_name: String, image: Image },
+    /// A service whose port could not be determined and that receives its traffic on port 80
+    UnknownPort { service_name: String },
+    /// A post-deploy hook that reported the deployed app as unhealthy
+    FailedPostDeployHook { message: String },
+}
+
+impl std::fmt::Display for DeploymentWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeploymentWarning::SkippedCompanion(skipped_companion) => {
+                write!(f, "{skipped_companion}")
+            }
+            DeploymentWarning::MutableImageTag {
+                service_name,
+                image,
+            } => write!(
+                f,
+                "The image {image} of {service_name} uses a mutable tag and may change unexpectedly. Consider pinning the image by its digest."
+            ),
+            DeploymentWarning::UnknownPort { service_name } => write!(
+                f,
+                "The port of {service_name} could not be determined, traffic will be routed to port 80. Consider configuring a default port for its image."
+            ),
+            DeploymentWarning::FailedPostDeployHook { message } => {
+                write!(f, "The post-deploy hook failed: {message}")
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum AppGuardKind {
     Deployment,
@@ -139,6 +241,7 @@ impl AppsService {
             config,
             infrastructure,
             app_guards: Mutex::new(HashMap::new()),
+            progress: ProgressReporter::default(),
         })
     }
 
@@ -153,13 +256,18 @@ impl AppsService {
     /// Analyzes running containers and returns a map of `app-name` with the
     /// corresponding list of `Service`s.
     pub async fn fetch_apps(&self) -> Result<HashMap<AppName, Services>, AppsServiceError> {
-        Ok(self.infrastructure.fetch_services().await?)
+        Ok(sort_services_of_apps(
+            self.infrastructure.fetch_services().await?,
+            self.config.service_order(),
+        ))
     }
 
     /// Provides a [`Receiver`](tokio::sync::watch::Receiver) that notifies about changes of the
     /// list of running [`apps`](AppsService::fetch_apps).
     pub async fn app_updates(&self) -> Receiver<HashMap<AppName, Services>> {
         let infrastructure = dyn_clone::clone_box(&*self.infrastructure);
+        let order = self.config.service_order();
+        let crawl_interval = self.config.host_meta_config().crawl_interval();
         let (tx, rx) = tokio::sync::watch::channel::<HashMap<AppName, Services>>(HashMap::new());
 
         tokio::spawn(async move {
@@ -167,6 +275,7 @@ impl AppsService {
                 debug!("Fetching list of apps to send updates.");
                 match infrastructure.fetch_services().await {
                     Ok(services) => {
+                        let services = sort_services_of_apps(services, order);
                         tx.send_if_modified(move |state| {
                             if &services != state {
                                 debug!("List of apps changed, sending updates.");
@@ -182,7 +291,7 @@ impl AppsService {
                     }
                 }
 
-                tokio::time::sleep(Duration::from_secs(5)).await;
+                tokio::time::sleep(crawl_interval).await;
             }
         });
 
@@ -209,6 +318,49 @@ impl AppsService {
         }
     }
 
+    /// Acquires the guard for deploying or deleting the app. If another operation on the app is
+    /// in progress, the operation is rejected unless the operation lock strategy is `wait` (see
+    /// [`OperationLockStrategy`]). Then, it waits until the other operation has been finished, in
+    /// the same way as it waits for the operations of other PREvant instances. Parallel deletions
+    /// share the guard and wait for the [result](AppGuard::wait_for_result) instead.
+    async fn acquire_app_guard(
+        &self,
+        app_name: &AppName,
+        kind: AppGuardKind,
+    ) -> Result<Arc<AppGuard>, AppsServiceError> {
+        let wait = match self.config.runtime_config() {
+            Runtime::Docker(runtime) => {
+                runtime.operation_lock().strategy() == &OperationLockStrategy::Wait
+            }
+            Runtime::Kubernetes(_) => false,
+        };
+
+        loop {
+            let result = self
+                .create_or_get_app_guard(app_name.clone(), kind)
+                .and_then(|guard| {
+                    if kind == AppGuardKind::Deletion || guard.is_first() {
+                        Ok(guard)
+                    } else {
+                        Err(AppsServiceError::AppIsInDeployment {
+                            app_name: app_name.clone(),
+                        })
+                    }
+                });
+
+            match result {
+                Err(
+                    AppsServiceError::AppIsInDeployment { .. }
+                    | AppsServiceError::AppIsInDeletion { .. },
+                ) if wait => {
+                    debug!("Waiting for the operation on {app_name} to be finished");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
     async fn configs_to_replicate(
         &self,
         services_to_deploy: &[ServiceConfig],
@@ -227,6 +379,23 @@ impl AppsService {
             .map(|c| c.service_name())
             .collect::<HashSet<&String>>();
 
+        let live_environments = if self.config.replicate_live_environment() {
+            self.infrastructure
+                .fetch_live_environments(replicate_from_app_name)
+                .await?
+        } else {
+            HashMap::new()
+        };
+
+        let app_environments = match self.config.replicated_environment_precedence() {
+            ReplicatedEnvironmentPrecedence::Template => HashMap::new(),
+            ReplicatedEnvironmentPrecedence::App => running_services
+                .iter()
+                .filter(|c| c.container_type() == &ContainerType::Replica)
+                .filter_map(|c| c.env().map(|env| (c.service_name(), env)))
+                .collect::<HashMap<&String, &Environment>>(),
+        };
+
         Ok(self
             .infrastructure
             .get_configs_of_app(replicate_from_app_name)
@@ -237,6 +406,12 @@ impl AppsService {
             .map(|config| {
                 let mut replicated_config = config;
                 replicated_config.set_container_type(ContainerType::Replica);
+                if let Some(live_env) = live_environments.get(replicated_config.service_name()) {
+                    apply_live_environment(&mut replicated_config, live_env);
+                }
+                if let Some(app_env) = app_environments.get(replicated_config.service_name()) {
+                    apply_app_environment(&mut replicated_config, app_env);
+                }
                 replicated_config
             })
             .collect::<Vec<ServiceConfig>>())
@@ -252,12 +427,129 @@ impl AppsService {
             .get_status_change(&status_id.to_string())
             .await?
         {
-            services = s;
+            services = s.sorted_by(self.config.service_order());
             tokio::time::sleep(Duration::from_millis(500)).await;
         }
         Ok(services)
     }
 
+    /// Streams the progress of the given status change until it has finished. Status changes
+    /// that are processed by another PREvant instance are reported as
+    /// [`StatusChangeProgress::Deploying`] until they have finished because only their services
+    /// can be observed. Returns `None` if the status change is unknown or has finished already.
+    pub async fn status_change_progress(
+        self: &Arc<Self>,
+        status_id: &AppStatusChangeId,
+    ) -> Result<Option<BoxStream<'static, StatusChangeProgress>>, AppsServiceError> {
+        if let Some(rx) = self.progress.subscribe(status_id) {
+            return Ok(Some(progress_stream(rx)));
+        }
+
+        let Some(services) = self
+            .infrastructure
+            .get_status_change(&status_id.to_string())
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let apps = self.clone();
+        let status_id = status_id.to_string();
+        Ok(Some(Box::pin(async_stream::stream! {
+            let mut services = services;
+            yield StatusChangeProgress::Deploying {
+                services: service_names(&services),
+            };
+
+            loop {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                match apps.infrastructure.get_status_change(&status_id).await {
+                    Ok(Some(s)) => services = s,
+                    Ok(None) => {
+                        yield StatusChangeProgress::finished(&services);
+                        break;
+                    }
+                    Err(err) => {
+                        yield StatusChangeProgress::Failed {
+                            message: err.to_string(),
+                        };
+                        break;
+                    }
+                }
+            }
+        })))
+    }
+
+    fn validate_user_defined_parameters(
+        &self,
+        user_defined_parameters: Option<serde_json::Value>,
+    ) -> Result<Option<UserDefinedParameters>, AppsServiceError> {
+        Ok(
+            match (
+                self.config.user_defined_schema_validator(),
+                user_defined_parameters,
+            ) {
+                (None, _) => None,
+                (Some(validator), None) => Some(
+                    UserDefinedParameters::new(serde_json::json!({}), &validator).map_err(|e| {
+                        AppsServiceError::InvalidUserDefinedParameters { err: e.to_string() }
+                    })?,
+                ),
+                (Some(validator), Some(value)) => {
+                    Some(UserDefinedParameters::new(value, &validator).map_err(|e| {
+                        AppsServiceError::InvalidUserDefinedParameters { err: e.to_string() }
+                    })?)
+                }
+            },
+        )
+    }
+
+    /// Restores the user-defined parameters of the last deployment request of the app from its
+    /// running services so that companions relying on them can be templated again. Apps that have
+    /// been deployed without storing these parameters cannot be restored if a schema is configured
+    /// because their companions would be templated without the parameters.
+    fn restore_user_defined_parameters(
+        &self,
+        app_name: &AppName,
+        running_services: &Services,
+    ) -> Result<Option<UserDefinedParameters>, AppsServiceError> {
+        let user_defined_parameters = running_services
+            .iter()
+            .find_map(|service| service.config.user_defined_parameters().cloned());
+
+        if user_defined_parameters.is_none()
+            && self.config.user_defined_schema_validator().is_some()
+        {
+            return Err(AppsServiceError::UnknownUserDefinedParameters {
+                app_name: app_name.clone(),
+            });
+        }
+
+        self.validate_user_defined_parameters(user_defined_parameters)
+    }
+
+    /// Resolves the application companions that an app with the given name would receive
+    /// without deploying anything. The same app selectors, templating, conditions and deployment
+    /// hooks apply as in [`create_or_update`](Self::create_or_update). Service companions are not
+    /// included because they depend on the services of the app.
+    pub async fn preview_companions(
+        &self,
+        app_name: &AppName,
+        user_defined_parameters: Option<serde_json::Value>,
+    ) -> Result<DeploymentUnit, AppsServiceError> {
+        let user_defined_parameters =
+            self.validate_user_defined_parameters(user_defined_parameters)?;
+
+        Ok(DeploymentUnitBuilder::init(app_name.clone(), Vec::new())
+            .extend_with_config(&self.config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, user_defined_parameters)?
+            .apply_hooks(&self.config)
+            .await?
+            .build())
+    }
+
     /// Creates or updates an app to review with the given service configurations.
     ///
     /// The list of given services will be extended with:
@@ -265,82 +557,207 @@ impl AppsService {
     /// - the application companions (see README)
     /// - the service companions (see README)
     ///
+    /// The deployment is made on behalf of PREvant itself, e.g. to ensure the default app, and,
+    /// thus, it is not subject to the protection of the default app. Requests of API clients go
+    /// through [`create_or_update_with_metadata`](Self::create_or_update_with_metadata).
+    ///
     /// # Arguments
-    /// * `replicate_from` - The application name that is used as a template.
+    /// * `replication` - Which application is used as a template for the services that are not
+    ///   part of `service_configs`.
     pub async fn create_or_update(
         &self,
         app_name: &AppName,
         status_id: &AppStatusChangeId,
-        replicate_from: Option<AppName>,
+        replication: Replication,
         service_configs: &[ServiceConfig],
         user_defined_parameters: Option<serde_json::Value>,
-    ) -> Result<Services, AppsServiceError> {
-        let user_defined_parameters = match (
-            self.config.user_defined_schema_validator(),
+    ) -> Result<DeployedServices, AppsServiceError> {
+        self.create_or_update_with_metadata(
+            app_name,
+            status_id,
+            replication,
+            service_configs,
             user_defined_parameters,
-        ) {
-            (None, _) => None,
-            (Some(validator), None) => Some(
-                UserDefinedParameters::new(serde_json::json!({}), &validator).map_err(|e| {
-                    AppsServiceError::InvalidUserDefinedParameters { err: e.to_string() }
-                })?,
-            ),
-            (Some(validator), Some(value)) => {
-                Some(UserDefinedParameters::new(value, &validator).map_err(|e| {
-                    AppsServiceError::InvalidUserDefinedParameters { err: e.to_string() }
-                })?)
-            }
-        };
+            BTreeMap::new(),
+            true,
+        )
+        .await
+    }
 
-        let guard = self.create_or_get_app_guard(app_name.clone(), AppGuardKind::Deployment)?;
+    /// Same as [`create_or_update`](Self::create_or_update) but stamps the given metadata, e.g.
+    /// the team or cost center, onto each deployed service. The protected default app is only
+    /// changed if `force` is set. The services of API clients have to be checked with
+    /// [`validate_service_configs`](Self::validate_service_configs) beforehand.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_or_update_with_metadata(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+        replication: Replication,
+        service_configs: &[ServiceConfig],
+        user_defined_parameters: Option<serde_json::Value>,
+        metadata: BTreeMap<String, String>,
+        force: bool,
+    ) -> Result<DeployedServices, AppsServiceError> {
+        // The status change is registered before waiting for the app guard so that clients can
+        // follow it as soon as the status id has been handed out.
+        self.progress
+            .start(status_id, StatusChangeProgress::Preparing);
 
-        if !guard.is_first() {
-            return Err(AppsServiceError::AppIsInDeployment {
-                app_name: app_name.clone(),
-            });
+        let prepared = async {
+            self.check_default_app_protection(app_name, force)?;
+
+            let user_defined_parameters =
+                self.validate_user_defined_parameters(user_defined_parameters)?;
+
+            let guard = self
+                .acquire_app_guard(app_name, AppGuardKind::Deployment)
+                .await?;
+
+            Ok::<_, AppsServiceError>((guard, user_defined_parameters))
         }
+        .await;
+
+        let (guard, user_defined_parameters) = match prepared {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                self.progress.finish(
+                    status_id,
+                    StatusChangeProgress::Failed {
+                        message: err.to_string(),
+                    },
+                );
+                return Err(err);
+            }
+        };
 
-        guard.notify_with_result(
-            self,
+        let result = crate::telemetry::in_span(
+            "create or update app",
+            vec![KeyValue::new("app.name", app_name.to_string())],
             self.create_or_update_impl(
                 app_name,
                 status_id,
-                replicate_from,
+                replication,
                 service_configs,
                 user_defined_parameters,
-            )
-            .await,
+                metadata,
+            ),
         )
+        .await;
+
+        self.progress.finish(
+            status_id,
+            match &result {
+                Ok(deployed) => StatusChangeProgress::finished(&deployed.services),
+                Err(err) => StatusChangeProgress::Failed {
+                    message: err.to_string(),
+                },
+            },
+        );
+
+        guard.notify_with_result(
+            self,
+            result
+                .as_ref()
+                .map(|deployed| deployed.services.clone())
+                .map_err(Clone::clone),
+        )?;
+
+        result
     }
 
-    async fn create_or_update_impl(
+    /// Resolves the services that [`create_or_update`](Self::create_or_update) would deploy,
+    /// including replicated services and companions with applied templating and hooks, without
+    /// deploying anything.
+    pub async fn create_or_update_dry_run(
         &self,
         app_name: &AppName,
-        status_id: &AppStatusChangeId,
-        replicate_from: Option<AppName>,
+        replication: Replication,
         service_configs: &[ServiceConfig],
-        user_defined_parameters: Option<UserDefinedParameters>,
-    ) -> Result<Services, AppsServiceError> {
-        if let Some(app_limit) = self.config.app_limit() {
-            let apps = self.fetch_apps().await?;
+        user_defined_parameters: Option<serde_json::Value>,
+    ) -> Result<DeploymentUnit, AppsServiceError> {
+        let user_defined_parameters =
+            self.validate_user_defined_parameters(user_defined_parameters)?;
 
-            if apps
-                .keys()
-                // filtering the app_name that is send because otherwise clients wouldn't be able
-                // to update an existing application.
-                .filter(|existing_app_name| *existing_app_name != app_name)
-                .count()
-                + 1
-                > app_limit
-            {
-                return Err(AppsError::AppLimitExceeded { limit: app_limit });
+        let (deployment_unit, _images) = self
+            .resolve_deployment_unit(
+                app_name,
+                replication,
+                service_configs,
+                user_defined_parameters,
+                &mut DeploymentTimings::default(),
+            )
+            .await?;
+        Ok(deployment_unit)
+    }
+
+    /// Checks the services requested by an API client against the configured restrictions, e.g.
+    /// the environment variables clients must not set. Only the services of the request must be
+    /// checked, not the services that are added from the configuration, e.g. of an
+    /// [app template](Self::expand_template), because these are trusted.
+    pub fn validate_service_configs(
+        &self,
+        service_configs: &[ServiceConfig],
+    ) -> Result<(), AppsServiceError> {
+        if !self.config.are_exposed_ports_allowed() {
+            let service_names = service_configs
+                .iter()
+                .filter(|config| config.expose_port())
+                .map(|config| config.service_name().clone())
+                .collect::<Vec<_>>();
+            if !service_names.is_empty() {
+                return Err(AppsServiceError::ExposedPortsNotAllowed { service_names });
+            }
+        }
+
+        if let Some(runtime) = service_configs
+            .iter()
+            .filter_map(|config| config.runtime())
+            .find(|runtime| !self.config.additional_runtimes().contains_key(*runtime))
+        {
+            return Err(AppsServiceError::UnknownRuntime {
+                runtime: runtime.clone(),
+            });
+        }
+
+        for service_config in service_configs {
+            let env_names = service_config
+                .env()
+                .iter()
+                .flat_map(|env| env.iter())
+                .map(|variable| variable.key())
+                .filter(|key| !self.config.is_env_override_allowed(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !env_names.is_empty() {
+                return Err(AppsServiceError::EnvOverridesNotAllowed {
+                    service_name: service_config.service_name().clone(),
+                    env_names,
+                });
             }
         }
 
+        Ok(())
+    }
+
+    async fn resolve_deployment_unit(
+        &self,
+        app_name: &AppName,
+        replication: Replication,
+        service_configs: &[ServiceConfig],
+        user_defined_parameters: Option<UserDefinedParameters>,
+        timings: &mut DeploymentTimings,
+    ) -> Result<(DeploymentUnit, HashSet<Image>), AppsServiceError> {
         let mut configs = service_configs.to_vec();
 
-        let replicate_from_app_name = replicate_from.unwrap_or_else(AppName::master);
-        if &replicate_from_app_name != app_name {
+        let replicate_from_app_name = match replication {
+            Replication::FromDefault => Some(AppName::master()),
+            Replication::From(replicate_from_app_name) => Some(replicate_from_app_name),
+            Replication::None => None,
+        }
+        // an application cannot be replicated from itself
+        .filter(|replicate_from_app_name| replicate_from_app_name != app_name);
+        if let Some(replicate_from_app_name) = replicate_from_app_name {
             configs.extend(
                 self.configs_to_replicate(service_configs, app_name, &replicate_from_app_name)
                     .await?,
@@ -360,14 +777,50 @@ impl AppsService {
             })
             .collect::<Vec<_>>();
 
+        let (deployment_unit, image_digests) = self
+            .build_deployment_unit(
+                app_name,
+                configs,
+                configs_for_templating,
+                user_defined_parameters,
+                timings,
+            )
+            .await?;
+
+        Ok((deployment_unit, image_digests.into_keys().collect()))
+    }
+
+    /// Builds the deployment unit of the given service configurations: it adds the companions,
+    /// resolves the images, applies the templating, the hooks and the base route of the
+    /// infrastructure. `configs_for_templating` are services of the app that are only available
+    /// to the templating but that are not deployed.
+    ///
+    /// Returns the deployment unit and the digests of the resolved images.
+    async fn build_deployment_unit(
+        &self,
+        app_name: &AppName,
+        configs: Vec<ServiceConfig>,
+        configs_for_templating: Vec<ServiceConfig>,
+        user_defined_parameters: Option<UserDefinedParameters>,
+        timings: &mut DeploymentTimings,
+    ) -> Result<(DeploymentUnit, HashMap<Image, String>), AppsServiceError> {
         let deployment_unit_builder = DeploymentUnitBuilder::init(app_name.clone(), configs)
             .extend_with_config(&self.config)
             .extend_with_templating_only_service_configs(configs_for_templating);
 
         let images = deployment_unit_builder.images();
-        let image_infos = Registry::new(&self.config)
-            .resolve_image_infos(&images)
-            .await?;
+        let phase_started = Instant::now();
+        let image_infos = crate::telemetry::in_span(
+            "resolve images",
+            Vec::new(),
+            Registry::new(&self.config).resolve_image_infos(&images),
+        )
+        .await?;
+        timings.record("resolve-images", phase_started);
+        let image_digests = image_infos
+            .iter()
+            .map(|(image, image_info)| (image.clone(), image_info.digest().clone()))
+            .collect::<HashMap<_, _>>();
 
         let base_traefik_ingress_route = self
             .infrastructure
@@ -385,71 +838,617 @@ impl AppsService {
             .apply_hooks(&self.config)
             .await?;
 
-        let deployment_unit = if let Some(base_traefik_ingress_route) = base_traefik_ingress_route {
-            trace!(
-                "The base URL for {app_name} is: {:?}",
-                base_traefik_ingress_route
-                    .to_url()
-                    .map(|url| url.to_string())
-            );
-            deployment_unit_builder
-                .apply_base_traefik_ingress_route(base_traefik_ingress_route)
-                .build()
-        } else {
-            deployment_unit_builder.build()
-        };
+        let mut deployment_unit =
+            if let Some(base_traefik_ingress_route) = base_traefik_ingress_route {
+                trace!(
+                    "The base URL for {app_name} is: {:?}",
+                    base_traefik_ingress_route
+                        .to_url()
+                        .map(|url| url.to_string())
+                );
+                deployment_unit_builder
+                    .apply_base_traefik_ingress_route(base_traefik_ingress_route)
+                    .build()
+            } else {
+                deployment_unit_builder.build()
+            };
+        deployment_unit.set_rollback_on_failure(self.config.rollback_on_failure());
+        deployment_unit.apply_user_defined_parameters();
+
+        Ok((deployment_unit, image_digests))
+    }
 
-        let services = self
+    /// Fetches the running services of the app and the configurations of its instances and
+    /// replicas, from which [`Self::build_deployment_unit`] can restore the app.
+    async fn fetch_running_services_and_configs(
+        &self,
+        app_name: &AppName,
+    ) -> Result<(Services, Vec<ServiceConfig>), AppsServiceError> {
+        let running_services = self
             .infrastructure
-            .deploy_services(
-                &status_id.to_string(),
-                &deployment_unit,
-                &self.config.container_config(),
-            )
-            .await?;
+            .fetch_services()
+            .await?
+            .remove(app_name)
+            .ok_or_else(|| AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            })?;
 
-        Ok(services)
+        let configs = running_services
+            .iter()
+            .filter(|service| {
+                matches!(
+                    service.container_type(),
+                    ContainerType::Instance | ContainerType::Replica
+                )
+            })
+            .map(|service| service.config.clone())
+            .collect::<Vec<_>>();
+
+        Ok((running_services, configs))
     }
 
-    /// Deletes all services for the given `app_name`.
-    pub async fn delete_app(
+    async fn create_or_update_impl(
         &self,
         app_name: &AppName,
         status_id: &AppStatusChangeId,
-    ) -> Result<Services, AppsServiceError> {
-        let guard = self.create_or_get_app_guard(app_name.clone(), AppGuardKind::Deletion)?;
+        replication: Replication,
+        service_configs: &[ServiceConfig],
+        user_defined_parameters: Option<UserDefinedParameters>,
+        metadata: BTreeMap<String, String>,
+    ) -> Result<DeployedServices, AppsServiceError> {
+        let started = Instant::now();
+        let mut timings = DeploymentTimings::default();
 
-        if !guard.is_first() {
-            guard.wait_for_result()
-        } else {
-            guard.notify_with_result(self, self.delete_app_impl(app_name, status_id).await)
+        if let Some(app_limit) = self.config.app_limit() {
+            let apps = self.fetch_apps().await?;
+
+            if apps
+                .keys()
+                // filtering the app_name that is send because otherwise clients wouldn't be able
+                // to update an existing application.
+                .filter(|existing_app_name| *existing_app_name != app_name)
+                .count()
+                + 1
+                > app_limit
+            {
+                return Err(AppsError::AppLimitExceeded { limit: app_limit });
+            }
         }
-    }
 
-    async fn delete_app_impl(
-        &self,
-        app_name: &AppName,
-        status_id: &AppStatusChangeId,
-    ) -> Result<Services, AppsServiceError> {
-        let services = self
-            .infrastructure
-            .stop_services(&status_id.to_string(), app_name)
+        let (mut deployment_unit, images) = self
+            .resolve_deployment_unit(
+                app_name,
+                replication,
+                service_configs,
+                user_defined_parameters,
+                &mut timings,
+            )
             .await?;
-        if services.is_empty() {
-            Err(AppsServiceError::AppNotFound {
-                app_name: app_name.clone(),
-            })
-        } else {
-            Ok(services)
+        deployment_unit.apply_metadata(&metadata);
+        deployment_unit.apply_deployment_time(Utc::now());
+        let running_services = self
+            .infrastructure
+            .fetch_services()
+            .await?
+            .remove(app_name)
+            .unwrap_or_else(Services::empty);
+        deployment_unit.apply_creation_time(&running_services);
+
+        let services_with_unknown_port = deployment_unit
+            .services()
+            .iter()
+            .filter(|service| !service.is_port_known())
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        if !services_with_unknown_port.is_empty()
+            && self.config.unknown_port_strategy() == UnknownPortStrategy::Fail
+        {
+            return Err(AppsServiceError::UnknownServicePorts {
+                service_names: services_with_unknown_port,
+            });
         }
-    }
 
-    pub async fn stream_logs<'a>(
-        &'a self,
-        app_name: &'a AppName,
-        service_name: &'a str,
-        since: &'a Option<DateTime<FixedOffset>>,
-        limit: &'a Option<usize>,
+        let mutable_images = Registry::new(&self.config).mutable_images(&images);
+        let warnings = deployment_unit
+            .skipped_companions()
+            .iter()
+            .cloned()
+            .map(DeploymentWarning::SkippedCompanion)
+            .chain(
+                deployment_unit
+                    .services()
+                    .iter()
+                    .filter(|service| service.container_type() == &ContainerType::Instance)
+                    .filter(|service| mutable_images.contains(service.image()))
+                    .map(|service| DeploymentWarning::MutableImageTag {
+                        service_name: service.service_name().clone(),
+                        image: service.image().clone(),
+                    }),
+            )
+            .chain(
+                services_with_unknown_port
+                    .into_iter()
+                    .map(|service_name| DeploymentWarning::UnknownPort { service_name }),
+            )
+            .collect::<Vec<_>>();
+        for warning in &warnings {
+            match warning {
+                DeploymentWarning::UnknownPort { .. } => warn!("{app_name}: {warning}"),
+                _ => info!("{app_name}: {warning}"),
+            }
+        }
+
+        let lifecycle_event = if !self.config.notifications_config().has_webhooks() {
+            None
+        } else if self
+            .infrastructure
+            .fetch_app_names()
+            .await?
+            .contains(app_name)
+        {
+            Some(AppLifecycleEvent::Updated)
+        } else {
+            Some(AppLifecycleEvent::Created)
+        };
+
+        self.progress.report(
+            status_id,
+            StatusChangeProgress::Deploying {
+                services: deployment_unit
+                    .services()
+                    .iter()
+                    .map(|service| service.service_name().clone())
+                    .collect(),
+            },
+        );
+
+        let phase_started = Instant::now();
+        let services = crate::telemetry::in_span(
+            "deploy services",
+            Vec::new(),
+            self.infrastructure.deploy_services(
+                &status_id.to_string(),
+                &deployment_unit,
+                &self.config.container_config(),
+            ),
+        )
+        .await?
+        .sorted_by(self.config.service_order());
+        timings.record("deploy", phase_started);
+
+        let mut warnings = warnings;
+        if !self.config.hooks("postDeploy").is_empty() {
+            self.progress
+                .report(status_id, StatusChangeProgress::Verifying);
+        }
+        let phase_started = Instant::now();
+        let post_deploy_hook_result = Hooks::new(&self.config)
+            .apply_post_deploy_hook(&deployment_unit, &services)
+            .await;
+        if !self.config.hooks("postDeploy").is_empty() {
+            timings.record("post-deploy-hook", phase_started);
+        }
+        if let Err(message) = post_deploy_hook_result {
+            match self.config.post_deploy_hook_failure_strategy() {
+                PostDeployHookFailureStrategy::Warn => {
+                    warn!("{app_name}: The post-deploy hook failed: {message}");
+                    warnings.push(DeploymentWarning::FailedPostDeployHook { message });
+                }
+                PostDeployHookFailureStrategy::Fail => {
+                    return Err(AppsServiceError::PostDeployHookFailed {
+                        app_name: app_name.clone(),
+                        message,
+                    });
+                }
+            }
+        }
+
+        timings.total = started.elapsed();
+
+        if let Some(event) = lifecycle_event {
+            notifications::notify(
+                self.config.notifications_config(),
+                event,
+                app_name,
+                &services,
+            );
+        }
+
+        Ok(DeployedServices {
+            services,
+            warnings,
+            timings,
+        })
+    }
+
+    /// Re-runs the templating and the hooks against the services of the running app and
+    /// redeploys only the companions whose resolved configuration differs from the running one
+    /// (see [`ServiceConfig::config_hash`]), e.g. to debug templates and hooks without a full
+    /// redeployment. The services of the deployment requests are considered for templating but
+    /// they will not be redeployed because their complete configuration cannot be restored from
+    /// the infrastructure. The templating only relies on their name, type, image and port, which
+    /// can be restored, and on the stored user-defined parameters of the last deployment (see
+    /// [`Self::restore_user_defined_parameters`]).
+    ///
+    /// Returns the services that have been redeployed.
+    pub async fn reconcile(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+        force: bool,
+    ) -> Result<Services, AppsServiceError> {
+        self.check_default_app_protection(app_name, force)?;
+        let guard = self
+            .acquire_app_guard(app_name, AppGuardKind::Deployment)
+            .await?;
+
+        let result = crate::telemetry::in_span(
+            "reconcile app",
+            vec![KeyValue::new("app.name", app_name.to_string())],
+            self.reconcile_impl(app_name, status_id),
+        )
+        .await;
+
+        guard.notify_with_result(self, result)
+    }
+
+    async fn reconcile_impl(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+    ) -> Result<Services, AppsServiceError> {
+        let (running_services, configs) = self.fetch_running_services_and_configs(app_name).await?;
+        let user_defined_parameters =
+            self.restore_user_defined_parameters(app_name, &running_services)?;
+        let (mut deployment_unit, _image_digests) = self
+            .build_deployment_unit(
+                app_name,
+                configs,
+                Vec::new(),
+                user_defined_parameters,
+                &mut DeploymentTimings::default(),
+            )
+            .await?;
+
+        let _unchanged = deployment_unit.split_off(|service| {
+            let is_companion = matches!(
+                service.container_type(),
+                ContainerType::ApplicationCompanion | ContainerType::ServiceCompanion
+            );
+            !is_companion
+                || running_services.iter().any(|running| {
+                    running.service_name() == service.service_name()
+                        && running.config.deployed_config_hash() == Some(&service.config_hash())
+                })
+        });
+
+        if deployment_unit.services().is_empty() {
+            debug!("{app_name}: Nothing to reconcile");
+            return Ok(Services::empty());
+        }
+
+        if let Some(running_service) = running_services.iter().next() {
+            deployment_unit.apply_metadata(running_service.config.metadata());
+        }
+        // Reconciling restores the app as it has been requested and thus, it is not a deployment
+        // that extends the app's lifetime (see `[applications] ttl`).
+        deployment_unit.apply_deployment_time(
+            running_services
+                .last_deployment()
+                .cloned()
+                .unwrap_or_else(Utc::now),
+        );
+        deployment_unit.apply_creation_time(&running_services);
+
+        let service_names = deployment_unit
+            .services()
+            .iter()
+            .map(|service| service.service_name().clone())
+            .collect::<HashSet<_>>();
+        info!(
+            "{app_name}: Redeploying the changed companions {}",
+            service_names.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+
+        let services = self
+            .infrastructure
+            .deploy_services(
+                &status_id.to_string(),
+                &deployment_unit,
+                &self.config.container_config(),
+            )
+            .await?;
+
+        Ok(Services::from(
+            services
+                .into_iter()
+                .filter(|service| service_names.contains(service.service_name()))
+                .collect::<Vec<_>>(),
+        )
+        .sorted_by(self.config.service_order()))
+    }
+
+    /// Pulls the images of the running app again and recreates the services whose image digest
+    /// has changed, e.g. because a new image has been pushed with the same tag. The services keep
+    /// the specification of their running containers or deployments and only their image is
+    /// updated (see [`Infrastructure::update_images`]). Companions that must never be redeployed
+    /// keep running.
+    ///
+    /// Returns the services of the app.
+    pub async fn redeploy(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+        force: bool,
+    ) -> Result<Services, AppsServiceError> {
+        self.check_default_app_protection(app_name, force)?;
+        let guard = self
+            .acquire_app_guard(app_name, AppGuardKind::Deployment)
+            .await?;
+
+        let result = crate::telemetry::in_span(
+            "redeploy app",
+            vec![KeyValue::new("app.name", app_name.to_string())],
+            self.redeploy_impl(app_name, status_id),
+        )
+        .await;
+
+        guard.notify_with_result(self, result)
+    }
+
+    async fn redeploy_impl(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+    ) -> Result<Services, AppsServiceError> {
+        let (running_services, configs) = self.fetch_running_services_and_configs(app_name).await?;
+        // The deployment unit only determines the deployment strategies of the services and the
+        // digests of their images, so that apps deployed without the stored parameters can be
+        // redeployed as well.
+        let user_defined_parameters =
+            match self.restore_user_defined_parameters(app_name, &running_services) {
+                Err(AppsServiceError::UnknownUserDefinedParameters { .. }) => None,
+                result => result?,
+            };
+        let (deployment_unit, image_digests) = self
+            .build_deployment_unit(
+                app_name,
+                configs,
+                Vec::new(),
+                user_defined_parameters,
+                &mut DeploymentTimings::default(),
+            )
+            .await?;
+
+        let image_digests =
+            image_digests_of_running_services(&deployment_unit, &running_services, &image_digests);
+
+        let services = self
+            .infrastructure
+            .update_images(&status_id.to_string(), app_name, &image_digests)
+            .await?;
+
+        Ok(services.sorted_by(self.config.service_order()))
+    }
+
+    /// Deploys the default app (`master`) with the services of the configuration if PREvant
+    /// should ensure its existence (see `[applications] ensureDefaultApp`) and if the app is not
+    /// running yet. Returns `None` if nothing has been deployed.
+    pub async fn ensure_default_app(&self) -> Result<Option<DeployedServices>, AppsServiceError> {
+        let Some(service_configs) = self.config.default_app_services_to_ensure() else {
+            return Ok(None);
+        };
+
+        let app_name = AppName::master();
+        if self.fetch_app_names().await?.contains(&app_name) {
+            return Ok(None);
+        }
+
+        info!("Deploying default app {app_name} because it is not running.");
+        self.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::None,
+            service_configs,
+            None,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Returns the names, sorted alphabetically, of the apps that start with the given prefix.
+    /// Apps that are protected by the configuration are left out because they can only be deleted
+    /// one by one with `force`. The default app is always left out because the other apps
+    /// replicate their services from it.
+    pub async fn fetch_unprotected_app_names_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<AppName>, AppsServiceError> {
+        let mut app_names = self
+            .fetch_app_names()
+            .await?
+            .into_iter()
+            .filter(|app_name| app_name.as_str().starts_with(prefix))
+            .filter(|app_name| {
+                app_name != &AppName::master() && !self.config.is_app_protected(app_name)
+            })
+            .collect::<Vec<_>>();
+        app_names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(app_names)
+    }
+
+    /// Rejects changes of the default app if it is protected by the configuration (see
+    /// `[applications] protectDefaultApp`), unless `force` is set.
+    fn check_default_app_protection(
+        &self,
+        app_name: &AppName,
+        force: bool,
+    ) -> Result<(), AppsServiceError> {
+        if !force && self.config.is_default_app_protected(app_name) {
+            return Err(AppsServiceError::DefaultAppIsProtected {
+                app_name: app_name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Deletes all services for the given `app_name`. Apps that are protected by the
+    /// configuration are only deleted if `force` is set.
+    pub async fn delete_app(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+        force: bool,
+    ) -> Result<Services, AppsServiceError> {
+        if !force && self.config.is_app_protected(app_name) {
+            // An app that does not exist cannot be protected, clients should see that it is missing
+            if !self
+                .infrastructure
+                .fetch_app_names()
+                .await?
+                .contains(app_name)
+            {
+                return Err(AppsServiceError::AppNotFound {
+                    app_name: app_name.clone(),
+                });
+            }
+            return Err(AppsServiceError::AppIsProtected {
+                app_name: app_name.clone(),
+            });
+        }
+
+        let guard = self
+            .acquire_app_guard(app_name, AppGuardKind::Deletion)
+            .await?;
+
+        if !guard.is_first() {
+            guard.wait_for_result()
+        } else {
+            self.progress
+                .start(status_id, StatusChangeProgress::Stopping);
+            let result = self.delete_app_impl(app_name, status_id).await;
+            self.progress.finish(
+                status_id,
+                match &result {
+                    Ok(services) => StatusChangeProgress::finished(services),
+                    Err(err) => StatusChangeProgress::Failed {
+                        message: err.to_string(),
+                    },
+                },
+            );
+            guard.notify_with_result(self, result)
+        }
+    }
+
+    async fn delete_app_impl(
+        &self,
+        app_name: &AppName,
+        status_id: &AppStatusChangeId,
+    ) -> Result<Services, AppsServiceError> {
+        let services = self
+            .infrastructure
+            .stop_services(&status_id.to_string(), app_name)
+            .await?
+            .sorted_by(self.config.service_order());
+        if services.is_empty() {
+            Err(AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            })
+        } else {
+            notifications::notify(
+                self.config.notifications_config(),
+                AppLifecycleEvent::Deleted,
+                app_name,
+                &services,
+            );
+            Ok(services)
+        }
+    }
+
+    /// Pins or unpins the app. Pinned apps are not deleted automatically when they exceed the
+    /// configured TTL.
+    pub async fn set_pinned(
+        &self,
+        app_name: &AppName,
+        pinned: bool,
+        force: bool,
+    ) -> Result<(), AppsServiceError> {
+        self.check_default_app_protection(app_name, force)?;
+        if !self.fetch_app_names().await?.contains(app_name) {
+            return Err(AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            });
+        }
+
+        self.infrastructure.set_pinned(app_name, pinned).await?;
+        Ok(())
+    }
+
+    /// Returns whether the app has been pinned (see [`Self::set_pinned`]).
+    pub async fn is_pinned(&self, app_name: &AppName) -> Result<bool, AppsServiceError> {
+        Ok(self
+            .infrastructure
+            .fetch_pinned_app_names()
+            .await?
+            .contains(app_name))
+    }
+
+    /// Deletes all apps that have not been deployed within the configured TTL (see
+    /// `[applications] ttl`). The default app (`master`), protected apps, and pinned apps are
+    /// never deleted.
+    /// Returns the names of the deleted apps.
+    pub async fn delete_expired_apps(&self) -> Result<Vec<AppName>, AppsServiceError> {
+        self.delete_apps_expired_at(Utc::now()).await
+    }
+
+    async fn delete_apps_expired_at(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<AppName>, AppsServiceError> {
+        let Some(ttl) = self
+            .config
+            .app_ttl()
+            .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+        else {
+            return Ok(Vec::new());
+        };
+
+        let pinned_apps = self.infrastructure.fetch_pinned_app_names().await?;
+        let mut deleted_apps = Vec::new();
+        for (app_name, services) in self.fetch_apps().await? {
+            if app_name == AppName::master()
+                || self.config.is_app_protected(&app_name)
+                || pinned_apps.contains(&app_name)
+            {
+                continue;
+            }
+
+            let Some(creation) = services.creation() else {
+                continue;
+            };
+            if *creation + ttl > now {
+                continue;
+            }
+
+            info!("Deleting app {app_name} because it exceeded its TTL (created at {creation}).");
+            match self
+                .delete_app(&app_name, &AppStatusChangeId::new(), false)
+                .await
+            {
+                Ok(_) => deleted_apps.push(app_name),
+                Err(err) => error!("Cannot delete expired app {app_name}: {err}"),
+            }
+        }
+
+        Ok(deleted_apps)
+    }
+
+    pub async fn stream_logs<'a>(
+        &'a self,
+        app_name: &'a AppName,
+        service_name: &'a str,
+        since: &'a Option<DateTime<FixedOffset>>,
+        limit: &'a Option<usize>,
     ) -> BoxStream<'a, Result<(DateTime<FixedOffset>, String), anyhow::Error>> {
         self.infrastructure
             .get_logs(app_name, service_name, since, limit, true)
@@ -462,46 +1461,249 @@ impl AppsService {
         service_name: &'a str,
         since: &'a Option<DateTime<FixedOffset>>,
         limit: &'a Option<usize>,
+        filter: Option<&LogFilter>,
     ) -> Result<Option<LogChunk>, AppsServiceError> {
+        // The limit applies to the filtered log lines, thus, the infrastructure must not truncate
+        // the logs when they are filtered.
+        let infrastructure_limit = if filter.is_some() { &None } else { limit };
+
         let mut log_lines = Vec::new();
         let mut log_stream = self
             .infrastructure
-            .get_logs(app_name, service_name, since, limit, false)
+            .get_logs(app_name, service_name, since, infrastructure_limit, false)
             .await;
 
         while let Some(result) = log_stream.next().await {
             if let Ok(log_line) = result {
-                log_lines.push(log_line);
+                if filter.is_none_or(|filter| filter.matches(&log_line.1)) {
+                    log_lines.push(log_line);
+                }
+            }
+            if limit.is_some_and(|limit| log_lines.len() >= limit) {
+                break;
             }
         }
 
         Ok(Some(LogChunk::from(log_lines)))
     }
 
+    /// Returns the current CPU and memory usage of each service of the app and the total usage.
+    pub async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<AppResourceUsage, AppsServiceError> {
+        match self.infrastructure.fetch_resource_usage(app_name).await? {
+            Some(usages) => Ok(AppResourceUsage::from(usages)),
+            None => Err(AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            }),
+        }
+    }
+
+    /// Returns the services of the app and the dependencies between them.
+    pub async fn fetch_app_graph(&self, app_name: &AppName) -> Result<AppGraph, AppsServiceError> {
+        let services = self.fetch_apps().await?.remove(app_name).ok_or_else(|| {
+            AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            }
+        })?;
+        let live_environments = self
+            .infrastructure
+            .fetch_live_environments(app_name)
+            .await?;
+
+        Ok(AppGraph::new(&services, &live_environments))
+    }
+
+    /// Returns the image of the service and the information the registry provides for it. Images
+    /// that are referenced by digest are not resolved through the registry.
+    pub async fn resolve_service_image(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<(Image, Option<ImageInfo>), AppsServiceError> {
+        let services = self.fetch_apps().await?.remove(app_name).ok_or_else(|| {
+            AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            }
+        })?;
+        let image = services
+            .iter()
+            .find(|service| service.service_name() == service_name)
+            .map(|service| service.config.image().clone())
+            .ok_or_else(|| AppsServiceError::ServiceNotFound {
+                app_name: app_name.clone(),
+                service_name: service_name.to_string(),
+            })?;
+
+        let mut image_infos = Registry::new(&self.config)
+            .resolve_image_infos(&HashSet::from([image.clone()]))
+            .await?;
+        let image_info = image_infos.remove(&image);
+
+        Ok((image, image_info))
+    }
+
+    /// Expands the services of the server-side app template with the given name. The given
+    /// services are merged over the services of the template.
+    pub fn expand_template(
+        &self,
+        template_name: &str,
+        services: Vec<ServiceConfig>,
+    ) -> Result<Vec<ServiceConfig>, AppsServiceError> {
+        self.config
+            .expand_app_template(template_name, services)
+            .ok_or_else(|| AppsServiceError::UnknownTemplate {
+                template_name: template_name.to_string(),
+            })
+    }
+
     pub async fn change_status(
         &self,
         app_name: &AppName,
         service_name: &str,
         status: ServiceStatus,
-    ) -> Result<Option<Service>, AppsServiceError> {
-        Ok(self
+        force: bool,
+    ) -> Result<Service, AppsServiceError> {
+        self.check_default_app_protection(app_name, force)?;
+        if let Some(service) = self
             .infrastructure
             .change_status(app_name, service_name, status)
-            .await?)
-    }
-}
+            .await?
+        {
+            return Ok(service);
+        }
 
-/// Defines error cases for the [`Apps`](Apps)
-#[derive(Debug, Clone, thiserror::Error)]
+        if self
+            .infrastructure
+            .fetch_app_names()
+            .await?
+            .contains(app_name)
+        {
+            Err(AppsServiceError::ServiceNotFound {
+                app_name: app_name.clone(),
+                service_name: service_name.to_string(),
+            })
+        } else {
+            Err(AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            })
+        }
+    }
+
+    /// Changes the number of replicas of the service and returns the service as it was before the
+    /// change.
+    pub async fn scale_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        replicas: NonZeroU32,
+        force: bool,
+    ) -> Result<Service, AppsServiceError> {
+        self.check_default_app_protection(app_name, force)?;
+        match self
+            .infrastructure
+            .scale_service(app_name, service_name, replicas)
+            .await
+        {
+            Ok(Some(service)) => return Ok(service),
+            Ok(None) => {}
+            Err(err) if err.is::<ScalingNotSupported>() => {
+                return Err(AppsServiceError::ScalingNotSupported {
+                    app_name: app_name.clone(),
+                    service_name: service_name.to_string(),
+                });
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if self
+            .infrastructure
+            .fetch_app_names()
+            .await?
+            .contains(app_name)
+        {
+            Err(AppsServiceError::ServiceNotFound {
+                app_name: app_name.clone(),
+                service_name: service_name.to_string(),
+            })
+        } else {
+            Err(AppsServiceError::AppNotFound {
+                app_name: app_name.clone(),
+            })
+        }
+    }
+}
+
+/// Sorts the services of each app by the given order.
+fn sort_services_of_apps(
+    apps: HashMap<AppName, Services>,
+    order: ServiceOrder,
+) -> HashMap<AppName, Services> {
+    apps.into_iter()
+        .map(|(app_name, services)| (app_name, services.sorted_by(order)))
+        .collect()
+}
+
+/// Replaces the values of the replicated environment variables with the values the template's
+/// container is currently running with. Templated variables keep their template because their
+/// live values have been rendered for the template app.
+fn apply_live_environment(config: &mut ServiceConfig, live_env: &Environment) {
+    let Some(env) = config.env() else {
+        return;
+    };
+
+    let env = Environment::new(
+        env.iter()
+            .map(|variable| match live_env.variable(variable.key()) {
+                Some(live_variable) if !variable.templated() => {
+                    variable.clone().with_value(live_variable.value().clone())
+                }
+                _ => variable.clone(),
+            })
+            .collect(),
+    );
+    config.set_env(Some(env));
+}
+
+/// Replaces the values of the replicated environment variables with the values of the replica
+/// that is already running in the app, so that they are kept when the replica is refreshed.
+fn apply_app_environment(config: &mut ServiceConfig, app_env: &Environment) {
+    let Some(env) = config.env() else {
+        return;
+    };
+
+    let env = Environment::new(
+        env.iter()
+            .map(|variable| match app_env.variable(variable.key()) {
+                Some(app_variable) => app_variable.clone(),
+                None => variable.clone(),
+            })
+            .collect(),
+    );
+    config.set_env(Some(env));
+}
+
+/// Defines error cases for the [`Apps`](Apps)
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum AppsServiceError {
     #[error("Cannot find app {app_name}.")]
     AppNotFound { app_name: AppName },
+    #[error("Cannot find service {service_name} of app {app_name}.")]
+    ServiceNotFound {
+        app_name: AppName,
+        service_name: String,
+    },
     #[error("Cannot create more than {limit} apps")]
     AppLimitExceeded { limit: usize },
     #[error("The app {app_name} is currently within deployment by another request.")]
     AppIsInDeployment { app_name: AppName },
     #[error("The app {app_name} is currently within deletion in by another request.")]
     AppIsInDeletion { app_name: AppName },
+    #[error("The app {app_name} is protected from deletion.")]
+    AppIsProtected { app_name: AppName },
+    #[error("The default app {app_name} is protected from changes through the API.")]
+    DefaultAppIsProtected { app_name: AppName },
     /// Will be used when the service cannot interact correctly with the infrastructure.
     #[error("Cannot interact with infrastructure: {error}")]
     InfrastructureError { error: Arc<anyhow::Error> },
@@ -514,10 +1716,64 @@ pub enum AppsServiceError {
     UnableToResolveImage { error: Arc<RegistryError> },
     #[error("Invalid deployment hook.")]
     InvalidDeploymentHook,
+    #[error("The hook {hook_path:?} did not finish within {}.", humantime::format_duration(*timeout))]
+    HookTimeout {
+        hook_path: PathBuf,
+        timeout: Duration,
+    },
     #[error("Failed to parse traefik rule ({raw_rule}): {err}")]
     FailedToParseTraefikRule { raw_rule: String, err: String },
     #[error("User defined payload does not match to the configured value: {err}")]
     InvalidUserDefinedParameters { err: String },
+    #[error("Exposing the ports of the services {} is not allowed.", service_names.join(", "))]
+    ExposedPortsNotAllowed { service_names: Vec<String> },
+    #[error("The environment variables {} of service {service_name} must not be set.", env_names.join(", "))]
+    EnvOverridesNotAllowed {
+        service_name: String,
+        env_names: Vec<String>,
+    },
+    #[error("There is no app template named {template_name}.")]
+    UnknownTemplate { template_name: String },
+    #[error("There is no runtime named {runtime}.")]
+    UnknownRuntime { runtime: String },
+    #[error("The ports of the services {} could not be determined.", service_names.join(", "))]
+    UnknownServicePorts { service_names: Vec<String> },
+    #[error("The app {app_name} has been deployed but the post-deploy hook failed: {message}")]
+    PostDeployHookFailed { app_name: AppName, message: String },
+    #[error("The dependencies of the services {} form a cycle.", service_names.join(", "))]
+    CyclicServiceDependencies { service_names: Vec<String> },
+    #[error("The user-defined parameters of app {app_name} are unknown because it has been deployed by an older version of PREvant.")]
+    UnknownUserDefinedParameters { app_name: AppName },
+    #[error(
+        "The service {service_name} of app {app_name} cannot be scaled by the infrastructure."
+    )]
+    ScalingNotSupported {
+        app_name: AppName,
+        service_name: String,
+    },
+}
+
+/// Maps the names of the running services that may be redeployed with an updated image to the
+/// current digest of their running image. Services that must never be redeployed and services
+/// without a known digest, e.g. because their image is referenced by its digest, are left out.
+fn image_digests_of_running_services(
+    deployment_unit: &DeploymentUnit,
+    running_services: &Services,
+    image_digests: &HashMap<Image, String>,
+) -> HashMap<String, String> {
+    running_services
+        .iter()
+        .filter(|running| {
+            deployment_unit.services().iter().any(|service| {
+                service.service_name() == running.service_name()
+                    && !matches!(service.strategy(), DeploymentStrategy::RedeployNever)
+            })
+        })
+        .filter_map(|running| {
+            let digest = image_digests.get(running.config.image())?;
+            Some((running.service_name().clone(), digest.clone()))
+        })
+        .collect()
 }
 
 impl From<ConfigError> for AppsServiceError {
@@ -560,8 +1816,8 @@ mod tests {
     use crate::models::service::State;
     use crate::models::EnvironmentVariable;
     use crate::sc;
-    use chrono::Utc;
     use futures::StreamExt;
+    use regex::Regex;
     use secstr::SecUtf8;
     use std::hash::Hash;
     use std::io::Write;
@@ -596,847 +1852,2239 @@ mod tests {
 
         hook_file.write_all(script.as_bytes()).unwrap();
 
-        let config = crate::config_from_str!(&format!(
-            r#"
-            [hooks]
-            deployment = {:?}
-            "#,
-            hook_file.path()
-        ));
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [hooks]
+            deployment = {:?}
+            "#,
+            hook_file.path()
+        ));
+
+        (hook_file, config)
+    }
+
+    #[tokio::test]
+    async fn should_create_app_for_master() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+        assert_eq!(deployed_apps.len(), 1);
+        let services = deployed_apps.get(&AppName::master()).unwrap();
+        assert_eq!(services.len(), 1);
+        assert_contains_service!(services, "service-a", ContainerType::Instance);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_replication_from_master() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::From(AppName::master()),
+            &vec![sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps
+            .get(&AppName::from_str("branch").unwrap())
+            .unwrap();
+        assert_eq!(services.len(), 2);
+        assert_contains_service!(services, "service-b", ContainerType::Instance);
+        assert_contains_service!(services, "service-a", ContainerType::Replica);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_replicate_without_replication() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a"), sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::None,
+            &[sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps
+            .get(&AppName::from_str("branch").unwrap())
+            .unwrap();
+        assert_eq!(services.len(), 1);
+        assert_contains_service!(services, "service-b", ContainerType::Instance);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_override_replicas_from_master() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::From(AppName::master()),
+            &vec![sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::From(AppName::master()),
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps
+            .get(&AppName::from_str("branch").unwrap())
+            .unwrap();
+        assert_eq!(services.len(), 2);
+        assert_contains_service!(services, "service-a", ContainerType::Instance);
+        assert_contains_service!(services, "service-b", ContainerType::Instance);
+
+        Ok(())
+    }
+
+    async fn deploy_db_with_replicated_user(
+        apps: &AppsService,
+        app_name: &str,
+        user: &str,
+    ) -> Result<(), AppsServiceError> {
+        let mut db = sc!("db");
+        db.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_replicated(String::from("USER"), SecUtf8::from(user)),
+        ])));
+
+        apps.create_or_update(
+            &AppName::from_str(app_name).unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[db],
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn replicated_user_of_branch(config: Config) -> Result<String, AppsServiceError> {
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        deploy_db_with_replicated_user(&apps, "master", "admin").await?;
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::From(AppName::master()),
+            &[sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        deploy_db_with_replicated_user(&apps, "master", "root").await?;
+        apps.create_or_update(
+            &AppName::from_str("branch").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::From(AppName::master()),
+            &[sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+        let db = deployed_apps
+            .get(&AppName::from_str("branch").unwrap())
+            .unwrap()
+            .iter()
+            .find(|service| service.service_name() == "db")
+            .unwrap()
+            .clone();
+        Ok(db
+            .config
+            .env()
+            .unwrap()
+            .variable("USER")
+            .unwrap()
+            .value()
+            .unsecure()
+            .to_string())
+    }
+
+    #[tokio::test]
+    async fn should_refresh_replicated_env_from_template_app() -> Result<(), AppsServiceError> {
+        let user = replicated_user_of_branch(Config::default()).await?;
+
+        assert_eq!(user, "root");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_keep_replicated_env_of_running_replica() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            replicatedEnvironmentPrecedence = "app"
+            "#
+        );
+
+        let user = replicated_user_of_branch(config).await?;
+
+        assert_eq!(user, "admin");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_create_app_for_master_with_secrets() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [services.mariadb]
+            [[services.mariadb.secrets]]
+            name = "user"
+            data = "SGVsbG8="
+            appSelector = "master"
+        "#
+        );
+
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("mariadb")],
+            None,
+        )
+        .await?;
+
+        let configs = apps
+            .infrastructure
+            .get_configs_of_app(&AppName::master())
+            .await?;
+        assert_eq!(configs.len(), 1);
+
+        let files = configs.get(0).unwrap().files().unwrap();
+        assert_eq!(
+            files.get(&PathBuf::from("/run/secrets/user")).unwrap(),
+            &SecUtf8::from("Hello")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_create_app_for_master_without_secrets_because_of_none_matching_app_selector(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [services.mariadb]
+            [[services.mariadb.secrets]]
+            name = "user"
+            data = "SGVsbG8="
+            appSelector = "master"
+        "#
+        );
+
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::from_str("master-1x").unwrap(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("mariadb")],
+            None,
+        )
+        .await?;
+
+        let configs = apps
+            .infrastructure
+            .get_configs_of_app(&AppName::from_str("master-1x").unwrap())
+            .await?;
+        assert_eq!(configs.len(), 1);
+
+        let files = configs.get(0).unwrap().files();
+        assert_eq!(files, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_collect_log_chunk_from_infrastructure() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        let log_chunk = apps
+            .get_logs(
+                &app_name,
+                &String::from("service-a"),
+                &None,
+                &Some(100),
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            log_chunk.since(),
+            &DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z").unwrap()
+        );
+
+        assert_eq!(
+            log_chunk.until(),
+            &DateTime::parse_from_rfc3339("2019-07-18T07:35:00.000000000Z").unwrap()
+        );
+
+        assert_eq!(
+            log_chunk.log_lines(),
+            r#"Log msg 1 of service-a of app master
+Log msg 2 of service-a of app master
+Log msg 3 of service-a of app master
+"#
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_filter_log_lines_before_limiting_them() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
+        let app_name = AppName::master();
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let log_chunk = apps
+            .get_logs(
+                &app_name,
+                "service-a",
+                &None,
+                &Some(1),
+                Some(&LogFilter::new(Regex::new("msg [23]").unwrap(), false)),
+            )
+            .await?
+            .unwrap();
+        assert_eq!(
+            log_chunk.log_lines(),
+            "Log msg 2 of service-a of app master\n"
+        );
+
+        let log_chunk = apps
+            .get_logs(
+                &app_name,
+                "service-a",
+                &None,
+                &None,
+                Some(&LogFilter::new(Regex::new("msg [23]").unwrap(), true)),
+            )
+            .await?
+            .unwrap();
+        assert_eq!(
+            log_chunk.log_lines(),
+            "Log msg 1 of service-a of app master\n"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_stream_logs_from_infrastructure() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::from_str("master").unwrap();
+        let services = vec![sc!("service-a"), sc!("service-b")];
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &services,
+            None,
+        )
+        .await?;
+        for service in services {
+            let mut log_stream = apps
+                .stream_logs(&app_name, service.service_name(), &None, &None)
+                .await;
+
+            assert_eq!(
+                log_stream.next().await.unwrap().unwrap(),
+                (
+                    DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z").unwrap(),
+                    format!(
+                        "Log msg 1 of {} of app {app_name}\n",
+                        service.service_name()
+                    )
+                )
+            );
+
+            assert_eq!(
+                log_stream.next().await.unwrap().unwrap(),
+                (
+                    DateTime::parse_from_rfc3339("2019-07-18T07:30:00.000000000Z").unwrap(),
+                    format!(
+                        "Log msg 2 of {} of app {app_name}\n",
+                        service.service_name()
+                    )
+                )
+            );
+
+            assert_eq!(
+                log_stream.next().await.unwrap().unwrap(),
+                (
+                    DateTime::parse_from_rfc3339("2019-07-18T07:35:00.000000000Z").unwrap(),
+                    format!(
+                        "Log msg 3 of {} of app {app_name}\n",
+                        service.service_name()
+                    )
+                )
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_deploy_companions() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'keycloak/keycloak:23.0'
+
+            [companions.db]
+            serviceName = 'db'
+            type = 'service'
+            image = 'postgres:16.1'
+        "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps.get(&app_name).unwrap();
+        assert_eq!(services.len(), 3);
+        assert_contains_service!(services, "openid", ContainerType::ApplicationCompanion);
+        assert_contains_service!(services, "db", ContainerType::ServiceCompanion);
+        assert_contains_service!(services, "service-a", ContainerType::Instance);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_filter_companions_if_services_to_deploy_contain_same_service_name(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'keycloak/keycloak:23.0'
+
+            [companions.db]
+            serviceName = 'db'
+            type = 'service'
+            image = 'postgres:16.1'
+        "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+        let configs = vec![sc!("openid"), sc!("db")];
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &configs,
+            None,
+        )
+        .await?;
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps.get(&app_name).unwrap();
+        assert_eq!(services.len(), 2);
+        assert_contains_service!(services, "openid", ContainerType::Instance);
+        assert_contains_service!(services, "db", ContainerType::Instance);
+
+        let openid_configs: Vec<ServiceConfig> = apps
+            .infrastructure
+            .get_configs_of_app(&app_name)
+            .await?
+            .into_iter()
+            .filter(|config| config.service_name() == "openid")
+            .collect();
+        assert_eq!(openid_configs.len(), 1);
+        assert_eq!(openid_configs[0].image(), configs[0].image());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_merge_with_companion_config_if_services_to_deploy_contain_same_service_name(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'keycloak/keycloak:23.0'
+            env = [ "VAR_1=abcd", "VAR_2=1234" ]
+
+            [companions.openid.labels]
+            'traefik.frontend.rule' = 'PathPrefix:/example.com/openid/;'
+            'traefik.frontend.priority' = '20000'
+        "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+
+        let configs = vec![crate::sc!(
+            "openid",
+            labels = (),
+            env = ("VAR_1" => "efg"),
+            files = ()
+        )];
+
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &configs,
+            None,
+        )
+        .await?;
+
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let services = deployed_apps.get(&app_name).unwrap();
+        assert_eq!(services.len(), 1);
+        assert_contains_service!(services, "openid", ContainerType::Instance);
+
+        let openid_configs: Vec<ServiceConfig> = apps
+            .infrastructure
+            .get_configs_of_app(&app_name)
+            .await?
+            .into_iter()
+            .filter(|config| config.service_name() == "openid")
+            .collect();
+        assert_eq!(openid_configs.len(), 1);
+
+        use secstr::SecUtf8;
+        let openid_env = openid_configs[0].env().unwrap();
+        assert_eq!(
+            openid_env.variable("VAR_1"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_1"),
+                SecUtf8::from("efg")
+            ))
+        );
+        assert_eq!(
+            openid_env.variable("VAR_2"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_2"),
+                SecUtf8::from("1234")
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_include_running_instance_in_templating() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'keycloak/keycloak:23.0'
+            env = [ """SERVICES={{~#each services~}}{{name}},{{~/each~}}""" ]
+        "#
+        );
+
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+        let app_name = AppName::master();
+
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![crate::sc!("service-a")],
+            None,
+        )
+        .await?;
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![crate::sc!("service-b")],
+            None,
+        )
+        .await?;
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![crate::sc!("service-c")],
+            None,
+        )
+        .await?;
+
+        let mut services = apps.infrastructure.fetch_services().await?;
+        let openid_config = services
+            .remove(&AppName::master())
+            .unwrap()
+            .into_iter()
+            .find(|service| service.service_name() == "openid")
+            .map(|service| service.config)
+            .unwrap();
+        let openid_env = openid_config.env().unwrap().get(0).unwrap();
+
+        assert_eq!(
+            openid_env.value().unsecure(),
+            "service-a,service-b,service-c,"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_delete_apps() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+        let deleted_services = apps
+            .delete_app(&app_name, &AppStatusChangeId::new(), false)
+            .await?;
+
+        assert_eq!(
+            deleted_services,
+            vec![Service {
+                id: "service-a".to_string(),
+                config: crate::sc!("service-a"),
+                state: State {
+                    status: ServiceStatus::Running,
+                    started_at: Some(
+                        DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z")
+                            .unwrap()
+                            .with_timezone(&Utc)
+                    ),
+                    replicas: None,
+                }
+            }]
+            .into(),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_delete_protected_apps() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            protected = "master"
+            "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let result = apps
+            .delete_app(&app_name, &AppStatusChangeId::new(), false)
+            .await;
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::AppIsProtected { .. })
+        ));
+
+        let deleted_services = apps
+            .delete_app(&app_name, &AppStatusChangeId::new(), true)
+            .await?;
+        assert_eq!(deleted_services.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_find_missing_protected_app() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            protected = "master"
+            "#
+        );
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let result = apps
+            .delete_app(&AppName::master(), &AppStatusChangeId::new(), false)
+            .await;
+        assert!(matches!(result, Err(AppsServiceError::AppNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_ensure_default_app() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ensureDefaultApp = true
+
+            [[applications.defaultAppServices]]
+            serviceName = 'whoami'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let deployed = apps.ensure_default_app().await?;
+        assert!(matches!(deployed, Some(deployed) if deployed.services.len() == 1));
+
+        let apps_names = apps.fetch_app_names().await?;
+        assert!(apps_names.contains(&AppName::master()));
+
+        let deployed = apps.ensure_default_app().await?;
+        assert!(deployed.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_ensure_protected_default_app() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ensureDefaultApp = true
+            protectDefaultApp = true
+
+            [[applications.defaultAppServices]]
+            serviceName = 'whoami'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let deployed = apps.ensure_default_app().await?;
+        assert!(matches!(deployed, Some(deployed) if deployed.services.len() == 1));
+
+        let result = apps
+            .change_status(&AppName::master(), "whoami", ServiceStatus::Paused, false)
+            .await;
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::DefaultAppIsProtected { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_ensure_default_app_by_default() -> Result<(), AppsServiceError> {
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(Config::default(), infrastructure)?;
+
+        let deployed = apps.ensure_default_app().await?;
+        assert!(deployed.is_none());
+        assert!(apps.fetch_app_names().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_delete_apps_from_parallel_threads_returning_the_same_result(
+    ) -> Result<(), AppsServiceError> {
+        let config = Config::default();
+        let infrastructure = Box::new(Dummy::with_delay(std::time::Duration::from_millis(500)));
+        let apps = Arc::new(AppsService::new(config, infrastructure)?);
+
+        let app_name = AppName::master();
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a")],
+            None,
+        )
+        .await?;
+
+        let apps_clone = apps.clone();
+        let handle1 = std::thread::spawn(move || {
+            let rt = runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap();
+            rt.block_on(apps_clone.delete_app(&app_name, &AppStatusChangeId::new(), false))
+        });
+        let app_name = AppName::master();
+        let handle2 = std::thread::spawn(move || {
+            let rt = runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap();
+            rt.block_on(apps.delete_app(&app_name, &AppStatusChangeId::new(), false))
+        });
+
+        assert_eq!(handle1.join().unwrap()?, handle2.join().unwrap()?,);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_deploy_companions_with_file_mount() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.db1]
+            serviceName = 'db1'
+            type = 'service'
+            image = 'postgres:16.1'
+
+            [companions.db1.volumes]
+            '/etc/mysql1/my.cnf' = 'EFGH'
+
+            [companions.db2]
+            serviceName = 'db2'
+            type = 'service'
+            image = 'postgres:16.1'
+
+            [companions.db2.files]
+            '/etc/mysql2/my.cnf' = 'ABCD'
+        "#
+        );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        let app_name = AppName::master();
+        let configs = vec![sc!("db1"), sc!("db2")];
+        apps.create_or_update(
+            &app_name,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &configs,
+            None,
+        )
+        .await?;
+        let deployed_apps = apps.fetch_apps().await?;
+
+        let db_config1: Vec<ServiceConfig> = apps
+            .infrastructure
+            .get_configs_of_app(&app_name)
+            .await?
+            .into_iter()
+            .filter(|config| config.service_name() == "db1")
+            .collect();
+
+        let db_config2: Vec<ServiceConfig> = apps
+            .infrastructure
+            .get_configs_of_app(&app_name)
+            .await?
+            .into_iter()
+            .filter(|config| config.service_name() == "db2")
+            .collect();
+
+        let services = deployed_apps.get(&app_name).unwrap();
+
+        assert_eq!(services.len(), 2);
+        assert_eq!(
+            db_config1[0]
+                .files()
+                .expect("Empty Map")
+                .get(&PathBuf::from("/etc/mysql1/my.cnf"))
+                .expect("Invalid entry in Map"),
+            &SecUtf8::from("EFGH")
+        );
+
+        assert_eq!(
+            db_config2[0]
+                .files()
+                .expect("Empty Map")
+                .get(&PathBuf::from("/etc/mysql2/my.cnf"))
+                .expect("Invalid entry in Map"),
+            &SecUtf8::from("ABCD")
+        );
 
-        (hook_file, config)
+        Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_app_for_master() -> Result<(), AppsServiceError> {
-        let config = Config::default();
+    async fn should_create_app_with_hooks_applied() -> Result<(), AppsServiceError> {
+        let script = r#"
+        function deploymentHook( appName, configs ) {
+            if( appName === 'master' ) {
+                return configs.filter( service => service.name !== 'service-b' );
+            }
+            return configs;
+        }
+        "#;
+
+        let (_temp_js_file, config) = config_with_deployment_hook(script);
+        let app_name = &AppName::master();
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
         apps.create_or_update(
-            &AppName::master(),
+            &app_name,
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a")],
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
 
-        let deployed_apps = apps.fetch_apps().await?;
-        assert_eq!(deployed_apps.len(), 1);
-        let services = deployed_apps.get(&AppName::master()).unwrap();
-        assert_eq!(services.len(), 1);
-        assert_contains_service!(services, "service-a", ContainerType::Instance);
+        let deployed_services = apps.fetch_apps().await?;
+        let service_names = deployed_services
+            .iter()
+            .flat_map(|(_, services)| services.iter().map(|s| s.service_name().as_str()))
+            .collect::<Vec<&str>>();
+
+        assert_eq!(service_names, vec!["service-a"]);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_replication_from_master() -> Result<(), AppsServiceError> {
-        let config = Config::default();
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+    async fn should_create_app_with_base_ingress_route() -> Result<(), AppsServiceError> {
+        let infrastructure = Box::new(Dummy::with_base_route(TraefikIngressRoute::with_rule(
+            TraefikRouterRule::host_rule(vec![String::from("example.com")]),
+        )));
+        let apps = AppsService::new(Config::default(), infrastructure)?;
 
+        let app_name = &AppName::master();
         apps.create_or_update(
-            &AppName::master(),
+            &app_name,
             &AppStatusChangeId::new(),
-            None,
+            Replication::FromDefault,
             &vec![sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
 
-        apps.create_or_update(
-            &AppName::from_str("branch").unwrap(),
-            &AppStatusChangeId::new(),
-            Some(AppName::master()),
-            &vec![sc!("service-b")],
-            None,
-        )
-        .await?;
-
-        let deployed_apps = apps.fetch_apps().await?;
+        let services = apps
+            .infrastructure
+            .as_any()
+            .downcast_ref::<Dummy>()
+            .unwrap()
+            .services();
 
-        let services = deployed_apps
-            .get(&AppName::from_str("branch").unwrap())
-            .unwrap();
-        assert_eq!(services.len(), 2);
-        assert_contains_service!(services, "service-b", ContainerType::Instance);
-        assert_contains_service!(services, "service-a", ContainerType::Replica);
+        assert!(iters_equal_anyorder(
+            services
+                .iter()
+                .flat_map(|s| s.ingress_route().routes().iter())
+                .map(|route| route.rule()),
+            [
+                TraefikRouterRule::from_str(
+                    "Host(`example.com`) && PathPrefix(`/master/service-b/`)"
+                )
+                .unwrap(),
+                TraefikRouterRule::from_str(
+                    "Host(`example.com`) && PathPrefix(`/master/service-a/`)"
+                )
+                .unwrap()
+            ]
+            .iter()
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_override_replicas_from_master() -> Result<(), AppsServiceError> {
-        let config = Config::default();
+    async fn should_create_app_without_base_ingress_route() -> Result<(), AppsServiceError> {
         let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+        let apps = AppsService::new(Config::default(), infrastructure)?;
 
+        let app_name = &AppName::master();
         apps.create_or_update(
-            &AppName::master(),
+            &app_name,
             &AppStatusChangeId::new(),
-            None,
+            Replication::FromDefault,
             &vec![sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
 
-        apps.create_or_update(
-            &AppName::from_str("branch").unwrap(),
-            &AppStatusChangeId::new(),
-            Some(AppName::master()),
-            &vec![sc!("service-b")],
-            None,
-        )
-        .await?;
-
-        apps.create_or_update(
-            &AppName::from_str("branch").unwrap(),
-            &AppStatusChangeId::new(),
-            Some(AppName::master()),
-            &vec![sc!("service-a")],
-            None,
-        )
-        .await?;
-
-        let deployed_apps = apps.fetch_apps().await?;
+        let services = apps
+            .infrastructure
+            .as_any()
+            .downcast_ref::<Dummy>()
+            .unwrap()
+            .services();
 
-        let services = deployed_apps
-            .get(&AppName::from_str("branch").unwrap())
-            .unwrap();
-        assert_eq!(services.len(), 2);
-        assert_contains_service!(services, "service-a", ContainerType::Instance);
-        assert_contains_service!(services, "service-b", ContainerType::Instance);
+        assert!(iters_equal_anyorder(
+            services
+                .iter()
+                .flat_map(|s| s.ingress_route().routes().iter())
+                .map(|route| route.rule()),
+            [
+                TraefikRouterRule::path_prefix_rule(["/master/service-a"]),
+                TraefikRouterRule::path_prefix_rule(["/master/service-b"])
+            ]
+            .iter()
+        ));
 
         Ok(())
     }
 
+    fn iters_equal_anyorder<T: Eq + Hash>(
+        mut i1: impl Iterator<Item = T>,
+        i2: impl Iterator<Item = T>,
+    ) -> bool {
+        let set: HashSet<T> = i2.collect();
+        i1.all(|x| set.contains(&x))
+    }
+
     #[tokio::test]
-    async fn should_create_app_for_master_with_secrets() -> Result<(), AppsServiceError> {
+    async fn do_not_create_app_when_exceeding_application_number_limit(
+    ) -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [services.mariadb]
-            [[services.mariadb.secrets]]
-            name = "user"
-            data = "SGVsbG8="
-            appSelector = "master"
+            [applications]
+            max = 1
         "#
         );
-
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
         apps.create_or_update(
             &AppName::master(),
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("mariadb")],
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
 
-        let configs = apps
-            .infrastructure
-            .get_configs_of_app(&AppName::master())
+        let result = apps
+            .create_or_update(
+                &AppName::from_str("other").unwrap(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &vec![sc!("service-a"), sc!("service-b")],
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::AppLimitExceeded { limit: 1 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_apply_app_environment_to_replicated_config() {
+        let mut config = sc!("db");
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_replicated(String::from("USER"), SecUtf8::from("admin")),
+            EnvironmentVariable::with_replicated(String::from("PASSWORD"), SecUtf8::from("secret")),
+        ])));
+
+        apply_app_environment(
+            &mut config,
+            &Environment::new(vec![
+                EnvironmentVariable::with_replicated(String::from("USER"), SecUtf8::from("root")),
+                EnvironmentVariable::with_replicated(String::from("OTHER"), SecUtf8::from("value")),
+            ]),
+        );
+
+        let env = config.env().unwrap();
+        assert_eq!(env.iter().count(), 2);
+        assert_eq!(env.variable("USER").unwrap().value().unsecure(), "root");
+        assert_eq!(
+            env.variable("PASSWORD").unwrap().value().unsecure(),
+            "secret"
+        );
+    }
+
+    #[test]
+    fn should_apply_live_environment_to_replicated_config() {
+        let mut config = sc!("db");
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_replicated(String::from("USER"), SecUtf8::from("admin")),
+            EnvironmentVariable::with_templating(
+                String::from("URL"),
+                SecUtf8::from("http://{{application.name}}"),
+            ),
+        ])));
+
+        apply_live_environment(
+            &mut config,
+            &Environment::new(vec![
+                EnvironmentVariable::new(String::from("USER"), SecUtf8::from("root")),
+                EnvironmentVariable::new(String::from("URL"), SecUtf8::from("http://master")),
+                EnvironmentVariable::new(String::from("OTHER"), SecUtf8::from("value")),
+            ]),
+        );
+
+        let env = config.env().unwrap();
+        assert_eq!(env.iter().count(), 2);
+        assert_eq!(env.variable("USER").unwrap().value().unsecure(), "root");
+        assert!(env.variable("USER").unwrap().replicate());
+        assert_eq!(
+            env.variable("URL").unwrap().value().unsecure(),
+            "http://{{application.name}}"
+        );
+    }
+
+    #[tokio::test]
+    async fn record_timings_of_deployment_phases() -> Result<(), AppsServiceError> {
+        let (_hook_file, config) = config_with_failing_post_deploy_hook("warn");
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let deployed = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
             .await?;
-        assert_eq!(configs.len(), 1);
 
-        let files = configs.get(0).unwrap().files().unwrap();
+        let phases = deployed.timings.phases();
         assert_eq!(
-            files.get(&PathBuf::from("/run/secrets/user")).unwrap(),
-            &SecUtf8::from("Hello")
+            phases.iter().map(|(phase, _)| *phase).collect::<Vec<_>>(),
+            vec!["resolve-images", "deploy", "post-deploy-hook"]
+        );
+        assert!(
+            deployed.timings.total()
+                >= phases
+                    .iter()
+                    .map(|(_, duration)| *duration)
+                    .sum::<Duration>()
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_app_for_master_without_secrets_because_of_none_matching_app_selector(
-    ) -> Result<(), AppsServiceError> {
+    async fn do_not_create_app_with_unknown_runtime() -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [services.mariadb]
-            [[services.mariadb.secrets]]
-            name = "user"
-            data = "SGVsbG8="
-            appSelector = "master"
-        "#
+            [runtimes.cluster]
+            type = 'Kubernetes'
+            "#
         );
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let mut service_config = sc!("service-a");
+        service_config.set_runtime(Some(String::from("other-cluster")));
+
+        let result = apps.validate_service_configs(&[service_config]);
 
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::UnknownRuntime { runtime }) if runtime == "other-cluster"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn do_not_create_app_with_exposed_ports_if_not_allowed() -> Result<(), AppsServiceError> {
+        let config = config_from_str!("");
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
-        apps.create_or_update(
-            &AppName::from_str("master-1x").unwrap(),
-            &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("mariadb")],
-            None,
-        )
-        .await?;
+        let mut service_config = sc!("service-a");
+        service_config.set_expose_port(true);
 
-        let configs = apps
-            .infrastructure
-            .get_configs_of_app(&AppName::from_str("master-1x").unwrap())
-            .await?;
-        assert_eq!(configs.len(), 1);
+        let result = apps.validate_service_configs(&[service_config, sc!("service-b")]);
 
-        let files = configs.get(0).unwrap().files();
-        assert_eq!(files, None);
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::ExposedPortsNotAllowed { service_names })
+                if service_names == vec![String::from("service-a")]
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_collect_log_chunk_from_infrastructure() -> Result<(), AppsServiceError> {
-        let config = Config::default();
+    async fn warn_about_services_with_unknown_port() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [[images.defaults]]
+            selector = 'sha256:.+'
+            port = 8080
+        "#
+        );
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
-        let app_name = AppName::master();
-
-        apps.create_or_update(
-            &app_name,
-            &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
-            None,
-        )
-        .await?;
-
-        let log_chunk = apps
-            .get_logs(&app_name, &String::from("service-a"), &None, &Some(100))
-            .await
-            .unwrap()
-            .unwrap();
+        let deployed = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await?;
+        assert!(deployed.warnings.is_empty());
 
+        let apps = AppsService::new(config_from_str!(""), Box::new(Dummy::new()))?;
+        let deployed = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await?;
         assert_eq!(
-            log_chunk.since(),
-            &DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z").unwrap()
+            deployed.warnings,
+            vec![DeploymentWarning::UnknownPort {
+                service_name: String::from("service-a")
+            }]
         );
 
-        assert_eq!(
-            log_chunk.until(),
-            &DateTime::parse_from_rfc3339("2019-07-18T07:35:00.000000000Z").unwrap()
-        );
+        Ok(())
+    }
 
-        assert_eq!(
-            log_chunk.log_lines(),
-            r#"Log msg 1 of service-a of app master
-Log msg 2 of service-a of app master
-Log msg 3 of service-a of app master
-"#
-        );
+    fn config_with_failing_post_deploy_hook(failure_strategy: &str) -> (NamedTempFile, Config) {
+        let mut hook_file = NamedTempFile::new().unwrap();
+        hook_file
+            .write_all(
+                br#"
+                function postDeployHook( app ) {
+                    return { passed: false, message: 'smoke test failed' };
+                }
+                "#,
+            )
+            .unwrap();
 
-        Ok(())
+        let config = config_from_str!(&format!(
+            r#"
+            [applications]
+            postDeployHookFailure = '{failure_strategy}'
+
+            [hooks]
+            postDeploy = {:?}
+            "#,
+            hook_file.path()
+        ));
+
+        (hook_file, config)
     }
 
     #[tokio::test]
-    async fn should_stream_logs_from_infrastructure() -> Result<(), AppsServiceError> {
-        let config = Config::default();
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+    async fn warn_about_failed_post_deploy_hook() -> Result<(), AppsServiceError> {
+        let (_hook_file, config) = config_with_failing_post_deploy_hook("warn");
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
 
-        let app_name = AppName::from_str("master").unwrap();
-        let services = vec![sc!("service-a"), sc!("service-b")];
-        apps.create_or_update(&app_name, &AppStatusChangeId::new(), None, &services, None)
+        let deployed = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
             .await?;
-        for service in services {
-            let mut log_stream = apps
-                .stream_logs(&app_name, service.service_name(), &None, &None)
-                .await;
 
-            assert_eq!(
-                log_stream.next().await.unwrap().unwrap(),
-                (
-                    DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z").unwrap(),
-                    format!(
-                        "Log msg 1 of {} of app {app_name}\n",
-                        service.service_name()
-                    )
-                )
-            );
+        assert!(deployed
+            .warnings
+            .contains(&DeploymentWarning::FailedPostDeployHook {
+                message: String::from("smoke test failed")
+            }));
 
-            assert_eq!(
-                log_stream.next().await.unwrap().unwrap(),
-                (
-                    DateTime::parse_from_rfc3339("2019-07-18T07:30:00.000000000Z").unwrap(),
-                    format!(
-                        "Log msg 2 of {} of app {app_name}\n",
-                        service.service_name()
-                    )
-                )
-            );
+        Ok(())
+    }
 
-            assert_eq!(
-                log_stream.next().await.unwrap().unwrap(),
-                (
-                    DateTime::parse_from_rfc3339("2019-07-18T07:35:00.000000000Z").unwrap(),
-                    format!(
-                        "Log msg 3 of {} of app {app_name}\n",
-                        service.service_name()
-                    )
-                )
-            );
-        }
+    #[tokio::test]
+    async fn fail_deployment_with_failed_post_deploy_hook_if_configured(
+    ) -> Result<(), AppsServiceError> {
+        let (_hook_file, config) = config_with_failing_post_deploy_hook("fail");
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let result = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::PostDeployHookFailed { message, .. }) if message == "smoke test failed"
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_deploy_companions() -> Result<(), AppsServiceError> {
+    async fn do_not_create_app_with_unknown_port_if_configured() -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [companions.openid]
-            serviceName = 'openid'
-            type = 'application'
-            image = 'keycloak/keycloak:23.0'
-
-            [companions.db]
-            serviceName = 'db'
-            type = 'service'
-            image = 'postgres:16.1'
+            [images]
+            unknownPort = 'fail'
         "#
         );
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
-        let app_name = AppName::master();
-        apps.create_or_update(
-            &app_name,
-            &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a")],
-            None,
-        )
-        .await?;
-        let deployed_apps = apps.fetch_apps().await?;
+        let result = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await;
 
-        let services = deployed_apps.get(&app_name).unwrap();
-        assert_eq!(services.len(), 3);
-        assert_contains_service!(services, "openid", ContainerType::ApplicationCompanion);
-        assert_contains_service!(services, "db", ContainerType::ServiceCompanion);
-        assert_contains_service!(services, "service-a", ContainerType::Instance);
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::UnknownServicePorts { service_names })
+                if service_names == vec![String::from("service-a")]
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_filter_companions_if_services_to_deploy_contain_same_service_name(
-    ) -> Result<(), AppsServiceError> {
+    async fn do_not_create_app_with_denied_env_overrides() -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [companions.openid]
-            serviceName = 'openid'
-            type = 'application'
-            image = 'keycloak/keycloak:23.0'
-
-            [companions.db]
-            serviceName = 'db'
-            type = 'service'
-            image = 'postgres:16.1'
+            [applications]
+            deniedEnvOverrides = [ "DATABASE_URL" ]
         "#
         );
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
-        let app_name = AppName::master();
-        let configs = vec![sc!("openid"), sc!("db")];
-        apps.create_or_update(&app_name, &AppStatusChangeId::new(), None, &configs, None)
-            .await?;
-        let deployed_apps = apps.fetch_apps().await?;
+        let mut service_config = sc!("service-a");
+        service_config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::new(String::from("LOG_LEVEL"), SecUtf8::from("debug")),
+            EnvironmentVariable::new(String::from("DATABASE_URL"), SecUtf8::from("postgres://db")),
+        ])));
 
-        let services = deployed_apps.get(&app_name).unwrap();
-        assert_eq!(services.len(), 2);
-        assert_contains_service!(services, "openid", ContainerType::Instance);
-        assert_contains_service!(services, "db", ContainerType::Instance);
+        let result = apps.validate_service_configs(&[service_config]);
 
-        let openid_configs: Vec<ServiceConfig> = apps
-            .infrastructure
-            .get_configs_of_app(&app_name)
-            .await?
-            .into_iter()
-            .filter(|config| config.service_name() == "openid")
-            .collect();
-        assert_eq!(openid_configs.len(), 1);
-        assert_eq!(openid_configs[0].image(), configs[0].image());
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::EnvOverridesNotAllowed { service_name, env_names })
+                if service_name == "service-a" && env_names == vec![String::from("DATABASE_URL")]
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_merge_with_companion_config_if_services_to_deploy_contain_same_service_name(
-    ) -> Result<(), AppsServiceError> {
+    async fn create_app_with_exposed_ports_if_allowed() -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [companions.openid]
-            serviceName = 'openid'
-            type = 'application'
-            image = 'keycloak/keycloak:23.0'
-            env = [ "VAR_1=abcd", "VAR_2=1234" ]
-
-            [companions.openid.labels]
-            'traefik.frontend.rule' = 'PathPrefix:/example.com/openid/;'
-            'traefik.frontend.priority' = '20000'
+            [applications]
+            allowExposedPorts = true
         "#
         );
         let infrastructure = Box::new(Dummy::new());
         let apps = AppsService::new(config, infrastructure)?;
 
-        let app_name = AppName::master();
-
-        let configs = vec![crate::sc!(
-            "openid",
-            labels = (),
-            env = ("VAR_1" => "efg"),
-            files = ()
-        )];
+        let mut service_config = sc!("service-a");
+        service_config.set_expose_port(true);
 
-        apps.create_or_update(&app_name, &AppStatusChangeId::new(), None, &configs, None)
+        let deployed_services = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[service_config],
+                None,
+            )
             .await?;
 
-        let deployed_apps = apps.fetch_apps().await?;
-
-        let services = deployed_apps.get(&app_name).unwrap();
-        assert_eq!(services.len(), 1);
-        assert_contains_service!(services, "openid", ContainerType::Instance);
+        assert_eq!(deployed_services.services.len(), 1);
 
-        let openid_configs: Vec<ServiceConfig> = apps
-            .infrastructure
-            .get_configs_of_app(&app_name)
-            .await?
-            .into_iter()
-            .filter(|config| config.service_name() == "openid")
-            .collect();
-        assert_eq!(openid_configs.len(), 1);
+        Ok(())
+    }
 
-        use secstr::SecUtf8;
-        let openid_env = openid_configs[0].env().unwrap();
-        assert_eq!(
-            openid_env.variable("VAR_1"),
-            Some(&EnvironmentVariable::new(
-                String::from("VAR_1"),
-                SecUtf8::from("efg")
-            ))
-        );
-        assert_eq!(
-            openid_env.variable("VAR_2"),
-            Some(&EnvironmentVariable::new(
-                String::from("VAR_2"),
-                SecUtf8::from("1234")
-            ))
+    #[tokio::test]
+    async fn do_update_app_when_exceeding_application_number_limit() -> Result<(), AppsServiceError>
+    {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            max = 1
+        "#
         );
+        let infrastructure = Box::new(Dummy::new());
+        let apps = AppsService::new(config, infrastructure)?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &vec![sc!("service-a"), sc!("service-b")],
+            None,
+        )
+        .await?;
+
+        let result = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &vec![sc!("service-c")],
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Ok(deployed) if deployed.services.len() == 3
+        ));
 
         Ok(())
     }
 
-    #[tokio::test]
-    async fn should_include_running_instance_in_templating() -> Result<(), AppsServiceError> {
-        let config = config_from_str!(
+    fn config_with_companions(openid_version: &str) -> Config {
+        config_from_str!(&format!(
             r#"
             [companions.openid]
             serviceName = 'openid'
             type = 'application'
-            image = 'keycloak/keycloak:23.0'
-            env = [ """SERVICES={{~#each services~}}{{name}},{{~/each~}}""" ]
-        "#
-        );
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            env = {{ VERSION = '{openid_version}' }}
 
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
-        let app_name = AppName::master();
+            [companions.db]
+            serviceName = 'db'
+            type = 'service'
+            image = 'sha256:6e2b2a5ee1ae2b8ea6f6a5bbe46d80d6a6f6ba3d1c10a1e4c16b3b1a1f1ac3e4'
+            "#
+        ))
+    }
+
+    #[tokio::test]
+    async fn should_reconcile_only_changed_companions() -> Result<(), AppsServiceError> {
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(
+            config_with_companions("1"),
+            Box::new(infrastructure.clone()),
+        )?;
 
         apps.create_or_update(
-            &app_name,
+            &AppName::master(),
             &AppStatusChangeId::new(),
-            None,
-            &vec![crate::sc!("service-a")],
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
+
+        let reconciled = apps
+            .reconcile(&AppName::master(), &AppStatusChangeId::new(), false)
+            .await?;
+        assert!(reconciled.is_empty());
+
+        let apps = AppsService::new(config_with_companions("2"), Box::new(infrastructure))?;
+        let reconciled = apps
+            .reconcile(&AppName::master(), &AppStatusChangeId::new(), false)
+            .await?;
+
+        assert_eq!(reconciled.len(), 1);
+        assert_contains_service!(reconciled, "openid", ContainerType::ApplicationCompanion);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_keep_creation_time_of_services_across_deployments(
+    ) -> Result<(), AppsServiceError> {
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(Config::default(), Box::new(infrastructure.clone()))?;
+        let app_name = AppName::from_str("master").unwrap();
+
         apps.create_or_update(
             &app_name,
             &AppStatusChangeId::new(),
-            None,
-            &vec![crate::sc!("service-b")],
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
+        let first_deployment = infrastructure
+            .fetch_services()
+            .await?
+            .remove(&app_name)
+            .unwrap();
+        let service_a = first_deployment.iter().next().unwrap();
+        assert_eq!(
+            service_a.config.created_at(),
+            service_a.config.deployed_at()
+        );
+
         apps.create_or_update(
             &app_name,
             &AppStatusChangeId::new(),
-            None,
-            &vec![crate::sc!("service-c")],
+            Replication::FromDefault,
+            &[sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
-
-        let mut services = apps.infrastructure.fetch_services().await?;
-        let openid_config = services
-            .remove(&AppName::master())
-            .unwrap()
-            .into_iter()
-            .find(|service| service.service_name() == "openid")
-            .map(|service| service.config)
+        let second_deployment = infrastructure
+            .fetch_services()
+            .await?
+            .remove(&app_name)
             .unwrap();
-        let openid_env = openid_config.env().unwrap().get(0).unwrap();
 
-        assert_eq!(
-            openid_env.value().unsecure(),
-            "service-a,service-b,service-c,"
-        );
+        for service in second_deployment.iter() {
+            match service.service_name().as_str() {
+                "service-a" => {
+                    assert_eq!(service.config.created_at(), service_a.config.created_at());
+                    assert!(service.config.deployed_at() >= service_a.config.deployed_at());
+                }
+                "service-b" => {
+                    assert!(service.config.created_at().is_some());
+                    assert_eq!(service.config.created_at(), service.config.deployed_at());
+                }
+                name => panic!("unexpected service {}", name),
+            }
+        }
 
         Ok(())
     }
 
+    fn config_with_user_defined_companion() -> Config {
+        config_from_str!(
+            r#"
+            [companions.templating.userDefinedSchema]
+            type = "object"
+            properties = { user = { type = "string" } }
+
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+
+            [companions.openid.env]
+            USER = { value = "{{userDefined.user}}", templated = true }
+            "#
+        )
+    }
+
     #[tokio::test]
-    async fn should_delete_apps() -> Result<(), AppsServiceError> {
-        let config = Config::default();
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+    async fn should_reconcile_with_user_defined_parameters_of_deployment(
+    ) -> Result<(), AppsServiceError> {
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(
+            config_with_user_defined_companion(),
+            Box::new(infrastructure.clone()),
+        )?;
 
-        let app_name = AppName::master();
         apps.create_or_update(
-            &app_name,
+            &AppName::master(),
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a")],
-            None,
+            Replication::FromDefault,
+            &[sc!("service-a")],
+            Some(serde_json::json!({ "user": "alice" })),
         )
         .await?;
-        let deleted_services = apps
-            .delete_app(&app_name, &AppStatusChangeId::new())
+
+        let reconciled = apps
+            .reconcile(&AppName::master(), &AppStatusChangeId::new(), false)
             .await?;
+        assert!(reconciled.is_empty());
 
+        let apps = apps.fetch_apps().await?;
+        let openid = apps[&AppName::master()]
+            .iter()
+            .find(|service| service.service_name() == "openid")
+            .unwrap();
         assert_eq!(
-            deleted_services,
-            vec![Service {
-                id: "service-a".to_string(),
-                config: crate::sc!("service-a"),
-                state: State {
-                    status: ServiceStatus::Running,
-                    started_at: Some(
-                        DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z")
-                            .unwrap()
-                            .with_timezone(&Utc)
-                    ),
-                }
-            }]
-            .into(),
+            openid.config.user_defined_parameters(),
+            Some(&serde_json::json!({ "user": "alice" }))
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_delete_apps_from_parallel_threads_returning_the_same_result(
+    async fn should_not_reconcile_without_stored_user_defined_parameters(
     ) -> Result<(), AppsServiceError> {
-        let config = Config::default();
-        let infrastructure = Box::new(Dummy::with_delay(std::time::Duration::from_millis(500)));
-        let apps = Arc::new(AppsService::new(config, infrastructure)?);
-
-        let app_name = AppName::master();
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(Config::default(), Box::new(infrastructure.clone()))?;
         apps.create_or_update(
-            &app_name,
+            &AppName::master(),
             &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
-            &vec![sc!("service-a")],
+        )
+        .await?;
+
+        let apps = AppsService::new(
+            config_with_user_defined_companion(),
+            Box::new(infrastructure),
+        )?;
+        let err = apps
+            .reconcile(&AppName::master(), &AppStatusChangeId::new(), false)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            AppsServiceError::UnknownUserDefinedParameters { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_start_companions_after_their_dependencies() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.migration]
+            serviceName = 'migration'
+            type = 'application'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            dependsOn = [ 'db' ]
+
+            [companions.db]
+            serviceName = 'db'
+            type = 'application'
+            image = 'sha256:6e2b2a5ee1ae2b8ea6f6a5bbe46d80d6a6f6ba3d1c10a1e4c16b3b1a1f1ac3e4'
+            "#
+        );
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(config, Box::new(infrastructure.clone()))?;
+
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
 
-        let apps_clone = apps.clone();
-        let handle1 = std::thread::spawn(move || {
-            let rt = runtime::Builder::new_current_thread()
-                .enable_time()
-                .build()
-                .unwrap();
-            rt.block_on(apps_clone.delete_app(&app_name, &AppStatusChangeId::new()))
-        });
-        let app_name = AppName::master();
-        let handle2 = std::thread::spawn(move || {
-            let rt = runtime::Builder::new_current_thread()
-                .enable_time()
-                .build()
-                .unwrap();
-            rt.block_on(apps.delete_app(&app_name, &AppStatusChangeId::new()))
-        });
+        let started_services = infrastructure.started_services();
+        let position = |service_name: &str| {
+            started_services
+                .iter()
+                .position(|started| started == service_name)
+                .unwrap()
+        };
+        assert_eq!(started_services.len(), 3);
+        assert!(position("db") < position("migration"));
 
-        assert_eq!(handle1.join().unwrap()?, handle2.join().unwrap()?,);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_services_if_deployment_fails() -> Result<(), AppsServiceError> {
+        let infrastructure = Dummy::with_failing_service(3);
+        let apps = AppsService::new(Config::default(), Box::new(infrastructure.clone()))?;
+
+        let err = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b"), sc!("service-c")],
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string().ends_with("have been rolled back."),
+            "{}",
+            err
+        );
+        assert!(infrastructure.services().is_empty());
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_deploy_companions_with_file_mount() -> Result<(), AppsServiceError> {
+    async fn should_not_roll_back_existing_services_if_update_fails() -> Result<(), AppsServiceError>
+    {
         let config = config_from_str!(
             r#"
-            [companions.db1]
-            serviceName = 'db1'
+            [companions.db]
+            serviceName = 'db'
             type = 'service'
-            image = 'postgres:16.1'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            deploymentStrategy = 'redeploy-never'
+            "#
+        );
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(config.clone(), Box::new(infrastructure.clone()))?;
+        apps.create_or_update(
+            &AppName::master(),
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a")],
+            None,
+        )
+        .await?;
 
-            [companions.db1.volumes]
-            '/etc/mysql1/my.cnf' = 'EFGH'
+        let apps = AppsService::new(config, Box::new(infrastructure.fail_service(3)))?;
+        let err = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-b"), sc!("service-c")],
+                None,
+            )
+            .await
+            .unwrap_err();
 
-            [companions.db2]
-            serviceName = 'db2'
-            type = 'service'
-            image = 'postgres:16.1'
+        assert!(
+            err.to_string().ends_with("have been rolled back."),
+            "{}",
+            err
+        );
+        let mut service_names = infrastructure
+            .services()
+            .iter()
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        service_names.sort();
+        assert_eq!(service_names, vec!["db", "service-a"]);
 
-            [companions.db2.files]
-            '/etc/mysql2/my.cnf' = 'ABCD'
-        "#
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_keep_started_services_if_rollback_is_disabled() -> Result<(), AppsServiceError>
+    {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            rollbackOnFailure = false
+            "#
+        );
+        let infrastructure = Dummy::with_failing_service(3);
+        let apps = AppsService::new(config, Box::new(infrastructure.clone()))?;
+
+        let result = apps
+            .create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b"), sc!("service-c")],
+                None,
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(infrastructure.services().len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn do_not_reconcile_unknown_app() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
+
+        let result = apps
+            .reconcile(&AppName::master(), &AppStatusChangeId::new(), false)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::AppNotFound { app_name }) if app_name == AppName::master()
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_delete_expired_apps() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ttl = "1h"
+            "#
         );
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
 
-        let app_name = AppName::master();
-        let configs = vec![sc!("db1"), sc!("db2")];
-        apps.create_or_update(&app_name, &AppStatusChangeId::new(), None, &configs, None)
+        let feature = AppName::from_str("feature-xxx").unwrap();
+        for app_name in [AppName::master(), feature.clone()] {
+            apps.create_or_update(
+                &app_name,
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
             .await?;
-        let deployed_apps = apps.fetch_apps().await?;
-
-        let db_config1: Vec<ServiceConfig> = apps
-            .infrastructure
-            .get_configs_of_app(&app_name)
-            .await?
-            .into_iter()
-            .filter(|config| config.service_name() == "db1")
-            .collect();
-
-        let db_config2: Vec<ServiceConfig> = apps
-            .infrastructure
-            .get_configs_of_app(&app_name)
-            .await?
-            .into_iter()
-            .filter(|config| config.service_name() == "db2")
-            .collect();
+        }
 
-        let services = deployed_apps.get(&app_name).unwrap();
+        let deleted_apps = apps.delete_apps_expired_at(Utc::now()).await?;
+        assert!(deleted_apps.is_empty());
 
-        assert_eq!(services.len(), 2);
+        let deleted_apps = apps
+            .delete_apps_expired_at(Utc::now() + chrono::Duration::hours(2))
+            .await?;
+        assert_eq!(deleted_apps, vec![feature]);
         assert_eq!(
-            db_config1[0]
-                .files()
-                .expect("Empty Map")
-                .get(&PathBuf::from("/etc/mysql1/my.cnf"))
-                .expect("Invalid entry in Map"),
-            &SecUtf8::from("EFGH")
+            apps.fetch_app_names().await?,
+            HashSet::from([AppName::master()])
         );
 
-        assert_eq!(
-            db_config2[0]
-                .files()
-                .expect("Empty Map")
-                .get(&PathBuf::from("/etc/mysql2/my.cnf"))
-                .expect("Invalid entry in Map"),
-            &SecUtf8::from("ABCD")
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_delete_expired_pinned_apps() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ttl = "1h"
+            "#
         );
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let feature = AppName::from_str("feature-xxx").unwrap();
+        apps.create_or_update(
+            &feature,
+            &AppStatusChangeId::new(),
+            Replication::FromDefault,
+            &[sc!("service-a")],
+            None,
+        )
+        .await?;
+        apps.set_pinned(&feature, true, false).await?;
+
+        let deleted_apps = apps
+            .delete_apps_expired_at(Utc::now() + chrono::Duration::hours(2))
+            .await?;
+        assert!(deleted_apps.is_empty());
+
+        apps.set_pinned(&feature, false, false).await?;
+        let deleted_apps = apps
+            .delete_apps_expired_at(Utc::now() + chrono::Duration::hours(2))
+            .await?;
+        assert_eq!(deleted_apps, vec![feature]);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_app_with_hooks_applied() -> Result<(), AppsServiceError> {
-        let script = r#"
-        function deploymentHook( appName, configs ) {
-            if( appName === 'master' ) {
-                return configs.filter( service => service.name !== 'service-b' );
-            }
-            return configs;
-        }
-        "#;
-
-        let (_temp_js_file, config) = config_with_deployment_hook(script);
-        let app_name = &AppName::master();
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
+    async fn should_pin_and_unpin_app() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
 
+        let feature = AppName::from_str("feature-xxx").unwrap();
         apps.create_or_update(
-            &app_name,
+            &feature,
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
+        assert!(!apps.is_pinned(&feature).await?);
 
-        let deployed_services = apps.fetch_apps().await?;
-        let service_names = deployed_services
-            .iter()
-            .flat_map(|(_, services)| services.iter().map(|s| s.service_name().as_str()))
-            .collect::<Vec<&str>>();
+        apps.set_pinned(&feature, true, false).await?;
+        assert!(apps.is_pinned(&feature).await?);
 
-        assert_eq!(service_names, vec!["service-a"]);
+        apps.set_pinned(&feature, false, false).await?;
+        assert!(!apps.is_pinned(&feature).await?);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_app_with_base_ingress_route() -> Result<(), AppsServiceError> {
-        let infrastructure = Box::new(Dummy::with_base_route(TraefikIngressRoute::with_rule(
-            TraefikRouterRule::host_rule(vec![String::from("example.com")]),
-        )));
-        let apps = AppsService::new(Config::default(), infrastructure)?;
+    async fn should_remove_pin_with_app() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
 
-        let app_name = &AppName::master();
+        let feature = AppName::from_str("feature-xxx").unwrap();
         apps.create_or_update(
-            &app_name,
+            &feature,
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
+        apps.set_pinned(&feature, true, false).await?;
 
-        let services = apps
-            .infrastructure
-            .as_any()
-            .downcast_ref::<Dummy>()
-            .unwrap()
-            .services();
+        apps.delete_app(&feature, &AppStatusChangeId::new(), false)
+            .await?;
 
-        assert!(iters_equal_anyorder(
-            services
-                .iter()
-                .flat_map(|s| s.ingress_route().routes().iter())
-                .map(|route| route.rule()),
-            [
-                TraefikRouterRule::from_str(
-                    "Host(`example.com`) && PathPrefix(`/master/service-b/`)"
-                )
-                .unwrap(),
-                TraefikRouterRule::from_str(
-                    "Host(`example.com`) && PathPrefix(`/master/service-a/`)"
-                )
-                .unwrap()
-            ]
-            .iter()
+        assert!(!apps.is_pinned(&feature).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn do_not_pin_unknown_app() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
+
+        let result = apps.set_pinned(&AppName::master(), true, false).await;
+
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::AppNotFound { app_name }) if app_name == AppName::master()
         ));
+        assert!(!apps.is_pinned(&AppName::master()).await?);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_app_without_base_ingress_route() -> Result<(), AppsServiceError> {
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(Config::default(), infrastructure)?;
+    async fn should_not_delete_apps_without_ttl() -> Result<(), AppsServiceError> {
+        let apps = AppsService::new(Config::default(), Box::new(Dummy::new()))?;
 
-        let app_name = &AppName::master();
+        let feature = AppName::from_str("feature-xxx").unwrap();
         apps.create_or_update(
-            &app_name,
+            &feature,
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
+            Replication::FromDefault,
+            &[sc!("service-a")],
             None,
         )
         .await?;
 
-        let services = apps
-            .infrastructure
-            .as_any()
-            .downcast_ref::<Dummy>()
-            .unwrap()
-            .services();
-
-        assert!(iters_equal_anyorder(
-            services
-                .iter()
-                .flat_map(|s| s.ingress_route().routes().iter())
-                .map(|route| route.rule()),
-            [
-                TraefikRouterRule::path_prefix_rule(["/master/service-a"]),
-                TraefikRouterRule::path_prefix_rule(["/master/service-b"])
-            ]
-            .iter()
-        ));
+        let deleted_apps = apps
+            .delete_apps_expired_at(Utc::now() + chrono::Duration::days(365))
+            .await?;
+        assert!(deleted_apps.is_empty());
 
         Ok(())
     }
 
-    fn iters_equal_anyorder<T: Eq + Hash>(
-        mut i1: impl Iterator<Item = T>,
-        i2: impl Iterator<Item = T>,
-    ) -> bool {
-        let set: HashSet<T> = i2.collect();
-        i1.all(|x| set.contains(&x))
+    #[tokio::test]
+    async fn should_notify_webhooks_about_lifecycle_events() -> Result<(), AppsServiceError> {
+        let webhook = notifications::mock_webhook(Vec::new()).await;
+        let config = config_from_str!(&format!(
+            r#"
+            [[notifications.webhooks]]
+            url = '{}'
+            "#,
+            webhook.uri()
+        ));
+        let apps = AppsService::new(config, Box::new(Dummy::new()))?;
+
+        let app_name = AppName::from_str("feature-xxx").unwrap();
+        for _ in 0..2 {
+            apps.create_or_update(
+                &app_name,
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await?;
+        }
+        apps.delete_app(&app_name, &AppStatusChangeId::new(), false)
+            .await?;
+
+        // the notifications are delivered in the background, thus, their order is not guaranteed
+        let mut events = Vec::new();
+        for notification in notifications::received_notifications(&webhook, 3).await {
+            assert_eq!(notification["appName"], "feature-xxx");
+            assert_eq!(notification["services"][0]["name"], "service-a");
+            events.push(notification["event"].as_str().unwrap().to_string());
+        }
+        events.sort();
+        assert_eq!(events, vec!["created", "deleted", "updated"]);
+
+        Ok(())
     }
 
     #[tokio::test]
-    async fn do_not_create_app_when_exceeding_application_number_limit(
+    async fn should_update_images_of_running_services_except_never_redeployed(
     ) -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [applications]
-            max = 1
-        "#
+            [companions.db]
+            serviceName = 'db'
+            type = 'service'
+            image = 'sha256:9b2a28eb47540823042a2ba401386845089bb7b62a9637d55816132c4c3c36eb'
+            deploymentStrategy = 'redeploy-never'
+            "#
         );
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
-
+        let infrastructure = Dummy::new();
+        let apps = AppsService::new(config.clone(), Box::new(infrastructure.clone()))?;
+        let app_name = AppName::master();
         apps.create_or_update(
-            &AppName::master(),
+            &app_name,
             &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
+            Replication::FromDefault,
+            &[sc!("service-a"), sc!("service-b")],
             None,
         )
         .await?;
 
-        let result = apps
-            .create_or_update(
-                &AppName::from_str("other").unwrap(),
-                &AppStatusChangeId::new(),
+        let (running_services, configs) =
+            apps.fetch_running_services_and_configs(&app_name).await?;
+        let (deployment_unit, _) = apps
+            .build_deployment_unit(
+                &app_name,
+                configs,
+                Vec::new(),
                 None,
-                &vec![sc!("service-a"), sc!("service-b")],
+                &mut DeploymentTimings::default(),
+            )
+            .await?;
+
+        let image_digests = running_services
+            .iter()
+            .filter(|service| service.service_name() != "service-b")
+            .map(|service| (service.config.image().clone(), String::from("sha256:new")))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            image_digests_of_running_services(&deployment_unit, &running_services, &image_digests),
+            HashMap::from([(String::from("service-a"), String::from("sha256:new"))])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_reject_parallel_deployment_of_same_app() -> Result<(), AppsServiceError> {
+        let infrastructure = Dummy::with_delay(std::time::Duration::from_millis(200));
+        let apps = AppsService::new(Config::default(), Box::new(infrastructure))?;
+        let app_name = AppName::master();
+        let (status_id_a, status_id_b) = (AppStatusChangeId::new(), AppStatusChangeId::new());
+        let (configs_a, configs_b) = ([sc!("service-a")], [sc!("service-b")]);
+
+        let (first, second) = tokio::join!(
+            apps.create_or_update(
+                &app_name,
+                &status_id_a,
+                Replication::FromDefault,
+                &configs_a,
+                None,
+            ),
+            apps.create_or_update(
+                &app_name,
+                &status_id_b,
+                Replication::FromDefault,
+                &configs_b,
                 None,
             )
-            .await;
+        );
 
+        assert!(first.is_ok());
         assert!(matches!(
-            result,
-            Err(AppsServiceError::AppLimitExceeded { limit: 1 })
+            second,
+            Err(AppsServiceError::AppIsInDeployment { .. })
         ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn do_update_app_when_exceeding_application_number_limit() -> Result<(), AppsServiceError>
-    {
+    async fn should_wait_for_parallel_deployment_of_same_app() -> Result<(), AppsServiceError> {
         let config = config_from_str!(
             r#"
-            [applications]
-            max = 1
-        "#
+            [runtime]
+            type = 'Docker'
+
+            [runtime.operationLock]
+            strategy = 'wait'
+            ttl = '30m'
+            "#
+        );
+        let infrastructure = Dummy::with_delay(std::time::Duration::from_millis(200));
+        let apps = AppsService::new(config, Box::new(infrastructure.clone()))?;
+        let app_name = AppName::master();
+        let (status_id_a, status_id_b) = (AppStatusChangeId::new(), AppStatusChangeId::new());
+        let (configs_a, configs_b) = ([sc!("service-a")], [sc!("service-b")]);
+
+        let (first, second) = tokio::join!(
+            apps.create_or_update(
+                &app_name,
+                &status_id_a,
+                Replication::FromDefault,
+                &configs_a,
+                None,
+            ),
+            apps.create_or_update(
+                &app_name,
+                &status_id_b,
+                Replication::FromDefault,
+                &configs_b,
+                None,
+            )
         );
-        let infrastructure = Box::new(Dummy::new());
-        let apps = AppsService::new(config, infrastructure)?;
 
-        apps.create_or_update(
-            &AppName::master(),
-            &AppStatusChangeId::new(),
-            None,
-            &vec![sc!("service-a"), sc!("service-b")],
-            None,
-        )
-        .await?;
+        first?;
+        second?;
+        let mut service_names = infrastructure
+            .services()
+            .iter()
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        service_names.sort();
+        assert_eq!(service_names, vec!["service-a", "service-b"]);
 
-        let result = apps
-            .create_or_update(
-                &AppName::master(),
-                &AppStatusChangeId::new(),
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_report_progress_of_deployment_waiting_for_app_guard(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [runtime]
+            type = 'Docker'
+
+            [runtime.operationLock]
+            strategy = 'wait'
+            ttl = '30m'
+            "#
+        );
+        let infrastructure = Dummy::with_delay(std::time::Duration::from_millis(200));
+        let apps = Arc::new(AppsService::new(config, Box::new(infrastructure))?);
+        let app_name = AppName::master();
+        let (status_id_a, status_id_b) = (AppStatusChangeId::new(), AppStatusChangeId::new());
+        let (configs_a, configs_b) = ([sc!("service-a")], [sc!("service-b")]);
+
+        let (first, second, progress) = tokio::join!(
+            apps.create_or_update(
+                &app_name,
+                &status_id_a,
+                Replication::FromDefault,
+                &configs_a,
                 None,
-                &vec![sc!("service-c")],
+            ),
+            apps.create_or_update(
+                &app_name,
+                &status_id_b,
+                Replication::FromDefault,
+                &configs_b,
                 None,
-            )
-            .await;
+            ),
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                apps.status_change_progress(&status_id_b).await
+            }
+        );
 
+        first?;
+        second?;
+        let progress = progress?
+            .expect("progress of the waiting deployment")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(progress.first(), Some(&StatusChangeProgress::Preparing));
         assert!(matches!(
-            result,
-            Ok(services) if services.len() == 3
+            progress.last(),
+            Some(StatusChangeProgress::Finished { .. })
         ));
 
         Ok(())
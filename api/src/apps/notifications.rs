@@ -0,0 +1,295 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use crate::config::{AppLifecycleEvent, NotificationsConfig};
+use crate::models::service::{ContainerType, Services};
+use crate::models::AppName;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use url::Url;
+
+/// The number of attempts to deliver a notification to a webhook before giving up.
+const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+/// The delay before the first retry. It doubles with every further retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The JSON payload that is posted to the webhooks, e.g.
+///
+/// ```json
+/// {
+///   "event": "created",
+///   "appName": "master",
+///   "services": [ { "name": "nginx", "type": "instance", "image": "docker.io/library/nginx:latest" } ],
+///   "metadata": {}
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppLifecycleNotification {
+    event: AppLifecycleEvent,
+    app_name: String,
+    services: Vec<NotifiedService>,
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NotifiedService {
+    name: String,
+    #[serde(rename = "type")]
+    container_type: ContainerType,
+    image: String,
+}
+
+impl AppLifecycleNotification {
+    fn new(event: AppLifecycleEvent, app_name: &AppName, services: &Services) -> Self {
+        Self {
+            event,
+            app_name: app_name.to_string(),
+            services: services
+                .iter()
+                .map(|service| NotifiedService {
+                    name: service.service_name().clone(),
+                    container_type: service.container_type().clone(),
+                    image: service.config.image().to_string(),
+                })
+                .collect(),
+            metadata: services
+                .iter()
+                .flat_map(|service| service.config.metadata().clone())
+                .collect(),
+        }
+    }
+}
+
+/// Sends the event to all webhooks that subscribed to it. The delivery happens in the background
+/// and it is best-effort: failed deliveries are retried a few times and then logged, so they
+/// never affect the outcome of the app's deployment or deletion.
+pub(super) fn notify(
+    config: &NotificationsConfig,
+    event: AppLifecycleEvent,
+    app_name: &AppName,
+    services: &Services,
+) {
+    let urls = config
+        .webhooks_for(event)
+        .map(|webhook| webhook.url().clone())
+        .collect::<Vec<_>>();
+    if urls.is_empty() {
+        return;
+    }
+
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            error!("Cannot create HTTP client for the notification webhooks: {err}");
+            return;
+        }
+    };
+
+    let notification = AppLifecycleNotification::new(event, app_name, services);
+    let payload = match serde_json::to_value(&notification) {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!("Cannot serialize the {event} notification of {app_name}: {err}");
+            return;
+        }
+    };
+
+    for url in urls {
+        let client = client.clone();
+        let payload = payload.clone();
+        let app_name = app_name.clone();
+        tokio::spawn(async move {
+            if let Err(err) = deliver(&client, &url, &payload).await {
+                error!("Cannot notify {url} that {app_name} has been {event}: {err}");
+            }
+        });
+    }
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    url: &Url,
+    payload: &serde_json::Value,
+) -> Result<(), reqwest::Error> {
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let result = client
+            .post(url.clone())
+            .json(payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match result {
+            Ok(_) => return Ok(()),
+            Err(err) if attempt < MAX_DELIVERY_ATTEMPTS => {
+                debug!("Attempt {attempt} to notify {url} failed, retrying in {backoff:?}: {err}");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Accepts webhook requests and responds with the given status codes in order (`200` once they
+/// are exhausted).
+#[cfg(test)]
+pub(super) async fn mock_webhook(statuses: Vec<u16>) -> wiremock::MockServer {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    for status in statuses {
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Waits until the webhook has received the given number of notifications and returns their JSON
+/// bodies in the order of their arrival.
+#[cfg(test)]
+pub(super) async fn received_notifications(
+    webhook: &wiremock::MockServer,
+    count: usize,
+) -> Vec<serde_json::Value> {
+    let receive = async {
+        loop {
+            let requests = webhook.received_requests().await.unwrap_or_default();
+            if requests.len() >= count {
+                return requests
+                    .iter()
+                    .map(|request| request.body_json::<serde_json::Value>().unwrap())
+                    .collect::<Vec<_>>();
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), receive)
+        .await
+        .expect("The webhook should have received the notifications")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::service::{Service, ServiceStatus, State};
+    use crate::sc;
+    use std::str::FromStr;
+
+    fn services() -> Services {
+        let mut config = sc!("nginx", "nginx:1.27");
+        config.set_metadata(BTreeMap::from([(
+            String::from("owner"),
+            String::from("jane"),
+        )]));
+        Services::from(vec![Service {
+            id: String::from("some-id"),
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: None,
+                replicas: None,
+            },
+            config,
+        }])
+    }
+
+    #[test]
+    fn should_serialize_notification() {
+        let notification = AppLifecycleNotification::new(
+            AppLifecycleEvent::Created,
+            &AppName::from_str("feature-x").unwrap(),
+            &services(),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            serde_json::json!({
+                "event": "created",
+                "appName": "feature-x",
+                "services": [{
+                    "name": "nginx",
+                    "type": "instance",
+                    "image": "docker.io/library/nginx:1.27"
+                }],
+                "metadata": {
+                    "owner": "jane"
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_retry_failed_deliveries() {
+        let webhook = mock_webhook(vec![503]).await;
+        let payload = serde_json::json!({ "event": "deleted" });
+
+        deliver(
+            &reqwest::Client::new(),
+            &Url::parse(&webhook.uri()).unwrap(),
+            &payload,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            received_notifications(&webhook, 2).await,
+            vec![payload.clone(), payload]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_give_up_after_bounded_number_of_attempts() {
+        let webhook = mock_webhook(vec![500; 5]).await;
+        let payload = serde_json::json!({ "event": "deleted" });
+
+        let result = deliver(
+            &reqwest::Client::new(),
+            &Url::parse(&webhook.uri()).unwrap(),
+            &payload,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            webhook.received_requests().await.unwrap().len(),
+            MAX_DELIVERY_ATTEMPTS as usize
+        );
+    }
+}
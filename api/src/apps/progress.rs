@@ -0,0 +1,199 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use crate::models::service::Services;
+use crate::models::AppStatusChangeId;
+use futures::stream::BoxStream;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::watch::{self, Receiver, Sender};
+
+/// The phases that a status change (deployment or deletion of an app) passes through. The
+/// infrastructure does not report the progress of single containers (e.g. pulling an image), so
+/// the services are handed over to the infrastructure as a whole in [`Self::Deploying`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "phase", rename_all = "camelCase")]
+pub enum StatusChangeProgress {
+    /// Replicating services, resolving images, and applying templating and hooks.
+    Preparing,
+    /// The infrastructure pulls the images, and creates and starts the containers.
+    Deploying {
+        services: Vec<String>,
+    },
+    /// The post-deploy hooks verify the deployed services.
+    Verifying,
+    /// The infrastructure stops the services of the app.
+    Stopping,
+    Finished {
+        services: Vec<String>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl StatusChangeProgress {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Finished { .. } | Self::Failed { .. })
+    }
+
+    pub(super) fn finished(services: &Services) -> Self {
+        Self::Finished {
+            services: service_names(services),
+        }
+    }
+}
+
+pub(super) fn service_names(services: &Services) -> Vec<String> {
+    services
+        .iter()
+        .map(|service| service.service_name().clone())
+        .collect()
+}
+
+/// Keeps the progress of the status changes that are processed by this PREvant instance so that
+/// clients can subscribe to it.
+#[derive(Default)]
+pub(super) struct ProgressReporter {
+    senders: Mutex<HashMap<AppStatusChangeId, Sender<StatusChangeProgress>>>,
+}
+
+impl ProgressReporter {
+    pub fn start(&self, status_id: &AppStatusChangeId, progress: StatusChangeProgress) {
+        let (tx, _rx) = watch::channel(progress);
+        self.senders.lock().unwrap().insert(*status_id, tx);
+    }
+
+    /// Reports the progress of a status change that has been started with [`Self::start`].
+    /// Other status changes, e.g. of a dry run, are ignored.
+    pub fn report(&self, status_id: &AppStatusChangeId, progress: StatusChangeProgress) {
+        if let Some(tx) = self.senders.lock().unwrap().get(status_id) {
+            tx.send_replace(progress);
+        }
+    }
+
+    pub fn finish(&self, status_id: &AppStatusChangeId, progress: StatusChangeProgress) {
+        if let Some(tx) = self.senders.lock().unwrap().remove(status_id) {
+            tx.send_replace(progress);
+        }
+    }
+
+    pub fn subscribe(
+        &self,
+        status_id: &AppStatusChangeId,
+    ) -> Option<Receiver<StatusChangeProgress>> {
+        self.senders
+            .lock()
+            .unwrap()
+            .get(status_id)
+            .map(|tx| tx.subscribe())
+    }
+}
+
+/// Emits every distinct progress of the receiver until the status change has finished.
+pub(super) fn progress_stream(
+    mut rx: Receiver<StatusChangeProgress>,
+) -> BoxStream<'static, StatusChangeProgress> {
+    Box::pin(async_stream::stream! {
+        let mut last = None;
+        loop {
+            let progress = rx.borrow_and_update().clone();
+            let is_terminal = progress.is_terminal();
+            if last.as_ref() != Some(&progress) {
+                last = Some(progress.clone());
+                yield progress;
+            }
+            if is_terminal {
+                break;
+            }
+
+            if rx.changed().await.is_err() {
+                let progress = rx.borrow().clone();
+                if last.as_ref() != Some(&progress) {
+                    yield progress;
+                }
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn should_stream_progress_until_finished() {
+        let reporter = ProgressReporter::default();
+        let status_id = AppStatusChangeId::new();
+        reporter.start(&status_id, StatusChangeProgress::Preparing);
+
+        let stream = progress_stream(reporter.subscribe(&status_id).unwrap());
+        reporter.report(
+            &status_id,
+            StatusChangeProgress::Deploying {
+                services: vec![String::from("service-a")],
+            },
+        );
+        reporter.finish(
+            &status_id,
+            StatusChangeProgress::Finished {
+                services: vec![String::from("service-a")],
+            },
+        );
+
+        assert_eq!(
+            stream.collect::<Vec<_>>().await,
+            vec![StatusChangeProgress::Finished {
+                services: vec![String::from("service-a")],
+            }]
+        );
+        assert!(reporter.subscribe(&status_id).is_none());
+    }
+
+    #[test]
+    fn should_ignore_progress_of_unknown_status_change() {
+        let reporter = ProgressReporter::default();
+        let status_id = AppStatusChangeId::new();
+
+        reporter.report(&status_id, StatusChangeProgress::Verifying);
+
+        assert!(reporter.subscribe(&status_id).is_none());
+    }
+
+    #[test]
+    fn should_serialize_phase() {
+        assert_eq!(
+            serde_json::to_value(StatusChangeProgress::Deploying {
+                services: vec![String::from("service-a")],
+            })
+            .unwrap(),
+            serde_json::json!({ "phase": "deploying", "services": ["service-a"] })
+        );
+    }
+}
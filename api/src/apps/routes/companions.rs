@@ -0,0 +1,185 @@
+use crate::{
+    apps::{Apps, AppsError},
+    deployment::deployment_unit::DeploymentUnit,
+    http_result::HttpResult,
+    models::{AppName, ContainerType},
+};
+use rocket::{serde::json::Json, State};
+use serde::Serialize;
+use std::{str::FromStr, sync::Arc};
+
+/// Previews the application companions that an app with the given name would receive, e.g.
+/// `GET /api/companions?appName=feature-x&userDefined={"database":"mariadb"}`.
+#[get("/?<preview_query..>", format = "application/json")]
+pub(super) async fn companions(
+    preview_query: PreviewQuery,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Json<CompanionPreview>> {
+    let app_name = AppName::from_str(&preview_query.app_name)?;
+    let user_defined_parameters = match preview_query.user_defined {
+        Some(user_defined) => Some(serde_json::from_str(&user_defined).map_err(|err| {
+            AppsError::InvalidUserDefinedParameters {
+                err: err.to_string(),
+            }
+        })?),
+        None => None,
+    };
+
+    let deployment_unit = apps
+        .preview_companions(&app_name, user_defined_parameters)
+        .await?;
+
+    Ok(Json(CompanionPreview::from(deployment_unit)))
+}
+
+#[derive(FromForm)]
+pub(super) struct PreviewQuery {
+    #[field(name = "appName")]
+    app_name: String,
+    #[field(name = "userDefined")]
+    user_defined: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(super) struct CompanionPreview {
+    companions: Vec<PreviewedCompanion>,
+    skipped: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct PreviewedCompanion {
+    name: String,
+    #[serde(rename = "type")]
+    container_type: ContainerType,
+    image: String,
+}
+
+impl From<DeploymentUnit> for CompanionPreview {
+    fn from(deployment_unit: DeploymentUnit) -> Self {
+        Self {
+            companions: deployment_unit
+                .services()
+                .iter()
+                .map(|service| PreviewedCompanion {
+                    name: service.service_name().clone(),
+                    container_type: service.container_type().clone(),
+                    image: service.image().to_string(),
+                })
+                .collect(),
+            skipped: deployment_unit
+                .skipped_companions()
+                .iter()
+                .map(|skipped| skipped.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::Dummy;
+    use rocket::{http::Status, local::asynchronous::Client};
+
+    async fn create_client() -> Client {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+            [companions.adminer]
+            serviceName = "adminer"
+            type = "application"
+            image = "adminer:4.8.1"
+
+            [companions.mailhog]
+            serviceName = "mailhog"
+            type = "application"
+            image = "mailhog/mailhog:v1.0.1"
+            appSelector = "master"
+
+            [companions.db]
+            serviceName = "db"
+            type = "application"
+            image = "mariadb:10.3.17"
+            condition = "{{userDefined.withDatabase}}"
+
+            [companions.templating.userDefinedSchema]
+            type = "object"
+            properties = { withDatabase = { type = "boolean" } }
+            "#,
+        )
+        .unwrap();
+        let infrastructure = Box::new(Dummy::new());
+        let apps = Arc::new(Apps::new(config, infrastructure).unwrap());
+
+        let rocket = rocket::build().manage(apps).mount("/", routes![companions]);
+        Client::tracked(rocket).await.expect("valid rocket")
+    }
+
+    #[tokio::test]
+    async fn should_preview_companions_of_app() {
+        let client = create_client().await;
+
+        let response = client
+            .get("/?appName=feature-x&userDefined=%7B%22withDatabase%22%3Atrue%7D")
+            .header(rocket::http::ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let mut body = response.into_json::<serde_json::Value>().await.unwrap();
+        body["companions"]
+            .as_array_mut()
+            .unwrap()
+            .sort_by_key(|c| c["name"].as_str().unwrap().to_string());
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "companions": [{
+                    "name": "adminer",
+                    "type": "app-companion",
+                    "image": "docker.io/library/adminer:4.8.1"
+                }, {
+                    "name": "db",
+                    "type": "app-companion",
+                    "image": "docker.io/library/mariadb:10.3.17"
+                }],
+                "skipped": [
+                    "Companion mailhog has been skipped because its app selector does not match."
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_skip_companions_whose_condition_is_not_met() {
+        let client = create_client().await;
+
+        let response = client
+            .get("/?appName=feature-x")
+            .header(rocket::http::ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_json::<serde_json::Value>().await.unwrap();
+        assert_eq!(
+            body["skipped"],
+            serde_json::json!([
+                "Companion mailhog has been skipped because its app selector does not match.",
+                "Companion db has been skipped because its condition is not met."
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_user_defined_parameters() {
+        let client = create_client().await;
+
+        let response = client
+            .get("/?appName=feature-x&userDefined=%7B%22withDatabase%22%3A%22yes%22%7D")
+            .header(rocket::http::ContentType::JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
@@ -1,16 +1,124 @@
-use crate::models::ServiceConfig;
+use crate::models::{parse_compose_file, ServiceConfig};
 use http::StatusCode;
 use http_api_problem::HttpApiProblem;
+use regex::Regex;
 use rocket::{
-    data::{FromData, Outcome},
-    http::Status,
+    data::{FromData, Limits, Outcome},
+    http::{ContentType, Status},
     serde::json::Json,
     Data, Request,
 };
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 pub struct CreateAppPayload {
     pub services: Vec<ServiceConfig>,
     pub user_defined_parameters: Option<serde_json::Value>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A service of the payload that could not be parsed, identified by its position in the list of
+/// services.
+#[derive(Serialize)]
+struct InvalidService {
+    index: usize,
+    detail: String,
+}
+
+/// Parses each service on its own so that all invalid services are reported at once instead of
+/// failing on the first one.
+fn parse_services(
+    services: Vec<serde_json::Value>,
+) -> Result<Vec<ServiceConfig>, Box<HttpApiProblem>> {
+    let mut configs = Vec::with_capacity(services.len());
+    let mut invalid_services = Vec::new();
+
+    for (index, service) in services.into_iter().enumerate() {
+        match serde_json::from_value::<ServiceConfig>(service) {
+            Ok(config) => configs.push(config),
+            Err(err) => invalid_services.push(InvalidService {
+                index,
+                detail: err.to_string(),
+            }),
+        }
+    }
+
+    let detail = match invalid_services.as_slice() {
+        [] => return Ok(configs),
+        [invalid_service] => invalid_service.detail.clone(),
+        invalid_services => format!("{} services are invalid", invalid_services.len()),
+    };
+
+    Err(Box::new(
+        HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
+            .detail(detail)
+            .value("errors", &invalid_services),
+    ))
+}
+
+/// Parses the metadata of the request, e.g. the team or cost center. The keys must be valid
+/// names of Kubernetes annotations because they will be stored as labels or annotations of the
+/// services.
+fn parse_metadata(metadata: serde_json::Value) -> Result<BTreeMap<String, String>, String> {
+    lazy_static! {
+        static ref KEY: Regex =
+            Regex::new(r"^[A-Za-z0-9]([-A-Za-z0-9_.]{0,61}[A-Za-z0-9])?$").unwrap();
+    }
+
+    let metadata = serde_json::from_value::<BTreeMap<String, String>>(metadata)
+        .map_err(|_| String::from("expected an JSON object with string values for metadata"))?;
+
+    if let Some(key) = metadata.keys().find(|key| !KEY.is_match(key)) {
+        return Err(format!(
+            "Invalid metadata key {key}: it must consist of at most 63 alphanumeric characters, '-', '_' or '.'"
+        ));
+    }
+
+    Ok(metadata)
+}
+
+/// Whether the payload is YAML, e.g. `application/yaml` or `text/x-yaml`.
+fn is_yaml(content_type: &ContentType) -> bool {
+    (content_type.top() == "application" || content_type.top() == "text")
+        && (content_type.sub() == "yaml" || content_type.sub() == "x-yaml")
+}
+
+/// Reads the services from a [Compose file](https://compose-spec.io/) so that users can deploy
+/// the stack they already describe with Docker Compose.
+async fn compose_payload<'r>(
+    req: &'r Request<'_>,
+    data: Data<'r>,
+) -> Outcome<'r, CreateAppPayload> {
+    let limit = req.limits().get("yaml").unwrap_or(Limits::JSON);
+    let content = match data.open(limit).into_string().await {
+        Ok(content) if content.is_complete() => content.into_inner(),
+        Ok(_) => {
+            return Outcome::Error((
+                Status::PayloadTooLarge,
+                HttpApiProblem::with_title_and_type(StatusCode::PAYLOAD_TOO_LARGE)
+                    .detail(format!("The Compose file exceeds the limit of {limit}")),
+            ))
+        }
+        Err(err) => {
+            return Outcome::Error((
+                Status::BadRequest,
+                HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
+                    .detail(err.to_string()),
+            ))
+        }
+    };
+
+    match parse_compose_file(&content) {
+        Ok(services) => Outcome::Success(CreateAppPayload {
+            services,
+            user_defined_parameters: None,
+            metadata: BTreeMap::new(),
+        }),
+        Err(err) => Outcome::Error((
+            Status::BadRequest,
+            HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST).detail(err.to_string()),
+        )),
+    }
 }
 
 #[rocket::async_trait]
@@ -18,6 +126,10 @@ impl<'r> FromData<'r> for CreateAppPayload {
     type Error = HttpApiProblem;
 
     async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        if req.content_type().is_some_and(is_yaml) {
+            return compose_payload(req, data).await;
+        }
+
         let rocket::outcome::Outcome::Success(data) =
             Json::<serde_json::Value>::from_data(req, data).await
         else {
@@ -28,39 +140,22 @@ impl<'r> FromData<'r> for CreateAppPayload {
             ));
         };
 
-        let (services, user_defined_parameters) = match data.0 {
+        let (services, user_defined_parameters, metadata) = match data.0 {
             serde_json::Value::Array(services) => (
-                match serde_json::from_value(serde_json::Value::Array(services)) {
+                match parse_services(services) {
                     Ok(services) => services,
-                    Err(err) => {
-                        return Outcome::Error((
-                            Status::BadRequest,
-                            HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
-                                .detail(err.to_string()),
-                        ));
-                    }
+                    Err(problem) => return Outcome::Error((Status::BadRequest, *problem)),
                 },
                 None,
+                BTreeMap::new(),
             ),
             serde_json::Value::Object(mut object) => (
                 match object.remove("services") {
                     Some(services) => match services {
-                        serde_json::Value::Array(services) => {
-                            match serde_json::from_value::<Vec<ServiceConfig>>(
-                                serde_json::Value::Array(services),
-                            ) {
-                                Ok(services) => services,
-                                Err(err) => {
-                                    return Outcome::Error((
-                                        Status::BadRequest,
-                                        HttpApiProblem::with_title_and_type(
-                                            StatusCode::BAD_REQUEST,
-                                        )
-                                        .detail(err.to_string()),
-                                    ));
-                                }
-                            }
-                        }
+                        serde_json::Value::Array(services) => match parse_services(services) {
+                            Ok(services) => services,
+                            Err(problem) => return Outcome::Error((Status::BadRequest, *problem)),
+                        },
                         _ => {
                             return Outcome::Error((
                                 Status::BadRequest,
@@ -72,6 +167,17 @@ impl<'r> FromData<'r> for CreateAppPayload {
                     None => Vec::new(),
                 },
                 object.remove("userDefined"),
+                match object.remove("metadata").map(parse_metadata) {
+                    Some(Ok(metadata)) => metadata,
+                    Some(Err(detail)) => {
+                        return Outcome::Error((
+                            Status::BadRequest,
+                            HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
+                                .detail(detail),
+                        ))
+                    }
+                    None => BTreeMap::new(),
+                },
             ),
             _ => {
                 return Outcome::Error((
@@ -85,6 +191,7 @@ impl<'r> FromData<'r> for CreateAppPayload {
         Outcome::Success(Self {
             services,
             user_defined_parameters,
+            metadata,
         })
     }
 }
@@ -131,6 +238,114 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compose_payload() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(
+                r#"
+                services:
+                  db:
+                    image: mariadb:10.11
+                  blog:
+                    image: wordpress
+                "#,
+            )
+            .header(ContentType::new("application", "yaml"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[tokio::test]
+    async fn compose_payload_with_named_network() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(
+                r#"
+                services:
+                  db:
+                    image: mariadb:10.11
+                    networks:
+                      - backend
+                  blog:
+                    image: wordpress
+                    networks:
+                      - backend
+                networks:
+                  backend: {}
+                "#,
+            )
+            .header(ContentType::new("application", "yaml"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[tokio::test]
+    async fn compose_payload_with_unsupported_volume() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(
+                r#"
+                services:
+                  db:
+                    image: mariadb:10.11
+                    volumes:
+                      - db-data:/var/lib/mysql
+                volumes:
+                  db-data: {}
+                "#,
+            )
+            .header(ContentType::new("application", "yaml"))
+            .dispatch()
+            .await;
+
+        let body = response.into_string().await.unwrap();
+        assert_json_include!(
+            actual: serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            expected: json!({
+                "status": 400,
+                "detail": "Service db mounts db-data:/var/lib/mysql, which is not supported; provide files as configs with inline content"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_compose_payload() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(
+                r#"
+                services:
+                  api:
+                    build: .
+                "#,
+            )
+            .header(ContentType::new("application", "yaml"))
+            .dispatch()
+            .await;
+
+        let body = response.into_string().await.unwrap();
+        assert_json_include!(
+            actual: serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            expected: json!({
+                "status": 400,
+                "detail": "Service api must provide an image because PREvant cannot build images"
+            })
+        );
+    }
+
     #[tokio::test]
     async fn invalid_payload_at_root() {
         let client = create_client().await;
@@ -228,4 +443,85 @@ mod tests {
             })
         );
     }
+
+    #[tokio::test]
+    async fn collect_all_invalid_services() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(
+                json!([{
+                    "serviceName": "db",
+                    "image": "private-registry.example.com/_/postgres"
+                }, {
+                    "serviceName": "nginx",
+                    "image": "nginx"
+                }, {
+                    "serviceName": "adminer"
+                }])
+                .to_string(),
+            )
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        let body = response.into_string().await.unwrap();
+        assert_json_include!(
+            actual: serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            expected: json!({
+                "status": 400,
+                "detail": "2 services are invalid",
+                "errors": [{
+                    "index": 0,
+                    "detail": "Invalid image: private-registry.example.com/_/postgres"
+                }, {
+                    "index": 2,
+                    "detail": "missing field `image`"
+                }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_metadata_key() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(json!({ "services": [], "metadata": { "team/name": "foo" } }).to_string())
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        let body = response.into_string().await.unwrap();
+        assert_json_include!(
+            actual: serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            expected: json!({
+                "status": 400,
+                "detail": "Invalid metadata key team/name: it must consist of at most 63 alphanumeric characters, '-', '_' or '.'"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_metadata_value() {
+        let client = create_client().await;
+
+        let response = client
+            .post("/")
+            .body(json!({ "services": [], "metadata": { "team": 42 } }).to_string())
+            .header(ContentType::JSON)
+            .dispatch()
+            .await;
+
+        let body = response.into_string().await.unwrap();
+        assert_json_include!(
+            actual: serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            expected: json!({
+                "status": 400,
+                "detail": "expected an JSON object with string values for metadata"
+            })
+        );
+    }
 }
@@ -0,0 +1,70 @@
+use crate::{deployment::deployment_unit::DeploymentUnit, models::ContainerType};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// The services that a deployment request would deploy, returned by `POST /api/apps/<app>`
+/// with `dryRun=true` instead of deploying them.
+///
+/// The endpoint is not authenticated and the services may carry secrets of the configuration,
+/// so the preview only names the environment variables and the paths of the files but never
+/// renders their values.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DeploymentPreview {
+    services: Vec<PreviewedService>,
+    skipped: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewedService {
+    name: String,
+    #[serde(rename = "type")]
+    container_type: ContainerType,
+    image: String,
+    env: BTreeSet<String>,
+    files: BTreeSet<PathBuf>,
+    ingress_routes: Vec<String>,
+}
+
+impl From<DeploymentUnit> for DeploymentPreview {
+    fn from(deployment_unit: DeploymentUnit) -> Self {
+        let mut services = deployment_unit
+            .services()
+            .iter()
+            .map(|service| PreviewedService {
+                name: service.service_name().clone(),
+                container_type: service.container_type().clone(),
+                image: service.image().to_string(),
+                env: service
+                    .env()
+                    .iter()
+                    .flat_map(|env| env.iter())
+                    .map(|variable| variable.key().clone())
+                    .collect(),
+                files: service
+                    .files()
+                    .iter()
+                    .flat_map(|files| files.keys())
+                    .cloned()
+                    .collect(),
+                ingress_routes: service
+                    .ingress_route()
+                    .routes()
+                    .iter()
+                    .map(|route| route.rule().to_string())
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            services,
+            skipped: deployment_unit
+                .skipped_companions()
+                .iter()
+                .map(|skipped| skipped.to_string())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::apps::{Apps, HostMetaCache};
+use crate::models::request_info::RequestInfo;
+use crate::models::service::{ServiceStatus, ServiceWithHostMeta, ServicesWithHostMeta};
+use crate::models::AppName;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Json;
+use rocket::State;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use url::Url;
+
+pub type AppsSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn graphql_schema() -> AppsSchema {
+    Schema::new(Query, EmptyMutation, EmptySubscription)
+}
+
+/// Answers GraphQL queries for the apps, e.g. `{ apps { name services { name version {
+/// softwareVersion } } } }`, so that clients fetch only the fields they need.
+#[post("/", format = "application/json", data = "<request>")]
+pub(super) async fn graphql(
+    schema: &State<AppsSchema>,
+    apps: &State<Arc<Apps>>,
+    host_meta_cache: HostMetaCache,
+    request_info: RequestInfo,
+    request: Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = request
+        .into_inner()
+        .data(apps.inner().clone())
+        .data(host_meta_cache)
+        .data(request_info);
+
+    Json(schema.execute(request).await)
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn apps(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<App>> {
+        let mut apps = fetch_apps(ctx)
+            .await?
+            .into_iter()
+            .map(|(name, services)| App { name, services })
+            .collect::<Vec<_>>();
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(apps)
+    }
+
+    async fn app(&self, ctx: &Context<'_>, name: String) -> async_graphql::Result<Option<App>> {
+        let name = AppName::from_str(&name)?;
+        let mut apps = fetch_apps(ctx).await?;
+        Ok(apps.remove(&name).map(|services| App { name, services }))
+    }
+
+    async fn service(
+        &self,
+        ctx: &Context<'_>,
+        app: String,
+        name: String,
+    ) -> async_graphql::Result<Option<Service>> {
+        let app = self.app(ctx, app).await?;
+        Ok(app.and_then(|app| app.service(name)))
+    }
+}
+
+async fn fetch_apps(
+    ctx: &Context<'_>,
+) -> async_graphql::Result<HashMap<AppName, ServicesWithHostMeta>> {
+    let apps = ctx.data::<Arc<Apps>>()?;
+    let host_meta_cache = ctx.data::<HostMetaCache>()?;
+    let request_info = ctx.data::<RequestInfo>()?;
+
+    let services = apps.fetch_apps().await?;
+    Ok(host_meta_cache.update_meta_data(services, request_info))
+}
+
+pub struct App {
+    name: AppName,
+    services: ServicesWithHostMeta,
+}
+
+#[Object]
+impl App {
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn services(&self) -> Vec<Service> {
+        self.services.iter().cloned().map(Service).collect()
+    }
+
+    #[graphql(name = "service")]
+    async fn service_by_name(&self, name: String) -> Option<Service> {
+        self.service(name)
+    }
+}
+
+impl App {
+    fn service(&self, name: String) -> Option<Service> {
+        self.services
+            .iter()
+            .find(|service| service.config().service_name() == &name)
+            .cloned()
+            .map(Service)
+    }
+}
+
+pub struct Service(ServiceWithHostMeta);
+
+#[Object]
+impl Service {
+    async fn name(&self) -> &str {
+        self.0.config().service_name()
+    }
+
+    #[graphql(name = "type")]
+    async fn container_type(&self) -> String {
+        self.0.config().container_type().to_string()
+    }
+
+    async fn image(&self) -> String {
+        self.0.config().image().to_string()
+    }
+
+    async fn status(&self) -> Status {
+        Status::from(&self.0.state().status)
+    }
+
+    async fn url(&self) -> Option<&Url> {
+        self.0.service_url()
+    }
+
+    async fn exposed_port(&self) -> Option<u16> {
+        self.0.config().exposed_host_port()
+    }
+
+    async fn version(&self) -> Option<Version> {
+        let web_host_meta = self.0.web_host_meta();
+        if web_host_meta.is_empty() {
+            return None;
+        }
+
+        Some(Version {
+            git_commit: web_host_meta.commit(),
+            software_version: web_host_meta.version(),
+            date_modified: web_host_meta.date_modified(),
+        })
+    }
+
+    async fn open_api_url(&self) -> Option<&Url> {
+        self.0.web_host_meta().openapi()
+    }
+
+    async fn async_api_url(&self) -> Option<&Url> {
+        self.0.web_host_meta().asyncapi()
+    }
+
+    async fn deployed_at(&self) -> Option<DateTime<Utc>> {
+        self.0.config().deployed_at().cloned()
+    }
+
+    async fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.0.config().created_at().cloned()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Version {
+    git_commit: Option<String>,
+    software_version: Option<String>,
+    date_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(async_graphql::Enum, Clone, Copy, Eq, PartialEq)]
+pub enum Status {
+    Running,
+    Starting,
+    Paused,
+    Frozen,
+}
+
+impl From<&ServiceStatus> for Status {
+    fn from(status: &ServiceStatus) -> Self {
+        match status {
+            ServiceStatus::Running => Status::Running,
+            ServiceStatus::Starting => Status::Starting,
+            ServiceStatus::Paused => Status::Paused,
+            ServiceStatus::Frozen => Status::Frozen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::Replication;
+    use crate::infrastructure::Dummy;
+    use crate::models::AppStatusChangeId;
+    use crate::sc;
+    use rocket::http::{ContentType, Header, Status as HttpStatus};
+    use rocket::local::asynchronous::Client;
+    use serde_json::{json, Value};
+
+    async fn create_client() -> Client {
+        let infrastructure = Box::new(Dummy::new());
+        let apps = Arc::new(Apps::new(Default::default(), infrastructure).unwrap());
+        for app_name in [AppName::master(), AppName::from_str("feature-x").unwrap()].iter() {
+            apps.create_or_update(
+                app_name,
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b")],
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let (host_meta_cache, _host_meta_crawler) = crate::host_meta_crawling(&Default::default());
+        let rocket = rocket::build()
+            .manage(apps)
+            .manage(host_meta_cache)
+            .manage(graphql_schema())
+            .mount("/graphql", routes![graphql]);
+        Client::tracked(rocket).await.expect("valid rocket")
+    }
+
+    async fn query(client: &Client, query: &str) -> Value {
+        let response = client
+            .post("/graphql")
+            .header(ContentType::JSON)
+            .header(Header::new("host", "prevant.example.com"))
+            .body(json!({ "query": query }).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), HttpStatus::Ok);
+
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_query_names_of_apps_and_services() {
+        let client = create_client().await;
+
+        let response = query(&client, "{ apps { name services { name } } }").await;
+
+        assert_eq!(
+            response,
+            json!({
+                "data": {
+                    "apps": [{
+                        "name": "feature-x",
+                        "services": [{ "name": "service-a" }, { "name": "service-b" }]
+                    }, {
+                        "name": "master",
+                        "services": [{ "name": "service-a" }, { "name": "service-b" }]
+                    }]
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_query_service_of_app() {
+        let client = create_client().await;
+
+        let response = query(
+            &client,
+            r#"{ service(app: "master", name: "service-b") { name type status version { softwareVersion } } }"#,
+        )
+        .await;
+
+        assert_eq!(
+            response,
+            json!({
+                "data": {
+                    "service": {
+                        "name": "service-b",
+                        "type": "instance",
+                        "status": "RUNNING",
+                        "version": null
+                    }
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_query_unknown_app() {
+        let client = create_client().await;
+
+        let response = query(&client, r#"{ app(name: "unknown") { name } }"#).await;
+
+        assert_eq!(response, json!({ "data": { "app": null } }));
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_app_name() {
+        let client = create_client().await;
+
+        let response = query(&client, r#"{ app(name: "Invalid/Name") { name } }"#).await;
+
+        assert_eq!(response["data"], json!(null));
+        assert!(response["errors"]
+            .as_array()
+            .is_some_and(|errors| errors.len() == 1));
+    }
+}
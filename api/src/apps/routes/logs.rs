@@ -1,20 +1,30 @@
 use crate::{
     apps::Apps,
-    http_result::HttpResult,
-    models::{AppName, AppNameError, LogChunk},
+    http_result::{HttpApiError, HttpResult},
+    models::{AppName, AppNameError, LogChunk, LogFilter},
 };
-use chrono::DateTime;
-use futures::stream::StreamExt;
+use chrono::{DateTime, FixedOffset};
+use flate2::{write::GzEncoder, Compression};
+use futures::stream::{BoxStream, StreamExt};
+use futures::SinkExt;
 use http_api_problem::HttpApiProblem;
-use rocket::http::hyper::header::{ACCEPT, CONTENT_DISPOSITION, LINK};
+use regex::Regex;
+use rocket::http::hyper::header::{
+    ACCEPT, ACCEPT_ENCODING, CONTENT_DISPOSITION, CONTENT_ENCODING, LINK, VARY,
+};
 use rocket::{
+    data::{IoHandler, IoStream},
     http::{Accept, ContentType, RawStr, Status},
     request::FromRequest,
     response::stream::{Event, EventStream},
     response::{Responder, Response},
     Request, State,
 };
-use std::{str::FromStr, sync::Arc};
+use std::{io::Write, pin::Pin, str::FromStr, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::WebSocketStream;
 
 #[get("/<app_name>/logs/<service_name>?<log_query..>", rank = 1)]
 pub(super) async fn logs<'r>(
@@ -25,23 +35,17 @@ pub(super) async fn logs<'r>(
     apps: &State<Arc<Apps>>,
 ) -> HttpResult<LogsResponse<'r>> {
     let app_name = app_name?;
-
-    let since = match log_query.since {
-        None => None,
-        Some(since) => match DateTime::parse_from_rfc3339(&since) {
-            Ok(since) => Some(since),
-            Err(err) => {
-                return Err(HttpApiProblem::with_title_and_type(
-                    http_api_problem::StatusCode::BAD_REQUEST,
-                )
-                .detail(format!("{}", err))
-                .into());
-            }
-        },
-    };
+    let since = log_query.since()?;
+    let filter = log_query.filter()?;
 
     let log_chunk = apps
-        .get_logs(&app_name, service_name, &since, &log_query.limit)
+        .get_logs(
+            &app_name,
+            service_name,
+            &since,
+            &log_query.limit,
+            filter.as_ref(),
+        )
         .await?;
 
     Ok(LogsResponse {
@@ -49,6 +53,8 @@ pub(super) async fn logs<'r>(
         app_name,
         service_name,
         limit: log_query.limit,
+        grep: log_query.grep,
+        invert: log_query.invert,
         as_attachment: log_query.as_attachment,
     })
 }
@@ -65,19 +71,7 @@ pub(super) async fn stream_logs<'r>(
     apps: &'r State<Arc<Apps>>,
 ) -> HttpResult<EventStream![Event + 'r]> {
     let app_name = app_name?;
-    let since = match &log_query.since {
-        None => None,
-        Some(since) => match DateTime::parse_from_rfc3339(since) {
-            Ok(since) => Some(since),
-            Err(err) => {
-                return Err(HttpApiProblem::with_title_and_type(
-                    http_api_problem::StatusCode::BAD_REQUEST,
-                )
-                .detail(format!("{}", err))
-                .into());
-            }
-        },
-    };
+    let since = log_query.since()?;
 
     Ok(EventStream! {
         let mut log_chunk = apps
@@ -95,16 +89,43 @@ pub(super) async fn stream_logs<'r>(
     })
 }
 
+/// Streams the logs of a service over a WebSocket, one text frame per log line, so that browsers
+/// can tail the logs and reconnect with `since` if the connection drops. The socket is closed when
+/// the log stream ends, e.g. because the container stopped.
+#[get("/<app_name>/logs/<service_name>/ws?<log_query..>")]
+pub(super) async fn stream_logs_over_websocket(
+    upgrade: Result<WebSocketUpgrade, NoWebSocketUpgrade>,
+    app_name: Result<AppName, AppNameError>,
+    service_name: String,
+    log_query: LogQuery,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<LogsWebSocket> {
+    let upgrade = upgrade?;
+    let app_name = app_name?;
+    let since = log_query.since()?;
+
+    Ok(LogsWebSocket {
+        upgrade,
+        apps: apps.inner().clone(),
+        app_name,
+        service_name,
+        since,
+        limit: log_query.limit,
+    })
+}
+
 pub struct LogsResponse<'a> {
     log_chunk: Option<LogChunk>,
     app_name: AppName,
     service_name: &'a str,
     limit: Option<usize>,
+    grep: Option<String>,
+    invert: bool,
     as_attachment: bool,
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for LogsResponse<'r> {
-    fn respond_to(self, _request: &'r Request) -> Result<Response<'o>, Status> {
+    fn respond_to(self, request: &'r Request) -> Result<Response<'o>, Status> {
         use std::io::Cursor;
         let log_chunk = match self.log_chunk {
             None => {
@@ -122,7 +143,7 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for LogsResponse<'r> {
 
         let from = *log_chunk.until() + chrono::Duration::milliseconds(1);
 
-        let next_logs_url = match self.limit {
+        let mut next_logs_url = match self.limit {
             Some(limit) => format!(
                 "/api/apps/{}/logs/{}?limit={}&since={}",
                 self.app_name,
@@ -137,6 +158,13 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for LogsResponse<'r> {
                 RawStr::new(&from.to_rfc3339()).percent_encode(),
             ),
         };
+        if let Some(grep) = &self.grep {
+            next_logs_url.push_str("&grep=");
+            next_logs_url.extend(url::form_urlencoded::byte_serialize(grep.as_bytes()));
+            if self.invert {
+                next_logs_url.push_str("&invert=true");
+            }
+        }
 
         let content_disposition_value = if self.as_attachment {
             format!(
@@ -149,24 +177,203 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for LogsResponse<'r> {
             String::from("inline")
         };
 
-        let log_lines = log_chunk.log_lines();
-        Response::build()
+        let mut response = Response::build();
+        response
             .header(ContentType::Plain)
             .raw_header(LINK.as_str(), format!("<{}>;rel=next", next_logs_url))
-            .raw_header(CONTENT_DISPOSITION.as_str(), content_disposition_value)
+            .raw_header(CONTENT_DISPOSITION.as_str(), content_disposition_value);
+
+        let log_lines = log_chunk.log_lines();
+        if self.as_attachment {
+            // Downloaded logs can become large, thus, they are compressed if the client supports it.
+            response.raw_header(VARY.as_str(), ACCEPT_ENCODING.as_str());
+            if accepts_gzip(request) {
+                let compressed = gzip(log_lines.as_bytes()).map_err(|err| {
+                    error!("Cannot compress logs: {err}");
+                    Status::InternalServerError
+                })?;
+                return response
+                    .raw_header(CONTENT_ENCODING.as_str(), "gzip")
+                    .sized_body(compressed.len(), Cursor::new(compressed))
+                    .ok();
+            }
+        }
+
+        response
             .sized_body(log_lines.len(), Cursor::new(log_lines.clone()))
             .ok()
     }
 }
 
+fn accepts_gzip(request: &Request) -> bool {
+    request
+        .headers()
+        .get(ACCEPT_ENCODING.as_str())
+        .flat_map(|accept_encoding| accept_encoding.split(','))
+        .any(|coding| {
+            let mut parameters = coding.split(';').map(str::trim);
+            parameters
+                .next()
+                .is_some_and(|coding| coding.eq_ignore_ascii_case("gzip"))
+                && parameters.all(|parameter| parameter.replace(' ', "") != "q=0")
+        })
+}
+
+fn gzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+pub struct LogsWebSocket {
+    upgrade: WebSocketUpgrade,
+    apps: Arc<Apps>,
+    app_name: AppName,
+    service_name: String,
+    since: Option<DateTime<FixedOffset>>,
+    limit: Option<usize>,
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for LogsWebSocket {
+    fn respond_to(self, _request: &'r Request) -> Result<Response<'o>, Status> {
+        Response::build()
+            .raw_header(
+                "Sec-WebSocket-Accept",
+                derive_accept_key(self.upgrade.key.as_bytes()),
+            )
+            .upgrade("websocket", self)
+            .ok()
+    }
+}
+
+#[rocket::async_trait]
+impl IoHandler for LogsWebSocket {
+    async fn io(self: Pin<Box<Self>>, io: IoStream) -> std::io::Result<()> {
+        let websocket = WebSocketStream::from_raw_socket(io, Role::Server, None).await;
+        let log_lines = self
+            .apps
+            .stream_logs(&self.app_name, &self.service_name, &self.since, &self.limit)
+            .await;
+
+        forward_logs(websocket, log_lines).await;
+        Ok(())
+    }
+}
+
+/// Sends the log lines as text frames until the log stream ends or the client goes away.
+async fn forward_logs<S>(
+    mut websocket: WebSocketStream<S>,
+    mut log_lines: BoxStream<'_, Result<(DateTime<FixedOffset>, String), anyhow::Error>>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        tokio::select! {
+            log_line = log_lines.next() => match log_line {
+                Some(Ok((_, log_line))) => {
+                    if websocket.send(Message::text(log_line)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Err(err)) => {
+                    debug!("Cannot stream logs: {err}");
+                    break;
+                }
+                None => break,
+            },
+            message = websocket.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    if let Err(err) = websocket.close(None).await {
+        trace!("Cannot close log stream: {err}");
+    }
+}
+
+/// A request guard for requests that ask for upgrading the connection to a WebSocket.
+pub(super) struct WebSocketUpgrade {
+    key: String,
+}
+
+#[derive(Debug)]
+pub(super) struct NoWebSocketUpgrade;
+
+impl From<NoWebSocketUpgrade> for HttpApiError {
+    fn from(_: NoWebSocketUpgrade) -> Self {
+        HttpApiError::from(
+            HttpApiProblem::with_title_and_type(http_api_problem::StatusCode::BAD_REQUEST)
+                .detail("The request must upgrade the connection to a WebSocket."),
+        )
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebSocketUpgrade {
+    type Error = NoWebSocketUpgrade;
+
+    async fn from_request(req: &'r Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        let headers = req.headers();
+        let is_upgrade = headers
+            .get("Upgrade")
+            .any(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+        let is_supported_version = headers
+            .get("Sec-WebSocket-Version")
+            .any(|version| version == "13");
+
+        match headers.get_one("Sec-WebSocket-Key") {
+            Some(key) if is_upgrade && is_supported_version => {
+                rocket::request::Outcome::Success(WebSocketUpgrade {
+                    key: key.to_string(),
+                })
+            }
+            _ => rocket::request::Outcome::Error((Status::BadRequest, NoWebSocketUpgrade)),
+        }
+    }
+}
+
 #[derive(FromForm)]
 pub(super) struct LogQuery {
     since: Option<String>,
     limit: Option<usize>,
+    grep: Option<String>,
+    invert: bool,
     #[field(name = "asAttachment")]
     as_attachment: bool,
 }
 
+impl LogQuery {
+    fn since(&self) -> Result<Option<DateTime<FixedOffset>>, InvalidLogQuery> {
+        self.since
+            .as_ref()
+            .map(|since| DateTime::parse_from_rfc3339(since))
+            .transpose()
+            .map_err(|err| InvalidLogQuery(err.to_string()))
+    }
+
+    fn filter(&self) -> Result<Option<LogFilter>, InvalidLogQuery> {
+        self.grep
+            .as_ref()
+            .map(|grep| Regex::new(grep))
+            .transpose()
+            .map(|regex| regex.map(|regex| LogFilter::new(regex, self.invert)))
+            .map_err(|err| InvalidLogQuery(err.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct InvalidLogQuery(String);
+
+impl From<InvalidLogQuery> for HttpApiError {
+    fn from(InvalidLogQuery(detail): InvalidLogQuery) -> Self {
+        HttpApiProblem::with_title_and_type(http_api_problem::StatusCode::BAD_REQUEST)
+            .detail(detail)
+            .into()
+    }
+}
+
 pub(super) struct AcceptingPlainText;
 
 #[rocket::async_trait]
@@ -197,11 +404,17 @@ impl<'r> FromRequest<'r> for AcceptingPlainText {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{apps::HostMetaCache, infrastructure::Dummy, models::AppStatusChangeId, sc};
+    use crate::{
+        apps::{HostMetaCache, Replication},
+        infrastructure::Dummy,
+        models::AppStatusChangeId,
+        sc,
+    };
     use rocket::{
         http::{hyper::header::CONTENT_TYPE, Accept, Header},
         local::asynchronous::Client,
     };
+    use std::io::Read;
 
     async fn set_up_rocket_with_dummy_infrastructure_and_a_running_app(
         host_meta_cache: HostMetaCache,
@@ -212,22 +425,23 @@ mod test {
             .create_or_update(
                 &AppName::master(),
                 &AppStatusChangeId::new(),
-                None,
+                Replication::FromDefault,
                 &vec![sc!("service-a")],
                 None,
             )
             .await?;
 
-        let rocket = rocket::build()
-            .manage(host_meta_cache)
-            .manage(apps)
-            .mount("/api/apps", routes![logs, stream_logs]);
+        let rocket = rocket::build().manage(host_meta_cache).manage(apps).mount(
+            "/api/apps",
+            routes![logs, stream_logs, stream_logs_over_websocket],
+        );
         Ok(Client::tracked(rocket).await.expect("valid rocket"))
     }
 
     #[tokio::test]
     async fn log_weblink_with_no_limit() -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -249,7 +463,8 @@ mod test {
 
     #[tokio::test]
     async fn log_weblink_with_some_limit() -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -267,10 +482,52 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn log_lines_filtered_by_grep() -> Result<(), crate::apps::AppsServiceError> {
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
+
+        let client =
+            set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+        let response = client
+            .get("/api/apps/master/logs/service-a?limit=1&grep=msg+%5B12%5D%7Cx%26y&invert=true")
+            .header(Accept::Text)
+            .dispatch()
+            .await;
+        assert_eq!(
+            response.headers().get_one("Link"),
+            Some("</api/apps/master/logs/service-a?limit=1&since=2019-07-18T07:35:00.001%2B00:00&grep=msg+%5B12%5D%7Cx%26y&invert=true>;rel=next")
+        );
+        assert_eq!(
+            response.into_string().await.unwrap(),
+            "Log msg 3 of service-a of app master\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_lines_with_invalid_grep() -> Result<(), crate::apps::AppsServiceError> {
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
+
+        let client =
+            set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+        let response = client
+            .get("/api/apps/master/logs/service-a?grep=%5B")
+            .header(Accept::Text)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn log_content_disposition_for_downloading_as_attachment(
     ) -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -291,7 +548,8 @@ mod test {
     #[tokio::test]
     async fn log_content_disposition_for_displaying_as_inline(
     ) -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -307,10 +565,75 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn log_compressed_for_downloading_as_attachment(
+    ) -> Result<(), crate::apps::AppsServiceError> {
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
+
+        let client =
+            set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+        let response = client
+            .get("/api/apps/master/logs/service-a?limit=2&asAttachment=true")
+            .header(Accept::Text)
+            .header(Header::new("Accept-Encoding", "gzip, deflate, br"))
+            .dispatch()
+            .await;
+        assert_eq!(
+            response.headers().get_one(CONTENT_ENCODING.as_str()),
+            Some("gzip")
+        );
+
+        let mut log_lines = String::new();
+        flate2::read::GzDecoder::new(response.into_bytes().await.unwrap().as_slice())
+            .read_to_string(&mut log_lines)
+            .unwrap();
+        assert_eq!(
+            log_lines,
+            "Log msg 1 of service-a of app master\nLog msg 2 of service-a of app master\n"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_uncompressed_for_clients_without_gzip_support(
+    ) -> Result<(), crate::apps::AppsServiceError> {
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
+
+        let client =
+            set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+        for (query, accept_encoding) in [
+            ("asAttachment=true", "gzip;q=0, br"),
+            ("asAttachment=false", "gzip"),
+        ]
+        .iter()
+        {
+            let response = client
+                .get(format!("/api/apps/master/logs/service-a?{query}"))
+                .header(Accept::Text)
+                .header(Header::new("Accept-Encoding", *accept_encoding))
+                .dispatch()
+                .await;
+            assert_eq!(response.headers().get_one(CONTENT_ENCODING.as_str()), None);
+            assert!(response
+                .into_string()
+                .await
+                .unwrap()
+                .starts_with("Log msg 1"));
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn log_content_type_when_accepting_text_star() -> Result<(), crate::apps::AppsServiceError>
     {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -333,7 +656,8 @@ mod test {
     #[tokio::test]
     async fn respond_with_plain_log_content_type_when_accepting_with_firefox_accept_default_value(
     ) -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -356,7 +680,8 @@ mod test {
     #[tokio::test]
     async fn log_content_type_when_accepting_text_stream(
     ) -> Result<(), crate::apps::AppsServiceError> {
-        let (host_meta_cache, mut _host_meta_crawler) = crate::host_meta_crawling();
+        let (host_meta_cache, mut _host_meta_crawler) =
+            crate::host_meta_crawling(&Default::default());
 
         let client =
             set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
@@ -372,4 +697,88 @@ mod test {
 
         Ok(())
     }
+
+    mod websocket {
+        use super::*;
+        use rocket::http::Status;
+
+        #[tokio::test]
+        async fn accept_websocket_upgrade() -> Result<(), crate::apps::AppsServiceError> {
+            let (host_meta_cache, mut _host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
+
+            let client =
+                set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+            let response = client
+                .get("/api/apps/master/logs/service-a/ws?since=2019-07-22T08:42:47-00:00")
+                .header(Header::new("Connection", "Upgrade"))
+                .header(Header::new("Upgrade", "websocket"))
+                .header(Header::new("Sec-WebSocket-Version", "13"))
+                .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+                .dispatch()
+                .await;
+
+            assert_eq!(
+                response.headers().get_one("Sec-WebSocket-Accept"),
+                Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+            );
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn reject_request_without_websocket_upgrade(
+        ) -> Result<(), crate::apps::AppsServiceError> {
+            let (host_meta_cache, mut _host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
+
+            let client =
+                set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
+
+            let response = client
+                .get("/api/apps/master/logs/service-a/ws")
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::BadRequest);
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn forward_log_lines_as_text_frames_and_close_at_the_end() {
+            let (server, client) = tokio::io::duplex(1024);
+            let server = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+            let mut client = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+
+            let log_lines = futures::stream::iter(vec![
+                Ok((
+                    DateTime::parse_from_rfc3339("2019-07-18T07:25:00Z").unwrap(),
+                    String::from("Log msg 1\n"),
+                )),
+                Ok((
+                    DateTime::parse_from_rfc3339("2019-07-18T07:30:00Z").unwrap(),
+                    String::from("Log msg 2\n"),
+                )),
+            ])
+            .boxed();
+            let forwarding = tokio::spawn(forward_logs(server, log_lines));
+
+            let mut messages = Vec::new();
+            while let Some(Ok(message)) = client.next().await {
+                messages.push(message);
+            }
+            forwarding.await.unwrap();
+
+            assert_eq!(
+                messages,
+                vec![
+                    Message::text("Log msg 1\n"),
+                    Message::text("Log msg 2\n"),
+                    Message::Close(None),
+                ]
+            );
+        }
+    }
 }
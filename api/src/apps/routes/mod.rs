@@ -25,23 +25,29 @@
  */
 
 use crate::apps::HostMetaCache;
-use crate::apps::{Apps, AppsError};
+use crate::apps::{Apps, AppsError, DeployedServices, DeploymentTimings, Replication};
 use crate::http_result::{HttpApiError, HttpResult};
 use crate::models::request_info::RequestInfo;
-use crate::models::service::{Service, ServiceStatus, Services, ServicesWithHostMeta};
-use crate::models::{AppName, AppNameError};
+use crate::models::service::{ServiceStatus, Services, ServicesWithHostMeta};
+use crate::models::{AppGraph, AppName, AppNameError, AppResourceUsage, Image};
 use crate::models::{AppStatusChangeId, AppStatusChangeIdError};
+use crate::registry::ImageInfo;
 use create_app_payload::CreateAppPayload;
+use dry_run::DeploymentPreview;
 use http_api_problem::{HttpApiProblem, StatusCode};
+use rate_limit::{CreateAppRateLimit, RateLimitExceeded};
 use regex::Regex;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::status::Accepted;
 use rocket::response::stream::{Event, EventStream};
 use rocket::response::{Responder, Response};
 use rocket::serde::json::Json;
+use rocket::Either;
 use rocket::{Shutdown, State};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::future::Future;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::task::Poll;
 use std::time::Duration;
@@ -50,34 +56,90 @@ use tokio::sync::watch::Receiver;
 use tokio::time::timeout;
 use tokio_stream::StreamExt;
 
+pub use graphql::graphql_schema;
+pub use rate_limit::CreateAppRateLimiter;
+
+mod companions;
 mod create_app_payload;
+mod dry_run;
+mod graphql;
 mod logs;
+mod rate_limit;
 
 pub fn apps_routes() -> Vec<rocket::Route> {
     rocket::routes![
         apps,
         stream_apps,
         delete_app,
+        delete_apps,
         create_app,
+        reconcile_app,
+        redeploy_app,
+        pinned,
+        pin_app,
         logs::logs,
         logs::stream_logs,
+        logs::stream_logs_over_websocket,
         change_status,
+        scale_service,
         status_change,
+        status_change_events,
+        app_readiness,
+        app_graph,
+        resource_usage,
+        service_image,
     ]
 }
 
-#[get("/", format = "application/json", rank = 1)]
+pub fn companions_routes() -> Vec<rocket::Route> {
+    rocket::routes![companions::companions]
+}
+
+pub fn graphql_routes() -> Vec<rocket::Route> {
+    rocket::routes![graphql::graphql]
+}
+
+#[get("/?<label>", format = "application/json", rank = 1)]
 async fn apps(
     apps: &State<Arc<Apps>>,
     request_info: RequestInfo,
     host_meta_cache: &State<HostMetaCache>,
+    label: Option<Vec<String>>,
 ) -> HttpResult<Json<HashMap<AppName, ServicesWithHostMeta>>> {
-    let services = apps.fetch_apps().await?;
+    let label = label.unwrap_or_default();
+    let labels = parse_label_filters(&label).map_err(|label| {
+        HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
+            .detail(format!("Invalid label filter {label}: expected key=value"))
+    })?;
+
+    let mut services = apps.fetch_apps().await?;
+    if !labels.is_empty() {
+        services.retain(|_, services| {
+            services.iter().any(|service| {
+                labels.iter().all(|(key, value)| {
+                    service.config.metadata().get(*key).map(String::as_str) == Some(*value)
+                })
+            })
+        });
+    }
+
     Ok(Json(
         host_meta_cache.update_meta_data(services, &request_info),
     ))
 }
 
+/// Parses filters like `team=foo` that match the metadata of the services of an app. Returns the
+/// invalid filter, if any.
+fn parse_label_filters(labels: &[String]) -> Result<Vec<(&str, &str)>, &String> {
+    labels
+        .iter()
+        .map(|label| match label.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key, value)),
+            _ => Err(label),
+        })
+        .collect()
+}
+
 #[get("/", format = "text/event-stream", rank = 2)]
 async fn stream_apps(
     apps_updates: &State<Receiver<HashMap<AppName, Services>>>,
@@ -133,18 +195,53 @@ async fn status_change(
     }
 }
 
-#[delete("/<app_name>")]
+/// Streams the progress of a status change as server-sent events until it has finished, e.g.
+/// `{"phase":"deploying","services":["service-a"]}`.
+#[get(
+    "/<app_name>/status-changes/<status_id>/events",
+    format = "text/event-stream"
+)]
+async fn status_change_events(
+    app_name: Result<AppName, AppNameError>,
+    status_id: Result<AppStatusChangeId, AppStatusChangeIdError>,
+    apps: &State<Arc<Apps>>,
+    mut end: Shutdown,
+) -> HttpResult<EventStream![]> {
+    let _app_name = app_name?;
+    let status_id = status_id?;
+
+    let Some(mut progress) = apps.status_change_progress(&status_id).await? else {
+        return Err(HttpApiProblem::with_title_and_type(StatusCode::NOT_FOUND).into());
+    };
+
+    Ok(EventStream! {
+        loop {
+            let next = select! {
+                next = progress.next() => next,
+                _ = &mut end => None,
+            };
+            match next {
+                Some(progress) => yield Event::json(&progress),
+                None => break,
+            }
+        }
+    })
+}
+
+#[delete("/<app_name>?<force>")]
 pub async fn delete_app(
     app_name: Result<AppName, AppNameError>,
     apps: &State<Arc<Apps>>,
     options: RunOptions,
+    force: Option<bool>,
 ) -> HttpResult<AsyncCompletion<Json<Services>>> {
     let app_name = app_name?;
     let app_name_cloned = app_name.clone();
     let status_id = AppStatusChangeId::new();
+    let force = force.unwrap_or(false);
 
     let apps = (**apps).clone();
-    let future = async move { apps.delete_app(&app_name, &status_id).await };
+    let future = async move { apps.delete_app(&app_name, &status_id, force).await };
 
     match spawn_with_options(options, future).await? {
         Poll::Pending => Ok(AsyncCompletion::Pending(app_name_cloned, status_id)),
@@ -153,11 +250,40 @@ pub async fn delete_app(
     }
 }
 
+/// Deletes all apps whose names start with the given prefix in the background and responds with
+/// the locations of their status changes.
+#[delete("/?<prefix>")]
+async fn delete_apps(
+    prefix: &str,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Accepted<Json<Vec<String>>>> {
+    if prefix.is_empty() {
+        return Err(HttpApiProblem::with_title_and_type(StatusCode::BAD_REQUEST)
+            .detail("The prefix must not be empty.")
+            .into());
+    }
+
+    let mut locations = Vec::new();
+    for app_name in apps.fetch_unprotected_app_names_with_prefix(prefix).await? {
+        let status_id = AppStatusChangeId::new();
+        locations.push(status_change_location(&app_name, &status_id));
+
+        let apps = (**apps).clone();
+        tokio::spawn(async move {
+            if let Err(err) = apps.delete_app(&app_name, &status_id, false).await {
+                error!("Cannot delete {app_name}: {err}");
+            }
+        });
+    }
+
+    Ok(Accepted(Json(locations)))
+}
+
 pub async fn delete_app_sync(
     app_name: Result<AppName, AppNameError>,
     apps: &State<Arc<Apps>>,
 ) -> HttpResult<Json<Services>> {
-    match delete_app(app_name, apps, RunOptions::Sync).await? {
+    match delete_app(app_name, apps, RunOptions::Sync, None).await? {
         AsyncCompletion::Pending(_, _) => {
             Err(HttpApiProblem::with_title_and_type(StatusCode::INTERNAL_SERVER_ERROR).into())
         }
@@ -165,37 +291,104 @@ pub async fn delete_app_sync(
     }
 }
 
-#[post(
-    "/<app_name>?<create_app_form..>",
-    format = "application/json",
-    data = "<payload>"
-)]
+#[post("/<app_name>?<create_app_form..>", data = "<payload>")]
 pub async fn create_app(
+    rate_limit: Result<CreateAppRateLimit, RateLimitExceeded>,
     app_name: Result<AppName, AppNameError>,
     apps: &State<Arc<Apps>>,
     create_app_form: CreateAppOptions,
     payload: Result<CreateAppPayload, HttpApiProblem>,
     options: RunOptions,
-) -> HttpResult<AsyncCompletion<Json<Services>>> {
-    let payload = payload.map_err(HttpApiError::from)?;
+) -> HttpResult<Either<AsyncCompletion<DeployedServices>, Json<DeploymentPreview>>> {
+    rate_limit?;
+    let CreateAppPayload {
+        services,
+        user_defined_parameters,
+        metadata,
+    } = payload.map_err(HttpApiError::from)?;
+    apps.validate_service_configs(&services)?;
+    let services = match create_app_form.template() {
+        Some(template_name) => apps.expand_template(template_name, services)?,
+        None => services,
+    };
 
     let status_id = AppStatusChangeId::new();
     let app_name = app_name?;
+    let replication = create_app_form.replication();
+
+    if create_app_form.dry_run() {
+        let deployment_unit = apps
+            .create_or_update_dry_run(&app_name, replication, &services, user_defined_parameters)
+            .await?;
+        return Ok(Either::Right(Json(DeploymentPreview::from(
+            deployment_unit,
+        ))));
+    }
+
+    let force = create_app_form.force();
     let app_name_cloned = app_name.clone();
-    let replicate_from = create_app_form.replicate_from().clone();
 
     let apps = (**apps).clone();
     let future = async move {
-        apps.create_or_update(
+        apps.create_or_update_with_metadata(
             &app_name.clone(),
             &status_id,
-            replicate_from,
-            &payload.services,
-            payload.user_defined_parameters,
+            replication,
+            &services,
+            user_defined_parameters,
+            metadata,
+            force,
         )
         .await
     };
 
+    match spawn_with_options(options, future).await? {
+        Poll::Pending => Ok(Either::Left(AsyncCompletion::Pending(
+            app_name_cloned,
+            status_id,
+        ))),
+        Poll::Ready(Ok(deployed)) => Ok(Either::Left(AsyncCompletion::Ready(deployed))),
+        Poll::Ready(Err(err)) => Err(err.into()),
+    }
+}
+
+#[post("/<app_name>/reconcile?<force>")]
+async fn reconcile_app(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+    options: RunOptions,
+    force: Option<bool>,
+) -> HttpResult<AsyncCompletion<Json<Services>>> {
+    let app_name = app_name?;
+    let app_name_cloned = app_name.clone();
+    let status_id = AppStatusChangeId::new();
+    let force = force.unwrap_or(false);
+
+    let apps = (**apps).clone();
+    let future = async move { apps.reconcile(&app_name, &status_id, force).await };
+
+    match spawn_with_options(options, future).await? {
+        Poll::Pending => Ok(AsyncCompletion::Pending(app_name_cloned, status_id)),
+        Poll::Ready(Ok(services)) => Ok(AsyncCompletion::Ready(Json(services))),
+        Poll::Ready(Err(err)) => Err(err.into()),
+    }
+}
+
+#[post("/<app_name>/redeploy?<force>")]
+async fn redeploy_app(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+    options: RunOptions,
+    force: Option<bool>,
+) -> HttpResult<AsyncCompletion<Json<Services>>> {
+    let app_name = app_name?;
+    let app_name_cloned = app_name.clone();
+    let status_id = AppStatusChangeId::new();
+    let force = force.unwrap_or(false);
+
+    let apps = (**apps).clone();
+    let future = async move { apps.redeploy(&app_name, &status_id, force).await };
+
     match spawn_with_options(options, future).await? {
         Poll::Pending => Ok(AsyncCompletion::Pending(app_name_cloned, status_id)),
         Poll::Ready(Ok(services)) => Ok(AsyncCompletion::Ready(Json(services))),
@@ -203,8 +396,40 @@ pub async fn create_app(
     }
 }
 
+#[get("/<app_name>/pinned", format = "application/json")]
+async fn pinned(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Json<PinnedData>> {
+    let app_name = app_name?;
+    if !apps.fetch_app_names().await?.contains(&app_name) {
+        return Err(AppsError::AppNotFound { app_name }.into());
+    }
+
+    Ok(Json(PinnedData {
+        pinned: apps.is_pinned(&app_name).await?,
+    }))
+}
+
 #[put(
-    "/<app_name>/states/<service_name>",
+    "/<app_name>/pinned?<force>",
+    format = "application/json",
+    data = "<pinned_data>"
+)]
+async fn pin_app(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+    pinned_data: Json<PinnedData>,
+    force: Option<bool>,
+) -> HttpResult<Json<PinnedData>> {
+    let app_name = app_name?;
+    apps.set_pinned(&app_name, pinned_data.pinned, force.unwrap_or(false))
+        .await?;
+    Ok(pinned_data)
+}
+
+#[put(
+    "/<app_name>/states/<service_name>?<force>",
     format = "application/json",
     data = "<status_data>"
 )]
@@ -213,13 +438,89 @@ async fn change_status(
     service_name: String,
     apps: &State<Arc<Apps>>,
     status_data: Json<ServiceStatusData>,
+    force: Option<bool>,
 ) -> HttpResult<ServiceStatusResponse> {
     let app_name = app_name?;
     let status = status_data.status.clone();
 
-    let service = apps.change_status(&app_name, &service_name, status).await?;
+    apps.change_status(&app_name, &service_name, status, force.unwrap_or(false))
+        .await?;
+
+    Ok(ServiceStatusResponse)
+}
+
+#[put(
+    "/<app_name>/<service_name>/scale?<force>",
+    format = "application/json",
+    data = "<scale_data>",
+    rank = 2
+)]
+async fn scale_service(
+    app_name: Result<AppName, AppNameError>,
+    service_name: String,
+    apps: &State<Arc<Apps>>,
+    scale_data: Json<ServiceScaleData>,
+    force: Option<bool>,
+) -> HttpResult<ServiceStatusResponse> {
+    let app_name = app_name?;
+
+    apps.scale_service(
+        &app_name,
+        &service_name,
+        scale_data.replicas,
+        force.unwrap_or(false),
+    )
+    .await?;
+
+    Ok(ServiceStatusResponse)
+}
+
+#[get("/<app_name>/ready", format = "application/json")]
+async fn app_readiness(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<AppReadiness> {
+    let app_name = app_name?;
+
+    let services = apps
+        .fetch_apps()
+        .await?
+        .remove(&app_name)
+        .ok_or(AppsError::AppNotFound { app_name })?;
+
+    Ok(AppReadiness::from(&services))
+}
+
+#[get("/<app_name>/graph", format = "application/json")]
+async fn app_graph(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Json<AppGraph>> {
+    let app_name = app_name?;
+    Ok(Json(apps.fetch_app_graph(&app_name).await?))
+}
+
+#[get("/<app_name>/stats", format = "application/json")]
+async fn resource_usage(
+    app_name: Result<AppName, AppNameError>,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Json<AppResourceUsage>> {
+    let app_name = app_name?;
+    Ok(Json(apps.fetch_resource_usage(&app_name).await?))
+}
 
-    Ok(ServiceStatusResponse { service })
+#[get(
+    "/<app_name>/services/<service_name>/image",
+    format = "application/json"
+)]
+async fn service_image(
+    app_name: Result<AppName, AppNameError>,
+    service_name: &str,
+    apps: &State<Arc<Apps>>,
+) -> HttpResult<Json<ImageProvenance>> {
+    let app_name = app_name?;
+    let (image, image_info) = apps.resolve_service_image(&app_name, service_name).await?;
+    Ok(Json(ImageProvenance::new(&image, image_info.as_ref())))
 }
 
 #[derive(Debug, PartialEq)]
@@ -266,21 +567,120 @@ fn map_join_error(err: tokio::task::JoinError) -> HttpApiError {
 pub struct CreateAppOptions {
     #[field(name = "replicateFrom")]
     replicate_from: Option<AppName>,
+    #[field(name = "noReplicate", default = false)]
+    no_replicate: bool,
+    template: Option<String>,
+    #[field(default = false)]
+    force: bool,
+    #[field(name = "dryRun", default = false)]
+    dry_run: bool,
 }
 
 impl CreateAppOptions {
-    fn replicate_from(&self) -> &Option<AppName> {
-        &self.replicate_from
+    /// `noReplicate` takes precedence over `replicateFrom`.
+    fn replication(&self) -> Replication {
+        if self.no_replicate {
+            return Replication::None;
+        }
+        match &self.replicate_from {
+            Some(replicate_from) => Replication::From(replicate_from.clone()),
+            None => Replication::FromDefault,
+        }
+    }
+
+    fn template(&self) -> Option<&str> {
+        self.template.as_deref()
+    }
+
+    fn force(&self) -> bool {
+        self.force
+    }
+
+    fn dry_run(&self) -> bool {
+        self.dry_run
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct PinnedData {
+    pinned: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ServiceStatusData {
     status: ServiceStatus,
 }
 
-pub struct ServiceStatusResponse {
-    service: Option<Service>,
+#[derive(Deserialize)]
+pub struct ServiceScaleData {
+    replicas: NonZeroU32,
+}
+
+/// Accepts the status change of a service. Unknown apps or services are reported as
+/// [`AppsError`] so that clients receive a problem body.
+pub struct ServiceStatusResponse;
+
+/// Aggregates the readiness of all services of an app. A service is considered as ready if it is
+/// running.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AppReadiness {
+    ready: bool,
+    services: Vec<ServiceReadiness>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ServiceReadiness {
+    name: String,
+    ready: bool,
+    status: ServiceStatus,
+}
+
+/// Describes where the image of a service comes from, e.g. to answer what exactly is running in
+/// an app during an audit.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ImageProvenance {
+    image: String,
+    registry: Option<String>,
+    repository: Option<String>,
+    tag: Option<String>,
+    digest: Option<String>,
+    labels: BTreeMap<String, String>,
+}
+
+impl ImageProvenance {
+    fn new(image: &Image, image_info: Option<&ImageInfo>) -> Self {
+        let digest = image
+            .digest()
+            .map(String::from)
+            .or_else(|| image_info.map(|info| info.digest().clone()));
+
+        Self {
+            image: image.to_string(),
+            registry: image.registry(),
+            repository: image.name(),
+            tag: image.tag(),
+            digest,
+            labels: image_info.map(ImageInfo::labels).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&Services> for AppReadiness {
+    fn from(services: &Services) -> Self {
+        let services = services
+            .iter()
+            .map(|service| ServiceReadiness {
+                name: service.service_name().clone(),
+                ready: *service.status() == ServiceStatus::Running,
+                status: service.status().clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            ready: services.iter().all(|service| service.ready),
+            services,
+        }
+    }
 }
 
 pub enum AsyncCompletion<T> {
@@ -294,24 +694,62 @@ where
 {
     fn respond_to(self, request: &'r Request) -> Result<Response<'static>, Status> {
         match self {
-            AsyncCompletion::Pending(app_name, status_id) => {
-                let url = format!("/api/apps/{}/status-changes/{}", app_name, status_id);
-                Response::build()
-                    .status(Status::Accepted)
-                    .raw_header("Location", url)
-                    .ok()
-            }
+            AsyncCompletion::Pending(app_name, status_id) => Response::build()
+                .status(Status::Accepted)
+                .raw_header("Location", status_change_location(&app_name, &status_id))
+                .ok(),
             AsyncCompletion::Ready(result) => result.respond_to(request),
         }
     }
 }
 
+fn status_change_location(app_name: &AppName, status_id: &AppStatusChangeId) -> String {
+    format!("/api/apps/{}/status-changes/{}", app_name, status_id)
+}
+
+/// Responds with the deployed services, adds a `Warning` header for each deployment warning and a
+/// `Server-Timing` header with the durations of the deployment phases.
+impl<'r> Responder<'r, 'static> for DeployedServices {
+    fn respond_to(self, request: &'r Request) -> Result<Response<'static>, Status> {
+        let mut response = Json(self.services).respond_to(request)?;
+        for warning in self.warnings {
+            response.adjoin_raw_header("Warning", format!("299 PREvant \"{warning}\""));
+        }
+        response.set_raw_header("Server-Timing", server_timing(&self.timings));
+        Ok(response)
+    }
+}
+
+/// Formats the timings as value of the `Server-Timing` header, e.g.
+/// `resolve-images;dur=3000.0, deploy;dur=9000.0, total;dur=12000.0`.
+fn server_timing(timings: &DeploymentTimings) -> String {
+    timings
+        .phases()
+        .iter()
+        .copied()
+        .chain(std::iter::once(("total", timings.total())))
+        .map(|(phase, duration)| format!("{phase};dur={:.1}", duration.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<'r> Responder<'r, 'static> for AppReadiness {
+    fn respond_to(self, request: &'r Request) -> Result<Response<'static>, Status> {
+        let status = if self.ready {
+            Status::Ok
+        } else {
+            Status::ServiceUnavailable
+        };
+
+        Response::build_from(Json(self).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
 impl<'r> Responder<'r, 'static> for ServiceStatusResponse {
     fn respond_to(self, _request: &'r Request) -> Result<Response<'static>, Status> {
-        match self.service {
-            Some(_service) => Response::build().status(Status::Accepted).ok(),
-            None => Response::build().status(Status::NotFound).ok(),
-        }
+        Response::build().status(Status::Accepted).ok()
     }
 }
 
@@ -319,19 +757,38 @@ impl From<AppsError> for HttpApiError {
     fn from(error: AppsError) -> Self {
         let status = match &error {
             AppsError::InvalidUserDefinedParameters { .. } => StatusCode::BAD_REQUEST,
+            AppsError::EnvOverridesNotAllowed { .. } => StatusCode::BAD_REQUEST,
             AppsError::AppLimitExceeded { .. } => StatusCode::PRECONDITION_FAILED,
             AppsError::UnableToResolveImage { error } => match **error {
                 crate::registry::RegistryError::ImageNotFound { .. } => StatusCode::NOT_FOUND,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
             AppsError::AppNotFound { .. } => StatusCode::NOT_FOUND,
+            AppsError::ServiceNotFound { .. } => StatusCode::NOT_FOUND,
             AppsError::AppIsInDeployment { .. } => StatusCode::CONFLICT,
             AppsError::AppIsInDeletion { .. } => StatusCode::CONFLICT,
+            AppsError::UnknownUserDefinedParameters { .. } => StatusCode::CONFLICT,
+            AppsError::AppIsProtected { .. } | AppsError::DefaultAppIsProtected { .. } => {
+                StatusCode::FORBIDDEN
+            }
+            AppsError::ExposedPortsNotAllowed { .. } => StatusCode::FORBIDDEN,
+            AppsError::UnknownServicePorts { .. } => StatusCode::BAD_REQUEST,
+            AppsError::PostDeployHookFailed { .. } => StatusCode::BAD_GATEWAY,
+            AppsError::UnknownTemplate { .. } => StatusCode::BAD_REQUEST,
+            AppsError::UnknownRuntime { .. } => StatusCode::BAD_REQUEST,
+            AppsError::ScalingNotSupported { .. } => StatusCode::NOT_IMPLEMENTED,
+            AppsError::InfrastructureError { error }
+                if crate::infrastructure::is_timeout(error) =>
+            {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             AppsError::FailedToParseTraefikRule { .. }
             | AppsError::InfrastructureError { .. }
             | AppsError::InvalidServerConfiguration { .. }
             | AppsError::InvalidTemplateFormat { .. }
-            | AppsError::InvalidDeploymentHook => {
+            | AppsError::InvalidDeploymentHook
+            | AppsError::HookTimeout { .. }
+            | AppsError::CyclicServiceDependencies { .. } => {
                 error!("Internal server error: {}", error);
                 StatusCode::INTERNAL_SERVER_ERROR
             }
@@ -485,7 +942,7 @@ mod tests {
     }
 
     mod url_rendering {
-        use crate::apps::{AppsService, HostMetaCache};
+        use crate::apps::{AppsService, HostMetaCache, Replication};
         use crate::infrastructure::Dummy;
         use crate::models::service::Services;
         use crate::models::{AppName, AppStatusChangeId};
@@ -510,7 +967,7 @@ mod tests {
                 .create_or_update(
                     &AppName::master(),
                     &AppStatusChangeId::new(),
-                    None,
+                    Replication::FromDefault,
                     &vec![sc!("service-a")],
                     None,
                 )
@@ -528,7 +985,8 @@ mod tests {
         #[tokio::test]
         async fn host_header_response_with_xforwardedhost_xforwardedproto_and_xforwardedport(
         ) -> Result<(), crate::apps::AppsServiceError> {
-            let (host_meta_cache, mut host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let client =
                 set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
             host_meta_crawler.fake_empty_host_meta_info(AppName::master(), "service-a".to_string());
@@ -561,7 +1019,8 @@ mod tests {
         #[tokio::test]
         async fn host_header_response_with_xforwardedproto_and_other_default_values(
         ) -> Result<(), crate::apps::AppsServiceError> {
-            let (host_meta_cache, mut host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let client =
                 set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
             host_meta_crawler.fake_empty_host_meta_info(AppName::master(), "service-a".to_string());
@@ -592,7 +1051,8 @@ mod tests {
         #[tokio::test]
         async fn host_header_response_with_xforwardedhost_and_other_default_values(
         ) -> Result<(), crate::apps::AppsServiceError> {
-            let (host_meta_cache, mut host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let client =
                 set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
             host_meta_crawler.fake_empty_host_meta_info(AppName::master(), "service-a".to_string());
@@ -622,7 +1082,8 @@ mod tests {
         #[tokio::test]
         async fn host_header_response_with_xforwardedport_and_default_values(
         ) -> Result<(), crate::apps::AppsServiceError> {
-            let (host_meta_cache, mut host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let client =
                 set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
             host_meta_crawler.fake_empty_host_meta_info(AppName::master(), "service-a".to_string());
@@ -653,7 +1114,8 @@ mod tests {
         #[tokio::test]
         async fn host_header_response_with_all_default_values(
         ) -> Result<(), crate::apps::AppsServiceError> {
-            let (host_meta_cache, mut host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let client =
                 set_up_rocket_with_dummy_infrastructure_and_a_running_app(host_meta_cache).await?;
             host_meta_crawler.fake_empty_host_meta_info(AppName::master(), "service-a".to_string());
@@ -681,7 +1143,8 @@ mod tests {
 
         #[tokio::test]
         async fn bad_request_without_host_header() {
-            let (host_meta_cache, _host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, _host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let infrastructure = Box::new(Dummy::new());
             let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
 
@@ -690,7 +1153,7 @@ mod tests {
                 .manage(apps)
                 .mount("/", routes![crate::apps::routes::apps]);
             let client = Client::tracked(rocket).await.expect("valid rocket");
-            let mut get = client.get(rocket::uri!(crate::apps::routes::apps));
+            let mut get = client.get(rocket::uri!(crate::apps::routes::apps(_)));
             get.add_header(ContentType::JSON);
             let response = get.dispatch().await;
             assert_eq!(response.status(), Status::BadRequest);
@@ -698,7 +1161,8 @@ mod tests {
 
         #[tokio::test]
         async fn with_invalid_headers() {
-            let (host_meta_cache, _host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, _host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let infrastructure = Box::new(Dummy::new());
             let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
 
@@ -708,7 +1172,7 @@ mod tests {
                 .mount("/", routes![crate::apps::routes::apps]);
             let client = Client::tracked(rocket).await.expect("valid rocket");
             let get = client
-                .get(rocket::uri!(crate::apps::routes::apps))
+                .get(rocket::uri!(crate::apps::routes::apps(_)))
                 .header(Header::new("x-forwarded-host", ""));
 
             let response = get.dispatch().await;
@@ -717,7 +1181,8 @@ mod tests {
 
         #[tokio::test]
         async fn with_invalid_proto() {
-            let (host_meta_cache, _host_meta_crawler) = crate::host_meta_crawling();
+            let (host_meta_cache, _host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
             let infrastructure = Box::new(Dummy::new());
             let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
 
@@ -727,7 +1192,7 @@ mod tests {
                 .mount("/", routes![crate::apps::routes::apps]);
             let client = Client::tracked(rocket).await.expect("valid rocket");
             let get = client
-                .get(rocket::uri!(crate::apps::routes::apps))
+                .get(rocket::uri!(crate::apps::routes::apps(_)))
                 .header(Header::new("x-forwarded-proto", "."));
 
             let response = get.dispatch().await;
@@ -777,7 +1242,11 @@ mod tests {
                     "type": "https://httpstatuses.com/400",
                     "status": 400,
                     "title": "Bad Request",
-                    "detail": "Invalid image: private-registry.example.com/_/postgres"
+                    "detail": "Invalid image: private-registry.example.com/_/postgres",
+                    "errors": [{
+                        "index": 0,
+                        "detail": "Invalid image: private-registry.example.com/_/postgres"
+                    }]
                 })
             );
         }
@@ -1035,4 +1504,1494 @@ mod tests {
             );
         }
     }
+
+    mod replication {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn client_with_master_app() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(
+                AppsService::new(crate::config::Config::default(), infrastructure).unwrap(),
+            );
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::create_app]);
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        async fn deployed_service_names(client: &Client, uri: &str) -> Vec<String> {
+            let response = client
+                .post(uri.to_string())
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "service-b",
+                        "image": format!("sha256:{}", "b".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let body = response.into_string().await.unwrap();
+            let mut names = serde_json::from_str::<Vec<serde_json::Value>>(&body)
+                .unwrap()
+                .into_iter()
+                .map(|service| service["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        }
+
+        #[tokio::test]
+        async fn should_replicate_from_master_by_default() {
+            let client = client_with_master_app().await;
+
+            let names = deployed_service_names(&client, "/feature").await;
+
+            assert_eq!(names, vec!["service-a", "service-b"]);
+        }
+
+        #[tokio::test]
+        async fn should_not_replicate_if_requested() {
+            let client = client_with_master_app().await;
+
+            let names = deployed_service_names(&client, "/feature?noReplicate=true").await;
+
+            assert_eq!(names, vec!["service-b"]);
+        }
+
+        #[tokio::test]
+        async fn should_not_replicate_even_with_explicit_replicate_from() {
+            let client = client_with_master_app().await;
+
+            let names =
+                deployed_service_names(&client, "/feature?replicateFrom=master&noReplicate=true")
+                    .await;
+
+            assert_eq!(names, vec!["service-b"]);
+        }
+    }
+
+    mod default_app_protection {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let config = toml::from_str::<crate::config::Config>(
+                r#"
+                [applications]
+                protectDefaultApp = true
+                "#,
+            )
+            .unwrap();
+            let infrastructure = Box::new(Dummy::with_scaling());
+            let apps = Arc::new(AppsService::new(config, infrastructure).unwrap());
+
+            let rocket = rocket::build().manage(apps).mount(
+                "/",
+                routes![
+                    crate::apps::routes::create_app,
+                    crate::apps::routes::delete_app,
+                    crate::apps::routes::reconcile_app,
+                    crate::apps::routes::redeploy_app,
+                    crate::apps::routes::pin_app,
+                    crate::apps::routes::change_status,
+                    crate::apps::routes::scale_service
+                ],
+            );
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        async fn deploy(client: &Client, uri: &str) -> Status {
+            client
+                .post(uri.to_string())
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await
+                .status()
+        }
+
+        #[tokio::test]
+        async fn should_reject_deployment_of_default_app() {
+            let client = create_client().await;
+
+            assert_eq!(deploy(&client, "/master").await, Status::Forbidden);
+        }
+
+        #[tokio::test]
+        async fn should_deploy_default_app_with_force() {
+            let client = create_client().await;
+
+            assert_eq!(deploy(&client, "/master?force=true").await, Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn should_deploy_other_apps() {
+            let client = create_client().await;
+
+            assert_eq!(deploy(&client, "/feature").await, Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn should_reject_deletion_of_default_app() {
+            let client = create_client().await;
+            assert_eq!(deploy(&client, "/master?force=true").await, Status::Ok);
+
+            let response = client.delete("/master").dispatch().await;
+            assert_eq!(response.status(), Status::Forbidden);
+
+            let response = client.delete("/master?force=true").dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        async fn change_default_app(client: &Client, query: &str) -> Vec<Status> {
+            let mut statuses = Vec::new();
+            for path in ["/master/reconcile", "/master/redeploy"].iter() {
+                let response = client.post(format!("{path}{query}")).dispatch().await;
+                statuses.push(response.status());
+            }
+            for (path, body) in [
+                ("/master/pinned", r#"{"pinned": true}"#),
+                ("/master/states/db", r#"{"status": "paused"}"#),
+                ("/master/db/scale", r#"{"replicas": 2}"#),
+            ]
+            .iter()
+            {
+                let response = client
+                    .put(format!("{path}{query}"))
+                    .header(ContentType::JSON)
+                    .body(*body)
+                    .dispatch()
+                    .await;
+                statuses.push(response.status());
+            }
+            statuses
+        }
+
+        #[tokio::test]
+        async fn should_reject_other_changes_of_default_app() {
+            let client = create_client().await;
+            assert_eq!(deploy(&client, "/master?force=true").await, Status::Ok);
+
+            assert_eq!(
+                change_default_app(&client, "").await,
+                vec![Status::Forbidden; 5]
+            );
+        }
+
+        #[tokio::test]
+        async fn should_change_default_app_with_force() {
+            let client = create_client().await;
+            assert_eq!(deploy(&client, "/master?force=true").await, Status::Ok);
+
+            assert_eq!(
+                change_default_app(&client, "?force=true").await,
+                vec![
+                    Status::Ok,
+                    Status::Ok,
+                    Status::Ok,
+                    Status::Accepted,
+                    Status::Accepted
+                ]
+            );
+        }
+    }
+
+    mod rate_limit {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let config = toml::from_str::<crate::config::Config>(
+                r#"
+                [apiAccess.rateLimit]
+                requests = 1
+                period = '1h'
+                "#,
+            )
+            .unwrap();
+            let rate_limiter = CreateAppRateLimiter::new(config.create_app_rate_limit());
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(config, infrastructure).unwrap());
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .manage(rate_limiter)
+                .mount("/", routes![crate::apps::routes::create_app]);
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        async fn deploy<'c>(
+            client: &'c Client,
+            app_name: &str,
+        ) -> rocket::local::asynchronous::LocalResponse<'c> {
+            client
+                .post(format!("/{app_name}"))
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await
+        }
+
+        #[tokio::test]
+        async fn should_reject_deployments_exceeding_rate_limit() {
+            let client = create_client().await;
+
+            let response = deploy(&client, "feature-a").await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let response = deploy(&client, "feature-b").await;
+            assert_eq!(response.status(), Status::TooManyRequests);
+            assert_eq!(response.headers().get_one("Retry-After"), Some("3600"));
+        }
+    }
+
+    mod reconcile {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+
+            let rocket = rocket::build().manage(apps).mount(
+                "/",
+                routes![
+                    crate::apps::routes::create_app,
+                    crate::apps::routes::reconcile_app
+                ],
+            );
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn should_reconcile_running_app() {
+            let client = create_client().await;
+            let response = client
+                .post("/master")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let response = client.post("/master/reconcile").dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(
+                response.into_json::<serde_json::Value>().await,
+                Some(serde_json::json!([]))
+            );
+        }
+
+        #[tokio::test]
+        async fn should_not_reconcile_unknown_app() {
+            let client = create_client().await;
+
+            let response = client.post("/master/reconcile").dispatch().await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod redeploy {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+
+            let rocket = rocket::build().manage(apps).mount(
+                "/",
+                routes![
+                    crate::apps::routes::create_app,
+                    crate::apps::routes::redeploy_app
+                ],
+            );
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn should_redeploy_running_app() {
+            let client = create_client().await;
+            let response = client
+                .post("/master")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let response = client.post("/master/redeploy").dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let services = response.into_json::<serde_json::Value>().await.unwrap();
+            assert_eq!(services[0]["name"], "db");
+            assert_eq!(services.as_array().map(Vec::len), Some(1));
+        }
+
+        #[tokio::test]
+        async fn should_not_redeploy_unknown_app() {
+            let client = create_client().await;
+
+            let response = client.post("/master/redeploy").dispatch().await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod dry_run {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> (Client, Arc<AppsService>) {
+            let config = toml::from_str::<crate::config::Config>(&format!(
+                r#"
+                [companions.openid]
+                serviceName = "openid"
+                type = "application"
+                image = "sha256:{}"
+
+                [companions.openid.env]
+                REDIRECT_URL = {{ value = "http://{{{{application.name}}}}.example.com", templated = true }}
+                "#,
+                "a".repeat(64)
+            ))
+            .unwrap();
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(config, infrastructure).unwrap());
+
+            let rocket = rocket::build()
+                .manage(apps.clone())
+                .mount("/", routes![crate::apps::routes::create_app]);
+            (Client::tracked(rocket).await.expect("valid rocket"), apps)
+        }
+
+        #[tokio::test]
+        async fn should_resolve_services_without_deploying_nor_revealing_values() {
+            let (client, apps) = create_client().await;
+
+            let response = client
+                .post("/feature-x?dryRun=true")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64)),
+                        "env": { "MYSQL_USER": "admin" }
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let body = response.into_json::<serde_json::Value>().await.unwrap();
+            assert_eq!(
+                body["services"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|service| (service["name"].clone(), service["env"].clone()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (serde_json::json!("db"), serde_json::json!(["MYSQL_USER"])),
+                    (
+                        serde_json::json!("openid"),
+                        serde_json::json!(["REDIRECT_URL"])
+                    ),
+                ]
+            );
+            assert_eq!(
+                body["services"][0]["ingressRoutes"],
+                serde_json::json!(["PathPrefix(`/feature-x/db/`)"])
+            );
+            assert!(!body.to_string().contains("admin"));
+
+            assert!(apps.fetch_app_names().await.unwrap().is_empty());
+        }
+    }
+
+    mod pinned {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+
+            let rocket = rocket::build().manage(apps).mount(
+                "/",
+                routes![
+                    crate::apps::routes::create_app,
+                    crate::apps::routes::pinned,
+                    crate::apps::routes::pin_app
+                ],
+            );
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn should_pin_app() {
+            let client = create_client().await;
+            let response = client
+                .post("/master")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let response = client
+                .put("/master/pinned")
+                .header(ContentType::JSON)
+                .body(r#"{"pinned": true}"#)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let response = client
+                .get("/master/pinned")
+                .header(ContentType::JSON)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(
+                response.into_json::<serde_json::Value>().await,
+                Some(serde_json::json!({ "pinned": true }))
+            );
+        }
+
+        #[tokio::test]
+        async fn should_not_pin_unknown_app() {
+            let client = create_client().await;
+
+            let response = client
+                .put("/master/pinned")
+                .header(ContentType::JSON)
+                .body(r#"{"pinned": true}"#)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod delete_apps {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::local::asynchronous::Client;
+        use std::str::FromStr;
+
+        #[tokio::test]
+        async fn should_delete_apps_with_prefix() {
+            let config = crate::config_from_str!(
+                r#"
+                [applications]
+                protected = 'feature-protected'
+                "#
+            );
+            let apps = Arc::new(AppsService::new(config, Box::new(Dummy::new())).unwrap());
+            for app_name in ["feature-a", "feature-b", "feature-protected", "master"] {
+                apps.create_or_update(
+                    &AppName::from_str(app_name).unwrap(),
+                    &AppStatusChangeId::new(),
+                    Replication::FromDefault,
+                    &[crate::sc!("db")],
+                    None,
+                )
+                .await
+                .unwrap();
+            }
+
+            let rocket = rocket::build()
+                .manage(apps.clone())
+                .mount("/api/apps", routes![crate::apps::routes::delete_apps]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client.delete("/api/apps?prefix=feature-").dispatch().await;
+            assert_eq!(response.status(), Status::Accepted);
+
+            let locations = response.into_json::<Vec<String>>().await.unwrap();
+            assert_eq!(locations.len(), 2);
+            assert!(locations[0].starts_with("/api/apps/feature-a/status-changes/"));
+            assert!(locations[1].starts_with("/api/apps/feature-b/status-changes/"));
+
+            let mut app_names = apps.fetch_app_names().await.unwrap();
+            while app_names.len() > 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                app_names = apps.fetch_app_names().await.unwrap();
+            }
+            assert_eq!(
+                app_names,
+                std::collections::HashSet::from([
+                    AppName::from_str("feature-protected").unwrap(),
+                    AppName::master()
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn should_not_delete_default_app_with_prefix() {
+            let apps =
+                Arc::new(AppsService::new(Default::default(), Box::new(Dummy::new())).unwrap());
+            for app_name in ["master", "master-feature"] {
+                apps.create_or_update(
+                    &AppName::from_str(app_name).unwrap(),
+                    &AppStatusChangeId::new(),
+                    Replication::FromDefault,
+                    &[crate::sc!("db")],
+                    None,
+                )
+                .await
+                .unwrap();
+            }
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/api/apps", routes![crate::apps::routes::delete_apps]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client.delete("/api/apps?prefix=master").dispatch().await;
+            assert_eq!(response.status(), Status::Accepted);
+
+            let locations = response.into_json::<Vec<String>>().await.unwrap();
+            assert_eq!(locations.len(), 1);
+            assert!(locations[0].starts_with("/api/apps/master-feature/status-changes/"));
+        }
+
+        #[tokio::test]
+        async fn should_not_delete_apps_without_prefix() {
+            let apps =
+                Arc::new(AppsService::new(Default::default(), Box::new(Dummy::new())).unwrap());
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/api/apps", routes![crate::apps::routes::delete_apps]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client.delete("/api/apps?prefix=").dispatch().await;
+
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+    }
+
+    mod status_change_events {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::Accept;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client(apps: Arc<AppsService>) -> Client {
+            let rocket = rocket::build().manage(apps).mount(
+                "/api/apps",
+                routes![crate::apps::routes::status_change_events],
+            );
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn should_stream_progress_of_deployment() {
+            let infrastructure = Box::new(Dummy::with_delay(Duration::from_millis(500)));
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            let status_id = AppStatusChangeId::new();
+
+            let deployment = {
+                let apps = apps.clone();
+                tokio::spawn(async move {
+                    apps.create_or_update(
+                        &AppName::master(),
+                        &status_id,
+                        Replication::FromDefault,
+                        &[crate::sc!("db")],
+                        None,
+                    )
+                    .await
+                })
+            };
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let client = create_client(apps).await;
+            let response = client
+                .get(format!(
+                    "/api/apps/master/status-changes/{status_id}/events"
+                ))
+                .header(Accept::EventStream)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let body = response.into_string().await.unwrap();
+            assert!(deployment.await.unwrap().is_ok());
+            assert!(
+                body.contains(r#"data:{"phase":"deploying","services":["db"]}"#),
+                "{}",
+                body
+            );
+            assert!(
+                body.trim_end()
+                    .ends_with(r#"data:{"phase":"finished","services":["db"]}"#),
+                "{}",
+                body
+            );
+        }
+
+        #[tokio::test]
+        async fn should_not_stream_unknown_status_change() {
+            let apps =
+                Arc::new(AppsService::new(Default::default(), Box::new(Dummy::new())).unwrap());
+            let client = create_client(apps).await;
+
+            let response = client
+                .get(format!(
+                    "/api/apps/master/status-changes/{}/events",
+                    AppStatusChangeId::new()
+                ))
+                .header(Accept::EventStream)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod deployment_timings {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        #[tokio::test]
+        async fn should_respond_with_server_timing() {
+            let config = toml::from_str::<crate::config::Config>("").unwrap();
+            let apps = Arc::new(AppsService::new(config, Box::new(Dummy::new())).unwrap());
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::create_app]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client
+                .post("/master")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let server_timing = response.headers().get_one("Server-Timing").unwrap();
+            let phases = server_timing
+                .split(", ")
+                .filter_map(|timing| timing.split_once(";dur="))
+                .map(|(phase, _)| phase)
+                .collect::<Vec<_>>();
+            assert_eq!(phases, vec!["resolve-images", "deploy", "total"]);
+        }
+    }
+
+    mod skipped_companions {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        #[tokio::test]
+        async fn should_report_skipped_companions_as_warning() {
+            let config = toml::from_str::<crate::config::Config>(&format!(
+                r#"
+                [companions.adminer]
+                serviceName = "adminer"
+                type = "application"
+                image = "sha256:{}"
+                appSelector = "master"
+                "#,
+                "a".repeat(64)
+            ))
+            .unwrap();
+            let apps = Arc::new(AppsService::new(config, Box::new(Dummy::new())).unwrap());
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::create_app]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client
+                .post("/feature-x?noReplicate=true")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(
+                response.headers().get_one("Warning"),
+                Some(
+                    "299 PREvant \"Companion adminer has been skipped because its app selector does not match.\""
+                )
+            );
+            let services = response.into_json::<serde_json::Value>().await.unwrap();
+            assert_eq!(services[0]["name"], "db");
+            assert_eq!(services.as_array().map(Vec::len), Some(1));
+        }
+    }
+
+    mod templates {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn client_with_template() -> Client {
+            let config = toml::from_str::<crate::config::Config>(&format!(
+                r#"
+                [applications]
+                deniedEnvOverrides = [ "DATABASE_URL" ]
+
+                [[templates.frontend-stack.services]]
+                serviceName = "frontend"
+                image = "sha256:{}"
+                exposePort = true
+
+                [[templates.frontend-stack.services]]
+                serviceName = "backend"
+                image = "sha256:{}"
+                env = {{ DATABASE_URL = "postgres://db" }}
+                "#,
+                "f".repeat(64),
+                "b".repeat(64)
+            ))
+            .unwrap();
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(config, infrastructure).unwrap());
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::create_app]);
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn should_deploy_services_of_template_and_request() {
+            let client = client_with_template().await;
+
+            let response = client
+                .post("/feature?template=frontend-stack")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64))
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let body = response.into_string().await.unwrap();
+            let mut names = serde_json::from_str::<Vec<serde_json::Value>>(&body)
+                .unwrap()
+                .into_iter()
+                .map(|service| service["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, vec!["backend", "db", "frontend"]);
+        }
+
+        #[tokio::test]
+        async fn should_reject_restricted_services_of_request() {
+            let client = client_with_template().await;
+
+            let response = client
+                .post("/feature?template=frontend-stack")
+                .header(ContentType::JSON)
+                .body(
+                    serde_json::json!([{
+                        "serviceName": "db",
+                        "image": format!("sha256:{}", "d".repeat(64)),
+                        "env": { "DATABASE_URL": "postgres://other-db" }
+                    }])
+                    .to_string(),
+                )
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+
+        #[tokio::test]
+        async fn should_reject_unknown_template() {
+            let client = client_with_template().await;
+
+            let response = client
+                .post("/feature?template=unknown")
+                .header(ContentType::JSON)
+                .body("[]")
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+    }
+
+    mod label_filter {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+        use std::collections::BTreeMap;
+        use std::str::FromStr;
+
+        async fn create_client() -> Client {
+            let (host_meta_cache, mut host_meta_crawler) =
+                crate::host_meta_crawling(&Default::default());
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+
+            for (app_name, team) in [("team-a", "foo"), ("team-b", "bar")] {
+                let app_name = AppName::from_str(app_name).unwrap();
+                apps.create_or_update_with_metadata(
+                    &app_name,
+                    &AppStatusChangeId::new(),
+                    Replication::FromDefault,
+                    &[sc!("service-a")],
+                    None,
+                    BTreeMap::from([(String::from("team"), String::from(team))]),
+                    false,
+                )
+                .await
+                .unwrap();
+                host_meta_crawler.fake_empty_host_meta_info(app_name, "service-a".to_string());
+            }
+
+            let rocket = rocket::build()
+                .manage(host_meta_cache)
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::apps]);
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        async fn app_names(client: &Client, uri: &str) -> Vec<String> {
+            let response = client
+                .get(uri.to_string())
+                .header(ContentType::JSON)
+                .header(rocket::http::Header::new("host", "localhost"))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let mut names = response
+                .into_json::<HashMap<String, serde_json::Value>>()
+                .await
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        }
+
+        #[tokio::test]
+        async fn should_return_all_apps_without_label_filter() {
+            let client = create_client().await;
+
+            assert_eq!(app_names(&client, "/").await, vec!["team-a", "team-b"]);
+        }
+
+        #[tokio::test]
+        async fn should_filter_apps_by_metadata() {
+            let client = create_client().await;
+
+            assert_eq!(app_names(&client, "/?label=team=foo").await, vec!["team-a"]);
+            assert!(app_names(&client, "/?label=team=foo&label=team=bar")
+                .await
+                .is_empty());
+        }
+
+        #[tokio::test]
+        async fn should_reject_invalid_label_filter() {
+            let client = create_client().await;
+
+            let response = client
+                .get("/?label=team")
+                .header(ContentType::JSON)
+                .header(rocket::http::Header::new("host", "localhost"))
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+    }
+
+    mod app_graph {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::app_graph]);
+
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn graph_of_app() {
+            let client = create_client().await;
+
+            let response = client.get("/master/graph").dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let body = response.into_json::<serde_json::Value>().await.unwrap();
+            assert_eq!(
+                body["nodes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|node| node["name"].as_str().unwrap())
+                    .collect::<Vec<_>>(),
+                vec!["service-a", "service-b"]
+            );
+            assert_eq!(body["edges"], serde_json::json!([]));
+        }
+
+        #[tokio::test]
+        async fn not_found_for_unknown_app() {
+            let client = create_client().await;
+
+            let response = client.get("/unknown/graph").dispatch().await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod service_image {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::local::asynchronous::Client;
+        use std::str::FromStr;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a", &format!("sha256:{}", "b".repeat(64)))],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::service_image]);
+
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn image_by_digest() {
+            let client = create_client().await;
+
+            let response = client
+                .get("/master/services/service-a/image")
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let body = response.into_string().await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "image": format!("sha256:{}", "b".repeat(64)),
+                    "registry": null,
+                    "repository": null,
+                    "tag": null,
+                    "digest": format!("sha256:{}", "b".repeat(64)),
+                    "labels": {}
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn not_found_for_unknown_service() {
+            let client = create_client().await;
+
+            let response = client
+                .get("/master/services/service-b/image")
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn not_found_for_unknown_app() {
+            let client = create_client().await;
+
+            let response = client
+                .get("/unknown/services/service-a/image")
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+
+        #[test]
+        fn provenance_of_named_image() {
+            let image = Image::from_str("quay.io/example/service-a:1.2.3").unwrap();
+
+            let provenance = ImageProvenance::new(&image, None);
+
+            assert_eq!(
+                provenance,
+                ImageProvenance {
+                    image: String::from("quay.io/example/service-a:1.2.3"),
+                    registry: Some(String::from("quay.io")),
+                    repository: Some(String::from("example/service-a")),
+                    tag: Some(String::from("1.2.3")),
+                    digest: None,
+                    labels: BTreeMap::new(),
+                }
+            );
+        }
+    }
+
+    mod app_readiness {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::models::service::{Service, State};
+        use crate::sc;
+        use rocket::local::asynchronous::Client;
+
+        async fn create_client() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::app_readiness]);
+
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        #[tokio::test]
+        async fn ready_if_all_services_are_running() {
+            let client = create_client().await;
+
+            let response = client.get("/master/ready").dispatch().await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let body = response.into_string().await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "ready": true,
+                    "services": [
+                        { "name": "service-a", "ready": true, "status": "running" },
+                        { "name": "service-b", "ready": true, "status": "running" }
+                    ]
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn not_found_for_unknown_app() {
+            let client = create_client().await;
+
+            let response = client.get("/unknown/ready").dispatch().await;
+
+            assert_eq!(response.status(), Status::NotFound);
+        }
+
+        #[test]
+        fn not_ready_if_a_service_is_not_running() {
+            let services = Services::from(vec![
+                Service {
+                    id: String::from("service-a"),
+                    config: sc!("service-a"),
+                    state: State {
+                        status: ServiceStatus::Running,
+                        started_at: None,
+                        replicas: None,
+                    },
+                },
+                Service {
+                    id: String::from("service-b"),
+                    config: sc!("service-b"),
+                    state: State {
+                        status: ServiceStatus::Paused,
+                        started_at: None,
+                        replicas: None,
+                    },
+                },
+            ]);
+
+            let readiness = AppReadiness::from(&services);
+
+            assert!(!readiness.ready);
+            assert_eq!(
+                readiness.services,
+                vec![
+                    ServiceReadiness {
+                        name: String::from("service-a"),
+                        ready: true,
+                        status: ServiceStatus::Running,
+                    },
+                    ServiceReadiness {
+                        name: String::from("service-b"),
+                        ready: false,
+                        status: ServiceStatus::Paused,
+                    },
+                ]
+            );
+        }
+    }
+
+    mod resource_usage {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::local::asynchronous::Client;
+
+        #[tokio::test]
+        async fn should_respond_with_usage_per_service_and_total() {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a"), sc!("service-b")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::resource_usage]);
+            let client = Client::tracked(rocket).await.expect("valid rocket");
+
+            let response = client.get("/master/stats").dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+
+            let body = response.into_string().await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "services": {
+                        "service-a": { "cpuCores": 0.1, "memoryBytes": 1024 },
+                        "service-b": { "cpuCores": 0.1, "memoryBytes": 1024 }
+                    },
+                    "total": { "cpuCores": 0.2, "memoryBytes": 2048 }
+                })
+            );
+
+            let response = client.get("/unknown/stats").dispatch().await;
+            assert_eq!(response.status(), Status::NotFound);
+        }
+    }
+
+    mod change_status {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn client_with_master_app() -> Client {
+            let infrastructure = Box::new(Dummy::new());
+            let apps = Arc::new(AppsService::new(Default::default(), infrastructure).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps)
+                .mount("/", routes![crate::apps::routes::change_status]);
+            Client::tracked(rocket).await.expect("valid rocket")
+        }
+
+        async fn put_status(client: &Client, uri: &str) -> (Status, String) {
+            let response = client
+                .put(uri.to_string())
+                .header(ContentType::JSON)
+                .body(r#"{"status": "paused"}"#)
+                .dispatch()
+                .await;
+            (
+                response.status(),
+                response.into_string().await.unwrap_or_default(),
+            )
+        }
+
+        #[tokio::test]
+        async fn should_accept_status_change_of_existing_service() {
+            let client = client_with_master_app().await;
+
+            let (status, _) = put_status(&client, "/master/states/service-a").await;
+
+            assert_eq!(status, Status::Accepted);
+        }
+
+        #[tokio::test]
+        async fn should_respond_with_problem_for_unknown_service() {
+            let client = client_with_master_app().await;
+
+            let (status, body) = put_status(&client, "/master/states/unknown").await;
+
+            assert_eq!(status, Status::NotFound);
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "type": "https://httpstatuses.com/404",
+                    "status": 404,
+                    "title": "Not Found",
+                    "detail": "Cannot find service unknown of app master."
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn should_respond_with_problem_for_unknown_app() {
+            let client = client_with_master_app().await;
+
+            let (status, body) = put_status(&client, "/unknown/states/service-a").await;
+
+            assert_eq!(status, Status::NotFound);
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "type": "https://httpstatuses.com/404",
+                    "status": 404,
+                    "title": "Not Found",
+                    "detail": "Cannot find app unknown."
+                })
+            );
+        }
+    }
+
+    mod scale_service {
+        use super::super::*;
+        use crate::apps::AppsService;
+        use crate::infrastructure::Dummy;
+        use crate::sc;
+        use rocket::http::ContentType;
+        use rocket::local::asynchronous::Client;
+
+        async fn client_with_master_app(infrastructure: Dummy) -> (Client, Arc<AppsService>) {
+            let apps =
+                Arc::new(AppsService::new(Default::default(), Box::new(infrastructure)).unwrap());
+            apps.create_or_update(
+                &AppName::master(),
+                &AppStatusChangeId::new(),
+                Replication::FromDefault,
+                &[sc!("service-a")],
+                None,
+            )
+            .await
+            .unwrap();
+
+            let rocket = rocket::build()
+                .manage(apps.clone())
+                .mount("/", routes![crate::apps::routes::scale_service]);
+            (Client::tracked(rocket).await.expect("valid rocket"), apps)
+        }
+
+        async fn put_scale(client: &Client, uri: &str, body: &str) -> (Status, String) {
+            let response = client
+                .put(uri.to_string())
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+                .await;
+            (
+                response.status(),
+                response.into_string().await.unwrap_or_default(),
+            )
+        }
+
+        #[tokio::test]
+        async fn should_scale_existing_service() {
+            let (client, apps) = client_with_master_app(Dummy::with_scaling()).await;
+
+            let (status, _) =
+                put_scale(&client, "/master/service-a/scale", r#"{"replicas": 3}"#).await;
+
+            assert_eq!(status, Status::Accepted);
+            let services = apps.fetch_apps().await.unwrap().remove(&AppName::master());
+            let service = services
+                .unwrap()
+                .into_iter()
+                .find(|s| s.service_name() == "service-a")
+                .unwrap();
+            assert_eq!(
+                serde_json::to_value(&service.state).unwrap()["replicas"],
+                serde_json::json!({ "desired": 3, "current": 3 })
+            );
+        }
+
+        #[tokio::test]
+        async fn should_reject_zero_replicas() {
+            let (client, _) = client_with_master_app(Dummy::with_scaling()).await;
+
+            let (status, _) =
+                put_scale(&client, "/master/service-a/scale", r#"{"replicas": 0}"#).await;
+
+            assert_eq!(status, Status::UnprocessableEntity);
+        }
+
+        #[tokio::test]
+        async fn should_respond_with_problem_for_unknown_service() {
+            let (client, _) = client_with_master_app(Dummy::with_scaling()).await;
+
+            let (status, body) =
+                put_scale(&client, "/master/unknown/scale", r#"{"replicas": 3}"#).await;
+
+            assert_eq!(status, Status::NotFound);
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap()["detail"],
+                "Cannot find service unknown of app master."
+            );
+        }
+
+        #[tokio::test]
+        async fn should_respond_with_problem_if_infrastructure_cannot_scale() {
+            let (client, _) = client_with_master_app(Dummy::new()).await;
+
+            let (status, body) =
+                put_scale(&client, "/master/service-a/scale", r#"{"replicas": 3}"#).await;
+
+            assert_eq!(status, Status::NotImplemented);
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+                serde_json::json!({
+                    "type": "https://httpstatuses.com/501",
+                    "status": 501,
+                    "title": "Not Implemented",
+                    "detail": "The service service-a of app master cannot be scaled by the infrastructure."
+                })
+            );
+        }
+    }
 }
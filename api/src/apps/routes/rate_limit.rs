@@ -0,0 +1,164 @@
+use crate::config::RateLimit;
+use crate::http_result::HttpApiError;
+use http_api_problem::{HttpApiProblem, StatusCode};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Limits the requests of the create app endpoint, configured by `[apiAccess.rateLimit]`. All
+/// clients share the same bucket because PREvant does not distinguish between users.
+pub struct CreateAppRateLimiter {
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl CreateAppRateLimiter {
+    pub fn new(rate_limit: Option<&RateLimit>) -> Self {
+        Self {
+            bucket: rate_limit.map(|rate_limit| {
+                Mutex::new(TokenBucket::new(
+                    rate_limit.requests(),
+                    rate_limit.period(),
+                    Instant::now(),
+                ))
+            }),
+        }
+    }
+
+    fn try_acquire(&self) -> Result<(), Duration> {
+        match &self.bucket {
+            Some(bucket) => bucket.lock().unwrap().try_acquire(Instant::now()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A bucket that holds up to `capacity` tokens and is refilled continuously so that it becomes
+/// full again after `period`. Each request takes one token.
+struct TokenBucket {
+    capacity: f64,
+    tokens_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, period: Duration, now: Instant) -> Self {
+        let capacity = f64::from(capacity);
+        Self {
+            capacity,
+            tokens_per_second: capacity / period.as_secs_f64().max(f64::EPSILON),
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token from the bucket or returns how long it takes until the next token is
+    /// available.
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.tokens_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.tokens_per_second,
+            ))
+        }
+    }
+}
+
+/// A request guard that takes a token from the [`CreateAppRateLimiter`] if Rocket manages one.
+pub struct CreateAppRateLimit;
+
+#[derive(Debug)]
+pub struct RateLimitExceeded {
+    retry_after: Duration,
+}
+
+impl From<RateLimitExceeded> for HttpApiError {
+    fn from(exceeded: RateLimitExceeded) -> Self {
+        HttpApiError::from(
+            HttpApiProblem::with_title_and_type(StatusCode::TOO_MANY_REQUESTS).detail(
+                "Too many apps have been created or updated recently. Please, try again later.",
+            ),
+        )
+        .with_retry_after(exceeded.retry_after)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CreateAppRateLimit {
+    type Error = RateLimitExceeded;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(rate_limiter) = request.rocket().state::<CreateAppRateLimiter>() else {
+            return Outcome::Success(CreateAppRateLimit);
+        };
+
+        match rate_limiter.try_acquire() {
+            Ok(()) => Outcome::Success(CreateAppRateLimit),
+            Err(retry_after) => {
+                Outcome::Error((Status::TooManyRequests, RateLimitExceeded { retry_after }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_after_in_secs(result: Result<(), Duration>) -> Result<(), u64> {
+        result.map_err(|retry_after| retry_after.as_secs_f64().round() as u64)
+    }
+
+    #[test]
+    fn should_allow_requests_up_to_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(60), now);
+
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+        assert_eq!(retry_after_in_secs(bucket.try_acquire(now)), Err(20));
+    }
+
+    #[test]
+    fn should_refill_tokens_over_time() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, Duration::from_secs(60), now);
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(retry_after_in_secs(bucket.try_acquire(later)), Err(20));
+
+        let later = now + Duration::from_secs(31);
+        assert_eq!(bucket.try_acquire(later), Ok(()));
+        assert!(bucket.try_acquire(later).is_err());
+    }
+
+    #[test]
+    fn should_not_refill_more_than_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(1, Duration::from_secs(1), now);
+
+        let later = now + Duration::from_secs(3600);
+        assert_eq!(bucket.try_acquire(later), Ok(()));
+        assert!(bucket.try_acquire(later).is_err());
+    }
+
+    #[test]
+    fn should_not_limit_without_configuration() {
+        let rate_limiter = CreateAppRateLimiter::new(None);
+
+        for _ in 0..100 {
+            assert_eq!(rate_limiter.try_acquire(), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use crate::models::ServiceConfig;
+use serde::Deserialize;
+
+/// A named set of services, stored server-side, that can be referenced when creating an app
+/// instead of sending the full list of services with each request.
+#[derive(Clone, Deserialize)]
+pub(super) struct AppTemplate {
+    services: Vec<ServiceConfig>,
+}
+
+impl AppTemplate {
+    /// Expands the template into its services and merges the given services over them. A given
+    /// service replaces the template's service with the same name but inherits its envs, files and
+    /// labels unless they are defined by the given service. Services that are not part of the
+    /// template are appended.
+    pub fn expand(&self, services: Vec<ServiceConfig>) -> Vec<ServiceConfig> {
+        let mut expanded = self.services.clone();
+
+        for mut service in services {
+            match expanded
+                .iter_mut()
+                .find(|s| s.service_name() == service.service_name())
+            {
+                Some(template_service) => {
+                    service.merge_with(template_service);
+                    *template_service = service;
+                }
+                None => expanded.push(service),
+            }
+        }
+
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Environment, EnvironmentVariable};
+    use crate::sc;
+    use secstr::SecUtf8;
+
+    fn template() -> AppTemplate {
+        toml::de::from_str::<AppTemplate>(
+            r#"
+            [[services]]
+            serviceName = "frontend"
+            image = "quay.io/example/frontend:latest"
+            env = { API_URL = "http://backend:8080", LOG_LEVEL = "info" }
+
+            [[services]]
+            serviceName = "backend"
+            image = "quay.io/example/backend:latest"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_expand_template_without_services() {
+        let services = template().expand(Vec::new());
+
+        assert_eq!(
+            services
+                .iter()
+                .map(|s| s.service_name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["frontend", "backend"]
+        );
+    }
+
+    #[test]
+    fn should_merge_services_over_template() {
+        let mut frontend = sc!("frontend", "quay.io/example/frontend:feature-xyz");
+        frontend.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
+            String::from("LOG_LEVEL"),
+            SecUtf8::from("debug"),
+        )])));
+
+        let services = template().expand(vec![frontend, sc!("db", "postgres")]);
+
+        assert_eq!(
+            services
+                .iter()
+                .map(|s| (s.service_name().as_str(), s.image().to_string()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "frontend",
+                    String::from("quay.io/example/frontend:feature-xyz")
+                ),
+                ("backend", String::from("quay.io/example/backend:latest")),
+                ("db", String::from("docker.io/library/postgres:latest")),
+            ]
+        );
+
+        let env = services[0].env().unwrap();
+        assert_eq!(
+            env.variable("LOG_LEVEL").unwrap().value(),
+            &SecUtf8::from("debug")
+        );
+        assert_eq!(
+            env.variable("API_URL").unwrap().value(),
+            &SecUtf8::from("http://backend:8080")
+        );
+    }
+}
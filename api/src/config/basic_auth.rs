@@ -0,0 +1,85 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use crate::{config::AppSelector, models::AppName};
+use secstr::SecUtf8;
+use serde::Deserialize;
+
+/// Protects the route of a service with [HTTP basic
+/// authentication](https://doc.traefik.io/traefik/middlewares/http/basicauth/). The users are
+/// provided as htpasswd entries, e.g. generated by `htpasswd -nb user password`.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct BasicAuth {
+    users: Vec<SecUtf8>,
+    #[serde(default = "AppSelector::default")]
+    app_selector: AppSelector,
+}
+
+impl BasicAuth {
+    pub fn matches_app_name(&self, app_name: &AppName) -> bool {
+        self.app_selector.matches(app_name)
+    }
+
+    pub fn users(&self) -> &[SecUtf8] {
+        &self.users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn should_parse_basic_auth() {
+        let basic_auth = toml::de::from_str::<BasicAuth>(
+            r#"
+            users = [ "test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/" ]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            basic_auth.users(),
+            &[SecUtf8::from("test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/")]
+        );
+        assert!(basic_auth.matches_app_name(&AppName::master()));
+    }
+
+    #[test]
+    fn should_not_match_app_name_with_specific_app_selector() {
+        let basic_auth = toml::de::from_str::<BasicAuth>(
+            r#"
+            users = [ "test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/" ]
+            appSelector = "master"
+            "#,
+        )
+        .unwrap();
+
+        assert!(!basic_auth.matches_app_name(&AppName::from_str("feature-xyz").unwrap()));
+    }
+}
@@ -0,0 +1,106 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use crate::{config::AppSelector, models::AppName};
+use secstr::SecUtf8;
+use serde::{de, Deserialize, Deserializer};
+use std::path::PathBuf;
+
+/// A CA bundle that is read from PREvant's file system and mounted into every deployed service.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct CaBundle {
+    #[serde(deserialize_with = "CaBundle::read_file", rename = "file")]
+    data: SecUtf8,
+    path: PathBuf,
+    #[serde(default = "AppSelector::default")]
+    app_selector: AppSelector,
+}
+
+impl CaBundle {
+    fn read_file<'de, D>(deserializer: D) -> Result<SecUtf8, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let file = PathBuf::deserialize(deserializer)?;
+        let data = std::fs::read_to_string(&file).map_err(|err| {
+            de::Error::custom(format!(
+                "Cannot read CA bundle {}: {err}",
+                file.to_string_lossy()
+            ))
+        })?;
+        Ok(SecUtf8::from(data))
+    }
+
+    pub fn matches_app_name(&self, app_name: &AppName) -> bool {
+        self.app_selector.matches(app_name)
+    }
+}
+
+impl From<CaBundle> for (PathBuf, SecUtf8) {
+    fn from(ca_bundle: CaBundle) -> Self {
+        (ca_bundle.path, ca_bundle.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn should_read_ca_bundle_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "-----BEGIN CERTIFICATE-----").unwrap();
+
+        let ca_bundle = toml::de::from_str::<CaBundle>(&format!(
+            r#"
+            file = "{}"
+            path = "/etc/ssl/certs/internal-ca.pem"
+            "#,
+            file.path().to_string_lossy()
+        ))
+        .unwrap();
+
+        assert!(ca_bundle.matches_app_name(&AppName::master()));
+        let (path, data) = ca_bundle.into();
+        assert_eq!(path, PathBuf::from("/etc/ssl/certs/internal-ca.pem"));
+        assert_eq!(data.unsecure(), "-----BEGIN CERTIFICATE-----");
+    }
+
+    #[test]
+    fn should_not_parse_ca_bundle_of_missing_file() {
+        let result = toml::de::from_str::<CaBundle>(
+            r#"
+            file = "/does/not/exist/ca.pem"
+            path = "/etc/ssl/certs/internal-ca.pem"
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+}
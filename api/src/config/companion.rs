@@ -23,26 +23,56 @@
  * THE SOFTWARE.
  * =========================LICENSE_END==================================
  */
-use crate::config::AppSelector;
+use crate::config::{AppSelector, ConfigError};
 use crate::models::service::ContainerType;
 use crate::models::user_defined_parameters::UserDefinedParameters;
-use crate::models::{AppName, Environment, Image, ServiceConfig};
+use crate::models::{
+    AppName, Environment, Healthcheck, Image, Probes, RestartPolicy, ServiceConfig,
+};
 use handlebars::{Handlebars, RenderError, RenderErrorReason};
 use jsonschema::Validator;
 use secstr::SecUtf8;
 use serde_value::Value;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use url::Url;
 
+/// Bounds the startup of PREvant if the server of `includeFrom` does not respond.
+const INCLUDE_FROM_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const INCLUDE_FROM_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Default, Deserialize)]
 pub(super) struct Companions {
     #[serde(default)]
     bootstrapping: Bootstrapping,
+    #[serde(rename = "includeFrom")]
+    include_from: Option<Url>,
     #[serde(flatten)]
-    companions: BTreeMap<String, Companion>,
+    companions: BTreeMap<String, CompanionDefinition>,
     #[serde(default)]
     templating: Templating,
+    #[serde(default, rename = "mergePrecedence")]
+    merge_precedence: CompanionMergePrecedence,
+}
+
+#[derive(Clone)]
+pub(super) enum CompanionDefinition {
+    /// A companion that will be deployed as container, see [`CompanionType`].
+    Container(Box<Companion>),
+    /// A companion that consists of arbitrary Kubernetes objects.
+    KubernetesManifest(ManifestCompanion),
+}
+
+/// Companion of `type = 'kubernetes-manifest'` that references a YAML file with Kubernetes
+/// objects. The file will be rendered as handlebars template, the same way as the bootstrapping
+/// containers, before the objects will be applied to the namespace of the app.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ManifestCompanion {
+    manifest: PathBuf,
+    #[serde(default = "AppSelector::default")]
+    app_selector: AppSelector,
 }
 
 #[derive(Clone, Deserialize)]
@@ -63,6 +93,13 @@ pub(super) struct Companion {
     routing: Option<Routing>,
     #[serde(default)]
     storage_strategy: StorageStrategy,
+    working_dir: Option<PathBuf>,
+    restart_policy: Option<RestartPolicy>,
+    healthcheck: Option<Healthcheck>,
+    probes: Option<Probes>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    condition: Option<String>,
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
@@ -81,6 +118,18 @@ pub enum StorageStrategy {
     MountDeclaredImageVolumes,
 }
 
+/// Determines whose environment variables, files, labels, and routing win if a companion is merged with a
+/// service of the deployment request that has the same name.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompanionMergePrecedence {
+    /// The values of the deployment request override the values of the companion.
+    #[default]
+    Service,
+    /// The values of the companion override the values of the deployment request.
+    Companion,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 pub enum DeploymentStrategy {
     #[serde(rename = "redeploy-always")]
@@ -151,7 +200,123 @@ impl<'de> serde::Deserialize<'de> for Templating {
     }
 }
 
+impl<'de> serde::Deserialize<'de> for CompanionDefinition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+
+        let is_manifest = match &value {
+            Value::Map(map) => {
+                map.get(&Value::String(String::from("type")))
+                    == Some(&Value::String(String::from("kubernetes-manifest")))
+            }
+            _ => false,
+        };
+
+        if is_manifest {
+            value
+                .deserialize_into::<ManifestCompanion>()
+                .map(CompanionDefinition::KubernetesManifest)
+                .map_err(serde::de::Error::custom)
+        } else {
+            value
+                .deserialize_into::<Companion>()
+                .map(|companion| CompanionDefinition::Container(Box::new(companion)))
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+impl CompanionDefinition {
+    fn as_container(&self) -> Option<&Companion> {
+        match self {
+            CompanionDefinition::Container(companion) => Some(companion),
+            CompanionDefinition::KubernetesManifest(_) => None,
+        }
+    }
+
+    fn as_kubernetes_manifest(&self) -> Option<&ManifestCompanion> {
+        match self {
+            CompanionDefinition::Container(_) => None,
+            CompanionDefinition::KubernetesManifest(manifest) => Some(manifest),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppTemplateData<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_url: &'a Option<Url>,
+}
+
+// TODO: apply same pattern as for companions. {{application.name}}, {{service.…}}…
+#[derive(Serialize)]
+struct InfrastructureTemplateData<'a, S> {
+    application: AppTemplateData<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    infrastructure: Option<S>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "userDefined")]
+    user_defined_parameters: &'a Option<UserDefinedParameters>,
+}
+
+/// Structure of a companion catalog that can be included via `[companions] includeFrom = "…"`.
+#[derive(Deserialize)]
+struct IncludedCompanions {
+    #[serde(default)]
+    companions: BTreeMap<String, CompanionDefinition>,
+}
+
 impl Companions {
+    pub(super) fn merge_precedence(&self) -> CompanionMergePrecedence {
+        self.merge_precedence
+    }
+
+    /// Fetches the companion catalog referenced by `includeFrom` and merges it into the local
+    /// companions. Companions that are defined locally take precedence over the included ones.
+    pub(super) async fn include_remote_companions(&mut self) -> Result<(), ConfigError> {
+        let Some(url) = self.include_from.clone() else {
+            return Ok(());
+        };
+
+        info!("Including companions from {url}");
+
+        let fetch = async {
+            reqwest::Client::builder()
+                .connect_timeout(INCLUDE_FROM_CONNECT_TIMEOUT)
+                .timeout(INCLUDE_FROM_REQUEST_TIMEOUT)
+                .build()?
+                .get(url.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await
+        };
+        let raw_companions =
+            fetch
+                .await
+                .map_err(|err| ConfigError::CannotFetchRemoteCompanions {
+                    url: url.clone(),
+                    error: err.to_string(),
+                })?;
+
+        self.merge_included_companions(&raw_companions)
+    }
+
+    fn merge_included_companions(&mut self, raw_companions: &str) -> Result<(), ConfigError> {
+        let included = toml::from_str::<IncludedCompanions>(raw_companions)?;
+
+        for (name, companion) in included.companions {
+            self.companions.entry(name).or_insert(companion);
+        }
+
+        Ok(())
+    }
+
     pub(super) fn companion_configs<P>(
         &self,
         app_name: &AppName,
@@ -161,10 +326,11 @@ impl Companions {
         P: Fn(&Companion) -> bool,
     {
         self.companions
-            .iter()
-            .filter(|(_, companion)| companion.matches_app_name(app_name))
-            .filter(|(_, companion)| predicate(companion))
-            .map(|(_, companion)| {
+            .values()
+            .filter_map(CompanionDefinition::as_container)
+            .filter(|companion| companion.matches_app_name(app_name))
+            .filter(|companion| predicate(companion))
+            .map(|companion| {
                 (
                     ServiceConfig::from(companion.clone()),
                     companion.deployment_strategy().clone(),
@@ -174,6 +340,18 @@ impl Companions {
             .collect()
     }
 
+    pub(super) fn companion_names_not_matching_app_selector(
+        &self,
+        app_name: &AppName,
+    ) -> Vec<String> {
+        self.companions
+            .values()
+            .filter_map(CompanionDefinition::as_container)
+            .filter(|companion| !companion.matches_app_name(app_name))
+            .map(|companion| companion.service_name.clone())
+            .collect()
+    }
+
     pub(super) fn user_defined_schema_validator(&self) -> Option<Validator> {
         let schema = self.templating.user_defined_schema.as_ref()?;
         Validator::new(schema).ok()
@@ -196,27 +374,9 @@ impl Companions {
     {
         let handlebars = Handlebars::new();
 
-        #[derive(Serialize)]
-        #[serde(rename_all = "camelCase")]
-        struct AppData<'a> {
-            name: &'a str,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            base_url: &'a Option<Url>,
-        }
-
-        // TODO: apply same pattern as for companions. {{application.name}}, {{service.…}}…
-        #[derive(Serialize)]
-        struct Data<'a, S> {
-            application: AppData<'a>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            infrastructure: Option<S>,
-            #[serde(skip_serializing_if = "Option::is_none", rename = "userDefined")]
-            user_defined_parameters: &'a Option<UserDefinedParameters>,
-        }
-
-        let data = Data {
+        let data = InfrastructureTemplateData {
             infrastructure,
-            application: AppData {
+            application: AppTemplateData {
                 name: app_name,
                 base_url,
             },
@@ -244,6 +404,67 @@ impl Companions {
     }
 }
 
+impl Companions {
+    pub(super) fn kubernetes_manifest_companion_names(&self, app_name: &AppName) -> Vec<String> {
+        self.companions
+            .iter()
+            .filter(|(_, companion)| {
+                companion
+                    .as_kubernetes_manifest()
+                    .is_some_and(|manifest_companion| {
+                        manifest_companion.app_selector.matches(app_name)
+                    })
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Reads and renders the manifests of all companions of `type = 'kubernetes-manifest'` that
+    /// match the app name. The template data is the same as for
+    /// [bootstrapping containers](Companions::companion_bootstrapping_containers).
+    pub(super) fn kubernetes_manifests<S>(
+        &self,
+        app_name: &AppName,
+        base_url: &Option<Url>,
+        infrastructure: Option<S>,
+        user_defined_parameters: &Option<UserDefinedParameters>,
+    ) -> Result<Vec<String>, RenderError>
+    where
+        S: serde::Serialize,
+    {
+        let handlebars = Handlebars::new();
+
+        let data = InfrastructureTemplateData {
+            infrastructure,
+            application: AppTemplateData {
+                name: app_name,
+                base_url,
+            },
+            user_defined_parameters,
+        };
+
+        let mut manifests = Vec::new();
+        for manifest_companion in self
+            .companions
+            .values()
+            .filter_map(CompanionDefinition::as_kubernetes_manifest)
+            .filter(|manifest_companion| manifest_companion.app_selector.matches(app_name))
+        {
+            let template =
+                std::fs::read_to_string(&manifest_companion.manifest).map_err(|err| {
+                    RenderErrorReason::Other(format!(
+                        "Cannot read manifest {}: {err}",
+                        manifest_companion.manifest.display()
+                    ))
+                })?;
+
+            manifests.push(handlebars.render_template(&template, &data)?);
+        }
+
+        Ok(manifests)
+    }
+}
+
 impl Companion {
     pub fn companion_type(&self) -> &CompanionType {
         &self.companion_type
@@ -276,7 +497,7 @@ impl From<Companion> for ServiceConfig {
                     .collect(),
             )
         }));
-        config.set_labels(companion.labels.clone());
+        config.set_custom_labels(companion.labels.clone().unwrap_or_default());
 
         if let Some(files) = &companion.files {
             config.set_files(Some(files.clone()));
@@ -286,6 +507,16 @@ impl From<Companion> for ServiceConfig {
             config.set_routing(routing.clone());
         }
 
+        config.set_working_dir(companion.working_dir.clone());
+        if let Some(restart_policy) = &companion.restart_policy {
+            config.set_restart_policy(restart_policy.clone());
+        }
+        config.set_healthcheck(companion.healthcheck.clone());
+        config.set_probes(companion.probes.clone());
+        config.set_depends_on(companion.depends_on.clone());
+        if let Some(condition) = &companion.condition {
+            config.set_condition(condition.clone());
+        }
         config.set_container_type(companion.companion_type.into());
 
         config
@@ -342,6 +573,170 @@ mod tests {
         };
     }
 
+    #[test]
+    fn should_parse_include_from() {
+        let companions = companions_from_str!(
+            r#"
+            includeFrom = 'https://example.com/companions.toml'
+        "#
+        );
+
+        assert_eq!(
+            companions.include_from,
+            Some(Url::parse("https://example.com/companions.toml").unwrap())
+        );
+        assert!(companions.companions.is_empty());
+    }
+
+    #[test]
+    fn should_merge_included_companions_with_precedence_of_local_companions() {
+        let mut companions = companions_from_str!(
+            r#"
+            [openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'private.example.com/library/openid:latest'
+        "#
+        );
+
+        companions
+            .merge_included_companions(
+                r#"
+                [companions.openid]
+                serviceName = 'keycloak'
+                type = 'application'
+                image = 'quay.io/keycloak/keycloak:latest'
+
+                [companions.adminer]
+                serviceName = 'adminer'
+                type = 'application'
+                image = 'adminer:latest'
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(companions.companions.len(), 2);
+        assert_eq!(
+            companions
+                .companions
+                .get("openid")
+                .and_then(CompanionDefinition::as_container)
+                .unwrap()
+                .service_name,
+            "openid"
+        );
+        assert_eq!(
+            companions
+                .companions
+                .get("adminer")
+                .and_then(CompanionDefinition::as_container)
+                .unwrap()
+                .service_name,
+            "adminer"
+        );
+    }
+
+    #[test]
+    fn should_fail_to_merge_invalid_included_companions() {
+        let mut companions = Companions::default();
+
+        let result = companions.merge_included_companions(
+            r#"
+            [companions.adminer]
+            type = 'application'
+            "#,
+        );
+
+        assert!(matches!(result, Err(ConfigError::ConfigFormatError { .. })));
+    }
+
+    #[test]
+    fn should_parse_kubernetes_manifest_companion() {
+        let companions = companions_from_str!(
+            r#"
+            [redis-operator]
+            type = 'kubernetes-manifest'
+            manifest = '/etc/prevant/redis.yaml'
+            appSelector = 'master'
+        "#
+        );
+
+        let manifest_companion = companions
+            .companions
+            .get("redis-operator")
+            .and_then(CompanionDefinition::as_kubernetes_manifest)
+            .unwrap();
+        assert_eq!(
+            manifest_companion.manifest,
+            PathBuf::from("/etc/prevant/redis.yaml")
+        );
+        assert_eq!(
+            companions.kubernetes_manifest_companion_names(&AppName::master()),
+            vec![String::from("redis-operator")]
+        );
+        assert!(companions
+            .kubernetes_manifest_companion_names(&AppName::from_str("other").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn should_render_kubernetes_manifests() {
+        let mut manifest = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut manifest,
+            br#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: {{application.name}}-config
+  namespace: {{infrastructure.namespace}}
+"#,
+        )
+        .unwrap();
+
+        let companions = companions_from_str!(&format!(
+            r#"
+            [config]
+            type = 'kubernetes-manifest'
+            manifest = '{}'
+
+            [openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'private.example.com/library/openid:latest'
+            "#,
+            manifest.path().display()
+        ));
+
+        let manifests = companions
+            .kubernetes_manifests(
+                &AppName::master(),
+                &None,
+                Some(serde_json::json!({
+                    "namespace": "my-namespace"
+                })),
+                &None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            manifests,
+            vec![String::from(
+                r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: master-config
+  namespace: my-namespace
+"#
+            )]
+        );
+        assert_eq!(
+            companions
+                .companion_configs(&AppName::master(), |_| true)
+                .len(),
+            1
+        );
+    }
+
     #[test]
     fn should_parse_companion_with_required_fields() {
         let companion = companion_from_str!(
@@ -362,6 +757,29 @@ mod tests {
             companion.deployment_strategy,
             DeploymentStrategy::RedeployAlways
         );
+        assert_eq!(companion.restart_policy, None);
+        assert_eq!(
+            ServiceConfig::from(companion).restart_policy(),
+            &RestartPolicy::Always
+        );
+    }
+
+    #[test]
+    fn should_parse_companion_with_restart_policy() {
+        let companion = companion_from_str!(
+            r#"
+            serviceName = 'migration'
+            type = 'application'
+            image = 'private.example.com/library/migration:latest'
+            restartPolicy = 'no'
+        "#
+        );
+
+        assert_eq!(companion.restart_policy, Some(RestartPolicy::No));
+        assert_eq!(
+            ServiceConfig::from(companion).restart_policy(),
+            &RestartPolicy::No
+        );
     }
 
     #[test]
@@ -28,8 +28,13 @@ use serde::{de, Deserialize, Deserializer};
 
 #[derive(Clone, Default, Deserialize)]
 pub struct ContainerConfig {
-    #[serde(deserialize_with = "ContainerConfig::parse_from_memory_string")]
+    #[serde(
+        default,
+        deserialize_with = "ContainerConfig::parse_from_memory_string"
+    )]
     memory_limit: Option<ByteSize>,
+    #[serde(default, deserialize_with = "ContainerConfig::parse_cpu_limit")]
+    cpu_limit: Option<f64>,
 }
 
 impl ContainerConfig {
@@ -55,7 +60,37 @@ impl ContainerConfig {
         }
     }
 
+    fn parse_cpu_limit<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let cpu_limit = f64::deserialize(deserializer)?;
+        if !cpu_limit.is_finite() || cpu_limit <= 0.0 {
+            return Err(de::Error::custom(format!(
+                "The CPU limit must be a positive number but was {cpu_limit}"
+            )));
+        }
+        Ok(Some(cpu_limit))
+    }
+
     pub fn memory_limit(&self) -> Option<ByteSize> {
         self.memory_limit
     }
+
+    /// The number of CPUs a container may use, e.g. `0.5` for half of a CPU.
+    pub fn cpu_limit(&self) -> Option<f64> {
+        self.cpu_limit
+    }
+
+    /// The CPU limit in units of 10<sup>-9</sup> CPUs, as expected by Docker.
+    pub fn nano_cpus(&self) -> Option<i64> {
+        self.cpu_limit()
+            .map(|cpu_limit| (cpu_limit * 1_000_000_000.0).round() as i64)
+    }
+
+    /// The CPU limit in millicores, as expected by Kubernetes, e.g. `500m`.
+    pub fn cpu_limit_in_millicores(&self) -> Option<String> {
+        self.cpu_limit
+            .map(|cpu_limit| format!("{}m", (cpu_limit * 1000.0).round() as u64))
+    }
 }
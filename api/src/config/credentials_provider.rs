@@ -0,0 +1,327 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use super::parse_optional_duration;
+use regex::Regex;
+use secstr::SecUtf8;
+use serde::Deserialize;
+use std::process::Stdio;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Fetches the password of a registry by running a command, e.g. `aws ecr get-login-password`, and
+/// uses its standard output as password. This enables registries that only hand out short-lived
+/// tokens.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct CredentialsProvider {
+    command: Vec<String>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    ttl: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    timeout: Option<Duration>,
+    #[serde(skip)]
+    cached_password: Arc<Mutex<Option<(SecUtf8, Instant)>>>,
+}
+
+impl CredentialsProvider {
+    fn new(command: Vec<String>, ttl: Option<Duration>) -> Self {
+        Self {
+            command,
+            ttl,
+            timeout: None,
+            cached_password: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the cached password if it is not close to its expiry, otherwise the command will be
+    /// executed again. A password is renewed when 90% of its TTL has passed so that it does not
+    /// expire while an image is being pulled. Without a TTL the command runs every time.
+    pub async fn password(&self) -> Result<SecUtf8, CredentialsProviderError> {
+        let mut cached_password = self.cached_password.lock().await;
+
+        if let (Some((password, fetched_at)), Some(ttl)) = (cached_password.as_ref(), self.ttl) {
+            if fetched_at.elapsed() < ttl.mul_f64(0.9) {
+                return Ok(password.clone());
+            }
+        }
+
+        let password = self.run_command().await?;
+        if self.ttl.is_some() {
+            *cached_password = Some((password.clone(), Instant::now()));
+        }
+
+        Ok(password)
+    }
+
+    async fn run_command(&self) -> Result<SecUtf8, CredentialsProviderError> {
+        let Some((program, args)) = self.command.split_first() else {
+            return Err(CredentialsProviderError::EmptyCommand);
+        };
+
+        // Dropping the output future on expiry of the timeout kills the command.
+        let timeout = self.timeout.unwrap_or(Duration::from_secs(60));
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(timeout, output)
+            .await
+            .map_err(|_| CredentialsProviderError::Timeout {
+                program: program.clone(),
+                timeout,
+            })?
+            .map_err(|err| CredentialsProviderError::CannotRunCommand {
+                program: program.clone(),
+                err,
+            })?;
+
+        if !output.status.success() {
+            return Err(CredentialsProviderError::CommandFailed {
+                program: program.clone(),
+                status: output.status.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        let password = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if password.is_empty() {
+            return Err(CredentialsProviderError::EmptyPassword {
+                program: program.clone(),
+            });
+        }
+
+        Ok(SecUtf8::from(password))
+    }
+}
+
+impl PartialEq for CredentialsProvider {
+    fn eq(&self, other: &Self) -> bool {
+        self.command == other.command && self.ttl == other.ttl && self.timeout == other.timeout
+    }
+}
+
+impl Eq for CredentialsProvider {}
+
+/// Fetches the short-lived tokens of an Amazon ECR registry with `aws ecr get-login-password`. The
+/// tokens are valid for twelve hours and the AWS CLI resolves the AWS credentials from its usual
+/// sources, e.g. environment variables or the instance metadata.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub(super) struct EcrCredentialsProvider {
+    /// The region of the registry. If unspecified, the region will be taken from the registry's
+    /// host, e.g. `eu-central-1` of `123456789012.dkr.ecr.eu-central-1.amazonaws.com`.
+    region: Option<String>,
+    #[serde(skip)]
+    provider: OnceLock<CredentialsProvider>,
+}
+
+impl EcrCredentialsProvider {
+    /// The username of all ECR registries.
+    pub const USERNAME: &'static str = "AWS";
+
+    fn region_of(registry_host: &str) -> Option<&str> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^\d{12}\.dkr\.ecr(?:-fips)?\.([a-z0-9-]+)\.amazonaws\.com(?:\.cn)?$")
+                    .unwrap();
+        }
+
+        RE.captures(registry_host)?
+            .get(1)
+            .map(|region| region.as_str())
+    }
+
+    pub async fn password(&self, registry_host: &str) -> Result<SecUtf8, CredentialsProviderError> {
+        let provider = match self.provider.get() {
+            Some(provider) => provider,
+            None => {
+                let region = match &self.region {
+                    Some(region) => region.as_str(),
+                    None => Self::region_of(registry_host).ok_or_else(|| {
+                        CredentialsProviderError::UnknownEcrRegion {
+                            registry_host: registry_host.to_string(),
+                        }
+                    })?,
+                };
+                self.provider.get_or_init(|| {
+                    CredentialsProvider::new(
+                        ["aws", "ecr", "get-login-password", "--region", region]
+                            .iter()
+                            .map(|arg| arg.to_string())
+                            .collect(),
+                        Some(Duration::from_secs(12 * 60 * 60)),
+                    )
+                })
+            }
+        };
+
+        provider.password().await
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialsProviderError {
+    #[error("The command of the credentials provider must not be empty")]
+    EmptyCommand,
+    #[error("Cannot run {program}: {err}")]
+    CannotRunCommand {
+        program: String,
+        err: std::io::Error,
+    },
+    #[error("{program} exited with {status}: {stderr}")]
+    CommandFailed {
+        program: String,
+        status: String,
+        stderr: String,
+    },
+    #[error("{program} did not finish within {}", humantime::format_duration(*timeout))]
+    Timeout { program: String, timeout: Duration },
+    #[error("{program} did not print a password")]
+    EmptyPassword { program: String },
+    #[error("Cannot determine the ECR region of {registry_host}, please configure the region")]
+    UnknownEcrRegion { registry_host: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_use_output_of_command_as_password() {
+        let provider = toml::de::from_str::<CredentialsProvider>(
+            r#"
+            command = [ "echo", "short-lived-token" ]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            provider.password().await.unwrap(),
+            SecUtf8::from("short-lived-token")
+        );
+    }
+
+    #[tokio::test]
+    async fn should_cache_password_within_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("counter");
+        let provider = toml::de::from_str::<CredentialsProvider>(&format!(
+            r#"
+            command = [ "sh", "-c", "echo x >> {0}; wc -l < {0}" ]
+            ttl = "1h"
+            "#,
+            counter.display()
+        ))
+        .unwrap();
+
+        assert_eq!(provider.password().await.unwrap(), SecUtf8::from("1"));
+        assert_eq!(provider.password().await.unwrap(), SecUtf8::from("1"));
+    }
+
+    #[tokio::test]
+    async fn should_rerun_command_without_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("counter");
+        let provider = toml::de::from_str::<CredentialsProvider>(&format!(
+            r#"
+            command = [ "sh", "-c", "echo x >> {0}; wc -l < {0}" ]
+            "#,
+            counter.display()
+        ))
+        .unwrap();
+
+        assert_eq!(provider.password().await.unwrap(), SecUtf8::from("1"));
+        assert_eq!(provider.password().await.unwrap(), SecUtf8::from("2"));
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_unsuccessful_command() {
+        let provider = toml::de::from_str::<CredentialsProvider>(
+            r#"
+            command = [ "sh", "-c", "echo 'token expired' >&2; exit 1" ]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            provider.password().await,
+            Err(CredentialsProviderError::CommandFailed { stderr, .. }) if stderr == "token expired"
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_command_exceeding_timeout() {
+        let provider = toml::de::from_str::<CredentialsProvider>(
+            r#"
+            command = [ "sleep", "10" ]
+            timeout = "100ms"
+            "#,
+        )
+        .unwrap();
+
+        let started = Instant::now();
+        let result = provider.password().await;
+
+        assert!(matches!(
+            result,
+            Err(CredentialsProviderError::Timeout { program, .. }) if program == "sleep"
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn should_determine_region_of_ecr_registry() {
+        assert_eq!(
+            EcrCredentialsProvider::region_of("123456789012.dkr.ecr.eu-central-1.amazonaws.com"),
+            Some("eu-central-1")
+        );
+        assert_eq!(
+            EcrCredentialsProvider::region_of(
+                "123456789012.dkr.ecr-fips.us-gov-west-1.amazonaws.com"
+            ),
+            Some("us-gov-west-1")
+        );
+        assert_eq!(
+            EcrCredentialsProvider::region_of("123456789012.dkr.ecr.cn-north-1.amazonaws.com.cn"),
+            Some("cn-north-1")
+        );
+        assert_eq!(EcrCredentialsProvider::region_of("docker.io"), None);
+    }
+
+    #[tokio::test]
+    async fn should_not_fetch_ecr_password_without_region() {
+        let provider = EcrCredentialsProvider::default();
+
+        assert!(matches!(
+            provider.password("registry.example.com").await,
+            Err(CredentialsProviderError::UnknownEcrRegion { registry_host })
+                if registry_host == "registry.example.com"
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+use super::{parse_duration, parse_optional_duration};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{de, Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMetaConfig {
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    ttl: Option<Duration>,
+    #[serde(
+        default = "HostMetaConfig::default_crawl_interval",
+        deserialize_with = "parse_duration"
+    )]
+    crawl_interval: Duration,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    request_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_headers")]
+    headers: HeaderMap,
+    #[serde(default)]
+    http2: bool,
+    #[serde(default)]
+    services: HashMap<String, ServiceHostMetaConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+struct ServiceHostMetaConfig {
+    #[serde(default, deserialize_with = "parse_headers")]
+    headers: HeaderMap,
+    http2: Option<bool>,
+}
+
+impl Default for HostMetaConfig {
+    fn default() -> Self {
+        Self {
+            ttl: None,
+            crawl_interval: Self::default_crawl_interval(),
+            request_timeout: None,
+            headers: HeaderMap::new(),
+            http2: false,
+            services: HashMap::new(),
+        }
+    }
+}
+
+impl HostMetaConfig {
+    fn default_crawl_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// The duration after which the web host meta of a service is considered stale and will be
+    /// crawled again. If not set, the web host meta will only be crawled again when the
+    /// service has been restarted.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// How often the apps will be fetched from the infrastructure to find services whose web host
+    /// meta has to be crawled.
+    pub fn crawl_interval(&self) -> Duration {
+        self.crawl_interval
+    }
+
+    /// The duration after which a request of the web host meta will be aborted. If not set, the
+    /// crawler waits until the service responds.
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout
+    }
+
+    /// The headers that will be added to the request of the web host meta of the given service.
+    /// Headers configured for the service take precedence over the headers configured for all
+    /// services.
+    pub fn request_headers(&self, service_name: &str) -> HeaderMap {
+        let mut headers = self.headers.clone();
+        if let Some(service) = self.services.get(service_name) {
+            for (name, value) in service.headers.iter() {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        headers
+    }
+
+    /// Whether the web host meta of the given service will be requested with HTTP/2 over
+    /// unencrypted connections (prior knowledge) instead of HTTP/1.1. The setting of the service
+    /// takes precedence over the setting for all services.
+    pub fn http2(&self, service_name: &str) -> bool {
+        self.services
+            .get(service_name)
+            .and_then(|service| service.http2)
+            .unwrap_or(self.http2)
+    }
+}
+
+fn parse_headers<'de, D>(deserializer: D) -> Result<HeaderMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_headers = BTreeMap::<String, String>::deserialize(deserializer)?;
+
+    let mut headers = HeaderMap::with_capacity(raw_headers.len());
+    for (name, value) in raw_headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|err| de::Error::custom(format!("Invalid header name {name}: {err}")))?;
+        let mut value = HeaderValue::from_str(&value)
+            .map_err(|err| de::Error::custom(format!("Invalid value of header {name}: {err}")))?;
+        // The headers may carry credentials, e.g. API keys, that must not end up in the logs.
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_merge_request_headers_of_service() {
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            [headers]
+            Host = 'prevant.example.com'
+            X-Api-Key = 'secret'
+
+            [services.keycloak.headers]
+            Host = 'keycloak.example.com'
+            "#,
+        )
+        .unwrap();
+
+        let headers = config.request_headers("keycloak");
+        assert_eq!(headers.get("host").unwrap(), "keycloak.example.com");
+        assert_eq!(headers.get("x-api-key").unwrap(), "secret");
+
+        let headers = config.request_headers("nginx");
+        assert_eq!(headers.get("host").unwrap(), "prevant.example.com");
+        assert_eq!(headers.get("x-api-key").unwrap(), "secret");
+    }
+
+    #[test]
+    fn should_hide_values_of_request_headers() {
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            [headers]
+            X-Api-Key = 'secret'
+
+            [services.keycloak.headers]
+            Authorization = 'Bearer token'
+            "#,
+        )
+        .unwrap();
+
+        let headers = config.request_headers("keycloak");
+        assert!(headers.values().all(|value| value.is_sensitive()));
+        let debug = format!("{config:?}");
+        assert!(!debug.contains("secret"));
+        assert!(!debug.contains("Bearer token"));
+    }
+
+    #[test]
+    fn should_prefer_http2_setting_of_service() {
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            http2 = true
+
+            [services.legacy]
+            http2 = false
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.http2("nginx"));
+        assert!(!config.http2("legacy"));
+        assert!(!HostMetaConfig::default().http2("nginx"));
+    }
+
+    #[test]
+    fn should_parse_crawl_interval_and_request_timeout() {
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            crawlInterval = '1m'
+            requestTimeout = '10s'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.crawl_interval(), Duration::from_secs(60));
+        assert_eq!(config.request_timeout(), Some(Duration::from_secs(10)));
+
+        let config = HostMetaConfig::default();
+        assert_eq!(config.crawl_interval(), Duration::from_secs(5));
+        assert_eq!(config.request_timeout(), None);
+    }
+
+    #[test]
+    fn should_not_parse_invalid_header_name() {
+        let config = toml::from_str::<HostMetaConfig>(
+            r#"
+            [headers]
+            "Invalid Header" = 'value'
+            "#,
+        );
+
+        assert!(config.is_err());
+    }
+}
@@ -25,34 +25,54 @@
  */
 
 pub use self::companion::BootstrappingContainer;
+pub use self::companion::CompanionMergePrecedence;
 pub use self::companion::DeploymentStrategy;
 pub use self::companion::Routing;
 pub use self::companion::StorageStrategy;
 use self::companion::{Companion, CompanionType, Companions};
 pub use self::container::ContainerConfig;
-pub use self::runtime::Runtime;
+pub use self::credentials_provider::CredentialsProviderError;
+pub use self::host_meta::HostMetaConfig;
+pub use self::notifications::{AppLifecycleEvent, NotificationsConfig};
+pub use self::runtime::{
+    DockerRuntimeConfig, DockerTimeoutsConfig, KubernetesClusterConfig, OperationLockConfig,
+    OperationLockStrategy, Runtime,
+};
 use crate::models::user_defined_parameters::UserDefinedParameters;
 use crate::models::AppName;
-use crate::models::ServiceConfig;
+use crate::models::{Image, Probes, ServiceConfig};
 use app_selector::AppSelector;
+use app_template::AppTemplate;
+use basic_auth::BasicAuth;
+use ca_bundle::CaBundle;
 use clap::Parser;
+use credentials_provider::{CredentialsProvider, EcrCredentialsProvider};
 use figment::providers::{Env, Format, Toml};
 use figment::value::{Dict, Map, Tag, Value};
 use figment::{Metadata, Profile};
 use jsonschema::Validator;
+use regex::Regex;
 use secstr::SecUtf8;
 use serde::Deserialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::From;
 use std::fmt::Display;
 use std::io::Error as IOError;
+use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use toml::de::Error as TomlError;
 
 mod app_selector;
+mod app_template;
+mod basic_auth;
+mod ca_bundle;
 mod companion;
 mod container;
+mod credentials_provider;
+mod host_meta;
+mod notifications;
 mod runtime;
 mod secret;
 
@@ -140,9 +160,27 @@ pub enum JiraAuth {
     },
 }
 
+#[derive(Clone, Deserialize)]
+pub struct GitHubConfig {
+    #[serde(default = "GitHubConfig::default_host")]
+    host: String,
+    repository: String,
+    token: Option<SecUtf8>,
+}
+
 #[derive(Clone, Deserialize)]
 struct Service {
     secrets: Option<Vec<secret::Secret>>,
+    #[serde(rename = "basicAuth")]
+    basic_auth: Option<BasicAuth>,
+    #[serde(default)]
+    resources: ServiceResources,
+    probes: Option<Probes>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+struct ServiceResources {
+    gpus: Option<u32>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -150,27 +188,300 @@ pub struct Config {
     #[serde(default)]
     runtime: Runtime,
     #[serde(default)]
+    runtimes: BTreeMap<String, Runtime>,
+    #[serde(default)]
     applications: Applications,
     containers: Option<ContainerConfig>,
     jira: Option<JiraConfig>,
+    github: Option<GitHubConfig>,
     #[serde(default)]
     companions: Companions,
     services: Option<BTreeMap<String, Service>>,
-    hooks: Option<BTreeMap<String, PathBuf>>,
+    #[serde(default)]
+    hooks: Hooks,
     #[serde(default)]
     registries: BTreeMap<String, Registry>,
+    #[serde(default, rename = "hostMeta")]
+    host_meta: HostMetaConfig,
+    #[serde(default)]
+    images: Images,
+    #[serde(rename = "caBundle")]
+    ca_bundle: Option<CaBundle>,
+    #[serde(default)]
+    tracing: Tracing,
+    #[serde(default)]
+    frontend: Frontend,
+    #[serde(default)]
+    logging: Logging,
+    #[serde(default)]
+    templates: BTreeMap<String, AppTemplate>,
+    #[serde(default)]
+    notifications: NotificationsConfig,
+    #[serde(default, rename = "apiAccess")]
+    api_access: ApiAccess,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
 struct Registry {
     username: Option<String>,
     password: Option<SecUtf8>,
-    mirror: Option<String>,
+    credentials_provider: Option<CredentialsProvider>,
+    ecr: Option<EcrCredentialsProvider>,
+    mirror: Option<Mirror>,
+    max_concurrent_pulls: Option<usize>,
+}
+
+/// The mirror of a registry, either as plain host or with the credentials that the mirror itself
+/// requires, e.g. an internal proxy registry.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+enum Mirror {
+    Host(String),
+    WithCredentials {
+        host: String,
+        username: String,
+        password: SecUtf8,
+    },
+}
+
+impl Mirror {
+    fn host(&self) -> &str {
+        match self {
+            Mirror::Host(host) => host,
+            Mirror::WithCredentials { host, .. } => host,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+struct Hooks {
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    timeout: Option<Duration>,
+    #[serde(flatten)]
+    paths: BTreeMap<String, HookPaths>,
+}
+
+/// A hook can be configured as a single path or as an ordered list of paths that will be applied
+/// in sequence.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum HookPaths {
+    Single(PathBuf),
+    Chain(Vec<PathBuf>),
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiAccess {
+    rate_limit: Option<RateLimit>,
+}
+
+/// Limits how many requests may be made within a period. Requests that exceed the limit are
+/// rejected until enough of the period has passed.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RateLimit {
+    requests: NonZeroU32,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    period: Option<Duration>,
+}
+
+impl RateLimit {
+    pub fn requests(&self) -> u32 {
+        self.requests.get()
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period.unwrap_or(Duration::from_secs(60))
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Tracing {
+    otlp_endpoint: Option<url::Url>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Logging {
+    #[serde(default)]
+    format: LogFormat,
+}
+
+/// The format of the log lines that PREvant writes to stderr.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line with the fields `timestamp`, `level`, `target`, and `message`.
+    Json,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Frontend {
+    #[serde(default = "Frontend::default_enabled")]
+    enabled: bool,
+}
+
+impl Frontend {
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+impl Default for Frontend {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Images {
+    #[serde(default = "Images::default_mutable_tags")]
+    mutable_tags: Vec<String>,
+    #[serde(default)]
+    defaults: Vec<ImageDefaults>,
+    #[serde(default)]
+    unknown_port: UnknownPortStrategy,
+}
+
+/// Defines what happens if the port of a service can neither be determined from its image nor from
+/// the image defaults, so that the traffic would be routed to port 80.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnknownPortStrategy {
+    /// Deploys the service and warns the user.
+    #[default]
+    Warn,
+    /// Rejects the deployment.
+    Fail,
+}
+
+/// Defaults for all images whose fully qualified name matches the selector.
+#[derive(Clone, Deserialize)]
+struct ImageDefaults {
+    #[serde(with = "serde_regex")]
+    selector: Regex,
+    port: Option<u16>,
+}
+
+impl ImageDefaults {
+    fn matches(&self, image: &Image) -> bool {
+        let image = image.to_string();
+        self.selector
+            .find(&image)
+            .is_some_and(|m| m.as_str() == image)
+    }
+}
+
+impl Images {
+    fn default_mutable_tags() -> Vec<String> {
+        vec![String::from("latest")]
+    }
+}
+
+impl Default for Images {
+    fn default() -> Self {
+        Self {
+            mutable_tags: Self::default_mutable_tags(),
+            defaults: Vec::new(),
+            unknown_port: UnknownPortStrategy::default(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
 struct Applications {
     max: Option<usize>,
+    protected: Option<AppSelector>,
+    #[serde(default, rename = "ensureDefaultApp")]
+    ensure_default_app: bool,
+    #[serde(default, rename = "protectDefaultApp")]
+    protect_default_app: bool,
+    #[serde(default, rename = "defaultAppServices")]
+    default_app_services: Vec<ServiceConfig>,
+    #[serde(default, rename = "allowExposedPorts")]
+    allow_exposed_ports: bool,
+    #[serde(default, rename = "replicateLiveEnvironment")]
+    replicate_live_environment: bool,
+    #[serde(default, rename = "replicatedEnvironmentPrecedence")]
+    replicated_environment_precedence: ReplicatedEnvironmentPrecedence,
+    #[serde(default, rename = "postDeployHookFailure")]
+    post_deploy_hook_failure: PostDeployHookFailureStrategy,
+    #[serde(default, rename = "serviceOrder")]
+    service_order: ServiceOrder,
+    #[serde(rename = "allowedEnvOverrides")]
+    allowed_env_overrides: Option<HashSet<String>>,
+    #[serde(default, rename = "deniedEnvOverrides")]
+    denied_env_overrides: HashSet<String>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    ttl: Option<Duration>,
+    #[serde(
+        default,
+        rename = "ttlCheckInterval",
+        deserialize_with = "parse_optional_duration"
+    )]
+    ttl_check_interval: Option<Duration>,
+    #[serde(rename = "rollbackOnFailure")]
+    rollback_on_failure: Option<bool>,
+}
+
+/// Parses human-readable durations such as `30s` or `24h` of the configuration.
+fn parse_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let duration = String::deserialize(deserializer)?;
+    humantime::parse_duration(&duration).map_err(serde::de::Error::custom)
+}
+
+fn parse_optional_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    parse_duration(deserializer).map(Some)
+}
+
+/// Determines whose values win if a replicated environment variable of the template app is also
+/// defined by the replica that is already running in the app.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicatedEnvironmentPrecedence {
+    /// The values of the template app override the values of the running replica.
+    #[default]
+    Template,
+    /// The values of the running replica override the values of the template app.
+    App,
+}
+
+/// Defines what happens if a post-deploy hook reports that the deployed app is unhealthy.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PostDeployHookFailureStrategy {
+    /// Keeps the deployment and warns the user.
+    #[default]
+    Warn,
+    /// Keeps the deployment but responds with an error.
+    Fail,
+}
+
+/// Defines the order of the services of an app in the API responses.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceOrder {
+    /// Sorts the services by their name and then by their type.
+    #[default]
+    Name,
+    /// Sorts the services by their type (instances, replicas, app companions, and service
+    /// companions) and then by their name.
+    Type,
 }
 
 impl Config {
@@ -186,10 +497,33 @@ impl Config {
             .extract::<Config>()
     }
 
+    /// Resolves all parts of the configuration that are not part of the configuration file
+    /// itself, e.g. the companions referenced by `[companions] includeFrom`.
+    pub async fn include_remote_parts(&mut self) -> Result<(), ConfigError> {
+        self.companions.include_remote_companions().await
+    }
+
     pub fn runtime_config(&self) -> &Runtime {
         &self.runtime
     }
 
+    /// The named runtimes that services can be deployed to in addition to the default runtime (see
+    /// [`ServiceConfig::runtime`](crate::models::ServiceConfig::runtime)).
+    pub fn additional_runtimes(&self) -> &BTreeMap<String, Runtime> {
+        &self.runtimes
+    }
+
+    /// Returns a copy of this configuration that uses the given runtime as default runtime, so that
+    /// an infrastructure backend can be created for one of the [additional
+    /// runtimes](Self::additional_runtimes).
+    pub fn with_runtime(&self, runtime: Runtime) -> Self {
+        Self {
+            runtime,
+            runtimes: BTreeMap::new(),
+            ..self.clone()
+        }
+    }
+
     pub fn container_config(&self) -> ContainerConfig {
         match &self.containers {
             Some(containers) => containers.clone(),
@@ -197,10 +531,22 @@ impl Config {
         }
     }
 
+    pub fn host_meta_config(&self) -> &HostMetaConfig {
+        &self.host_meta
+    }
+
+    pub fn notifications_config(&self) -> &NotificationsConfig {
+        &self.notifications
+    }
+
     pub fn jira_config(&self) -> Option<JiraConfig> {
         self.jira.as_ref().cloned()
     }
 
+    pub fn github_config(&self) -> Option<GitHubConfig> {
+        self.github.as_ref().cloned()
+    }
+
     pub fn user_defined_schema_validator(&self) -> Option<Validator> {
         self.companions.user_defined_schema_validator()
     }
@@ -223,6 +569,13 @@ impl Config {
         })
     }
 
+    /// Returns the service names of all companions whose `appSelector` does not match the given
+    /// app name.
+    pub fn companion_names_not_matching_app_selector(&self, app_name: &AppName) -> Vec<String> {
+        self.companions
+            .companion_names_not_matching_app_selector(app_name)
+    }
+
     pub fn companion_bootstrapping_containers<S>(
         &self,
         app_name: &AppName,
@@ -241,6 +594,31 @@ impl Config {
         )
     }
 
+    /// Reads and renders the manifests of all Kubernetes manifest companions that match the
+    /// given app name.
+    pub fn companion_kubernetes_manifests<S>(
+        &self,
+        app_name: &AppName,
+        base_url: &Option<url::Url>,
+        infrastructure: Option<S>,
+        user_defined_parameters: &Option<UserDefinedParameters>,
+    ) -> Result<Vec<String>, handlebars::RenderError>
+    where
+        S: serde::Serialize,
+    {
+        self.companions.kubernetes_manifests(
+            app_name,
+            base_url,
+            infrastructure,
+            user_defined_parameters,
+        )
+    }
+
+    pub fn kubernetes_manifest_companion_names(&self, app_name: &AppName) -> Vec<String> {
+        self.companions
+            .kubernetes_manifest_companion_names(app_name)
+    }
+
     fn companion_configs<P>(
         &self,
         app_name: &AppName,
@@ -252,6 +630,10 @@ impl Config {
         self.companions.companion_configs(app_name, predicate)
     }
 
+    pub fn companion_merge_precedence(&self) -> CompanionMergePrecedence {
+        self.companions.merge_precedence()
+    }
+
     pub fn add_secrets_to(&self, service_config: &mut ServiceConfig, app_name: &AppName) {
         if let Some(services) = &self.services {
             if let Some(service) = services.get(service_config.service_name()) {
@@ -260,32 +642,297 @@ impl Config {
         }
     }
 
-    pub fn hook(&self, hook_name: &str) -> Option<&PathBuf> {
-        self.hooks.as_ref().and_then(|hooks| hooks.get(hook_name))
+    /// Protects the route of the service with basic authentication if the service configuration
+    /// provides users for the app.
+    pub fn add_basic_auth_to(&self, service_config: &mut ServiceConfig, app_name: &AppName) {
+        if let Some(basic_auth) = self
+            .services
+            .as_ref()
+            .and_then(|services| services.get(service_config.service_name()))
+            .and_then(|service| service.basic_auth.as_ref())
+            .filter(|basic_auth| basic_auth.matches_app_name(app_name))
+        {
+            service_config.set_basic_auth_users(basic_auth.users().to_vec());
+        }
+    }
+
+    /// Applies the resources, e.g. GPUs, that have been configured for the service.
+    pub fn add_resources_to(&self, service_config: &mut ServiceConfig) {
+        if let Some(gpus) = self
+            .services
+            .as_ref()
+            .and_then(|services| services.get(service_config.service_name()))
+            .and_then(|service| service.resources.gpus)
+            .filter(|gpus| *gpus > 0)
+        {
+            service_config.set_gpus(Some(gpus));
+        }
+    }
+
+    /// Applies the probes that have been configured for the service unless the service, e.g. a
+    /// companion, declares its own probes.
+    pub fn add_probes_to(&self, service_config: &mut ServiceConfig) {
+        if service_config.probes().is_some() {
+            return;
+        }
+        if let Some(probes) = self
+            .services
+            .as_ref()
+            .and_then(|services| services.get(service_config.service_name()))
+            .and_then(|service| service.probes.clone())
+        {
+            service_config.set_probes(Some(probes));
+        }
+    }
+
+    /// Mounts the configured CA bundle into the service if the bundle applies to the app.
+    pub fn add_ca_bundle_to(&self, service_config: &mut ServiceConfig, app_name: &AppName) {
+        if let Some(ca_bundle) = self
+            .ca_bundle
+            .as_ref()
+            .filter(|ca_bundle| ca_bundle.matches_app_name(app_name))
+        {
+            let (path, data) = ca_bundle.clone().into();
+            service_config.add_file(path, data);
+        }
+    }
+
+    /// Returns the paths of the hooks with the given name in the order they should be applied.
+    pub fn hooks(&self, hook_name: &str) -> &[PathBuf] {
+        match self.hooks.paths.get(hook_name) {
+            None => &[],
+            Some(HookPaths::Single(path)) => std::slice::from_ref(path),
+            Some(HookPaths::Chain(paths)) => paths,
+        }
+    }
+
+    /// The time after which PREvant stops waiting for a hook, or `None` if hooks may run
+    /// forever.
+    pub fn hook_timeout(&self) -> Option<Duration> {
+        self.hooks.timeout
     }
 
-    pub fn registry_credentials<'a, 'b: 'a>(
-        &'b self,
+    /// Returns the username and password for the given registry. If the registry has a credentials
+    /// provider, the password will be fetched from it instead of using the static password.
+    /// Registries that opted in to Amazon ECR use a short-lived token of the AWS CLI unless they
+    /// have a credentials provider. Failures of the providers are returned so that images are not
+    /// pulled anonymously instead.
+    pub async fn registry_credentials(
+        &self,
         registry_host: &str,
-    ) -> Option<(&'a str, &'a SecUtf8)> {
-        self.registries.get(registry_host).and_then(|registry| {
-            Some((
-                registry.username.as_ref()?.as_str(),
-                registry.password.as_ref()?,
-            ))
-        })
+    ) -> Result<Option<(String, SecUtf8)>, CredentialsProviderError> {
+        let Some(registry) = self.registries.get(registry_host) else {
+            return Ok(None);
+        };
+
+        if let (Some(ecr), None) = (&registry.ecr, &registry.credentials_provider) {
+            let password = ecr.password(registry_host).await?;
+            return Ok(Some((
+                registry
+                    .username
+                    .clone()
+                    .unwrap_or_else(|| String::from(EcrCredentialsProvider::USERNAME)),
+                password,
+            )));
+        }
+
+        let Some(username) = registry.username.clone() else {
+            return Ok(None);
+        };
+
+        let password = match &registry.credentials_provider {
+            Some(provider) => provider.password().await?,
+            None => match registry.password.clone() {
+                Some(password) => password,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some((username, password)))
+    }
+
+    /// Expands the app template with the given name into its services, merging the given services
+    /// over them. Returns `None` if there is no such template.
+    pub fn expand_app_template(
+        &self,
+        template_name: &str,
+        services: Vec<ServiceConfig>,
+    ) -> Option<Vec<ServiceConfig>> {
+        self.templates
+            .get(template_name)
+            .map(|template| template.expand(services))
     }
 
     pub fn registry_mirror<'a, 'b: 'a>(&'b self, registry_host: &str) -> Option<&'a str> {
         self.registries
             .get(registry_host)
             .and_then(|registry| registry.mirror.as_ref())
-            .map(|mirror| mirror.as_str())
+            .map(|mirror| mirror.host())
+    }
+
+    /// Returns the username and password for the mirror of the given registry. These are the
+    /// credentials configured for the mirror itself or, if there are none, the credentials of the
+    /// registry that the mirror's host refers to.
+    pub async fn registry_mirror_credentials(
+        &self,
+        registry_host: &str,
+    ) -> Result<Option<(String, SecUtf8)>, CredentialsProviderError> {
+        let Some(mirror) = self
+            .registries
+            .get(registry_host)
+            .and_then(|registry| registry.mirror.as_ref())
+        else {
+            return Ok(None);
+        };
+
+        match mirror {
+            Mirror::WithCredentials {
+                username, password, ..
+            } => Ok(Some((username.clone(), password.clone()))),
+            Mirror::Host(host) => {
+                let mirror_host = host.split('/').next().unwrap_or(host);
+                self.registry_credentials(mirror_host).await
+            }
+        }
+    }
+
+    pub fn registry_max_concurrent_pulls(&self, registry_host: &str) -> Option<usize> {
+        self.registries
+            .get(registry_host)
+            .and_then(|registry| registry.max_concurrent_pulls)
+    }
+
+    /// Checks if the image refers to a tag that might point to different images over time, e.g.
+    /// `latest`. Images that are pinned by their digest are never mutable.
+    pub fn is_mutable_image(&self, image: &Image) -> bool {
+        if image.digest().is_some() {
+            return false;
+        }
+        image
+            .tag()
+            .map(|tag| self.images.mutable_tags.contains(&tag))
+            .unwrap_or(false)
+    }
+
+    /// Returns the port of the first image defaults whose selector matches the image.
+    pub fn default_port_of(&self, image: &Image) -> Option<u16> {
+        self.images
+            .defaults
+            .iter()
+            .filter(|defaults| defaults.matches(image))
+            .find_map(|defaults| defaults.port)
+    }
+
+    pub fn unknown_port_strategy(&self) -> UnknownPortStrategy {
+        self.images.unknown_port
+    }
+
+    /// The endpoint of the OpenTelemetry collector to which spans of deployments will be exported.
+    pub fn otlp_endpoint(&self) -> Option<&url::Url> {
+        self.tracing.otlp_endpoint.as_ref()
+    }
+
+    /// Whether PREvant serves its web frontend. If disabled, PREvant runs purely as an API and
+    /// does not require the `frontend` directory.
+    pub fn is_frontend_enabled(&self) -> bool {
+        self.frontend.enabled
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.logging.format
     }
 
     pub fn app_limit(&self) -> Option<usize> {
         self.applications.max
     }
+
+    pub fn is_app_protected(&self, app_name: &AppName) -> bool {
+        self.is_default_app_protected(app_name)
+            || self
+                .applications
+                .protected
+                .as_ref()
+                .map(|app_selector| app_selector.matches(app_name))
+                .unwrap_or(false)
+    }
+
+    /// Whether the app is the default app (`master`), that serves as replication source, and
+    /// whether it must not be changed through the API.
+    pub fn is_default_app_protected(&self, app_name: &AppName) -> bool {
+        self.applications.protect_default_app && app_name == &AppName::master()
+    }
+
+    /// Whether the deployment request may ask for publishing the ports of services, bypassing
+    /// the reverse proxy.
+    pub fn are_exposed_ports_allowed(&self) -> bool {
+        self.applications.allow_exposed_ports
+    }
+
+    /// Whether a client may set the environment variable with the given name via the deployment
+    /// request. If an allow list is configured, only these variables can be set. Denied variables
+    /// can never be set.
+    pub fn is_env_override_allowed(&self, env_name: &str) -> bool {
+        if self.applications.denied_env_overrides.contains(env_name) {
+            return false;
+        }
+
+        self.applications
+            .allowed_env_overrides
+            .as_ref()
+            .map(|allowed| allowed.contains(env_name))
+            .unwrap_or(true)
+    }
+
+    /// Whether replicated environment variables should be resolved from the running containers of
+    /// the template app instead of the values stored when the template app had been deployed.
+    pub fn replicate_live_environment(&self) -> bool {
+        self.applications.replicate_live_environment
+    }
+
+    pub fn replicated_environment_precedence(&self) -> ReplicatedEnvironmentPrecedence {
+        self.applications.replicated_environment_precedence
+    }
+
+    pub fn post_deploy_hook_failure_strategy(&self) -> PostDeployHookFailureStrategy {
+        self.applications.post_deploy_hook_failure
+    }
+
+    pub fn service_order(&self) -> ServiceOrder {
+        self.applications.service_order
+    }
+
+    /// The age, counted from its creation, at which an app will be deleted automatically, or
+    /// `None` if apps live forever.
+    pub fn app_ttl(&self) -> Option<Duration> {
+        self.applications.ttl
+    }
+
+    /// How often PREvant looks for apps that exceeded their [TTL](Self::app_ttl).
+    pub fn app_ttl_check_interval(&self) -> Duration {
+        self.applications
+            .ttl_check_interval
+            .unwrap_or(Duration::from_secs(5 * 60))
+    }
+
+    /// Whether the services that have been started for a deployment should be removed again if
+    /// another service of the same deployment fails to start.
+    pub fn rollback_on_failure(&self) -> bool {
+        self.applications.rollback_on_failure.unwrap_or(true)
+    }
+
+    pub fn create_app_rate_limit(&self) -> Option<&RateLimit> {
+        self.api_access.rate_limit.as_ref()
+    }
+
+    /// Returns the service configurations of the default app (`master`) if PREvant should deploy
+    /// the default app on startup in case it is not running.
+    pub fn default_app_services_to_ensure(&self) -> Option<&[ServiceConfig]> {
+        if self.applications.ensure_default_app {
+            Some(&self.applications.default_app_services)
+        } else {
+            None
+        }
+    }
 }
 
 impl JiraConfig {
@@ -297,6 +944,28 @@ impl JiraConfig {
     }
 }
 
+impl GitHubConfig {
+    fn default_host() -> String {
+        String::from("https://api.github.com")
+    }
+
+    /// The URL of the GitHub REST API, e.g. `https://github.example.com/api/v3` for GitHub
+    /// Enterprise Server.
+    pub fn host(&self) -> &String {
+        &self.host
+    }
+
+    /// The repository, e.g. `aixigo/PREvant`, whose issues and pull requests are referenced by
+    /// the app names.
+    pub fn repository(&self) -> &String {
+        &self.repository
+    }
+
+    pub fn token(&self) -> Option<&SecUtf8> {
+        self.token.as_ref()
+    }
+}
+
 impl Service {
     pub fn add_secrets_to(&self, service_config: &mut ServiceConfig, app_name: &AppName) {
         if let Some(secrets) = &self.secrets {
@@ -315,6 +984,8 @@ pub enum ConfigError {
     CannotOpenConfigFile { error: IOError },
     #[error("Invalid config file format. {error}")]
     ConfigFormatError { error: TomlError },
+    #[error("Cannot fetch companions from {url}. {error}")]
+    CannotFetchRemoteCompanions { url: url::Url, error: String },
 }
 
 impl From<IOError> for ConfigError {
@@ -344,7 +1015,7 @@ macro_rules! config_from_str {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{service::ContainerType, Image};
+    use crate::models::service::ContainerType;
     use std::str::FromStr;
 
     macro_rules! service_config {
@@ -389,7 +1060,7 @@ mod tests {
                 config.container_type(),
                 &ContainerType::ApplicationCompanion
             );
-            assert_eq!(config.labels(), None);
+            assert!(config.custom_labels().is_empty());
         });
     }
 
@@ -421,7 +1092,7 @@ mod tests {
                 "docker.io/library/nginx:latest"
             );
             assert_eq!(config.container_type(), &ContainerType::ServiceCompanion);
-            assert_eq!(config.labels(), None);
+            assert!(config.custom_labels().is_empty());
         });
     }
 
@@ -486,7 +1157,7 @@ mod tests {
 
         assert_eq!(companion_configs.len(), 1);
         companion_configs.iter().for_each(|(config, _, _)| {
-            for (k, v) in config.labels().unwrap().iter() {
+            for (k, v) in config.custom_labels().iter() {
                 assert_eq!(k, "com.example.foo");
                 assert_eq!(v, "bar");
             }
@@ -519,7 +1190,7 @@ mod tests {
                 config.container_type(),
                 &ContainerType::ApplicationCompanion
             );
-            assert_eq!(config.labels(), None);
+            assert!(config.custom_labels().is_empty());
         });
     }
 
@@ -655,49 +1326,665 @@ mod tests {
     }
 
     #[test]
-    fn should_parse_config_with_default_container_runtime() {
-        let config = config_from_str!("");
+    fn should_set_basic_auth_users_of_service() {
+        let config = config_from_str!(
+            r#"
+            [services.adminer.basicAuth]
+            users = [ "test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/" ]
+            appSelector = "master"
+            "#
+        );
+
+        let mut service_config = service_config!("adminer");
+        config.add_basic_auth_to(&mut service_config, &AppName::master());
+        assert_eq!(
+            service_config.basic_auth_users(),
+            Some(&[SecUtf8::from("test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/")][..])
+        );
 
-        assert_eq!(config.runtime_config(), &Runtime::Docker);
+        let mut service_config = service_config!("adminer");
+        config.add_basic_auth_to(
+            &mut service_config,
+            &AppName::from_str("master-1x").unwrap(),
+        );
+        assert_eq!(service_config.basic_auth_users(), None);
     }
 
     #[test]
-    fn should_convert_cli_to_config_via_figment() {
-        let args = CliArgs::parse_from(["", "--runtime-type", "Kubernetes"]);
-
-        let config = figment::Figment::new()
-            .merge(args)
-            .extract::<Config>()
-            .unwrap();
+    fn should_parse_otlp_endpoint() {
+        let config = config_from_str!(
+            r#"
+            [tracing]
+            otlpEndpoint = "http://localhost:4318/v1/traces"
+            "#
+        );
 
         assert_eq!(
-            config.runtime_config(),
-            &Runtime::Kubernetes(Default::default())
+            config.otlp_endpoint(),
+            Some(&url::Url::parse("http://localhost:4318/v1/traces").unwrap())
         );
+        assert_eq!(config_from_str!("").otlp_endpoint(), None);
     }
 
     #[test]
-    fn should_parse_registry_credentials() {
-        figment::Jail::expect_with(|jail| {
-            jail.create_file(
-                "config.toml",
-                r#"
-                [registries.'docker.io']
-                username = "user"
-                password = "pass"
+    fn should_parse_frontend_enabled() {
+        let config = config_from_str!(
+            r#"
+            [frontend]
+            enabled = false
+            "#
+        );
+
+        assert!(!config.is_frontend_enabled());
+        assert!(config_from_str!("").is_frontend_enabled());
+    }
+
+    #[test]
+    fn should_parse_log_format() {
+        let config = config_from_str!(
+            r#"
+            [logging]
+            format = "json"
+            "#
+        );
+
+        assert_eq!(config.log_format(), LogFormat::Json);
+        assert_eq!(config_from_str!("").log_format(), LogFormat::Text);
+    }
+
+    #[test]
+    fn should_set_gpus_of_service() {
+        let config = config_from_str!(
+            r#"
+            [services.model.resources]
+            gpus = 1
+            "#
+        );
+
+        let mut service_config = service_config!("model");
+        config.add_resources_to(&mut service_config);
+        assert_eq!(service_config.gpus(), Some(1));
+
+        let mut service_config = service_config!("db");
+        config.add_resources_to(&mut service_config);
+        assert_eq!(service_config.gpus(), None);
+    }
+
+    #[test]
+    fn should_set_probes_of_service() {
+        let config = config_from_str!(
+            r#"
+            [services.api.probes.readiness]
+            path = '/health/ready'
+
+            [services.api.probes.liveness]
+            path = '/health/live'
+            port = 8081
+            "#
+        );
+
+        let mut service_config = service_config!("api");
+        config.add_probes_to(&mut service_config);
+        let probes = service_config.probes().unwrap();
+        assert_eq!(probes.readiness().unwrap().path(), "/health/ready");
+        assert_eq!(probes.liveness().unwrap().port(), Some(8081));
+
+        let mut service_config = service_config!("db");
+        config.add_probes_to(&mut service_config);
+        assert_eq!(service_config.probes(), None);
+    }
+
+    #[test]
+    fn should_parse_config_with_container_limits() {
+        let config = config_from_str!(
+            r#"
+            [containers]
+            cpu_limit = 2
+            "#
+        );
+
+        let container_config = config.container_config();
+        assert_eq!(container_config.memory_limit(), None);
+        assert_eq!(container_config.cpu_limit(), Some(2.0));
+    }
+
+    #[test]
+    fn should_not_parse_config_with_non_positive_cpu_limit() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [containers]
+            cpu_limit = 0
+            "#,
+        );
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn should_parse_config_with_app_ttl() {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ttl = '24h'
+            ttlCheckInterval = '1m'
+            "#
+        );
+
+        assert_eq!(config.app_ttl(), Some(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(config.app_ttl_check_interval(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn should_parse_config_without_app_ttl() {
+        let config = config_from_str!("");
+
+        assert_eq!(config.app_ttl(), None);
+        assert_eq!(config.app_ttl_check_interval(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn should_roll_back_on_failure_by_default() {
+        let config = config_from_str!("");
+
+        assert!(config.rollback_on_failure());
+    }
+
+    #[test]
+    fn should_parse_rollback_on_failure() {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            rollbackOnFailure = false
+            "#
+        );
+
+        assert!(!config.rollback_on_failure());
+    }
+
+    #[test]
+    fn should_parse_create_app_rate_limit() {
+        let config = config_from_str!(
+            r#"
+            [apiAccess.rateLimit]
+            requests = 20
+            period = '10m'
+            "#
+        );
+
+        let rate_limit = config.create_app_rate_limit().unwrap();
+        assert_eq!(rate_limit.requests(), 20);
+        assert_eq!(rate_limit.period(), Duration::from_secs(10 * 60));
+    }
+
+    #[test]
+    fn should_not_parse_rate_limit_allowing_no_requests() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [apiAccess.rateLimit]
+            requests = 0
+            "#,
+        );
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn should_parse_hooks_with_timeout() {
+        let config = config_from_str!(
+            r#"
+            [hooks]
+            timeout = "30s"
+            deployment = "/hooks/deployment.js"
+            postDeploy = [ "/hooks/smoke-test.js", "/hooks/notify.js" ]
+            "#
+        );
+
+        assert_eq!(config.hook_timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(
+            config.hooks("deployment"),
+            &[PathBuf::from("/hooks/deployment.js")]
+        );
+        assert_eq!(
+            config.hooks("postDeploy"),
+            &[
+                PathBuf::from("/hooks/smoke-test.js"),
+                PathBuf::from("/hooks/notify.js")
+            ]
+        );
+        assert!(config.hooks("timeout").is_empty());
+    }
+
+    #[test]
+    fn should_parse_hooks_without_timeout() {
+        let config = config_from_str!(
+            r#"
+            [hooks]
+            deployment = "/hooks/deployment.js"
+            "#
+        );
+
+        assert_eq!(config.hook_timeout(), None);
+    }
+
+    #[test]
+    fn should_parse_config_with_default_container_runtime() {
+        let config = config_from_str!("");
+
+        assert_eq!(
+            config.runtime_config(),
+            &Runtime::Docker(Default::default())
+        );
+    }
+
+    #[test]
+    fn should_parse_config_with_additional_runtimes() {
+        let config = config_from_str!(
+            r#"
+            [runtime]
+            type = 'Docker'
+
+            [runtimes.cluster]
+            type = 'Kubernetes'
+            "#
+        );
+
+        assert_eq!(
+            config.additional_runtimes().get("cluster"),
+            Some(&Runtime::Kubernetes(Default::default()))
+        );
+        assert_eq!(
+            config
+                .with_runtime(Runtime::Kubernetes(Default::default()))
+                .runtime_config(),
+            &Runtime::Kubernetes(Default::default())
+        );
+    }
+
+    #[test]
+    fn should_convert_cli_to_config_via_figment() {
+        let args = CliArgs::parse_from(["", "--runtime-type", "Kubernetes"]);
+
+        let config = figment::Figment::new()
+            .merge(args)
+            .extract::<Config>()
+            .unwrap();
+
+        assert_eq!(
+            config.runtime_config(),
+            &Runtime::Kubernetes(Default::default())
+        );
+    }
+
+    #[test]
+    fn should_parse_registry_credentials() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "config.toml",
+                r#"
+                [registries.'docker.io']
+                username = "user"
+                password = "pass"
                 "#,
             )?;
 
             let config = Config::from_figment(&Default::default())?;
 
+            let credentials = tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(config.registry_credentials("docker.io"))
+                .unwrap();
             assert_eq!(
-                config.registry_credentials("docker.io"),
-                Some(("user", &SecUtf8::from_str("pass").unwrap()))
+                credentials,
+                Some((String::from("user"), SecUtf8::from_str("pass").unwrap()))
             );
             Ok(())
         })
     }
 
+    #[tokio::test]
+    async fn should_fetch_registry_password_from_credentials_provider() {
+        let config = config_from_str!(
+            r#"
+            [registries.'registry.example.com']
+            username = "AWS"
+            password = "static-pass"
+
+            [registries.'registry.example.com'.credentialsProvider]
+            command = [ "echo", "short-lived-token" ]
+            ttl = "12h"
+            "#
+        );
+
+        assert_eq!(
+            config
+                .registry_credentials("registry.example.com")
+                .await
+                .unwrap(),
+            Some((
+                String::from("AWS"),
+                SecUtf8::from_str("short-lived-token").unwrap()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_prefer_credentials_provider_over_ecr() {
+        let config = config_from_str!(
+            r#"
+            [registries.'123456789012.dkr.ecr.eu-central-1.amazonaws.com']
+            username = "AWS"
+
+            [registries.'123456789012.dkr.ecr.eu-central-1.amazonaws.com'.ecr]
+
+            [registries.'123456789012.dkr.ecr.eu-central-1.amazonaws.com'.credentialsProvider]
+            command = [ "echo", "short-lived-token" ]
+
+            [registries.'registry.example.com'.ecr]
+            "#
+        );
+
+        assert_eq!(
+            config
+                .registry_credentials("123456789012.dkr.ecr.eu-central-1.amazonaws.com")
+                .await
+                .unwrap(),
+            Some((
+                String::from("AWS"),
+                SecUtf8::from_str("short-lived-token").unwrap()
+            ))
+        );
+        assert!(config
+            .registry_credentials("registry.example.com")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn should_fail_if_credentials_provider_fails() {
+        let config = config_from_str!(
+            r#"
+            [registries.'registry.example.com']
+            username = "AWS"
+
+            [registries.'registry.example.com'.credentialsProvider]
+            command = [ "false" ]
+            "#
+        );
+
+        assert!(config
+            .registry_credentials("registry.example.com")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn should_select_credentials_of_registry_mirror() {
+        let config = config_from_str!(
+            r#"
+            [registries.'docker.io']
+            username = "hub-user"
+            password = "hub-pass"
+            mirror = { host = "harbor.example.com/proxy", username = "robot", password = "robot-pass" }
+
+            [registries.'ghcr.io']
+            mirror = "mirror.example.com/ghcr"
+
+            [registries.'mirror.example.com']
+            username = "mirror-user"
+            password = "mirror-pass"
+
+            [registries.'quay.io']
+            mirror = "quay-mirror.example.com"
+            "#
+        );
+
+        assert_eq!(
+            config.registry_mirror("docker.io"),
+            Some("harbor.example.com/proxy")
+        );
+        assert_eq!(
+            config
+                .registry_mirror_credentials("docker.io")
+                .await
+                .unwrap(),
+            Some((
+                String::from("robot"),
+                SecUtf8::from_str("robot-pass").unwrap()
+            ))
+        );
+        assert_eq!(
+            config.registry_credentials("docker.io").await.unwrap(),
+            Some((
+                String::from("hub-user"),
+                SecUtf8::from_str("hub-pass").unwrap()
+            ))
+        );
+        assert_eq!(
+            config.registry_mirror_credentials("ghcr.io").await.unwrap(),
+            Some((
+                String::from("mirror-user"),
+                SecUtf8::from_str("mirror-pass").unwrap()
+            ))
+        );
+        assert_eq!(
+            config.registry_mirror_credentials("quay.io").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            config.registry_mirror_credentials("gcr.io").await.unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn should_parse_host_meta_ttl() {
+        let config = config_from_str!(
+            r#"
+            [hostMeta]
+            ttl = "5m"
+            "#
+        );
+
+        assert_eq!(
+            config.host_meta_config().ttl(),
+            Some(std::time::Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn should_parse_host_meta_crawl_interval() {
+        let config = config_from_str!(
+            r#"
+            [hostMeta]
+            crawlInterval = "30s"
+            requestTimeout = "2s"
+            "#
+        );
+
+        assert_eq!(
+            config.host_meta_config().crawl_interval(),
+            std::time::Duration::from_secs(30)
+        );
+        assert_eq!(
+            config.host_meta_config().request_timeout(),
+            Some(std::time::Duration::from_secs(2))
+        );
+        assert_eq!(
+            config_from_str!("").host_meta_config().crawl_interval(),
+            std::time::Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn should_parse_host_meta_without_ttl() {
+        let config = config_from_str!("");
+
+        assert_eq!(config.host_meta_config().ttl(), None);
+    }
+
+    #[test]
+    fn should_parse_protected_apps() {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            protected = "demo-.*"
+            "#
+        );
+
+        assert!(config.is_app_protected(&AppName::from_str("demo-1").unwrap()));
+        assert!(!config.is_app_protected(&AppName::master()));
+    }
+
+    #[test]
+    fn should_not_protect_apps_by_default() {
+        let config = config_from_str!("");
+
+        assert!(!config.is_app_protected(&AppName::master()));
+    }
+
+    #[test]
+    fn should_allow_all_env_overrides_by_default() {
+        let config = config_from_str!("");
+
+        assert!(config.is_env_override_allowed("DATABASE_URL"));
+    }
+
+    #[test]
+    fn should_parse_allowed_and_denied_env_overrides() {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            allowedEnvOverrides = [ "LOG_LEVEL", "DATABASE_URL" ]
+            deniedEnvOverrides = [ "DATABASE_URL" ]
+            "#
+        );
+
+        assert!(config.is_env_override_allowed("LOG_LEVEL"));
+        assert!(!config.is_env_override_allowed("DATABASE_URL"));
+        assert!(!config.is_env_override_allowed("JAVA_OPTS"));
+    }
+
+    #[test]
+    fn should_parse_default_app_services_to_ensure() {
+        let config = config_from_str!(
+            r#"
+            [applications]
+            ensureDefaultApp = true
+
+            [[applications.defaultAppServices]]
+            serviceName = 'whoami'
+            image = 'traefik/whoami'
+            "#
+        );
+
+        let services = config.default_app_services_to_ensure().unwrap();
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].service_name(), "whoami");
+        assert_eq!(
+            services[0].image(),
+            &Image::from_str("traefik/whoami").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_not_ensure_default_app_by_default() {
+        let config = config_from_str!("");
+
+        assert_eq!(config.default_app_services_to_ensure(), None);
+    }
+
+    #[test]
+    fn should_consider_latest_as_mutable_image_tag_by_default() {
+        let config = config_from_str!("");
+
+        assert!(config.is_mutable_image(&Image::from_str("nginx").unwrap()));
+        assert!(config.is_mutable_image(&Image::from_str("nginx:latest").unwrap()));
+        assert!(!config.is_mutable_image(&Image::from_str("nginx:1.27").unwrap()));
+        assert!(!config.is_mutable_image(
+            &Image::from_str(
+                "sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+            )
+            .unwrap()
+        ));
+        assert!(!config.is_mutable_image(
+            &Image::from_str(
+                "nginx:latest@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+            )
+            .unwrap()
+        ));
+    }
+
+    #[test]
+    fn should_parse_mutable_image_tags() {
+        let config = config_from_str!(
+            r#"
+            [images]
+            mutableTags = [ "main", "develop" ]
+            "#
+        );
+
+        assert!(config.is_mutable_image(&Image::from_str("nginx:main").unwrap()));
+        assert!(!config.is_mutable_image(&Image::from_str("nginx:latest").unwrap()));
+    }
+
+    #[test]
+    fn should_resolve_default_port_of_image() {
+        let config = config_from_str!(
+            r#"
+            [[images.defaults]]
+            selector = 'docker.io/library/postgres:.+'
+            port = 5432
+
+            [[images.defaults]]
+            selector = '.+/mariadb:.+'
+            port = 3306
+            "#
+        );
+
+        assert_eq!(
+            config.default_port_of(&Image::from_str("postgres:16").unwrap()),
+            Some(5432)
+        );
+        assert_eq!(
+            config.default_port_of(&Image::from_str("docker.io/library/mariadb:11").unwrap()),
+            Some(3306)
+        );
+        assert_eq!(
+            config.default_port_of(&Image::from_str("nginx:latest").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn should_parse_unknown_port_strategy() {
+        assert_eq!(
+            Config::default().unknown_port_strategy(),
+            UnknownPortStrategy::Warn
+        );
+
+        let config = config_from_str!(
+            r#"
+            [images]
+            unknownPort = 'fail'
+            "#
+        );
+
+        assert_eq!(config.unknown_port_strategy(), UnknownPortStrategy::Fail);
+    }
+
+    #[test]
+    fn should_parse_registry_max_concurrent_pulls() {
+        let config = config_from_str!(
+            r#"
+            [registries.'docker.io']
+            maxConcurrentPulls = 2
+            "#
+        );
+
+        assert_eq!(config.registry_max_concurrent_pulls("docker.io"), Some(2));
+        assert_eq!(config.registry_max_concurrent_pulls("quay.io"), None);
+    }
+
     #[test]
     fn should_return_application_companions_as_service_configs_with_volumes_as_files() {
         let config = config_from_str!(
@@ -770,6 +2057,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_github_config() {
+        let config = config_from_str!(
+            r#"
+            [github]
+            repository = 'aixigo/PREvant'
+            token = 'ghp_secret'
+        "#
+        );
+
+        let github_config = config.github_config().unwrap();
+        assert_eq!(github_config.host(), "https://api.github.com");
+        assert_eq!(github_config.repository(), "aixigo/PREvant");
+        assert_eq!(
+            github_config.token(),
+            Some(&SecUtf8::from_str("ghp_secret").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_parse_github_config_without_token() {
+        let config = config_from_str!(
+            r#"
+            [github]
+            host = 'https://github.example.com/api/v3'
+            repository = 'aixigo/PREvant'
+        "#
+        );
+
+        let github_config = config.github_config().unwrap();
+        assert_eq!(github_config.host(), "https://github.example.com/api/v3");
+        assert_eq!(github_config.token(), None);
+    }
+
+    #[test]
+    fn should_select_webhooks_by_lifecycle_event() {
+        let config = config_from_str!(
+            r#"
+            [[notifications.webhooks]]
+            url = 'https://chat.example.com/hooks/all'
+
+            [[notifications.webhooks]]
+            url = 'https://chat.example.com/hooks/deletions'
+            events = [ 'deleted' ]
+        "#
+        );
+
+        let urls = |event| {
+            config
+                .notifications_config()
+                .webhooks_for(event)
+                .map(|webhook| webhook.url().as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            urls(AppLifecycleEvent::Created),
+            vec!["https://chat.example.com/hooks/all"]
+        );
+        assert_eq!(
+            urls(AppLifecycleEvent::Deleted),
+            vec![
+                "https://chat.example.com/hooks/all",
+                "https://chat.example.com/hooks/deletions"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_not_have_webhooks_by_default() {
+        let config = config_from_str!("");
+
+        assert!(!config.notifications_config().has_webhooks());
+    }
+
     #[test]
     fn should_parse_jira_config_with_api_key() {
         let config = config_from_str!(
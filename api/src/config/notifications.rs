@@ -0,0 +1,81 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Display;
+use url::Url;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    webhooks: Vec<WebhookConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    url: Url,
+    /// The events that are sent to the webhook. All events are sent if empty.
+    #[serde(default)]
+    events: HashSet<AppLifecycleEvent>,
+}
+
+/// The lifecycle events of an app that can be sent to webhooks.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppLifecycleEvent {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Display for AppLifecycleEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppLifecycleEvent::Created => write!(f, "created"),
+            AppLifecycleEvent::Updated => write!(f, "updated"),
+            AppLifecycleEvent::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// Returns the webhooks that subscribed to the given event.
+    pub fn webhooks_for(&self, event: AppLifecycleEvent) -> impl Iterator<Item = &WebhookConfig> {
+        self.webhooks
+            .iter()
+            .filter(move |webhook| webhook.events.is_empty() || webhook.events.contains(&event))
+    }
+
+    pub fn has_webhooks(&self) -> bool {
+        !self.webhooks.is_empty()
+    }
+}
+
+impl WebhookConfig {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
@@ -23,20 +23,177 @@
  * THE SOFTWARE.
  * =========================LICENSE_END==================================
  */
+use super::parse_optional_duration;
 use bytesize::ByteSize;
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
+use std::num::NonZeroUsize;
+use std::time::Duration;
 use std::{collections::BTreeMap, path::PathBuf};
+use url::Url;
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 pub enum Runtime {
-    Docker,
+    Docker(DockerRuntimeConfig),
     Kubernetes(KubernetesRuntimeConfig),
 }
 
 impl Default for Runtime {
     fn default() -> Self {
-        Self::Docker
+        Self::Docker(Default::default())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerRuntimeConfig {
+    #[serde(default)]
+    operation_lock: OperationLockConfig,
+    max_concurrent_inspections: Option<NonZeroUsize>,
+    #[serde(default)]
+    timeouts: DockerTimeoutsConfig,
+}
+
+impl DockerRuntimeConfig {
+    pub fn operation_lock(&self) -> &OperationLockConfig {
+        &self.operation_lock
+    }
+
+    /// The number of containers that will be inspected concurrently while crawling the apps.
+    pub fn max_concurrent_inspections(&self) -> usize {
+        self.max_concurrent_inspections
+            .map(NonZeroUsize::get)
+            .unwrap_or(10)
+    }
+
+    pub fn timeouts(&self) -> &DockerTimeoutsConfig {
+        &self.timeouts
+    }
+}
+
+/// Bounds the duration of the calls to the Docker daemon per kind of operation, so that a hung
+/// daemon cannot stall the crawling of apps or deployments indefinitely. Without a timeout, PREvant
+/// waits as long as the daemon needs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerTimeoutsConfig {
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    list: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    inspect: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    create: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    pull: Option<Duration>,
+    #[serde(default, deserialize_with = "parse_optional_duration")]
+    readiness: Option<Duration>,
+}
+
+impl DockerTimeoutsConfig {
+    /// The timeout for listing containers.
+    pub fn list(&self) -> Option<Duration> {
+        self.list
+    }
+
+    /// The timeout for inspecting a container.
+    pub fn inspect(&self) -> Option<Duration> {
+        self.inspect
+    }
+
+    /// The timeout for creating a container.
+    pub fn create(&self) -> Option<Duration> {
+        self.create
+    }
+
+    /// The timeout for pulling an image.
+    pub fn pull(&self) -> Option<Duration> {
+        self.pull
+    }
+
+    /// The time a service may take to become ready before the services depending on it are
+    /// started. Unlike the calls to the Docker daemon, it is bounded to five minutes by default
+    /// because a service whose healthcheck never passes would block the deployment of the app.
+    pub fn readiness(&self) -> Duration {
+        self.readiness.unwrap_or(Duration::from_secs(5 * 60))
+    }
+}
+
+/// Defines how PREvant handles a deployment or deletion of an app while another operation on the
+/// same app is still in progress.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperationLockConfig {
+    strategy: OperationLockStrategy,
+    ttl: Option<Duration>,
+    release_on_startup: bool,
+}
+
+impl<'de> Deserialize<'de> for OperationLockConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RawOperationLockConfig {
+            #[serde(default)]
+            strategy: OperationLockStrategy,
+            #[serde(default, deserialize_with = "parse_optional_duration")]
+            ttl: Option<Duration>,
+            #[serde(default)]
+            release_on_startup: bool,
+        }
+
+        let raw = RawOperationLockConfig::deserialize(deserializer)?;
+
+        // Without a TTL, a lock left behind by a crashed instance would make waiting operations
+        // loop forever.
+        if raw.strategy == OperationLockStrategy::Wait && raw.ttl.is_none() {
+            return Err(de::Error::custom(
+                "The operation lock strategy wait requires a ttl.",
+            ));
+        }
+        // Other PREvant instances might share the Docker host, so that only the locks that are
+        // older than the TTL can be released safely.
+        if raw.release_on_startup && raw.ttl.is_none() {
+            return Err(de::Error::custom(
+                "Releasing the operation locks on startup requires a ttl.",
+            ));
+        }
+
+        Ok(Self {
+            strategy: raw.strategy,
+            ttl: raw.ttl,
+            release_on_startup: raw.release_on_startup,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationLockStrategy {
+    /// Rejects the operation if there is another operation in progress.
+    #[default]
+    FailFast,
+    /// Waits until the other operation has been finished.
+    Wait,
+}
+
+impl OperationLockConfig {
+    pub fn strategy(&self) -> &OperationLockStrategy {
+        &self.strategy
+    }
+
+    /// The duration after which a lock of an operation is considered stale, e.g. because PREvant
+    /// crashed while the operation was in progress, and will be released.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Whether the locks that are left over when PREvant starts and that are older than the
+    /// [TTL](Self::ttl) will be released. Younger locks might belong to operations of other PREvant
+    /// instances that share the same Docker host and are released once they become stale.
+    pub fn release_on_startup(&self) -> bool {
+        self.release_on_startup
     }
 }
 
@@ -49,9 +206,15 @@ pub struct KubernetesRuntimeConfig {
     downward_api: KubernetesDownwardApiConfig,
     #[serde(default)]
     storage_config: KubernetesStorageConfig,
+    #[serde(default)]
+    cluster: Box<KubernetesClusterConfig>,
 }
 
 impl KubernetesRuntimeConfig {
+    pub fn cluster(&self) -> &KubernetesClusterConfig {
+        &self.cluster
+    }
+
     pub fn downward_api(&self) -> &KubernetesDownwardApiConfig {
         &self.downward_api
     }
@@ -65,6 +228,45 @@ impl KubernetesRuntimeConfig {
     }
 }
 
+/// Defines how PREvant connects to the Kubernetes API server. If nothing is configured, the
+/// connection will be inferred from the in-cluster environment or the default kubeconfig.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KubernetesClusterConfig {
+    kubeconfig: Option<PathBuf>,
+    context: Option<String>,
+    url: Option<Url>,
+    certificate_authority: Option<PathBuf>,
+    client_certificate: Option<PathBuf>,
+    client_key: Option<PathBuf>,
+}
+
+impl KubernetesClusterConfig {
+    pub fn kubeconfig(&self) -> Option<&PathBuf> {
+        self.kubeconfig.as_ref()
+    }
+
+    pub fn context(&self) -> Option<&String> {
+        self.context.as_ref()
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    pub fn certificate_authority(&self) -> Option<&PathBuf> {
+        self.certificate_authority.as_ref()
+    }
+
+    pub fn client_certificate(&self) -> Option<&PathBuf> {
+        self.client_certificate.as_ref()
+    }
+
+    pub fn client_key(&self) -> Option<&PathBuf> {
+        self.client_key.as_ref()
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 pub struct KubernetesAnnotationsConfig {
     #[serde(default)]
@@ -140,7 +342,138 @@ mod tests {
 
         let runtime = toml::de::from_str::<Runtime>(runtime_toml).unwrap();
 
-        assert_eq!(runtime, Runtime::Docker);
+        assert_eq!(runtime, Runtime::Docker(Default::default()));
+    }
+
+    #[test]
+    fn parse_as_docker_runtime_with_operation_lock() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        [operationLock]
+        strategy = 'wait'
+        ttl = '30m'
+        releaseOnStartup = true
+        "#;
+
+        let runtime = toml::de::from_str::<Runtime>(runtime_toml).unwrap();
+
+        assert_eq!(
+            runtime,
+            Runtime::Docker(DockerRuntimeConfig {
+                operation_lock: OperationLockConfig {
+                    strategy: OperationLockStrategy::Wait,
+                    ttl: Some(Duration::from_secs(30 * 60)),
+                    release_on_startup: true,
+                },
+                max_concurrent_inspections: None,
+                timeouts: DockerTimeoutsConfig::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_waiting_for_operation_lock_without_ttl() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        [operationLock]
+        strategy = 'wait'
+        "#;
+
+        let err = toml::de::from_str::<Runtime>(runtime_toml).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("The operation lock strategy wait requires a ttl."),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn should_reject_releasing_operation_locks_on_startup_without_ttl() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        [operationLock]
+        releaseOnStartup = true
+        "#;
+
+        let err = toml::de::from_str::<Runtime>(runtime_toml).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("Releasing the operation locks on startup requires a ttl."),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn parse_as_docker_runtime_with_timeouts() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        [timeouts]
+        list = '10s'
+        inspect = '5s'
+        pull = '10m'
+        readiness = '2m'
+        "#;
+
+        let Runtime::Docker(runtime) = toml::de::from_str::<Runtime>(runtime_toml).unwrap() else {
+            panic!("Docker runtime expected");
+        };
+
+        assert_eq!(
+            runtime.timeouts(),
+            &DockerTimeoutsConfig {
+                list: Some(Duration::from_secs(10)),
+                inspect: Some(Duration::from_secs(5)),
+                create: None,
+                pull: Some(Duration::from_secs(10 * 60)),
+                readiness: Some(Duration::from_secs(2 * 60)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_as_docker_runtime_with_default_readiness_timeout() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        "#;
+
+        let Runtime::Docker(runtime) = toml::de::from_str::<Runtime>(runtime_toml).unwrap() else {
+            panic!("Docker runtime expected");
+        };
+
+        assert_eq!(runtime.timeouts().readiness(), Duration::from_secs(5 * 60));
+        assert_eq!(runtime.timeouts().list(), None);
+    }
+
+    #[test]
+    fn parse_as_docker_runtime_with_max_concurrent_inspections() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        maxConcurrentInspections = 25
+        "#;
+
+        let Runtime::Docker(runtime) = toml::de::from_str::<Runtime>(runtime_toml).unwrap() else {
+            panic!("Docker runtime expected");
+        };
+
+        assert_eq!(runtime.max_concurrent_inspections(), 25);
+        assert_eq!(
+            DockerRuntimeConfig::default().max_concurrent_inspections(),
+            10
+        );
+    }
+
+    #[test]
+    fn parse_as_docker_runtime_rejects_zero_concurrent_inspections() {
+        let runtime_toml = r#"
+        type = 'Docker'
+        maxConcurrentInspections = 0
+        "#;
+
+        assert!(toml::de::from_str::<Runtime>(runtime_toml).is_err());
     }
 
     #[test]
@@ -175,6 +508,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_as_kubernetes_runtime_with_cluster_config() {
+        let runtime_toml = r#"
+        type = 'Kubernetes'
+        [cluster]
+        url = 'https://k8s.example.com:6443'
+        certificateAuthority = '/etc/prevant/ca.crt'
+        clientCertificate = '/etc/prevant/client.crt'
+        clientKey = '/etc/prevant/client.key'
+        "#;
+
+        let runtime = toml::de::from_str::<Runtime>(runtime_toml).unwrap();
+
+        assert_eq!(
+            runtime,
+            Runtime::Kubernetes(KubernetesRuntimeConfig {
+                cluster: Box::new(KubernetesClusterConfig {
+                    kubeconfig: None,
+                    context: None,
+                    url: Some(Url::parse("https://k8s.example.com:6443").unwrap()),
+                    certificate_authority: Some(PathBuf::from("/etc/prevant/ca.crt")),
+                    client_certificate: Some(PathBuf::from("/etc/prevant/client.crt")),
+                    client_key: Some(PathBuf::from("/etc/prevant/client.key")),
+                }),
+                ..Default::default()
+            })
+        );
+    }
+
     #[test]
     fn provide_default_labels_path() {
         let runtime_toml = r#"
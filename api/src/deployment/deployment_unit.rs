@@ -29,10 +29,15 @@ use crate::apps::AppsServiceError;
 use crate::config::{Config, StorageStrategy};
 use crate::deployment::hooks::Hooks;
 use crate::infrastructure::{TraefikIngressRoute, TraefikMiddleware, TraefikRouterRule};
+use crate::models::service::Services;
 use crate::models::user_defined_parameters::UserDefinedParameters;
-use crate::models::{AppName, ContainerType, Image, ServiceConfig};
+use crate::models::{
+    AppName, ContainerType, Environment, EnvironmentVariable, Image, ServiceConfig, ServicePeer,
+};
 use crate::registry::ImageInfo;
-use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use secstr::SecUtf8;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 
 pub struct Initialized {
@@ -42,6 +47,7 @@ pub struct Initialized {
 
 pub struct WithCompanions {
     app_name: AppName,
+    skipped_companions: Vec<SkippedCompanion>,
     configs: Vec<ServiceConfig>,
     service_companions: Vec<(
         ServiceConfig,
@@ -53,10 +59,15 @@ pub struct WithCompanions {
         crate::config::DeploymentStrategy,
         crate::config::StorageStrategy,
     )>,
+    companion_merge_precedence: crate::config::CompanionMergePrecedence,
+    /// The ports configured for the images (see `[[images.defaults]]`) that take precedence over
+    /// the ports the images expose.
+    default_ports: HashMap<Image, u16>,
 }
 
 pub struct WithTemplatedConfigs {
     app_name: AppName,
+    skipped_companions: Vec<SkippedCompanion>,
     configs: Vec<ServiceConfig>,
     service_companions: Vec<(
         ServiceConfig,
@@ -68,11 +79,14 @@ pub struct WithTemplatedConfigs {
         crate::config::DeploymentStrategy,
         crate::config::StorageStrategy,
     )>,
+    companion_merge_precedence: crate::config::CompanionMergePrecedence,
+    default_ports: HashMap<Image, u16>,
     templating_only_service_configs: Vec<ServiceConfig>,
 }
 
 pub struct WithResolvedImages {
     app_name: AppName,
+    skipped_companions: Vec<SkippedCompanion>,
     configs: Vec<ServiceConfig>,
     service_companions: Vec<(
         ServiceConfig,
@@ -84,6 +98,7 @@ pub struct WithResolvedImages {
         crate::config::DeploymentStrategy,
         crate::config::StorageStrategy,
     )>,
+    companion_merge_precedence: crate::config::CompanionMergePrecedence,
     templating_only_service_configs: Vec<ServiceConfig>,
     image_infos: HashMap<Image, ImageInfo>,
 }
@@ -91,18 +106,21 @@ pub struct WithResolvedImages {
 pub struct WithAppliedTemplating {
     app_name: AppName,
     services: Vec<DeployableService>,
+    skipped_companions: Vec<SkippedCompanion>,
     user_defined_parameters: Option<UserDefinedParameters>,
 }
 
 pub struct WithAppliedHooks {
     app_name: AppName,
     services: Vec<DeployableService>,
+    skipped_companions: Vec<SkippedCompanion>,
     user_defined_parameters: Option<UserDefinedParameters>,
 }
 
 pub struct WithAppliedIngressRoute {
     app_name: AppName,
     services: Vec<DeployableService>,
+    skipped_companions: Vec<SkippedCompanion>,
     route: TraefikIngressRoute,
     user_defined_parameters: Option<UserDefinedParameters>,
 }
@@ -111,11 +129,14 @@ pub struct DeploymentUnitBuilder<Stage> {
     stage: Stage,
 }
 
+#[derive(Clone)]
 pub struct DeploymentUnit {
     app_name: AppName,
     services: Vec<DeployableService>,
+    skipped_companions: Vec<SkippedCompanion>,
     route: TraefikIngressRoute,
     user_defined_parameters: Option<UserDefinedParameters>,
+    rollback_on_failure: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -125,6 +146,53 @@ pub enum DeploymentStrategy {
     RedeployNever,
 }
 
+/// A companion that will not be deployed as separate service, see [`SkipReason`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedCompanion {
+    companion_name: String,
+    reason: SkipReason,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SkipReason {
+    /// The `appSelector` of the companion does not match the app name.
+    AppSelectorMismatch,
+    /// There is a service with the same name and the companion's configuration has been merged
+    /// into it.
+    MergedWithService,
+    /// The companion has been generated for a service that is a service companion itself.
+    ServiceIsCompanion { service_name: String },
+    /// The `condition` of the companion has not been met by the user-defined parameters.
+    ConditionNotMet,
+}
+
+impl std::fmt::Display for SkippedCompanion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            SkipReason::AppSelectorMismatch => write!(
+                f,
+                "Companion {} has been skipped because its app selector does not match.",
+                self.companion_name
+            ),
+            SkipReason::MergedWithService => write!(
+                f,
+                "Companion {} has been merged with the service of the same name.",
+                self.companion_name
+            ),
+            SkipReason::ServiceIsCompanion { service_name } => write!(
+                f,
+                "Companion {} has been skipped because {service_name} replaces a service companion.",
+                self.companion_name
+            ),
+            SkipReason::ConditionNotMet => write!(
+                f,
+                "Companion {} has been skipped because its condition is not met.",
+                self.companion_name
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DeployableService {
     raw_service_config: ServiceConfig,
@@ -160,6 +228,12 @@ impl DeployableService {
     pub fn declared_volumes(&self) -> &Vec<String> {
         &self.declared_volumes
     }
+
+    /// The name of the secret holding the users if the service is protected by basic
+    /// authentication.
+    pub fn basic_auth_secret_name(&self) -> String {
+        format!("{}-basic-auth", self.service_name().to_lowercase())
+    }
 }
 
 impl std::ops::Deref for DeployableService {
@@ -192,6 +266,98 @@ impl DeploymentUnit {
     pub fn user_defined_parameters(&self) -> &Option<UserDefinedParameters> {
         &self.user_defined_parameters
     }
+
+    /// Whether the infrastructure removes the services it started for this deployment unit if
+    /// another service of it fails to start.
+    pub fn rollback_on_failure(&self) -> bool {
+        self.rollback_on_failure
+    }
+
+    pub fn set_rollback_on_failure(&mut self, rollback_on_failure: bool) {
+        self.rollback_on_failure = rollback_on_failure;
+    }
+
+    /// Moves the services that match the predicate into a deployment unit of their own, e.g.
+    /// because they will be deployed by a different infrastructure. The skipped companions remain
+    /// in this deployment unit.
+    pub fn split_off<P>(&mut self, predicate: P) -> DeploymentUnit
+    where
+        P: Fn(&DeployableService) -> bool,
+    {
+        let (split_off, remaining) = std::mem::take(&mut self.services)
+            .into_iter()
+            .partition(|service| predicate(service));
+        self.services = remaining;
+
+        DeploymentUnit {
+            app_name: self.app_name.clone(),
+            services: split_off,
+            skipped_companions: Vec::new(),
+            route: self.route.clone(),
+            user_defined_parameters: self.user_defined_parameters.clone(),
+            rollback_on_failure: self.rollback_on_failure,
+        }
+    }
+
+    /// Groups the services into stages that must be started one after another because the
+    /// services of a stage depend on services of earlier stages (see `dependsOn` of companions).
+    pub fn deployment_stages(&self) -> Vec<Vec<&DeployableService>> {
+        let stages = dependency_stages(&self.services)
+            .unwrap_or_else(|_| vec![(0..self.services.len()).collect()]);
+
+        stages
+            .into_iter()
+            .map(|stage| stage.into_iter().map(|i| &self.services[i]).collect())
+            .collect()
+    }
+
+    /// Returns the companions that are not deployed as separate service and the reason why.
+    pub fn skipped_companions(&self) -> &[SkippedCompanion] {
+        &self.skipped_companions
+    }
+
+    /// Stamps the metadata of the deployment request onto each service of the unit, including
+    /// the replicated services and the companions.
+    pub fn apply_metadata(&mut self, metadata: &BTreeMap<String, String>) {
+        for service in self.services.iter_mut() {
+            service.set_metadata(metadata.clone());
+        }
+    }
+
+    /// Stamps the time of the deployment onto each service of the unit so that the time of the
+    /// last deployment of the application can be determined from the running services.
+    pub fn apply_deployment_time(&mut self, deployed_at: DateTime<Utc>) {
+        for service in self.services.iter_mut() {
+            service.set_deployed_at(Some(deployed_at));
+        }
+    }
+
+    /// Stamps the creation time onto each service of the unit: services that are already running
+    /// keep the time of their first deployment and all other services are created by this
+    /// deployment. Must be called after [`apply_deployment_time`](Self::apply_deployment_time).
+    pub fn apply_creation_time(&mut self, running_services: &Services) {
+        for service in self.services.iter_mut() {
+            let created_at = running_services
+                .iter()
+                .find(|running| running.service_name() == service.service_name())
+                .and_then(|running| running.config.created_at().or(running.config.deployed_at()))
+                .or(service.deployed_at())
+                .cloned();
+            service.set_created_at(created_at);
+        }
+    }
+
+    /// Stamps the user-defined parameters of the unit onto each service so that the companions
+    /// can be templated again later, e.g. by a reconciliation, without the deployment request.
+    pub fn apply_user_defined_parameters(&mut self) {
+        let user_defined_parameters = self
+            .user_defined_parameters
+            .as_ref()
+            .map(|parameters| parameters.data().clone());
+        for service in self.services.iter_mut() {
+            service.set_user_defined_parameters(user_defined_parameters.clone());
+        }
+    }
 }
 
 impl DeploymentUnitBuilder<Initialized> {
@@ -211,15 +377,47 @@ impl DeploymentUnitBuilder<Initialized> {
             config.add_secrets_to(service_config, &self.stage.app_name);
         }
 
-        let service_companions = config.service_companion_configs(&self.stage.app_name);
-        let app_companions = config.application_companion_configs(&self.stage.app_name);
+        let mut service_companions = config.service_companion_configs(&self.stage.app_name);
+        let mut app_companions = config.application_companion_configs(&self.stage.app_name);
+        let mut default_ports = HashMap::new();
+
+        for service_config in self
+            .stage
+            .configs
+            .iter_mut()
+            .chain(
+                service_companions
+                    .iter_mut()
+                    .map(|(companion, _, _)| companion),
+            )
+            .chain(app_companions.iter_mut().map(|(companion, _, _)| companion))
+        {
+            if let Some(port) = config.default_port_of(service_config.image()) {
+                default_ports.insert(service_config.image().clone(), port);
+            }
+            config.add_ca_bundle_to(service_config, &self.stage.app_name);
+            config.add_basic_auth_to(service_config, &self.stage.app_name);
+            config.add_resources_to(service_config);
+            config.add_probes_to(service_config);
+        }
+        let skipped_companions = config
+            .companion_names_not_matching_app_selector(&self.stage.app_name)
+            .into_iter()
+            .map(|companion_name| SkippedCompanion {
+                companion_name,
+                reason: SkipReason::AppSelectorMismatch,
+            })
+            .collect();
 
         DeploymentUnitBuilder {
             stage: WithCompanions {
                 app_name: self.stage.app_name,
+                skipped_companions,
                 configs: self.stage.configs,
                 service_companions,
                 app_companions,
+                companion_merge_precedence: config.companion_merge_precedence(),
+                default_ports,
             },
         }
     }
@@ -233,9 +431,12 @@ impl DeploymentUnitBuilder<WithCompanions> {
         DeploymentUnitBuilder {
             stage: WithTemplatedConfigs {
                 app_name: self.stage.app_name,
+                skipped_companions: self.stage.skipped_companions,
                 configs: self.stage.configs,
                 service_companions: self.stage.service_companions,
                 app_companions: self.stage.app_companions,
+                companion_merge_precedence: self.stage.companion_merge_precedence,
+                default_ports: self.stage.default_ports,
                 templating_only_service_configs,
             },
         }
@@ -278,13 +479,18 @@ impl DeploymentUnitBuilder<WithTemplatedConfigs> {
         mut self,
         image_infos: HashMap<Image, ImageInfo>,
     ) -> DeploymentUnitBuilder<WithResolvedImages> {
-        Self::assign_port_mappings_impl(self.stage.configs.iter_mut(), &image_infos);
+        Self::assign_port_mappings_impl(
+            self.stage.configs.iter_mut(),
+            &image_infos,
+            &self.stage.default_ports,
+        );
         Self::assign_port_mappings_impl(
             self.stage
                 .service_companions
                 .iter_mut()
                 .map(|(companion, _, _)| companion),
             &image_infos,
+            &self.stage.default_ports,
         );
         Self::assign_port_mappings_impl(
             self.stage
@@ -292,33 +498,43 @@ impl DeploymentUnitBuilder<WithTemplatedConfigs> {
                 .iter_mut()
                 .map(|(companion, _, _)| companion),
             &image_infos,
+            &self.stage.default_ports,
         );
         Self::assign_port_mappings_impl(
             self.stage.templating_only_service_configs.iter_mut(),
             &image_infos,
+            &self.stage.default_ports,
         );
 
         DeploymentUnitBuilder {
             stage: WithResolvedImages {
                 app_name: self.stage.app_name,
+                skipped_companions: self.stage.skipped_companions,
                 configs: self.stage.configs,
                 service_companions: self.stage.service_companions,
                 app_companions: self.stage.app_companions,
+                companion_merge_precedence: self.stage.companion_merge_precedence,
                 templating_only_service_configs: self.stage.templating_only_service_configs,
                 image_infos,
             },
         }
     }
 
-    fn assign_port_mappings_impl<'a, Iter>(configs: Iter, image_infos: &HashMap<Image, ImageInfo>)
-    where
+    fn assign_port_mappings_impl<'a, Iter>(
+        configs: Iter,
+        image_infos: &HashMap<Image, ImageInfo>,
+        default_ports: &HashMap<Image, u16>,
+    ) where
         Iter: Iterator<Item = &'a mut ServiceConfig>,
     {
         for config in configs {
-            if let Some(info) = image_infos.get(config.image()) {
-                if let Some(port) = info.exposed_port() {
-                    config.set_port(port);
-                }
+            let port = default_ports.get(config.image()).copied().or_else(|| {
+                image_infos
+                    .get(config.image())
+                    .and_then(|info| info.exposed_port())
+            });
+            if let Some(port) = port {
+                config.set_port(port);
             }
         }
     }
@@ -331,11 +547,27 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
         user_defined_parameters: Option<UserDefinedParameters>,
     ) -> Result<DeploymentUnitBuilder<WithAppliedTemplating>, AppsServiceError> {
         let mut services = HashMap::new();
+        let mut skipped_companions = self.stage.skipped_companions.clone();
+
+        // Services can refer to the other services of the request and to the running services
+        // of the app, e.g. with `{{services.api.url}}`.
+        let peers = self
+            .stage
+            .configs
+            .iter()
+            .chain(self.stage.templating_only_service_configs.iter())
+            .map(|config| {
+                let ingress_route =
+                    TraefikIngressRoute::with_defaults(&self.stage.app_name, config.service_name());
+                ServicePeer::new(config, ingress_route.path_prefix().map(String::from))
+            })
+            .collect::<Vec<_>>();
 
         for config in self.stage.configs.iter() {
             let templated_config = config.apply_templating(
                 &self.stage.app_name,
                 base_url,
+                &peers,
                 &user_defined_parameters,
             )?;
 
@@ -375,9 +607,18 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
                         &self.stage.app_name,
                         base_url,
                         service,
+                        &peers,
                         &user_defined_parameters,
                     )?;
 
+                if !templated_companion.is_condition_met() {
+                    skipped_companions.push(SkippedCompanion {
+                        companion_name: templated_companion.service_name().clone(),
+                        reason: SkipReason::ConditionNotMet,
+                    });
+                    continue;
+                }
+
                 service_companions.push(ServiceCompanion {
                     templated_companion,
                     strategy,
@@ -397,24 +638,42 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
                 });
 
         for companion in service_companions_of_request.iter() {
-            services
-                .get_mut(companion.templated_companion.service_name())
-                .unwrap()
-                .merge_with(&companion.templated_companion);
+            Self::merge_companion_into(
+                services
+                    .get_mut(companion.templated_companion.service_name())
+                    .unwrap(),
+                &companion.templated_companion,
+                self.stage.companion_merge_precedence,
+            );
+            skipped_companions.push(SkippedCompanion {
+                companion_name: companion.templated_companion.service_name().clone(),
+                reason: SkipReason::MergedWithService,
+            });
         }
 
-        let image_infos = &self.stage.image_infos;
-
-        // Exclude service_companions that are included in the request
-        services.extend(
+        let (service_companions_of_config, service_companions_of_companions): (Vec<_>, Vec<_>) =
             service_companions_of_config
                 .into_iter()
-                .filter(|service_companion| {
+                .partition(|service_companion| {
                     !service_companions_of_request.iter().any(|scor| {
                         &service_companion.for_service_name
                             == scor.templated_companion.service_name()
                     })
-                })
+                });
+        skipped_companions.extend(service_companions_of_companions.into_iter().map(
+            |service_companion| SkippedCompanion {
+                companion_name: service_companion.templated_companion.service_name().clone(),
+                reason: SkipReason::ServiceIsCompanion {
+                    service_name: service_companion.for_service_name,
+                },
+            },
+        ));
+
+        let image_infos = &self.stage.image_infos;
+
+        services.extend(
+            service_companions_of_config
+                .into_iter()
                 .map(|service_companion| {
                     Ok((
                         service_companion.templated_companion.service_name().clone(),
@@ -444,12 +703,28 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
                 &user_defined_parameters,
             )?;
 
+            if !companion_config.is_condition_met() {
+                skipped_companions.push(SkippedCompanion {
+                    companion_name: companion_config.service_name().clone(),
+                    reason: SkipReason::ConditionNotMet,
+                });
+                continue;
+            }
+
             // If a custom application companion was deployed, its config needs to be merged
             // with the companion config
             let existing_config = services.get_mut(companion_config.service_name());
 
             if let Some(existing_strategy) = existing_config {
-                existing_strategy.merge_with(&companion_config);
+                Self::merge_companion_into(
+                    existing_strategy,
+                    &companion_config,
+                    self.stage.companion_merge_precedence,
+                );
+                skipped_companions.push(SkippedCompanion {
+                    companion_name: companion_config.service_name().clone(),
+                    reason: SkipReason::MergedWithService,
+                });
             } else {
                 services.insert(
                     companion_config.service_name().clone(),
@@ -463,6 +738,9 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
             }
         }
 
+        Self::inject_link_environment(&mut services);
+        Self::add_basic_auth_middleware(&mut services);
+
         let mut strategies = services.into_values().collect::<Vec<_>>();
 
         strategies.sort_unstable_by(|a, b| {
@@ -475,11 +753,25 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
             stage: WithAppliedTemplating {
                 app_name: self.stage.app_name,
                 services: strategies,
+                skipped_companions,
                 user_defined_parameters,
             },
         })
     }
 
+    fn merge_companion_into(
+        service: &mut ServiceConfig,
+        companion: &ServiceConfig,
+        precedence: crate::config::CompanionMergePrecedence,
+    ) {
+        match precedence {
+            crate::config::CompanionMergePrecedence::Service => service.merge_with(companion),
+            crate::config::CompanionMergePrecedence::Companion => {
+                service.merge_with_precedence_of(companion)
+            }
+        }
+    }
+
     fn deployable_service(
         &self,
         raw_service_config: ServiceConfig,
@@ -578,6 +870,82 @@ impl DeploymentUnitBuilder<WithResolvedImages> {
         })
     }
 
+    /// Adds a basic auth middleware to the route of every service that is protected by basic
+    /// authentication. The middleware refers to the secret that holds the users of the service.
+    fn add_basic_auth_middleware(services: &mut HashMap<String, DeployableService>) {
+        for service in services.values_mut() {
+            if service.basic_auth_users().is_none() {
+                continue;
+            }
+
+            let name = service.basic_auth_secret_name();
+            service.ingress_route.add_middleware(TraefikMiddleware {
+                spec: serde_value::to_value(serde_json::json!({
+                    "basicAuth": { "secret": name }
+                }))
+                .unwrap(),
+                name,
+            });
+        }
+    }
+
+    /// Injects `<NAME>_HOST` and `<NAME>_PORT` for every service that the service links to.
+    /// Environment variables that are already defined by the service won't be overwritten.
+    fn inject_link_environment(services: &mut HashMap<String, DeployableService>) {
+        let peers = services
+            .iter()
+            .map(|(name, service)| (name.clone(), service.port()))
+            .collect::<HashMap<_, _>>();
+
+        for service in services.values_mut() {
+            let mut variables = Vec::new();
+            for link in service.links() {
+                let Some(port) = peers.get(link) else {
+                    warn!(
+                        "Service {} links to {link} which is not part of the app.",
+                        service.service_name()
+                    );
+                    continue;
+                };
+
+                let prefix = link
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect::<String>();
+
+                variables.push(EnvironmentVariable::new(
+                    format!("{prefix}_HOST"),
+                    SecUtf8::from(link.as_str()),
+                ));
+                variables.push(EnvironmentVariable::new(
+                    format!("{prefix}_PORT"),
+                    SecUtf8::from(port.to_string()),
+                ));
+            }
+
+            if variables.is_empty() {
+                continue;
+            }
+
+            let mut values = service
+                .env()
+                .map(|env| env.iter().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            for variable in variables {
+                if !values.iter().any(|value| value.key() == variable.key()) {
+                    values.push(variable);
+                }
+            }
+            service.set_env(Some(Environment::new(values)));
+        }
+    }
+
     fn container_type_index(container_type: &ContainerType) -> i32 {
         match container_type {
             ContainerType::ApplicationCompanion => 0,
@@ -597,10 +965,15 @@ impl DeploymentUnitBuilder<WithAppliedTemplating> {
             .apply_deployment_hook(&self.stage.app_name, self.stage.services)
             .await?;
 
+        if let Err(service_names) = dependency_stages(&services) {
+            return Err(AppsServiceError::CyclicServiceDependencies { service_names });
+        }
+
         Ok(DeploymentUnitBuilder {
             stage: WithAppliedHooks {
                 app_name: self.stage.app_name,
                 services,
+                skipped_companions: self.stage.skipped_companions,
                 user_defined_parameters: self.stage.user_defined_parameters,
             },
         })
@@ -626,6 +999,7 @@ impl DeploymentUnitBuilder<WithAppliedHooks> {
             stage: WithAppliedIngressRoute {
                 app_name: self.stage.app_name,
                 services: self.stage.services,
+                skipped_companions: self.stage.skipped_companions,
                 route,
                 user_defined_parameters: self.stage.user_defined_parameters,
             },
@@ -637,8 +1011,10 @@ impl DeploymentUnitBuilder<WithAppliedHooks> {
         DeploymentUnit {
             app_name: self.stage.app_name,
             services: self.stage.services,
+            skipped_companions: self.stage.skipped_companions,
             route,
             user_defined_parameters: self.stage.user_defined_parameters,
+            rollback_on_failure: true,
         }
     }
 }
@@ -648,12 +1024,54 @@ impl DeploymentUnitBuilder<WithAppliedIngressRoute> {
         DeploymentUnit {
             app_name: self.stage.app_name,
             services: self.stage.services,
+            skipped_companions: self.stage.skipped_companions,
             route: self.stage.route,
             user_defined_parameters: self.stage.user_defined_parameters,
+            rollback_on_failure: true,
         }
     }
 }
 
+/// Returns the indices of the services grouped into stages so that every service comes after the
+/// services it depends on. Dependencies on services that are not part of the given services, e.g.
+/// because they are running already, are ignored. Returns the names of the services whose
+/// dependencies cannot be resolved because of a cycle.
+fn dependency_stages(services: &[DeployableService]) -> Result<Vec<Vec<usize>>, Vec<String>> {
+    let index_by_name = services
+        .iter()
+        .enumerate()
+        .map(|(i, service)| (service.service_name().as_str(), i))
+        .collect::<HashMap<_, _>>();
+
+    let mut started = HashSet::new();
+    let mut remaining = (0..services.len()).collect::<Vec<_>>();
+    let mut stages = Vec::new();
+    while !remaining.is_empty() {
+        let (stage, blocked): (Vec<usize>, Vec<usize>) = remaining.into_iter().partition(|i| {
+            services[*i]
+                .depends_on()
+                .iter()
+                .filter_map(|service_name| index_by_name.get(service_name.as_str()))
+                .all(|dependency| started.contains(dependency))
+        });
+
+        if stage.is_empty() {
+            let mut service_names = blocked
+                .into_iter()
+                .map(|i| services[i].service_name().clone())
+                .collect::<Vec<_>>();
+            service_names.sort();
+            return Err(service_names);
+        }
+
+        started.extend(stage.iter().copied());
+        stages.push(stage);
+        remaining = blocked;
+    }
+
+    Ok(stages)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -661,6 +1079,7 @@ mod tests {
     use crate::models::{Environment, EnvironmentVariable};
     use crate::{config_from_str, sc};
     use secstr::SecUtf8;
+    use std::path::PathBuf;
 
     #[tokio::test]
     async fn should_return_unique_images() -> Result<(), AppsServiceError> {
@@ -717,6 +1136,146 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn should_apply_default_ports_of_images() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.db]
+            serviceName = 'db'
+            type = 'application'
+            image = 'postgres:16'
+
+            [[images.defaults]]
+            selector = 'docker.io/library/(mariadb|postgres):.+'
+            port = 5432
+            "#
+        );
+
+        let app_name = AppName::master();
+        let service_configs = vec![sc!("http1", "nginx:1.13")];
+
+        let unit = DeploymentUnitBuilder::init(app_name, service_configs)
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let ports = unit
+            .services
+            .iter()
+            .map(|service| (service.service_name().as_str(), service.port()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(ports, HashMap::from([("db", 5432), ("http1", 80)]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_prefer_default_ports_over_exposed_ports_of_images(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [[images.defaults]]
+            selector = 'docker.io/library/postgres:.+'
+            port = 5432
+            "#
+        );
+
+        let db = sc!("db", "postgres:16");
+        let http1 = sc!("http1", "nginx:1.13");
+        let image_infos = HashMap::from([
+            (
+                db.image().clone(),
+                ImageInfo::with_exposed_port("sha256:db", 8080),
+            ),
+            (
+                http1.image().clone(),
+                ImageInfo::with_exposed_port("sha256:http1", 8080),
+            ),
+        ]);
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![db, http1])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(image_infos)
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let ports = unit
+            .services
+            .iter()
+            .map(|service| (service.service_name().as_str(), service.port()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(ports, HashMap::from([("db", 5432), ("http1", 8080)]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_mount_ca_bundle_into_services_and_companions() -> Result<(), AppsServiceError> {
+        let mut ca_bundle = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut ca_bundle, b"-----BEGIN CERTIFICATE-----").unwrap();
+
+        let config = config_from_str!(&format!(
+            r#"
+            [companions.db]
+            serviceName = 'db'
+            type = 'application'
+            image = 'postgres:16'
+
+            [caBundle]
+            file = '{}'
+            path = '/etc/ssl/certs/internal-ca.pem'
+            appSelector = 'master'
+            "#,
+            ca_bundle.path().to_string_lossy()
+        ));
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("http1", "nginx:1.13")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        assert_eq!(unit.services.len(), 2);
+        for service in unit.services {
+            assert_eq!(
+                service
+                    .files()
+                    .and_then(|files| files.get(&PathBuf::from("/etc/ssl/certs/internal-ca.pem")))
+                    .map(|data| data.unsecure()),
+                Some("-----BEGIN CERTIFICATE-----")
+            );
+        }
+
+        let unit = DeploymentUnitBuilder::init(
+            AppName::from_str("feature").unwrap(),
+            vec![sc!("http1", "nginx:1.13")],
+        )
+        .extend_with_config(&config)
+        .extend_with_templating_only_service_configs(Vec::new())
+        .extend_with_image_infos(HashMap::new())
+        .apply_templating(&None, None)?
+        .apply_hooks(&config)
+        .await?
+        .build();
+
+        assert!(unit
+            .services
+            .iter()
+            .all(|service| service.files().is_none()));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn should_merge_with_application_companion_if_services_contain_same_service_name(
     ) -> Result<(), AppsServiceError> {
@@ -769,6 +1328,68 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn should_merge_with_application_companion_with_precedence_of_companion(
+    ) -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions]
+            mergePrecedence = 'companion'
+
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'private.example.com/library/openid:latest'
+            env = [ "VAR_1=abcd", "VAR_2=1234" ]
+        "#
+        );
+
+        let app_name = AppName::master();
+        let service_configs = vec![sc!(
+            "openid",
+            labels = (),
+            env = ("VAR_1" => "efg", "VAR_3" => "5678"),
+            files = ()
+        )];
+
+        let unit = DeploymentUnitBuilder::init(app_name, service_configs)
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let openid_configs: Vec<_> = unit.services;
+        assert_eq!(openid_configs.len(), 1);
+        let openid_env = openid_configs[0].env().unwrap();
+
+        assert_eq!(
+            openid_env.variable("VAR_1"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_1"),
+                SecUtf8::from("abcd")
+            ))
+        );
+        assert_eq!(
+            openid_env.variable("VAR_2"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_2"),
+                SecUtf8::from("1234")
+            ))
+        );
+        assert_eq!(
+            openid_env.variable("VAR_3"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_3"),
+                SecUtf8::from("5678")
+            ))
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn should_merge_with_service_companion_if_services_contain_same_service_name(
     ) -> Result<(), AppsServiceError> {
@@ -1412,4 +2033,330 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn should_protect_service_with_basic_auth() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [services.http1.basicAuth]
+            users = [ "test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/" ]
+        "#
+        );
+
+        let unit = DeploymentUnitBuilder::init(
+            AppName::master(),
+            vec![sc!("http1", "nginx:1.13"), sc!("http2", "nginx:1.13")],
+        )
+        .extend_with_config(&config)
+        .extend_with_templating_only_service_configs(Vec::new())
+        .extend_with_image_infos(HashMap::new())
+        .apply_templating(&None, None)?
+        .apply_hooks(&config)
+        .await?
+        .build();
+
+        let http1 = unit
+            .services
+            .iter()
+            .find(|s| s.service_name() == "http1")
+            .unwrap();
+        assert_eq!(
+            http1.basic_auth_users(),
+            Some(&[SecUtf8::from("test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/")][..])
+        );
+        assert_eq!(
+            http1.ingress_route().routes()[0].middlewares().last(),
+            Some(&crate::infrastructure::TraefikMiddleware {
+                name: String::from("http1-basic-auth"),
+                spec: serde_value::to_value(serde_json::json!({
+                    "basicAuth": {
+                        "secret": "http1-basic-auth"
+                    }
+                }))
+                .unwrap()
+            })
+        );
+
+        let http2 = unit
+            .services
+            .iter()
+            .find(|s| s.service_name() == "http2")
+            .unwrap();
+        assert_eq!(http2.basic_auth_users(), None);
+        assert!(!http2.ingress_route().routes()[0]
+            .middlewares()
+            .iter()
+            .any(|middleware| middleware.name() == "http2-basic-auth"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_skip_companions_whose_condition_is_not_met() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.mailhog]
+            serviceName = 'mailhog'
+            type = 'application'
+            image = 'mailhog/mailhog:latest'
+            condition = '{{userDefined.email}}'
+
+            [companions.adminer]
+            serviceName = 'adminer-{{service.name}}'
+            type = 'service'
+            image = 'adminer:4.8.1'
+            condition = '{{#if userDefined.email}}false{{else}}true{{/if}}'
+        "#
+        );
+
+        let user_defined_parameters = |email: bool| {
+            UserDefinedParameters::new(
+                serde_json::json!({ "email": email }),
+                &jsonschema::validator_for(&serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "email": { "type": "boolean" }
+                    }
+                }))
+                .unwrap(),
+            )
+            .unwrap()
+        };
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("http1", "nginx:1.13")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, Some(user_defined_parameters(true)))?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let mut service_names = unit
+            .services()
+            .iter()
+            .map(|service| service.service_name().as_str())
+            .collect::<Vec<_>>();
+        service_names.sort();
+        assert_eq!(service_names, vec!["http1", "mailhog"]);
+        assert_eq!(
+            unit.skipped_companions(),
+            &[SkippedCompanion {
+                companion_name: String::from("adminer-http1"),
+                reason: SkipReason::ConditionNotMet,
+            }]
+        );
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("http1", "nginx:1.13")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, Some(user_defined_parameters(false)))?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let mut service_names = unit
+            .services()
+            .iter()
+            .map(|service| service.service_name().as_str())
+            .collect::<Vec<_>>();
+        service_names.sort();
+        assert_eq!(service_names, vec!["adminer-http1", "http1"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_inject_environment_of_linked_services() -> Result<(), AppsServiceError> {
+        let config = Config::default();
+
+        let mut wordpress = sc!("wordpress", "wordpress:latest", env = ("DB_PORT" => "3306"));
+        wordpress.set_links(vec![String::from("db"), String::from("unknown")]);
+        let mut db = sc!("db", "mariadb:latest");
+        db.set_port(3306);
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![wordpress, db])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let wordpress = unit
+            .services()
+            .iter()
+            .find(|service| service.service_name() == "wordpress")
+            .unwrap();
+        let env = wordpress.env().unwrap();
+        assert_eq!(
+            env.variable("DB_HOST"),
+            Some(&EnvironmentVariable::new(
+                String::from("DB_HOST"),
+                SecUtf8::from("db")
+            ))
+        );
+        assert_eq!(
+            env.variable("DB_PORT"),
+            Some(&EnvironmentVariable::new(
+                String::from("DB_PORT"),
+                SecUtf8::from("3306")
+            ))
+        );
+        assert_eq!(env.variable("UNKNOWN_HOST"), None);
+
+        let db = unit
+            .services()
+            .iter()
+            .find(|service| service.service_name() == "db")
+            .unwrap();
+        assert_eq!(db.env(), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_report_skipped_companions() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.openid]
+            serviceName = 'openid'
+            type = 'application'
+            image = 'private.example.com/library/openid:latest'
+
+            [companions.adminer]
+            serviceName = 'adminer'
+            type = 'application'
+            image = 'adminer:latest'
+            appSelector = 'master-.*'
+        "#
+        );
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("openid")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        assert_eq!(unit.services().len(), 1);
+        assert_eq!(
+            unit.skipped_companions(),
+            &[
+                SkippedCompanion {
+                    companion_name: String::from("adminer"),
+                    reason: SkipReason::AppSelectorMismatch,
+                },
+                SkippedCompanion {
+                    companion_name: String::from("openid"),
+                    reason: SkipReason::MergedWithService,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_order_services_by_their_dependencies() -> Result<(), AppsServiceError> {
+        let config = config_from_str!(
+            r#"
+            [companions.db]
+            serviceName = '{{service.name}}-db'
+            type = 'service'
+            image = 'postgres:16'
+
+            [companions.migration]
+            serviceName = '{{service.name}}-migration'
+            type = 'service'
+            image = 'flyway/flyway:10'
+            dependsOn = [ '{{service.name}}-db' ]
+
+            [companions.adminer]
+            serviceName = 'adminer'
+            type = 'application'
+            image = 'adminer:latest'
+        "#
+        );
+        let mut service = sc!("api");
+        service.set_depends_on(vec![
+            String::from("api-migration"),
+            String::from("running-elsewhere"),
+        ]);
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![service])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let stages = unit
+            .deployment_stages()
+            .into_iter()
+            .map(|stage| {
+                let mut service_names = stage
+                    .into_iter()
+                    .map(|service| service.service_name().as_str())
+                    .collect::<Vec<_>>();
+                service_names.sort();
+                service_names
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stages,
+            vec![
+                vec!["adminer", "api-db"],
+                vec!["api-migration"],
+                vec!["api"]
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_reject_cyclic_dependencies() {
+        let config = config_from_str!(
+            r#"
+            [companions.db]
+            serviceName = 'db'
+            type = 'application'
+            image = 'postgres:16'
+            dependsOn = [ 'migration' ]
+
+            [companions.migration]
+            serviceName = 'migration'
+            type = 'application'
+            image = 'flyway/flyway:10'
+            dependsOn = [ 'db' ]
+
+            [companions.adminer]
+            serviceName = 'adminer'
+            type = 'application'
+            image = 'adminer:latest'
+        "#
+        );
+
+        let result = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("api")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)
+            .unwrap()
+            .apply_hooks(&config)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppsServiceError::CyclicServiceDependencies { service_names })
+                if service_names == vec![String::from("db"), String::from("migration")]
+        ));
+    }
 }
@@ -25,6 +25,7 @@
  */
 use crate::apps::AppsServiceError;
 use crate::config::Config;
+use crate::models::service::{ServiceStatus, Services};
 use crate::models::{AppName, ContainerType, Environment, EnvironmentVariable, Image};
 use boa_engine::property::Attribute;
 use boa_engine::{Context, JsValue, Source};
@@ -33,7 +34,14 @@ use std::collections::BTreeMap;
 use std::iter::IntoIterator;
 use std::path::{Path, PathBuf};
 
-use super::deployment_unit::DeployableService;
+use super::deployment_unit::{DeployableService, DeploymentUnit};
+
+/// The number of iterations after which the JavaScript engine aborts a loop of a hook. The engine
+/// cannot be interrupted from the outside, thus, this bounds the time a looping hook keeps its
+/// thread busy, even after PREvant stopped waiting for it (see `[hooks] timeout`).
+const HOOK_LOOP_ITERATION_LIMIT: u64 = 1_000_000;
+/// The depth of nested function calls after which the JavaScript engine aborts a hook.
+const HOOK_RECURSION_LIMIT: usize = 256;
 
 pub struct Hooks<'a> {
     hook_config: &'a Config,
@@ -49,10 +57,73 @@ impl<'a> Hooks<'a> {
         app_name: &AppName,
         services: Vec<DeployableService>,
     ) -> Result<Vec<DeployableService>, AppsServiceError> {
-        match self.hook_config.hook("deployment") {
-            None => Ok(services),
-            Some(hook_path) => self.parse_and_run_hook(app_name, services, hook_path).await,
+        let mut services = services;
+        for hook_path in self.hook_config.hooks("deployment") {
+            services = self
+                .parse_and_run_hook(app_name, services, hook_path)
+                .await?;
         }
+        Ok(services)
+    }
+
+    /// Runs the post-deploy hooks against the deployed services, e.g. to smoke test them. Returns
+    /// the failure message of the first hook that does not pass.
+    pub async fn apply_post_deploy_hook(
+        &self,
+        deployment_unit: &DeploymentUnit,
+        services: &Services,
+    ) -> Result<(), String> {
+        for hook_path in self.hook_config.hooks("postDeploy") {
+            let app = JsDeployedApp::new(deployment_unit, services);
+            let app = serde_json::to_value(app).expect("Should be serializable");
+
+            let result = self
+                .run_hook(hook_path, "postDeployHook", move |mut context| {
+                    let app =
+                        JsValue::from_json(&app, &mut context).expect("Unable to read JSON value");
+                    context
+                        .register_global_property(
+                            boa_engine::js_string!("app"),
+                            app,
+                            Attribute::READONLY,
+                        )
+                        .expect("Property registration failed unexpectedly");
+
+                    context
+                        .eval(Source::from_bytes("postDeployHook(app)"))
+                        .and_then(|result| result.to_json(&mut context))
+                        .map_err(|err| err.to_string())
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+
+            let result = match result {
+                None => continue,
+                Some(result) => result
+                    .map_err(|err| format!("The post-deploy hook {hook_path:?} failed: {err}"))?,
+            };
+
+            match serde_json::from_value::<JsPostDeployResult>(result) {
+                Ok(JsPostDeployResult::Passed(true))
+                | Ok(JsPostDeployResult::Detailed { passed: true, .. }) => {}
+                Ok(JsPostDeployResult::Detailed {
+                    passed: false,
+                    message: Some(message),
+                }) => return Err(message),
+                Ok(_) => {
+                    return Err(format!(
+                        "The post-deploy hook {hook_path:?} reported a failure."
+                    ))
+                }
+                Err(err) => {
+                    return Err(format!(
+                        "Cannot parse result of post-deploy hook {hook_path:?}: {err}"
+                    ))
+                }
+            }
+        }
+
+        Ok(())
     }
 
     async fn parse_and_run_hook(
@@ -61,43 +132,99 @@ impl<'a> Hooks<'a> {
         services: Vec<DeployableService>,
         hook_path: &Path,
     ) -> Result<Vec<DeployableService>, AppsServiceError> {
-        match Self::parse_hook(hook_path).await {
-            Some(mut context) => {
-                Self::register_configs_as_global_property(&mut context, &services);
+        let js_configs = Self::configs_to_json(&services);
+        let app_name = app_name.to_string();
+
+        let transformed_configs = self
+            .run_hook(hook_path, "deploymentHook", move |mut context| {
+                Self::register_configs_as_global_property(&mut context, &js_configs);
                 context
                     .register_global_property(
                         boa_engine::js_string!("appName"),
-                        JsValue::String(app_name.to_string().into()),
+                        JsValue::String(app_name.into()),
                         Attribute::READONLY,
                     )
                     .expect("Property registration failed unexpectedly");
 
-                let transformed_configs = context
+                context
                     .eval(Source::from_bytes(
                         "deploymentHook(appName, serviceConfigs)",
                     ))
-                    .unwrap();
-
-                let transformed_configs = transformed_configs.to_json(&mut context).unwrap();
+                    .and_then(|result| result.to_json(&mut context))
+                    .map_err(|err| err.to_string())
+            })
+            .await?;
 
+        match transformed_configs {
+            Some(Ok(transformed_configs)) => {
                 Self::parse_service_config(services, transformed_configs)
             }
+            Some(Err(err)) => {
+                error!("The deployment hook {hook_path:?} failed: {err}");
+                Err(AppsServiceError::InvalidDeploymentHook)
+            }
             None => Ok(services),
         }
     }
 
-    async fn parse_hook(hook_path: &Path) -> Option<Context> {
+    /// Evaluates the hook on a blocking thread and passes the JavaScript context with the defined
+    /// function to `run`. Returns `None` if the hook cannot be read or does not define the
+    /// function.
+    ///
+    /// If `[hooks] timeout` is configured, PREvant stops waiting for the hook after the timeout.
+    /// The JavaScript engine cannot interrupt a running script from the outside, thus, the script
+    /// keeps its thread busy until it finishes or exceeds the runtime limits of the engine (see
+    /// [`HOOK_LOOP_ITERATION_LIMIT`] and [`HOOK_RECURSION_LIMIT`]).
+    async fn run_hook<F, T>(
+        &self,
+        hook_path: &Path,
+        function_name: &'static str,
+        run: F,
+    ) -> Result<Option<T>, AppsServiceError>
+    where
+        F: FnOnce(Context) -> T + Send + 'static,
+        T: Send + 'static,
+    {
         let hook_content = match tokio::fs::read_to_string(hook_path).await {
             Ok(hook_content) => hook_content,
             Err(err) => {
                 error!("Cannot read hook file {:?}: {}", hook_path, err);
-                return None;
+                return Ok(None);
             }
         };
 
+        let path = hook_path.to_path_buf();
+        let task = tokio::task::spawn_blocking(move || {
+            Self::parse_hook(&path, &hook_content, function_name).map(run)
+        });
+
+        let result = match self.hook_config.hook_timeout() {
+            Some(timeout) => tokio::time::timeout(timeout, task).await.map_err(|_| {
+                error!("The hook {hook_path:?} did not finish within {timeout:?}");
+                AppsServiceError::HookTimeout {
+                    hook_path: hook_path.to_path_buf(),
+                    timeout,
+                }
+            })?,
+            None => task.await,
+        };
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    fn parse_hook(hook_path: &Path, hook_content: &str, function_name: &str) -> Option<Context> {
         let mut context = Context::default();
+        context
+            .runtime_limits_mut()
+            .set_loop_iteration_limit(HOOK_LOOP_ITERATION_LIMIT);
+        context
+            .runtime_limits_mut()
+            .set_recursion_limit(HOOK_RECURSION_LIMIT);
 
-        if let Err(err) = context.eval(Source::from_bytes(&hook_content)) {
+        if let Err(err) = context.eval(Source::from_bytes(hook_content)) {
             error!(
                 "Cannot populate hook {:?} to Javascript context: {:?}",
                 hook_path, err
@@ -105,22 +232,25 @@ impl<'a> Hooks<'a> {
             return None;
         }
 
-        if context.interner().get("deploymentHook").is_some() {
+        if context.interner().get(function_name).is_some() {
             Some(context)
         } else {
             None
         }
     }
 
-    fn register_configs_as_global_property(context: &mut Context, services: &[DeployableService]) {
+    fn configs_to_json(services: &[DeployableService]) -> serde_json::Value {
         let js_configs = services
             .iter()
             .map(JsServiceConfig::from)
             .collect::<Vec<_>>();
 
-        let js_configs = serde_json::to_value(js_configs).expect("Should be serializable");
+        serde_json::to_value(js_configs).expect("Should be serializable")
+    }
+
+    fn register_configs_as_global_property(context: &mut Context, js_configs: &serde_json::Value) {
         let js_configs =
-            JsValue::from_json(&js_configs, context).expect("Unable to read JSON value");
+            JsValue::from_json(js_configs, context).expect("Unable to read JSON value");
 
         context
             .register_global_property(
@@ -230,6 +360,58 @@ impl From<&DeployableService> for JsServiceConfig {
     }
 }
 
+/// The app that is passed to the post-deploy hook.
+#[derive(Serialize)]
+struct JsDeployedApp {
+    name: String,
+    services: Vec<JsDeployedService>,
+}
+
+#[derive(Serialize)]
+struct JsDeployedService {
+    name: String,
+    r#type: ContainerType,
+    status: ServiceStatus,
+    url: Option<String>,
+}
+
+impl JsDeployedApp {
+    fn new(deployment_unit: &DeploymentUnit, services: &Services) -> Self {
+        Self {
+            name: deployment_unit.app_name().to_string(),
+            services: services
+                .iter()
+                .map(|service| JsDeployedService {
+                    name: service.service_name().clone(),
+                    r#type: service.container_type().clone(),
+                    status: service.status().clone(),
+                    url: deployment_unit
+                        .services()
+                        .iter()
+                        .find(|deployed| {
+                            deployed.service_name() == service.service_name()
+                                && deployed.container_type() == service.container_type()
+                        })
+                        .and_then(|deployed| deployed.ingress_route().to_url())
+                        .map(|url| url.to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A post-deploy hook either returns a boolean or an object with the outcome and an optional
+/// message that explains the failure.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsPostDeployResult {
+    Passed(bool),
+    Detailed {
+        passed: bool,
+        message: Option<String>,
+    },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -551,6 +733,69 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn apply_chained_deployment_hooks_in_order() -> Result<(), AppsError> {
+        let mut first_hook_file = NamedTempFile::new().unwrap();
+        first_hook_file
+            .write_all(
+                br#"
+                function deploymentHook( appName, configs ) {
+                    return configs.map((config) => {
+                        config.env['VARIABLE_X'] = 'first';
+                        return config;
+                    });
+                }
+                "#,
+            )
+            .unwrap();
+        let mut second_hook_file = NamedTempFile::new().unwrap();
+        second_hook_file
+            .write_all(
+                br#"
+                function deploymentHook( appName, configs ) {
+                    return configs.map((config) => {
+                        config.env['VARIABLE_X'] = config.env['VARIABLE_X'] + '-second';
+                        return config;
+                    });
+                }
+                "#,
+            )
+            .unwrap();
+
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [hooks]
+            deployment = [ {:?}, {:?} ]
+            "#,
+            first_hook_file.path(),
+            second_hook_file.path()
+        ));
+
+        let unit = DeploymentUnitBuilder::init(AppName::master(), vec![crate::sc!("service-a")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await?
+            .build();
+
+        let deployed_variables = unit
+            .services()
+            .iter()
+            .filter_map(|service| service.env().cloned())
+            .flat_map(|env| env.into_iter())
+            .map(|env| (env.key().clone(), env.value().unsecure().to_string()))
+            .collect::<Vec<(String, String)>>();
+
+        assert_eq!(
+            deployed_variables,
+            vec![(String::from("VARIABLE_X"), String::from("first-second"))]
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn fail_with_hook_returning_invalid_object() -> Result<(), AppsError> {
         let script = r#"
@@ -584,4 +829,170 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn fail_with_hook_exceeding_timeout() -> Result<(), AppsError> {
+        let mut hook_file = NamedTempFile::new().unwrap();
+        hook_file
+            .write_all(
+                br#"
+                function deploymentHook( appName, configs ) {
+                    const end = Date.now() + 2000;
+                    while (Date.now() < end) {}
+                    return configs;
+                }
+                "#,
+            )
+            .unwrap();
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [hooks]
+            timeout = "100ms"
+            deployment = {:?}
+            "#,
+            hook_file.path()
+        ));
+
+        let result = DeploymentUnitBuilder::init(AppName::master(), vec![crate::sc!("service-a")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppsError::HookTimeout { hook_path, timeout })
+                if hook_path == hook_file.path() && timeout == std::time::Duration::from_millis(100)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fail_with_hook_exceeding_loop_iteration_limit() -> Result<(), AppsError> {
+        let mut hook_file = NamedTempFile::new().unwrap();
+        hook_file
+            .write_all(
+                br#"
+                function deploymentHook( appName, configs ) {
+                    while (true) {}
+                    return configs;
+                }
+                "#,
+            )
+            .unwrap();
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [hooks]
+            deployment = {:?}
+            "#,
+            hook_file.path()
+        ));
+
+        let result = DeploymentUnitBuilder::init(AppName::master(), vec![crate::sc!("service-a")])
+            .extend_with_config(&config)
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)?
+            .apply_hooks(&config)
+            .await;
+
+        assert!(matches!(result, Err(AppsError::InvalidDeploymentHook)));
+
+        Ok(())
+    }
+
+    fn config_with_post_deploy_hook(script: &str) -> (NamedTempFile, Config) {
+        let mut hook_file = NamedTempFile::new().unwrap();
+
+        hook_file.write_all(script.as_bytes()).unwrap();
+
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [hooks]
+            postDeploy = {:?}
+            "#,
+            hook_file.path()
+        ));
+
+        (hook_file, config)
+    }
+
+    async fn deployed_app(config: &Config) -> Result<(DeploymentUnit, Services), AppsError> {
+        let unit = DeploymentUnitBuilder::init(
+            AppName::master(),
+            vec![crate::sc!("service-a"), crate::sc!("service-b")],
+        )
+        .extend_with_config(config)
+        .extend_with_templating_only_service_configs(Vec::new())
+        .extend_with_image_infos(HashMap::new())
+        .apply_templating(&None, None)?
+        .apply_hooks(config)
+        .await?
+        .build();
+
+        let services = Services::from(
+            unit.services()
+                .iter()
+                .map(|service| crate::models::service::Service {
+                    id: service.service_name().clone(),
+                    state: crate::models::service::State {
+                        status: if service.service_name() == "service-a" {
+                            ServiceStatus::Running
+                        } else {
+                            ServiceStatus::Paused
+                        },
+                        started_at: None,
+                        replicas: None,
+                    },
+                    config: (**service).clone(),
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        Ok((unit, services))
+    }
+
+    #[tokio::test]
+    async fn pass_post_deploy_hook() -> Result<(), AppsError> {
+        let script = r#"
+        function postDeployHook( app ) {
+            return app.name === 'master' && app.services.length === 2;
+        }
+        "#;
+        let (_temp_js_file, config) = config_with_post_deploy_hook(script);
+        let (unit, services) = deployed_app(&config).await?;
+
+        let result = Hooks::new(&config)
+            .apply_post_deploy_hook(&unit, &services)
+            .await;
+
+        assert_eq!(result, Ok(()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fail_post_deploy_hook_with_message() -> Result<(), AppsError> {
+        let script = r#"
+        function postDeployHook( app ) {
+            const stopped = app.services
+                .filter(service => service.status !== 'running')
+                .map(service => service.name);
+            return { passed: stopped.length === 0, message: 'Not running: ' + stopped.join(', ') };
+        }
+        "#;
+        let (_temp_js_file, config) = config_with_post_deploy_hook(script);
+        let (unit, services) = deployed_app(&config).await?;
+
+        let result = Hooks::new(&config)
+            .apply_post_deploy_hook(&unit, &services)
+            .await;
+
+        assert_eq!(result, Err(String::from("Not running: service-b")));
+
+        Ok(())
+    }
 }
@@ -25,37 +25,60 @@
  */
 
 use http_api_problem::HttpApiProblem;
-use rocket::http::{hyper::header::CONTENT_TYPE, Header, Status};
+use rocket::http::hyper::header::CONTENT_TYPE;
+use rocket::http::{Header, Status};
 use rocket::request::Request;
 use rocket::response::{self, Responder, Response};
 use std::convert::From;
 use std::io::Cursor;
+use std::time::Duration;
 
 pub type HttpResult<T> = Result<T, HttpApiError>;
 
 #[derive(Debug)]
-pub struct HttpApiError(HttpApiProblem);
+pub struct HttpApiError {
+    problem: HttpApiProblem,
+    retry_after: Option<Duration>,
+}
+
+impl HttpApiError {
+    /// Tells the client with a `Retry-After` header how long it has to wait before repeating the
+    /// request.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+}
 
 impl From<HttpApiProblem> for HttpApiError {
     fn from(problem: HttpApiProblem) -> Self {
-        Self(problem)
+        Self {
+            problem,
+            retry_after: None,
+        }
     }
 }
 
 impl<'r> Responder<'r, 'static> for HttpApiError {
     fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
-        if self.0.status == Some(http_api_problem::StatusCode::NO_CONTENT) {
+        if self.problem.status == Some(http_api_problem::StatusCode::NO_CONTENT) {
             return rocket::response::status::NoContent.respond_to(request);
         }
 
-        let paylaod = self.0.json_bytes();
-        Response::build()
+        let paylaod = self.problem.json_bytes();
+        let mut response = Response::build();
+        if let Some(retry_after) = self.retry_after {
+            // Retry-After only supports whole seconds and the client must not retry too early
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response.header(Header::new("Retry-After", seconds.to_string()));
+        }
+        response
             .header(Header::new(
                 CONTENT_TYPE.as_str(),
                 "application/problem+json",
             ))
             .status(
-                self.0
+                self.problem
                     .status
                     .and_then(|status| Status::from_code(status.as_u16()))
                     .unwrap_or_default(),
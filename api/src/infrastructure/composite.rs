@@ -0,0 +1,586 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use super::infrastructure::{HttpForwarder, Infrastructure};
+use super::traefik::TraefikIngressRoute;
+use crate::config::ContainerConfig;
+use crate::deployment::DeploymentUnit;
+use crate::models::service::{Service, ServiceStatus, Services};
+use crate::models::{AppName, Environment, ResourceUsage, WebHostMeta};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
+
+/// Combines the infrastructure of the default runtime with the infrastructures of the additional
+/// runtimes (see `[runtimes]`). Each service is deployed to the runtime that is named by
+/// [`ServiceConfig::runtime`](crate::models::ServiceConfig::runtime) and the results of all
+/// runtimes are aggregated, so that an app can span multiple runtimes.
+pub struct CompositeInfrastructure {
+    default: Box<dyn Infrastructure>,
+    runtimes: BTreeMap<String, Box<dyn Infrastructure>>,
+}
+
+impl Clone for CompositeInfrastructure {
+    fn clone(&self) -> Self {
+        Self {
+            default: dyn_clone::clone_box(&*self.default),
+            runtimes: self
+                .runtimes
+                .iter()
+                .map(|(name, infrastructure)| {
+                    (name.clone(), dyn_clone::clone_box(&**infrastructure))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CompositeInfrastructure {
+    pub fn new(
+        default: Box<dyn Infrastructure>,
+        runtimes: BTreeMap<String, Box<dyn Infrastructure>>,
+    ) -> Self {
+        Self { default, runtimes }
+    }
+
+    /// All infrastructures with the name of their runtime, `None` for the default runtime.
+    fn infrastructures(&self) -> impl Iterator<Item = (Option<&String>, &dyn Infrastructure)> {
+        std::iter::once((None, &*self.default)).chain(
+            self.runtimes
+                .iter()
+                .map(|(name, infrastructure)| (Some(name), &**infrastructure)),
+        )
+    }
+
+    /// Finds the infrastructure that runs the given service of the app.
+    async fn infrastructure_of(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<Option<&dyn Infrastructure>> {
+        for (_, infrastructure) in self.infrastructures() {
+            let runs_service = infrastructure
+                .fetch_services()
+                .await?
+                .get(app_name)
+                .is_some_and(|services| {
+                    services
+                        .iter()
+                        .any(|service| service.service_name() == service_name)
+                });
+            if runs_service {
+                return Ok(Some(infrastructure));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn merge_services(services: Services, other: Services) -> Services {
+    Services::from(
+        services
+            .into_iter()
+            .chain(other.into_iter())
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[async_trait]
+impl Infrastructure for CompositeInfrastructure {
+    async fn fetch_services(&self) -> Result<HashMap<AppName, Services>> {
+        let mut apps = HashMap::<AppName, Services>::new();
+
+        for (runtime, infrastructure) in self.infrastructures() {
+            for (app_name, services) in infrastructure.fetch_services().await? {
+                let services = Services::from(
+                    services
+                        .into_iter()
+                        .map(|mut service| {
+                            service.config.set_runtime(runtime.cloned());
+                            service
+                        })
+                        .collect::<Vec<_>>(),
+                );
+
+                let services = match apps.remove(&app_name) {
+                    Some(existing_services) => merge_services(existing_services, services),
+                    None => services,
+                };
+                apps.insert(app_name, services);
+            }
+        }
+
+        Ok(apps)
+    }
+
+    async fn fetch_app_names(&self) -> Result<HashSet<AppName>> {
+        let mut app_names = HashSet::new();
+        for (_, infrastructure) in self.infrastructures() {
+            app_names.extend(infrastructure.fetch_app_names().await?);
+        }
+        Ok(app_names)
+    }
+
+    /// Deploys the services to their runtimes one runtime after another. Services that moved to
+    /// another runtime are stopped in their previous runtime once all runtimes have deployed their
+    /// services.
+    ///
+    /// Each runtime rolls back its own services if it fails to deploy them (see
+    /// `[applications] rollbackOnFailure`), but the services that other runtimes have deployed
+    /// before are kept because the runtimes cannot roll back as a whole.
+    async fn deploy_services(
+        &self,
+        status_id: &str,
+        deployment_unit: &DeploymentUnit,
+        container_config: &ContainerConfig,
+    ) -> Result<Services> {
+        if let Some(runtime) = deployment_unit
+            .services()
+            .iter()
+            .filter_map(|service| service.runtime())
+            .find(|runtime| !self.runtimes.contains_key(*runtime))
+        {
+            return Err(anyhow!("There is no runtime named {runtime}."));
+        }
+
+        let app_name = deployment_unit.app_name();
+        let mut moved_services = Vec::new();
+        for (runtime, infrastructure) in self.infrastructures() {
+            let Some(running_services) = infrastructure.fetch_services().await?.remove(app_name)
+            else {
+                continue;
+            };
+            for running_service in running_services.iter() {
+                let has_moved = deployment_unit.services().iter().any(|service| {
+                    service.service_name() == running_service.service_name()
+                        && service.runtime() != runtime
+                });
+                if has_moved {
+                    moved_services.push((infrastructure, running_service.service_name().clone()));
+                }
+            }
+        }
+
+        let mut default_unit = deployment_unit.clone();
+        let mut services = Services::empty();
+
+        for (name, infrastructure) in &self.runtimes {
+            let unit = default_unit.split_off(|service| service.runtime() == Some(name));
+            if unit.services().is_empty() {
+                continue;
+            }
+
+            services = merge_services(
+                services,
+                infrastructure
+                    .deploy_services(status_id, &unit, container_config)
+                    .await?,
+            );
+        }
+
+        if !default_unit.services().is_empty() {
+            services = merge_services(
+                services,
+                self.default
+                    .deploy_services(status_id, &default_unit, container_config)
+                    .await?,
+            );
+        }
+
+        for (infrastructure, service_name) in moved_services {
+            infrastructure.stop_service(app_name, &service_name).await?;
+        }
+
+        Ok(services)
+    }
+
+    async fn update_images(
+        &self,
+        status_id: &str,
+        app_name: &AppName,
+        image_digests: &HashMap<String, String>,
+    ) -> Result<Services> {
+        let mut services = Services::empty();
+        for (_, infrastructure) in self.infrastructures() {
+            services = merge_services(
+                services,
+                infrastructure
+                    .update_images(status_id, app_name, image_digests)
+                    .await?,
+            );
+        }
+        Ok(services)
+    }
+
+    async fn get_status_change(&self, status_id: &str) -> Result<Option<Services>> {
+        let mut status_change = None;
+        for (_, infrastructure) in self.infrastructures() {
+            if let Some(services) = infrastructure.get_status_change(status_id).await? {
+                status_change = Some(match status_change {
+                    Some(existing_services) => merge_services(existing_services, services),
+                    None => services,
+                });
+            }
+        }
+        Ok(status_change)
+    }
+
+    async fn release_orphaned_operation_locks(&self) -> Result<usize> {
+        let mut released = 0;
+        for (_, infrastructure) in self.infrastructures() {
+            released += infrastructure.release_orphaned_operation_locks().await?;
+        }
+        Ok(released)
+    }
+
+    async fn stop_services(&self, status_id: &str, app_name: &AppName) -> Result<Services> {
+        let mut services = Services::empty();
+        for (_, infrastructure) in self.infrastructures() {
+            services = merge_services(
+                services,
+                infrastructure.stop_services(status_id, app_name).await?,
+            );
+        }
+        Ok(services)
+    }
+
+    async fn stop_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<Option<Service>> {
+        match self.infrastructure_of(app_name, service_name).await? {
+            Some(infrastructure) => infrastructure.stop_service(app_name, service_name).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_logs<'a>(
+        &'a self,
+        app_name: &'a AppName,
+        service_name: &'a str,
+        from: &'a Option<DateTime<FixedOffset>>,
+        limit: &'a Option<usize>,
+        follow: bool,
+    ) -> BoxStream<'a, Result<(DateTime<FixedOffset>, String)>> {
+        match self.infrastructure_of(app_name, service_name).await {
+            Ok(Some(infrastructure)) => {
+                infrastructure
+                    .get_logs(app_name, service_name, from, limit, follow)
+                    .await
+            }
+            Ok(None) => stream::empty().boxed(),
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+
+    async fn change_status(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        status: ServiceStatus,
+    ) -> Result<Option<Service>> {
+        match self.infrastructure_of(app_name, service_name).await? {
+            Some(infrastructure) => {
+                infrastructure
+                    .change_status(app_name, service_name, status)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn scale_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        replicas: NonZeroU32,
+    ) -> Result<Option<Service>> {
+        match self.infrastructure_of(app_name, service_name).await? {
+            Some(infrastructure) => {
+                infrastructure
+                    .scale_service(app_name, service_name, replicas)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<Option<BTreeMap<String, ResourceUsage>>> {
+        let mut resource_usage: Option<BTreeMap<String, ResourceUsage>> = None;
+        for (_, infrastructure) in self.infrastructures() {
+            if let Some(usage) = infrastructure.fetch_resource_usage(app_name).await? {
+                resource_usage
+                    .get_or_insert_with(BTreeMap::new)
+                    .extend(usage);
+            }
+        }
+        Ok(resource_usage)
+    }
+
+    async fn fetch_live_environments(
+        &self,
+        app_name: &AppName,
+    ) -> Result<HashMap<String, Environment>> {
+        let mut live_environments = HashMap::new();
+        for (_, infrastructure) in self.infrastructures() {
+            live_environments.extend(infrastructure.fetch_live_environments(app_name).await?);
+        }
+        Ok(live_environments)
+    }
+
+    async fn fetch_pinned_app_names(&self) -> Result<HashSet<AppName>> {
+        let mut app_names = HashSet::new();
+        for (_, infrastructure) in self.infrastructures() {
+            app_names.extend(infrastructure.fetch_pinned_app_names().await?);
+        }
+        Ok(app_names)
+    }
+
+    /// Pins the app on each runtime that runs services of the app.
+    async fn set_pinned(&self, app_name: &AppName, pinned: bool) -> Result<()> {
+        for (_, infrastructure) in self.infrastructures() {
+            if infrastructure.fetch_app_names().await?.contains(app_name) {
+                infrastructure.set_pinned(app_name, pinned).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn http_forwarder(&self) -> Result<Box<dyn HttpForwarder>> {
+        let mut forwarders = Vec::with_capacity(self.runtimes.len() + 1);
+        for (_, infrastructure) in self.infrastructures() {
+            forwarders.push(infrastructure.http_forwarder().await?);
+        }
+        Ok(Box::new(CompositeHttpForwarder { forwarders }))
+    }
+
+    async fn base_traefik_ingress_route(&self) -> Result<Option<TraefikIngressRoute>> {
+        self.default.base_traefik_ingress_route().await
+    }
+}
+
+/// Asks the forwarders of all runtimes until one of them finds the service.
+struct CompositeHttpForwarder {
+    forwarders: Vec<Box<dyn HttpForwarder>>,
+}
+
+impl Clone for CompositeHttpForwarder {
+    fn clone(&self) -> Self {
+        Self {
+            forwarders: self
+                .forwarders
+                .iter()
+                .map(|forwarder| dyn_clone::clone_box(&**forwarder))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpForwarder for CompositeHttpForwarder {
+    async fn request_web_host_meta(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        request: http::Request<http_body_util::Empty<bytes::Bytes>>,
+    ) -> Result<Option<WebHostMeta>> {
+        let mut last_error = None;
+
+        for forwarder in &self.forwarders {
+            let mut copied_request = http::Request::builder()
+                .method(request.method().clone())
+                .uri(request.uri().clone())
+                .version(request.version())
+                .body(http_body_util::Empty::new())?;
+            *copied_request.headers_mut() = request.headers().clone();
+
+            match forwarder
+                .request_web_host_meta(app_name, service_name, copied_request)
+                .await
+            {
+                Ok(Some(web_host_meta)) => return Ok(Some(web_host_meta)),
+                Ok(None) => {}
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        match last_error {
+            Some(err) => Err(err),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::deployment_unit::DeploymentUnitBuilder;
+    use crate::infrastructure::Dummy;
+    use crate::models::ServiceConfig;
+    use crate::sc;
+
+    async fn deployment_unit(services: Vec<ServiceConfig>) -> DeploymentUnit {
+        DeploymentUnitBuilder::init(AppName::master(), services)
+            .extend_with_config(&Default::default())
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)
+            .unwrap()
+            .apply_hooks(&Default::default())
+            .await
+            .unwrap()
+            .build()
+    }
+
+    fn service_names(services: &Services) -> Vec<(&String, Option<&String>)> {
+        services
+            .iter()
+            .map(|service| (service.service_name(), service.config.runtime()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn deploy_services_to_their_runtimes() -> Result<()> {
+        let default = Dummy::new();
+        let cluster = Dummy::new();
+        let infrastructure = CompositeInfrastructure::new(
+            Box::new(default.clone()),
+            BTreeMap::from([(String::from("cluster"), Box::new(cluster.clone()) as Box<_>)]),
+        );
+
+        let mut db = sc!("db");
+        db.set_runtime(Some(String::from("cluster")));
+        let unit = deployment_unit(vec![sc!("api"), db]).await;
+
+        let deployed = infrastructure
+            .deploy_services("status-id", &unit, &ContainerConfig::default())
+            .await?;
+
+        assert_eq!(
+            service_names(&deployed),
+            vec![
+                (&String::from("api"), None),
+                (&String::from("db"), Some(&String::from("cluster")))
+            ]
+        );
+        assert_eq!(
+            default
+                .services()
+                .iter()
+                .map(|service| service.service_name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["api"]
+        );
+        assert_eq!(
+            cluster
+                .services()
+                .iter()
+                .map(|service| service.service_name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["db"]
+        );
+
+        let apps = infrastructure.fetch_services().await?;
+        assert_eq!(
+            service_names(&apps[&AppName::master()]),
+            vec![
+                (&String::from("api"), None),
+                (&String::from("db"), Some(&String::from("cluster")))
+            ]
+        );
+
+        let stopped = infrastructure
+            .stop_services("status-id", &AppName::master())
+            .await?;
+        assert_eq!(stopped.len(), 2);
+        assert!(infrastructure.fetch_services().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stop_services_in_their_previous_runtime() -> Result<()> {
+        let default = Dummy::new();
+        let cluster = Dummy::new();
+        let infrastructure = CompositeInfrastructure::new(
+            Box::new(default.clone()),
+            BTreeMap::from([(String::from("cluster"), Box::new(cluster.clone()) as Box<_>)]),
+        );
+
+        let unit = deployment_unit(vec![sc!("api"), sc!("db")]).await;
+        infrastructure
+            .deploy_services("status-id", &unit, &ContainerConfig::default())
+            .await?;
+
+        let mut db = sc!("db");
+        db.set_runtime(Some(String::from("cluster")));
+        let unit = deployment_unit(vec![db]).await;
+        infrastructure
+            .deploy_services("status-id", &unit, &ContainerConfig::default())
+            .await?;
+
+        let apps = infrastructure.fetch_services().await?;
+        assert_eq!(
+            service_names(&apps[&AppName::master()]),
+            vec![
+                (&String::from("api"), None),
+                (&String::from("db"), Some(&String::from("cluster")))
+            ]
+        );
+        assert_eq!(
+            default
+                .services()
+                .iter()
+                .map(|service| service.service_name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["api"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_services_of_unknown_runtimes() {
+        let infrastructure = CompositeInfrastructure::new(Box::new(Dummy::new()), BTreeMap::new());
+
+        let mut db = sc!("db");
+        db.set_runtime(Some(String::from("cluster")));
+        let unit = deployment_unit(vec![db]).await;
+
+        let result = infrastructure
+            .deploy_services("status-id", &unit, &ContainerConfig::default())
+            .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -24,24 +24,33 @@
  * =========================LICENSE_END==================================
  */
 
-use crate::config::{Config, ContainerConfig};
+use crate::config::{
+    Config, ContainerConfig, CredentialsProviderError, DockerRuntimeConfig, DockerTimeoutsConfig,
+    HostMetaConfig, OperationLockConfig, OperationLockStrategy, Runtime,
+};
 use crate::deployment::deployment_unit::{DeployableService, DeploymentStrategy};
 use crate::deployment::DeploymentUnit;
 use crate::infrastructure::{
-    HttpForwarder, Infrastructure, APP_NAME_LABEL, CONTAINER_TYPE_LABEL, IMAGE_LABEL,
-    REPLICATED_ENV_LABEL, SERVICE_NAME_LABEL, STATUS_ID,
+    metadata_from_labels, metadata_to_labels, HttpForwarder, Infrastructure, APP_NAME_LABEL,
+    CONFIG_HASH_LABEL, CONTAINER_TYPE_LABEL, CREATED_AT_LABEL, DEPLOYED_AT_LABEL, IMAGE_LABEL,
+    PINNED_LABEL, REPLICATED_ENV_LABEL, SERVICE_NAME_LABEL, STATUS_ID,
+    USER_DEFINED_PARAMETERS_LABEL,
 };
 use crate::models::service::{
     ContainerType, Service, ServiceError, ServiceStatus, Services, State,
 };
-use crate::models::{AppName, Environment, Image, ServiceConfig, WebHostMeta};
+use crate::models::{
+    AppName, Environment, EnvironmentVariable, Image, ResourceUsage,
+    RestartPolicy as ServiceRestartPolicy, ServiceConfig, WebHostMeta,
+};
 use anyhow::{anyhow, Result};
 use async_stream::stream;
 use async_trait::async_trait;
 use bollard::auth::DockerCredentials;
 use bollard::container::{
-    CreateContainerOptions, ListContainersOptions, LogOutput, StartContainerOptions,
-    UploadToContainerOptions,
+    CPUStats, CreateContainerOptions, DownloadFromContainerOptions, ListContainersOptions,
+    LogOutput, MemoryStats, MemoryStatsStats, RemoveContainerOptions, StartContainerOptions,
+    StatsOptions, UploadToContainerOptions,
 };
 use bollard::errors::Error as BollardError;
 use bollard::image::CreateImageOptions;
@@ -50,30 +59,66 @@ use bollard::network::{
 };
 use bollard::secret::Port;
 use bollard::service::{
-    ContainerCreateResponse, ContainerInspectResponse, ContainerStateStatusEnum, ContainerSummary,
-    CreateImageInfo, EndpointSettings, HostConfig, RestartPolicy, RestartPolicyNameEnum,
-    VolumeListResponse,
+    ContainerConfig as DockerContainerConfig, ContainerCreateResponse, ContainerInspectResponse,
+    ContainerState, ContainerStateStatusEnum, ContainerSummary, CreateImageInfo, DeviceRequest,
+    EndpointSettings, HealthConfig, HealthStatusEnum, HostConfig, ImageConfig, PortBinding,
+    PortMap, RestartPolicy, RestartPolicyNameEnum, VolumeListResponse,
 };
 use bollard::volume::{CreateVolumeOptions, ListVolumesOptions};
 use bollard::Docker;
+use bytes::Bytes;
 use chrono::{DateTime, FixedOffset, Utc};
 use futures::stream::BoxStream;
 use futures::stream::FuturesUnordered;
 use futures::{StreamExt, TryStreamExt};
 use http_body_util::BodyExt;
-use hyper_util::rt::TokioIo;
+use http_body_util::Empty;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
 use multimap::MultiMap;
+use opentelemetry::KeyValue;
 use rocket::form::validate::Contains;
-use std::collections::HashMap;
+use secstr::SecUtf8;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::{From, TryFrom};
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tokio::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 static CONTAINER_PORT_LABEL: &str = "traefik.port";
+/// Lists the paths of the files that have been copied into the container, so that they can be
+/// copied into a container that replaces it with a new version of its image.
+static FILE_PATHS_LABEL: &str = "com.aixigo.preview.servant.file-paths";
 
 #[derive(Clone)]
 pub struct DockerInfrastructure {
     config: Config,
+    pull_semaphores: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    host_meta_clients: HostMetaClients,
+}
+
+/// Pooled HTTP clients for crawling the web host meta. Keeping them across crawls avoids opening
+/// a new TCP connection to each service every time its web host meta is requested.
+#[derive(Clone)]
+struct HostMetaClients {
+    http1: Client<HttpConnector, Empty<Bytes>>,
+    http2: Client<HttpConnector, Empty<Bytes>>,
+}
+
+impl HostMetaClients {
+    fn new() -> Self {
+        let mut builder = Client::builder(TokioExecutor::new());
+        builder.pool_timer(TokioTimer::new());
+
+        Self {
+            http1: builder.clone().build_http(),
+            http2: builder.http2_only(true).build_http(),
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -94,11 +139,73 @@ pub enum DockerInfrastructureError {
     InvalidContainerState { container_id: String },
     #[error("Unexpected image details for container: {container_id}")]
     InvalidContainerImage { container_id: String },
+    #[error("The Kubernetes manifest companions {} are not supported by the Docker backend.", companion_names.join(", "))]
+    UnsupportedKubernetesManifestCompanions { companion_names: Vec<String> },
+    #[error("The services {} require GPUs but the Docker host does not provide the nvidia runtime.", service_names.join(", "))]
+    GpusNotSupported { service_names: Vec<String> },
+    #[error("The Docker daemon did not respond in time. Please, retry the operation.")]
+    Timeout,
+    #[error(
+        "The service {service_name} failed or did not become ready before the services depending on it could be started."
+    )]
+    DependencyNotReady { service_name: String },
+    #[error("Cannot fetch the credentials of registry {registry}: {err}")]
+    RegistryCredentialsUnavailable {
+        registry: String,
+        err: CredentialsProviderError,
+    },
+    #[error("{err} The started services {} have been rolled back.", service_names.join(", "))]
+    RolledBack {
+        service_names: Vec<String>,
+        err: Box<DockerInfrastructureError>,
+    },
+}
+
+/// How [`DockerInfrastructure::start_container`] brought the container of a service up to date.
+enum ContainerChange {
+    /// The existing container already matched the deployment.
+    Kept,
+    /// The existing container has been removed and a new one has been created.
+    Replaced,
+    /// The service did not have a container before.
+    Created,
 }
 
 impl DockerInfrastructure {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            pull_semaphores: Arc::new(Mutex::new(HashMap::new())),
+            host_meta_clients: HostMetaClients::new(),
+        }
+    }
+
+    fn operation_lock_config(&self) -> OperationLockConfig {
+        match self.config.runtime_config() {
+            Runtime::Docker(runtime) => runtime.operation_lock().clone(),
+            Runtime::Kubernetes(_) => OperationLockConfig::default(),
+        }
+    }
+
+    fn timeouts(&self) -> DockerTimeoutsConfig {
+        timeouts(&self.config)
+    }
+
+    fn max_concurrent_inspections(&self) -> usize {
+        match self.config.runtime_config() {
+            Runtime::Docker(runtime) => runtime.max_concurrent_inspections(),
+            Runtime::Kubernetes(_) => DockerRuntimeConfig::default().max_concurrent_inspections(),
+        }
+    }
+
+    /// Checks whether the Docker host provides the nvidia runtime that is required to pass GPUs
+    /// through to containers.
+    async fn supports_gpus() -> Result<bool, BollardError> {
+        let docker = Docker::connect_with_socket_defaults()?;
+        let info = docker.info().await?;
+        Ok(info
+            .runtimes
+            .is_some_and(|runtimes| runtimes.contains_key("nvidia")))
     }
 
     async fn find_status_change_container(
@@ -115,17 +222,37 @@ impl DockerInfrastructure {
         status_id: &str,
         app_name: &AppName,
     ) -> Result<ContainerInspectResponse> {
-        let existing_task = self
-            .get_status_change_containers(Some(app_name), None)
-            .await?
-            .into_iter()
-            .next();
+        let operation_lock = self.operation_lock_config();
+        loop {
+            let mut existing_task = None;
+            for task in self
+                .get_status_change_containers(Some(app_name), None)
+                .await?
+            {
+                if is_stale_operation_lock(&task, operation_lock.ttl(), Utc::now()) {
+                    warn!("Releasing stale operation lock {:?} of {app_name}", task.id);
+                    delete(inspect(task, self.timeouts().inspect()).await?).await?;
+                } else {
+                    existing_task = Some(task);
+                    break;
+                }
+            }
 
-        if let Some(existing_task) = existing_task {
-            // TODO: what to if there is already a deployment
-            return Err(anyhow!(
-                "There is already an operation in progress: {existing_task:?}"
-            ));
+            match (existing_task, operation_lock.strategy()) {
+                (None, _) => break,
+                (Some(existing_task), OperationLockStrategy::FailFast) => {
+                    return Err(anyhow!(
+                        "There is already an operation in progress: {existing_task:?}"
+                    ));
+                }
+                (Some(existing_task), OperationLockStrategy::Wait) => {
+                    debug!(
+                        "Waiting for operation {:?} of {app_name} to be finished",
+                        existing_task.id
+                    );
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                }
+            }
         }
 
         let image = Image::from_str("docker.io/library/busybox:stable").unwrap();
@@ -140,17 +267,23 @@ impl DockerInfrastructure {
 
         trace!("Create deployment task container {status_id} for {app_name}");
 
-        let container_info = docker
-            .create_container(
+        let container_info = with_timeout(
+            self.timeouts().create(),
+            docker.create_container(
                 None::<CreateContainerOptions<&str>>,
                 bollard::container::Config::<&str> {
                     image: Some("docker.io/library/busybox:stable"),
                     labels: Some(labels),
                     ..Default::default()
                 },
-            )
-            .await?;
-        Ok(docker.inspect_container(&container_info.id, None).await?)
+            ),
+        )
+        .await?;
+        Ok(with_timeout(
+            self.timeouts().inspect(),
+            docker.inspect_container(&container_info.id, None),
+        )
+        .await?)
     }
 
     async fn create_or_get_network_id(&self, app_name: &AppName) -> Result<String, BollardError> {
@@ -188,9 +321,11 @@ impl DockerInfrastructure {
     async fn connect_traefik(&self, network_id: &str) -> Result<(), BollardError> {
         let docker = Docker::connect_with_socket_defaults()?;
 
-        let containers = docker
-            .list_containers(None::<ListContainersOptions<&str>>)
-            .await?;
+        let containers = with_timeout(
+            self.timeouts().list(),
+            docker.list_containers(None::<ListContainersOptions<&str>>),
+        )
+        .await?;
 
         let traefik_container_id = containers
             .into_iter()
@@ -217,9 +352,11 @@ impl DockerInfrastructure {
 
     async fn disconnect_traefik(&self, network_id: &str) -> Result<(), BollardError> {
         let docker = Docker::connect_with_socket_defaults()?;
-        let containers = docker
-            .list_containers(None::<ListContainersOptions<&str>>)
-            .await?;
+        let containers = with_timeout(
+            self.timeouts().list(),
+            docker.list_containers(None::<ListContainersOptions<&str>>),
+        )
+        .await?;
         let traefik_container_id = containers
             .into_iter()
             .find(|c| c.image.as_ref().map_or(false, |s| s.contains("traefik")))
@@ -286,28 +423,145 @@ impl DockerInfrastructure {
 
         self.connect_traefik(&network_id).await?;
         let existing_volumes = Self::fetch_existing_volumes(app_name).await?;
-        let mut futures = services
+        let dependencies = services
             .iter()
-            .map(|service| {
-                self.start_container(
-                    app_name,
-                    &network_id,
-                    service,
-                    container_config,
-                    &existing_volumes,
-                )
-            })
-            .map(Box::pin)
-            .collect::<FuturesUnordered<_>>();
+            .flat_map(|service| service.depends_on())
+            .collect::<HashSet<_>>();
 
         let mut services: Vec<Service> = Vec::new();
-        while let Some(service) = futures.next().await {
-            services.push(service?);
+        let mut created_services: Vec<Service> = Vec::new();
+        for stage in deployment_unit.deployment_stages() {
+            let mut futures = stage
+                .into_iter()
+                .map(|service| {
+                    crate::telemetry::in_span(
+                        "start container",
+                        vec![KeyValue::new(
+                            "service.name",
+                            service.service_name().clone(),
+                        )],
+                        self.start_container(
+                            app_name,
+                            &network_id,
+                            service,
+                            container_config,
+                            &existing_volumes,
+                        ),
+                    )
+                })
+                .map(Box::pin)
+                .collect::<FuturesUnordered<_>>();
+
+            // All futures of the stage must complete, even if one of them fails, so that the
+            // rollback knows about every container that has been created.
+            let mut failure = None;
+            while let Some(service) = futures.next().await {
+                match service {
+                    Ok((service, ContainerChange::Created)) => {
+                        created_services.push(service.clone());
+                        services.push(service);
+                    }
+                    Ok((service, ContainerChange::Kept | ContainerChange::Replaced)) => {
+                        services.push(service)
+                    }
+                    Err(err) => {
+                        failure.get_or_insert(err);
+                    }
+                }
+            }
+            drop(futures);
+
+            if failure.is_none() {
+                for service in services
+                    .iter()
+                    .filter(|service| dependencies.contains(service.service_name()))
+                {
+                    if let Err(err) = self.wait_until_ready(service).await {
+                        failure = Some(err);
+                        break;
+                    }
+                }
+            }
+
+            if let Some(err) = failure {
+                if !deployment_unit.rollback_on_failure() {
+                    return Err(err);
+                }
+                return Err(self.roll_back(app_name, created_services, err).await);
+            }
         }
 
         Ok(Services::from(services))
     }
 
+    /// Removes the containers that have been created for services that did not exist before a
+    /// deployment that failed, so that the app does not end up with only a part of the requested
+    /// services. Containers that have been kept are left alone. Containers that replaced a previous
+    /// version are left running as well because the previous container has already been removed.
+    async fn roll_back(
+        &self,
+        app_name: &AppName,
+        started_services: Vec<Service>,
+        err: DockerInfrastructureError,
+    ) -> DockerInfrastructureError {
+        if started_services.is_empty() {
+            return err;
+        }
+
+        warn!("Rolling back the deployment of {app_name} because it failed: {err}",);
+
+        let docker = match Docker::connect_with_socket_defaults() {
+            Ok(docker) => docker,
+            Err(connect_err) => {
+                error!("Cannot roll back the deployment of {app_name}: {connect_err}");
+                return err;
+            }
+        };
+
+        let mut service_names = Vec::with_capacity(started_services.len());
+        for service in started_services {
+            if let Err(remove_err) = docker
+                .remove_container(
+                    service.id(),
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+            {
+                error!(
+                    "Cannot remove {} of {app_name} during rollback: {remove_err}",
+                    service.service_name()
+                );
+                continue;
+            }
+            service_names.push(service.service_name().clone());
+        }
+
+        DockerInfrastructureError::RolledBack {
+            service_names,
+            err: Box::new(err),
+        }
+    }
+
+    /// Waits until other services can rely on the service: its container runs and passed its
+    /// healthcheck, if any, or it has completed successfully, e.g. a database migration.
+    async fn wait_until_ready(&self, service: &Service) -> Result<(), DockerInfrastructureError> {
+        let docker = Docker::connect_with_socket_defaults()?;
+        let timeouts = self.timeouts();
+        wait_until_ready(service.service_name(), timeouts.readiness(), || async {
+            Ok(with_timeout(
+                timeouts.inspect(),
+                docker.inspect_container(service.id(), None),
+            )
+            .await?
+            .state
+            .unwrap_or_default())
+        })
+        .await
+    }
+
     async fn stop_services_impl(
         &self,
         app_name: &AppName,
@@ -386,7 +640,7 @@ impl DockerInfrastructure {
         service: &DeployableService,
         container_config: &ContainerConfig,
         existing_volumes: &VolumeListResponse,
-    ) -> Result<Service, DockerInfrastructureError> {
+    ) -> Result<(Service, ContainerChange), DockerInfrastructureError> {
         let docker = Docker::connect_with_socket_defaults()?;
         let service_name = service.service_name();
         let service_image = service.image();
@@ -395,29 +649,32 @@ impl DockerInfrastructure {
             self.pull_image(app_name, service).await?;
         }
         let mut image_to_delete = None;
-        if let Some(ref container_info) = Self::get_app_container(app_name, service_name).await? {
-            let container_details = docker
-                .inspect_container(
+        let mut change = ContainerChange::Created;
+        if let Some(ref container_info) = self.get_app_container(app_name, service_name).await? {
+            let container_details = with_timeout(
+                self.timeouts().inspect(),
+                docker.inspect_container(
                     container_info
                         .id
                         .as_ref()
                         .expect("id is mandatory for a docker container"),
                     None,
-                )
-                .await?;
+                ),
+            )
+            .await?;
 
             match service.strategy() {
                 DeploymentStrategy::RedeployOnImageUpdate(image_id)
                     if container_details.image.as_ref() == Some(image_id) =>
                 {
                     debug!("Container {container_info:?} of review app {app_name:?} is still running with the desired image id {image_id}");
-                    return Service::try_from(container_details);
+                    return Ok((Service::try_from(container_details)?, ContainerChange::Kept));
                 }
                 DeploymentStrategy::RedeployNever => {
                     debug!(
                         "Container {container_info:?} of review app {app_name:?} already deployed."
                     );
-                    return Service::try_from(container_details);
+                    return Ok((Service::try_from(container_details)?, ContainerChange::Kept));
                 }
                 DeploymentStrategy::RedeployAlways
                 | DeploymentStrategy::RedeployOnImageUpdate(_) => {}
@@ -450,6 +707,7 @@ impl DockerInfrastructure {
                 )
                 .await?;
             image_to_delete = container_details.image;
+            change = ContainerChange::Replaced;
         }
 
         info!(
@@ -463,9 +721,11 @@ impl DockerInfrastructure {
         let options =
             Self::create_container_options(app_name, service, container_config, &host_config_binds);
 
-        let container_info = docker
-            .create_container::<&str, String>(None, options)
-            .await?;
+        let container_info = with_timeout(
+            self.timeouts().create(),
+            docker.create_container::<&str, String>(None, options),
+        )
+        .await?;
         let container_id = container_info.id.as_ref();
 
         debug!("Created container: {container_info:?}");
@@ -491,19 +751,144 @@ impl DockerInfrastructure {
 
         debug!("Connected container {container_id} to {network_id}");
 
-        let container_details = docker.inspect_container(container_id, None).await?;
+        let container_details = with_timeout(
+            self.timeouts().inspect(),
+            docker.inspect_container(container_id, None),
+        )
+        .await?;
 
         if let Some(image) = image_to_delete {
-            info!("Clean up image {image:?} of app {app_name:?}");
-            match docker.remove_image(&image, None, None).await {
-                Ok(output) => {
-                    for o in output {
-                        debug!("{o:?}");
-                    }
-                }
-                Err(err) => debug!("Could not clean up image: {err:?}"),
-            };
+            clean_up_image(&docker, app_name, &image).await;
+        }
+        Ok((Service::try_from(container_details)?, change))
+    }
+
+    async fn update_images_impl(
+        &self,
+        app_name: &AppName,
+        outdated_containers: Vec<ContainerInspectResponse>,
+    ) -> Result<Vec<Service>, DockerInfrastructureError> {
+        let network_id = self.create_or_get_network_id(app_name).await?;
+
+        let mut services = Vec::with_capacity(outdated_containers.len());
+        for container_details in outdated_containers {
+            services.push(
+                self.recreate_container(app_name, &network_id, container_details)
+                    .await?,
+            );
+        }
+        Ok(services)
+    }
+
+    /// Replaces the container with a new one that runs the current version of its image but that
+    /// keeps the specification of the container (see [`recreated_container_options`]) and the
+    /// files that have been copied into it.
+    async fn recreate_container(
+        &self,
+        app_name: &AppName,
+        network_id: &str,
+        container_details: ContainerInspectResponse,
+    ) -> Result<Service, DockerInfrastructureError> {
+        let docker = Docker::connect_with_socket_defaults()?;
+        let service = Service::try_from(container_details.clone())?;
+        let container_id = container_details
+            .id
+            .as_ref()
+            .expect("id is mandatory for a docker container");
+
+        self.pull_image(app_name, &service.config).await?;
+
+        let previous_image_config = match &container_details.image {
+            Some(image) => {
+                not_found_to_none(docker.inspect_image(image).await)?.and_then(|image| image.config)
+            }
+            None => None,
+        };
+
+        let mut archives = Vec::new();
+        for path in file_paths(&container_details) {
+            let archive = docker
+                .download_from_container(
+                    container_id,
+                    Some(DownloadFromContainerOptions {
+                        path: path.to_string_lossy().to_string(),
+                    }),
+                )
+                .map_ok(|bytes| bytes.to_vec())
+                .try_concat()
+                .await?;
+            archives.push((path, archive));
+        }
+
+        info!(
+            "Replacing container {container_id} of {app_name} with a new version of {}",
+            service.config.image()
+        );
+        if container_details
+            .state
+            .as_ref()
+            .and_then(|state| state.running)
+            == Some(true)
+        {
+            docker.stop_container(container_id, None).await?;
+        }
+        docker.remove_container(container_id, None).await?;
+
+        let options = recreated_container_options(
+            container_details.config.unwrap_or_default(),
+            container_details.host_config,
+            previous_image_config,
+            service.config.image().to_string(),
+        );
+        let container_info = with_timeout(
+            self.timeouts().create(),
+            docker.create_container::<&str, String>(None, options),
+        )
+        .await?;
+        let container_id = container_info.id.as_ref();
+
+        for (path, archive) in archives {
+            docker
+                .upload_to_container(
+                    container_id,
+                    Some(UploadToContainerOptions {
+                        path: path
+                            .parent()
+                            .unwrap_or_else(|| Path::new("/"))
+                            .to_string_lossy()
+                            .to_string(),
+                        ..Default::default()
+                    }),
+                    archive.into(),
+                )
+                .await?;
+        }
+
+        docker
+            .start_container(container_id, None::<StartContainerOptions<&str>>)
+            .await?;
+        docker
+            .connect_network(
+                network_id,
+                ConnectNetworkOptions::<&str> {
+                    container: container_id,
+                    endpoint_config: EndpointSettings {
+                        aliases: Some(vec![service.service_name().to_string()]),
+                        ..Default::default()
+                    },
+                },
+            )
+            .await?;
+
+        if let Some(image) = container_details.image {
+            clean_up_image(&docker, app_name, &image).await;
         }
+
+        let container_details = with_timeout(
+            self.timeouts().inspect(),
+            docker.inspect_container(container_id, None),
+        )
+        .await?;
         Service::try_from(container_details)
     }
 
@@ -527,13 +912,22 @@ impl DockerInfrastructure {
             service_name = service_config.service_name()
         );
         labels.insert("traefik.frontend.rule".to_string(), traefik_frontend);
+        if let Some(users) = service_config.basic_auth_users() {
+            labels.insert(
+                "traefik.frontend.auth.basic.users".to_string(),
+                users
+                    .iter()
+                    .map(|user| user.unsecure())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
 
-        if let Some(config_labels) = service_config.labels() {
-            for (k, v) in config_labels {
-                labels.insert(k.to_string(), v.to_string());
-            }
+        for (k, v) in service_config.custom_labels() {
+            labels.insert(k.to_string(), v.to_string());
         }
 
+        labels.extend(metadata_to_labels(service_config.metadata()));
         labels.insert(APP_NAME_LABEL.to_string(), app_name.to_string());
         labels.insert(
             SERVICE_NAME_LABEL.to_string(),
@@ -543,6 +937,31 @@ impl DockerInfrastructure {
         labels.insert(CONTAINER_TYPE_LABEL.to_string(), container_type);
         let image_name = service_config.image().to_string();
         labels.insert(IMAGE_LABEL.to_string(), image_name);
+        labels.insert(CONFIG_HASH_LABEL.to_string(), service_config.config_hash());
+        if let Some(deployed_at) = service_config.deployed_at() {
+            labels.insert(DEPLOYED_AT_LABEL.to_string(), deployed_at.to_rfc3339());
+        }
+        if let Some(created_at) = service_config.created_at() {
+            labels.insert(CREATED_AT_LABEL.to_string(), created_at.to_rfc3339());
+        }
+        if let Some(user_defined_parameters) = service_config.user_defined_parameters() {
+            labels.insert(
+                USER_DEFINED_PARAMETERS_LABEL.to_string(),
+                user_defined_parameters.to_string(),
+            );
+        }
+        if let Some(files) = service_config.files() {
+            labels.insert(
+                FILE_PATHS_LABEL.to_string(),
+                serde_json::json!(files.keys().collect::<Vec<_>>()).to_string(),
+            );
+        }
+        if service_config.is_port_known() {
+            labels.insert(
+                CONTAINER_PORT_LABEL.to_string(),
+                service_config.port().to_string(),
+            );
+        }
 
         let replicated_env = service_config
             .env()
@@ -561,14 +980,61 @@ impl DockerInfrastructure {
             image: Some(service_config.image().to_string()),
             env,
             labels: Some(labels),
+            exposed_ports: service_config.expose_port().then(|| {
+                HashMap::from([(format!("{}/tcp", service_config.port()), HashMap::new())])
+            }),
+            working_dir: service_config
+                .working_dir()
+                .map(|working_dir| working_dir.to_string_lossy().to_string()),
+            healthcheck: service_config
+                .healthcheck()
+                .map(|healthcheck| HealthConfig {
+                    test: Some(vec![
+                        String::from("CMD-SHELL"),
+                        healthcheck.command().to_string(),
+                    ]),
+                    interval: Some(healthcheck.interval().as_nanos() as i64),
+                    retries: Some(i64::from(healthcheck.retries())),
+                    start_period: Some(healthcheck.start_period().as_nanos() as i64),
+                    ..Default::default()
+                }),
             host_config: Some(HostConfig {
                 restart_policy: Some(RestartPolicy {
-                    name: Some(RestartPolicyNameEnum::ALWAYS),
+                    name: Some(match service_config.restart_policy() {
+                        ServiceRestartPolicy::Always => RestartPolicyNameEnum::ALWAYS,
+                        ServiceRestartPolicy::OnFailure => RestartPolicyNameEnum::ON_FAILURE,
+                        ServiceRestartPolicy::No => RestartPolicyNameEnum::NO,
+                        ServiceRestartPolicy::UnlessStopped => {
+                            RestartPolicyNameEnum::UNLESS_STOPPED
+                        }
+                    }),
                     ..Default::default()
                 }),
                 binds: Some(host_config_binds.to_vec()),
                 memory,
                 memory_swap: memory,
+                nano_cpus: container_config.nano_cpus(),
+                port_bindings: service_config.expose_port().then(|| {
+                    // Without a host port, Docker assigns a free one.
+                    HashMap::from([(
+                        format!("{}/tcp", service_config.port()),
+                        Some(vec![PortBinding::default()]),
+                    )])
+                }),
+                device_requests: service_config.gpus().map(|gpus| {
+                    vec![DeviceRequest {
+                        count: Some(i64::from(gpus)),
+                        capabilities: Some(vec![vec![String::from("gpu")]]),
+                        ..Default::default()
+                    }]
+                }),
+                tmpfs: (!service_config.tmpfs().is_empty()).then(|| {
+                    service_config
+                        .tmpfs()
+                        .iter()
+                        .map(|path| (path.to_string_lossy().to_string(), String::new()))
+                        .collect()
+                }),
                 ..Default::default()
             }),
             ..Default::default()
@@ -637,6 +1103,25 @@ impl DockerInfrastructure {
             .await
     }
 
+    /// Docker cannot change the labels of existing containers. Thus, the pin of an app is stored
+    /// as an empty volume that is labeled with the app name and that will be deleted together
+    /// with the other volumes of the app.
+    async fn fetch_pin_volumes(
+        app_name: Option<&AppName>,
+    ) -> Result<VolumeListResponse, BollardError> {
+        let mut label_filters = vec![PINNED_LABEL.to_string()];
+        if let Some(app_name) = app_name {
+            label_filters.push(format!("{APP_NAME_LABEL}={app_name}"));
+        }
+
+        let docker = Docker::connect_with_socket_defaults()?;
+        docker
+            .list_volumes(Some(ListVolumesOptions {
+                filters: HashMap::from([("label".to_string(), label_filters)]),
+            }))
+            .await
+    }
+
     async fn create_docker_volume(
         app_name: &AppName,
         service: &DeployableService,
@@ -693,15 +1178,22 @@ impl DockerInfrastructure {
         &self,
         app_name: &AppName,
         config: &ServiceConfig,
-    ) -> Result<(), BollardError> {
+    ) -> Result<(), DockerInfrastructureError> {
         let image = config.image();
 
+        let _permit = self.acquire_pull_permit(image).await;
+
         info!(
             "Pulling {image:?} for {:?} of app {app_name:?}",
             config.service_name()
         );
 
-        let pull_results = pull(image, &self.config).await?;
+        let pull_results = crate::telemetry::in_span(
+            "pull image",
+            vec![KeyValue::new("image", image.to_string())],
+            pull(image, &self.config),
+        )
+        .await?;
 
         for pull_result in pull_results {
             debug!("{pull_result:?}");
@@ -710,7 +1202,28 @@ impl DockerInfrastructure {
         Ok(())
     }
 
+    /// Bounds the number of concurrent pulls per registry host if the registry has been configured
+    /// with `maxConcurrentPulls`.
+    async fn acquire_pull_permit(&self, image: &Image) -> Option<OwnedSemaphorePermit> {
+        let registry = image.registry()?;
+        let max_concurrent_pulls = self.config.registry_max_concurrent_pulls(&registry)?;
+
+        let semaphore = {
+            let mut semaphores = self
+                .pull_semaphores
+                .lock()
+                .expect("Pull semaphores should not be poisoned");
+            semaphores
+                .entry(registry)
+                .or_insert_with(|| Arc::new(Semaphore::new(max_concurrent_pulls.max(1))))
+                .clone()
+        };
+
+        semaphore.acquire_owned().await.ok()
+    }
+
     async fn get_containers(
+        timeouts: DockerTimeoutsConfig,
         filters: HashMap<String, Vec<String>>,
     ) -> Result<Vec<ContainerSummary>, BollardError> {
         let docker = Docker::connect_with_socket_defaults()?;
@@ -721,10 +1234,11 @@ impl DockerInfrastructure {
             ..Default::default()
         });
 
-        docker.list_containers(list_options).await
+        with_timeout(timeouts.list(), docker.list_containers(list_options)).await
     }
 
     async fn get_app_containers(
+        timeouts: DockerTimeoutsConfig,
         app_name: Option<&AppName>,
         service_name: Option<&str>,
     ) -> Result<Vec<ContainerSummary>, BollardError> {
@@ -745,7 +1259,7 @@ impl DockerInfrastructure {
                 .push(service_name_filter);
         }
 
-        Self::get_containers(filters).await
+        Self::get_containers(timeouts, filters).await
     }
 
     async fn get_status_change_containers(
@@ -765,14 +1279,15 @@ impl DockerInfrastructure {
         }
 
         let filters = HashMap::from([("label".to_string(), label_filters)]);
-        Self::get_containers(filters).await
+        Self::get_containers(self.timeouts(), filters).await
     }
 
     async fn get_app_container(
+        &self,
         app_name: &AppName,
         service_name: &str,
     ) -> Result<Option<ContainerSummary>, BollardError> {
-        Self::get_app_containers(Some(app_name), Some(service_name))
+        Self::get_app_containers(self.timeouts(), Some(app_name), Some(service_name))
             .await
             .map(|list| list.into_iter().next())
     }
@@ -784,11 +1299,16 @@ impl DockerInfrastructure {
     ) -> Result<MultiMap<AppName, ContainerInspectResponse>, DockerInfrastructureError> {
         debug!("Resolve container details for app {app_name:?}");
 
-        let container_list = Self::get_app_containers(app_name, service_name).await?;
+        let timeouts = self.timeouts();
+        let container_list = Self::get_app_containers(timeouts, app_name, service_name).await?;
+
+        let mut inspections = futures::stream::iter(container_list)
+            .map(|container| inspect(container, timeouts.inspect()))
+            .buffer_unordered(self.max_concurrent_inspections());
 
         let mut container_details = MultiMap::new();
-        for container in container_list.into_iter() {
-            if let Some(details) = not_found_to_none(inspect(container).await)? {
+        while let Some(details) = inspections.next().await {
+            if let Some(details) = not_found_to_none(details)? {
                 let app_name = match app_name {
                     Some(app_name) => app_name.clone(),
                     None => match details.config.as_ref().and_then(|con| {
@@ -846,6 +1366,31 @@ impl Infrastructure for DockerInfrastructure {
         deployment_unit: &DeploymentUnit,
         container_config: &ContainerConfig,
     ) -> Result<Services> {
+        let companion_names = self
+            .config
+            .kubernetes_manifest_companion_names(deployment_unit.app_name());
+        if !companion_names.is_empty() {
+            return Err(
+                DockerInfrastructureError::UnsupportedKubernetesManifestCompanions {
+                    companion_names,
+                }
+                .into(),
+            );
+        }
+
+        let gpu_service_names = deployment_unit
+            .services()
+            .iter()
+            .filter(|service| service.gpus().is_some())
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        if !gpu_service_names.is_empty() && !Self::supports_gpus().await? {
+            return Err(DockerInfrastructureError::GpusNotSupported {
+                service_names: gpu_service_names,
+            }
+            .into());
+        }
+
         let deployment_container = self
             .create_status_change_container(status_id, deployment_unit.app_name())
             .await?;
@@ -859,6 +1404,79 @@ impl Infrastructure for DockerInfrastructure {
         Ok(result?)
     }
 
+    async fn update_images(
+        &self,
+        status_id: &str,
+        app_name: &AppName,
+        image_digests: &HashMap<String, String>,
+    ) -> Result<Services> {
+        let (outdated_containers, up_to_date_containers): (Vec<_>, Vec<_>) = self
+            .get_container_details(Some(app_name), None)
+            .await?
+            .remove(app_name)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|details| {
+                let service_name = details
+                    .config
+                    .as_ref()
+                    .and_then(|config| config.labels.as_ref())
+                    .and_then(|labels| labels.get(SERVICE_NAME_LABEL));
+                service_name
+                    .and_then(|service_name| image_digests.get(service_name))
+                    .is_some_and(|digest| details.image.as_ref() != Some(digest))
+            });
+
+        let mut services = up_to_date_containers
+            .into_iter()
+            .filter_map(|details| Service::try_from(details).ok())
+            .collect::<Vec<_>>();
+        if outdated_containers.is_empty() {
+            return Ok(Services::from(services));
+        }
+
+        let deployment_container = self
+            .create_status_change_container(status_id, app_name)
+            .await?;
+
+        let result = self.update_images_impl(app_name, outdated_containers).await;
+
+        delete(deployment_container).await?;
+
+        services.extend(result?);
+        Ok(Services::from(services))
+    }
+
+    async fn release_orphaned_operation_locks(&self) -> Result<usize> {
+        if !self.operation_lock_config().release_on_startup() {
+            return Ok(0);
+        }
+
+        // Locks that are younger than the TTL might belong to operations of other PREvant
+        // instances that share the Docker host.
+        let ttl = self.operation_lock_config().ttl();
+        let now = Utc::now();
+        let orphaned_locks = self
+            .get_status_change_containers(None, None)
+            .await?
+            .into_iter()
+            .filter(|lock| is_stale_operation_lock(lock, ttl, now))
+            .collect::<Vec<_>>();
+        let number_of_locks = orphaned_locks.len();
+        for lock in orphaned_locks {
+            warn!(
+                "Releasing orphaned operation lock {:?} of {:?}",
+                lock.id,
+                lock.labels
+                    .as_ref()
+                    .and_then(|labels| labels.get(APP_NAME_LABEL))
+            );
+            delete(inspect(lock, self.timeouts().inspect()).await?).await?;
+        }
+
+        Ok(number_of_locks)
+    }
+
     async fn get_status_change(&self, status_id: &str) -> Result<Option<Services>> {
         Ok(
             match self
@@ -904,18 +1522,54 @@ impl Infrastructure for DockerInfrastructure {
         Ok(result?)
     }
 
-    async fn get_logs<'a>(
-        &'a self,
-        app_name: &'a AppName,
-        service_name: &'a str,
-        from: &'a Option<DateTime<FixedOffset>>,
-        limit: &'a Option<usize>,
-        follow: bool,
-    ) -> BoxStream<'a, Result<(DateTime<FixedOffset>, String)>> {
-        stream! {
-            match Self::
-                get_app_container(&AppName::from_str(app_name).unwrap(), service_name)
-                .await
+    async fn stop_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<Option<Service>> {
+        let Some(container_details) = self
+            .get_container_details(Some(app_name), Some(service_name))
+            .await?
+            .remove(app_name)
+        else {
+            return Ok(None);
+        };
+
+        let docker = Docker::connect_with_socket_defaults()?;
+        let mut stopped_service = None;
+        for details in container_details {
+            let id = details
+                .id
+                .clone()
+                .expect("id is mandatory for a docker container");
+            docker
+                .remove_container(
+                    &id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await?;
+            trace!("Removed container {id} of {service_name} for {app_name}");
+            stopped_service.get_or_insert(Service::try_from(details)?);
+        }
+
+        Ok(stopped_service)
+    }
+
+    async fn get_logs<'a>(
+        &'a self,
+        app_name: &'a AppName,
+        service_name: &'a str,
+        from: &'a Option<DateTime<FixedOffset>>,
+        limit: &'a Option<usize>,
+        follow: bool,
+    ) -> BoxStream<'a, Result<(DateTime<FixedOffset>, String)>> {
+        stream! {
+            match self
+                .get_app_container(&AppName::from_str(app_name).unwrap(), service_name)
+                .await
             {
                 Ok(None) => {}
                 Ok(Some(container)) => {
@@ -981,18 +1635,20 @@ impl Infrastructure for DockerInfrastructure {
         service_name: &str,
         status: ServiceStatus,
     ) -> Result<Option<Service>> {
-        match Self::get_app_container(app_name, service_name).await? {
+        match self.get_app_container(app_name, service_name).await? {
             Some(container) => {
                 let docker = Docker::connect_with_socket_defaults()?;
-                let details = docker
-                    .inspect_container(
+                let details = with_timeout(
+                    self.timeouts().inspect(),
+                    docker.inspect_container(
                         container
                             .id
                             .as_ref()
                             .expect("id is mandatory for a docker container"),
                         None,
-                    )
-                    .await?;
+                    ),
+                )
+                .await?;
 
                 macro_rules! run_future_and_map_err {
                     ( $future:expr, $log_format:expr ) => {
@@ -1020,20 +1676,32 @@ impl Infrastructure for DockerInfrastructure {
                     };
                 }
 
+                let container_id = container
+                    .id
+                    .as_ref()
+                    .expect("id is mandatory for a docker container");
+                let running = details
+                    .state
+                    .as_ref()
+                    .map(|state| state.running.unwrap_or_default())
+                    .unwrap();
+                let frozen = details
+                    .state
+                    .as_ref()
+                    .map(|state| state.paused.unwrap_or_default())
+                    .unwrap();
+
                 match status {
-                    ServiceStatus::Running => {
-                        if !details
-                            .state
-                            .as_ref()
-                            .map(|state| state.running.unwrap_or_default())
-                            .unwrap()
-                        {
+                    ServiceStatus::Running | ServiceStatus::Starting => {
+                        if frozen {
+                            run_future_and_map_err!(
+                                docker.unpause_container(container_id),
+                                "Could not unfreeze container: {}"
+                            );
+                        } else if !running {
                             run_future_and_map_err!(
                                 docker.start_container(
-                                    container
-                                        .id
-                                        .as_ref()
-                                        .expect("id is mandatory for a docker container"),
+                                    container_id,
                                     None::<StartContainerOptions::<&str>>,
                                 ),
                                 "Could not start container: {}"
@@ -1041,24 +1709,30 @@ impl Infrastructure for DockerInfrastructure {
                         }
                     }
                     ServiceStatus::Paused => {
-                        if details
-                            .state
-                            .as_ref()
-                            .map(|state| state.running.unwrap_or_default())
-                            .unwrap()
-                        {
+                        if running {
                             run_future_and_map_err!(
-                                docker.stop_container(
-                                    container
-                                        .id
-                                        .as_ref()
-                                        .expect("id is mandatory for a docker container"),
-                                    None
-                                ),
+                                docker.stop_container(container_id, None),
                                 "Could not pause container: {}"
                             );
                         }
                     }
+                    ServiceStatus::Frozen => {
+                        if !running {
+                            run_future_and_map_err!(
+                                docker.start_container(
+                                    container_id,
+                                    None::<StartContainerOptions::<&str>>,
+                                ),
+                                "Could not start container: {}"
+                            );
+                        }
+                        if !frozen {
+                            run_future_and_map_err!(
+                                docker.pause_container(container_id),
+                                "Could not freeze container: {}"
+                            );
+                        }
+                    }
                 }
 
                 Ok(Some(Service::try_from(details)?))
@@ -1067,13 +1741,133 @@ impl Infrastructure for DockerInfrastructure {
         }
     }
 
+    async fn fetch_live_environments(
+        &self,
+        app_name: &AppName,
+    ) -> Result<HashMap<String, Environment>> {
+        let container_details = self.get_container_details(Some(app_name), None).await?;
+
+        Ok(container_details
+            .get_vec(app_name)
+            .map(|details| details.iter().filter_map(live_environment).collect())
+            .unwrap_or_default())
+    }
+
+    async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<Option<BTreeMap<String, ResourceUsage>>> {
+        let containers = Self::get_app_containers(self.timeouts(), Some(app_name), None).await?;
+        if containers.is_empty() {
+            return Ok(None);
+        }
+
+        let docker = Docker::connect_with_socket_defaults()?;
+        let mut futures = containers
+            .into_iter()
+            .filter_map(|container| {
+                let service_name = container
+                    .labels
+                    .as_ref()
+                    .and_then(|labels| labels.get(SERVICE_NAME_LABEL))?
+                    .clone();
+                let id = container.id?;
+                let running = container.state.as_deref() == Some("running");
+                let docker = docker.clone();
+
+                Some(async move {
+                    if !running {
+                        return Ok((service_name, ResourceUsage::default()));
+                    }
+
+                    // Without one-shot, Docker collects two samples so that the CPU usage can be
+                    // calculated from the difference between them.
+                    let stats = docker
+                        .stats(
+                            &id,
+                            Some(StatsOptions {
+                                stream: false,
+                                one_shot: false,
+                            }),
+                        )
+                        .next()
+                        .await
+                        .transpose()?;
+
+                    let usage = stats
+                        .map(|stats| {
+                            ResourceUsage::new(
+                                cpu_cores(&stats.cpu_stats, &stats.precpu_stats),
+                                memory_bytes(&stats.memory_stats),
+                            )
+                        })
+                        .unwrap_or_default();
+
+                    Ok::<_, BollardError>((service_name, usage))
+                })
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut usages = BTreeMap::new();
+        while let Some(result) = futures.next().await {
+            let (service_name, usage) = result?;
+            usages.insert(service_name, usage);
+        }
+
+        Ok(Some(usages))
+    }
+
+    async fn fetch_pinned_app_names(&self) -> Result<HashSet<AppName>> {
+        Ok(Self::fetch_pin_volumes(None)
+            .await?
+            .volumes
+            .into_iter()
+            .flatten()
+            .filter_map(|volume| AppName::from_str(volume.labels.get(APP_NAME_LABEL)?).ok())
+            .collect())
+    }
+
+    async fn set_pinned(&self, app_name: &AppName, pinned: bool) -> Result<()> {
+        let docker = Docker::connect_with_socket_defaults()?;
+        let pin_volumes = Self::fetch_pin_volumes(Some(app_name))
+            .await?
+            .volumes
+            .unwrap_or_default();
+
+        if pinned && pin_volumes.is_empty() {
+            docker
+                .create_volume(CreateVolumeOptions {
+                    labels: HashMap::from([
+                        (APP_NAME_LABEL, app_name.as_str()),
+                        (PINNED_LABEL, "true"),
+                    ]),
+                    ..Default::default()
+                })
+                .await?;
+        } else if !pinned {
+            for volume in pin_volumes {
+                docker.remove_volume(&volume.name, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     async fn http_forwarder(&self) -> Result<Box<dyn HttpForwarder>> {
-        Ok(Box::new(DockerHttpForwarder {}))
+        Ok(Box::new(DockerHttpForwarder {
+            clients: self.host_meta_clients.clone(),
+            host_meta_config: self.config.host_meta_config().clone(),
+            timeouts: self.timeouts(),
+        }))
     }
 }
 
 #[derive(Clone)]
-struct DockerHttpForwarder;
+struct DockerHttpForwarder {
+    clients: HostMetaClients,
+    host_meta_config: HostMetaConfig,
+    timeouts: DockerTimeoutsConfig,
+}
 
 #[async_trait]
 impl HttpForwarder for DockerHttpForwarder {
@@ -1083,9 +1877,14 @@ impl HttpForwarder for DockerHttpForwarder {
         service_name: &str,
         request: http::Request<http_body_util::Empty<bytes::Bytes>>,
     ) -> Result<Option<WebHostMeta>> {
-        let Some(container_details) =
-            DockerInfrastructure::get_app_container(app_name, service_name).await?
-        else {
+        let Some(container_details) = DockerInfrastructure::get_app_containers(
+            self.timeouts,
+            Some(app_name),
+            Some(service_name),
+        )
+        .await?
+        .into_iter()
+        .next() else {
             return Ok(None);
         };
 
@@ -1110,16 +1909,28 @@ impl HttpForwarder for DockerHttpForwarder {
                 .context(format!("app {app_name}, service name {service_name}")));
         };
 
-        let stream = TcpStream::connect(format!("{ip}:{port}")).await?;
-        let (mut sender, connection) =
-            hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                warn!("Error in connection: {}", e);
-            }
-        });
+        let (mut parts, body) = request.into_parts();
+        parts.uri = http::Uri::builder()
+            .scheme("http")
+            .authority(format!("{ip}:{port}"))
+            .path_and_query(
+                parts
+                    .uri
+                    .path_and_query()
+                    .map(|path_and_query| path_and_query.as_str())
+                    .unwrap_or("/"),
+            )
+            .build()?;
 
-        let (_parts, body) = sender.send_request(request).await?.into_parts();
+        let client = if self.host_meta_config.http2(service_name) {
+            &self.clients.http2
+        } else {
+            &self.clients.http1
+        };
+        let (_parts, body) = client
+            .request(http::Request::from_parts(parts, body))
+            .await?
+            .into_parts();
 
         let body_bytes = body.collect().await?.to_bytes();
 
@@ -1152,15 +1963,27 @@ fn not_found_to_none<T>(result: Result<T, BollardError>) -> Result<Option<T>, Bo
 }
 
 /// Helper function to pull images
-async fn pull(image: &Image, config: &Config) -> Result<Vec<CreateImageInfo>, BollardError> {
+async fn pull(
+    image: &Image,
+    config: &Config,
+) -> Result<Vec<CreateImageInfo>, DockerInfrastructureError> {
     let pull_options = CreateImageOptions::<&str> {
         from_image: &image.to_string(),
         ..Default::default()
     };
     let docker_auth = if let Some(registry) = image.registry() {
-        if let Some((username, password)) = config.registry_credentials(&registry) {
+        let credentials = config
+            .registry_credentials(&registry)
+            .await
+            .map_err(
+                |err| DockerInfrastructureError::RegistryCredentialsUnavailable {
+                    registry: registry.clone(),
+                    err,
+                },
+            )?;
+        if let Some((username, password)) = credentials {
             Some(DockerCredentials {
-                username: Some(username.to_string()),
+                username: Some(username),
                 password: Some(password.unsecure().to_string()),
                 ..Default::default()
             })
@@ -1172,10 +1995,155 @@ async fn pull(image: &Image, config: &Config) -> Result<Vec<CreateImageInfo>, Bo
     };
 
     let docker = Docker::connect_with_socket_defaults()?;
-    docker
-        .create_image(Some(pull_options), None, docker_auth)
-        .try_collect()
+    Ok(with_timeout(
+        timeouts(config).pull(),
+        docker
+            .create_image(Some(pull_options), None, docker_auth)
+            .try_collect(),
+    )
+    .await?)
+}
+
+fn timeouts(config: &Config) -> DockerTimeoutsConfig {
+    match config.runtime_config() {
+        Runtime::Docker(runtime) => *runtime.timeouts(),
+        Runtime::Kubernetes(_) => DockerTimeoutsConfig::default(),
+    }
+}
+
+/// Polls the state of the service's container until it is ready and fails with
+/// [`DockerInfrastructureError::DependencyNotReady`] if the container failed or did not become
+/// ready within the timeout.
+async fn wait_until_ready<F, Fut>(
+    service_name: &str,
+    timeout: Duration,
+    mut inspect_state: F,
+) -> Result<(), DockerInfrastructureError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ContainerState, DockerInfrastructureError>>,
+{
+    let not_ready = || DockerInfrastructureError::DependencyNotReady {
+        service_name: service_name.to_string(),
+    };
+    let wait = async {
+        loop {
+            let state = inspect_state().await?;
+
+            let health_status = state.health.and_then(|health| health.status);
+            match (state.status, health_status) {
+                (Some(ContainerStateStatusEnum::EXITED), _) if state.exit_code == Some(0) => {
+                    return Ok(())
+                }
+                (Some(ContainerStateStatusEnum::RUNNING), Some(HealthStatusEnum::HEALTHY))
+                | (
+                    Some(ContainerStateStatusEnum::RUNNING),
+                    None | Some(HealthStatusEnum::EMPTY) | Some(HealthStatusEnum::NONE),
+                ) => return Ok(()),
+                (Some(ContainerStateStatusEnum::EXITED), _)
+                | (Some(ContainerStateStatusEnum::DEAD), _)
+                | (_, Some(HealthStatusEnum::UNHEALTHY)) => return Err(not_ready()),
+                _ => {
+                    trace!("Waiting for {service_name} to become ready");
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+            }
+        }
+    };
+
+    tokio::time::timeout(timeout, wait)
         .await
+        .unwrap_or_else(|_| Err(not_ready()))
+}
+
+/// Fails with [`BollardError::RequestTimeoutError`] if the Docker operation does not finish within
+/// the timeout.
+async fn with_timeout<T, F>(timeout: Option<Duration>, operation: F) -> Result<T, BollardError>
+where
+    F: Future<Output = Result<T, BollardError>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, operation)
+            .await
+            .unwrap_or(Err(BollardError::RequestTimeoutError)),
+        None => operation.await,
+    }
+}
+
+/// Whether the error has been caused by a call to the Docker daemon that exceeded its timeout.
+/// Such operations can be retried.
+pub fn is_timeout(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<BollardError>(),
+        Some(BollardError::RequestTimeoutError)
+    ) || matches!(
+        err.downcast_ref::<DockerInfrastructureError>(),
+        Some(DockerInfrastructureError::Timeout)
+    ) || matches!(
+        err.downcast_ref::<DockerInfrastructureError>(),
+        Some(DockerInfrastructureError::RolledBack { err, .. })
+            if matches!(**err, DockerInfrastructureError::Timeout)
+    )
+}
+
+/// Checks if the status change container, that locks an app during an operation, is older than the
+/// given TTL.
+fn is_stale_operation_lock(
+    container: &ContainerSummary,
+    ttl: Option<std::time::Duration>,
+    now: DateTime<Utc>,
+) -> bool {
+    let (Some(ttl), Some(created)) = (ttl, container.created) else {
+        return false;
+    };
+    let Some(created) = DateTime::<Utc>::from_timestamp(created, 0) else {
+        return false;
+    };
+
+    chrono::Duration::from_std(ttl)
+        .map(|ttl| created + ttl < now)
+        .unwrap_or(false)
+}
+
+/// Calculates the consumed CPU cores in the same way as `docker stats` does.
+fn cpu_cores(cpu_stats: &CPUStats, precpu_stats: &CPUStats) -> f64 {
+    let cpu_delta = cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(precpu_stats.cpu_usage.total_usage);
+    let system_delta = cpu_stats
+        .system_cpu_usage
+        .unwrap_or_default()
+        .saturating_sub(precpu_stats.system_cpu_usage.unwrap_or_default());
+
+    if system_delta == 0 {
+        return 0.0;
+    }
+
+    let online_cpus = cpu_stats.online_cpus.unwrap_or_else(|| {
+        cpu_stats
+            .cpu_usage
+            .percpu_usage
+            .as_ref()
+            .map(|usage| usage.len() as u64)
+            .unwrap_or(1)
+    });
+
+    cpu_delta as f64 / system_delta as f64 * online_cpus as f64
+}
+
+/// Calculates the used memory without the page cache in the same way as `docker stats` does.
+fn memory_bytes(memory_stats: &MemoryStats) -> u64 {
+    let inactive_file = match memory_stats.stats {
+        Some(MemoryStatsStats::V1(stats)) => stats.total_inactive_file,
+        Some(MemoryStatsStats::V2(stats)) => stats.inactive_file,
+        None => 0,
+    };
+
+    memory_stats
+        .usage
+        .unwrap_or_default()
+        .saturating_sub(inactive_file)
 }
 
 /// Helper function to delete containers with the aid of futures::future::join_all
@@ -1195,17 +2163,111 @@ async fn delete(
     Ok(details)
 }
 
+async fn clean_up_image(docker: &Docker, app_name: &AppName, image: &str) {
+    info!("Clean up image {image:?} of app {app_name:?}");
+    match docker.remove_image(image, None, None).await {
+        Ok(output) => {
+            for o in output {
+                debug!("{o:?}");
+            }
+        }
+        Err(err) => debug!("Could not clean up image: {err:?}"),
+    };
+}
+
+fn file_paths(container_details: &ContainerInspectResponse) -> Vec<PathBuf> {
+    container_details
+        .config
+        .as_ref()
+        .and_then(|config| config.labels.as_ref())
+        .and_then(|labels| labels.get(FILE_PATHS_LABEL))
+        .and_then(|paths| serde_json::from_str(paths).ok())
+        .unwrap_or_default()
+}
+
+/// Creates the options for a container that replaces the given container with a new version of
+/// its image. Only the parts of the configuration that PREvant sets itself (see
+/// [`DockerInfrastructure::create_container_options`]) are taken over because the configuration
+/// of a container also contains the defaults of its image, e.g. its command, and the new version
+/// of the image might come with other defaults. For the same reason, environment variables and
+/// labels that equal the ones of the previous image are left out.
+fn recreated_container_options(
+    container_config: DockerContainerConfig,
+    host_config: Option<HostConfig>,
+    previous_image_config: Option<ImageConfig>,
+    image: String,
+) -> bollard::container::Config<String> {
+    let image_config = previous_image_config.unwrap_or_default();
+    let image_env = image_config.env.unwrap_or_default();
+    let image_labels = image_config.labels.unwrap_or_default();
+    let image_working_dir = image_config.working_dir;
+    let image_healthcheck = image_config.healthcheck;
+
+    bollard::container::Config {
+        image: Some(image),
+        env: container_config.env.map(|env| {
+            env.into_iter()
+                .filter(|variable| !image_env.contains(variable))
+                .collect()
+        }),
+        labels: container_config.labels.map(|labels| {
+            labels
+                .into_iter()
+                .filter(|(key, value)| image_labels.get(key) != Some(value))
+                .collect()
+        }),
+        working_dir: container_config
+            .working_dir
+            .filter(|working_dir| image_working_dir.as_ref() != Some(working_dir)),
+        healthcheck: container_config
+            .healthcheck
+            .filter(|healthcheck| image_healthcheck.as_ref() != Some(healthcheck)),
+        host_config,
+        ..Default::default()
+    }
+}
+
 /// Helper function to inspect containers with the aid of futures::future::join_all
-async fn inspect(container: ContainerSummary) -> Result<ContainerInspectResponse, BollardError> {
+async fn inspect(
+    container: ContainerSummary,
+    timeout: Option<Duration>,
+) -> Result<ContainerInspectResponse, BollardError> {
     let docker = Docker::connect_with_socket_defaults()?;
-    docker
-        .inspect_container(
+    with_timeout(
+        timeout,
+        docker.inspect_container(
             &container
                 .id
                 .expect("id is mandatory for a docker container"),
             None,
-        )
-        .await
+        ),
+    )
+    .await
+}
+
+/// Extracts the service name and the environment variables of the container's configuration.
+fn live_environment(container_details: &ContainerInspectResponse) -> Option<(String, Environment)> {
+    let config = container_details.config.as_ref()?;
+    let service_name = config.labels.as_ref()?.get(SERVICE_NAME_LABEL)?.clone();
+
+    let env = config
+        .env
+        .iter()
+        .flatten()
+        .filter_map(|variable| variable.split_once('='))
+        .map(|(key, value)| EnvironmentVariable::new(key.to_string(), SecUtf8::from(value)))
+        .collect::<Vec<_>>();
+
+    Some((service_name, Environment::new(env)))
+}
+
+/// Finds the host port that Docker assigned to the port the service listens on.
+fn exposed_host_port(ports: &PortMap, container_port: u16) -> Option<u16> {
+    ports
+        .get(&format!("{container_port}/tcp"))?
+        .as_ref()?
+        .iter()
+        .find_map(|binding| binding.host_port.as_ref()?.parse::<u16>().ok())
 }
 
 fn find_port(
@@ -1239,6 +2301,16 @@ impl TryFrom<ContainerInspectResponse> for Service {
         container_details: ContainerInspectResponse,
     ) -> Result<Service, DockerInfrastructureError> {
         let mut labels = container_details.config.and_then(|config| config.labels);
+        let container_port = labels
+            .as_ref()
+            .and_then(|labels| labels.get(CONTAINER_PORT_LABEL))
+            .and_then(|port| port.parse::<u16>().ok())
+            .unwrap_or(80);
+        let exposed_host_port = container_details
+            .network_settings
+            .as_ref()
+            .and_then(|network_settings| network_settings.ports.as_ref())
+            .and_then(|ports| exposed_host_port(ports, container_port));
         let container_id = container_details
             .id
             .expect("id is mandatory for a docker container");
@@ -1276,6 +2348,10 @@ impl TryFrom<ContainerInspectResponse> for Service {
             }
         }?;
         let mut config = ServiceConfig::new(service_name.clone(), image);
+        config.set_exposed_host_port(exposed_host_port);
+        if let Some(labels) = &labels {
+            config.set_metadata(metadata_from_labels(labels));
+        }
 
         if let Some(lb) = labels
             .as_mut()
@@ -1283,6 +2359,31 @@ impl TryFrom<ContainerInspectResponse> for Service {
         {
             config.set_container_type(lb.parse::<ContainerType>()?);
         }
+        config.set_deployed_config_hash(
+            labels
+                .as_mut()
+                .and_then(|labels| labels.remove(CONFIG_HASH_LABEL)),
+        );
+        config.set_deployed_at(
+            labels
+                .as_mut()
+                .and_then(|labels| labels.remove(DEPLOYED_AT_LABEL))
+                .and_then(|deployed_at| DateTime::parse_from_rfc3339(&deployed_at).ok())
+                .map(|deployed_at| deployed_at.with_timezone(&Utc)),
+        );
+        config.set_created_at(
+            labels
+                .as_mut()
+                .and_then(|labels| labels.remove(CREATED_AT_LABEL))
+                .and_then(|created_at| DateTime::parse_from_rfc3339(&created_at).ok())
+                .map(|created_at| created_at.with_timezone(&Utc)),
+        );
+        config.set_user_defined_parameters(
+            labels
+                .as_mut()
+                .and_then(|labels| labels.remove(USER_DEFINED_PARAMETERS_LABEL))
+                .and_then(|parameters| serde_json::from_str(&parameters).ok()),
+        );
 
         if let Some(replicated_env) = labels
             .as_mut()
@@ -1306,15 +2407,26 @@ impl TryFrom<ContainerInspectResponse> for Service {
             .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
             .map(DateTime::<Utc>::from);
 
-        let status = match state.status.unwrap_or(ContainerStateStatusEnum::PAUSED) {
+        let health_status = state.health.and_then(|health| health.status);
+        let status = match state.status.unwrap_or(ContainerStateStatusEnum::EXITED) {
+            ContainerStateStatusEnum::RUNNING
+                if health_status == Some(HealthStatusEnum::STARTING) =>
+            {
+                ServiceStatus::Starting
+            }
             ContainerStateStatusEnum::RUNNING => ServiceStatus::Running,
+            ContainerStateStatusEnum::PAUSED => ServiceStatus::Frozen,
             _ => ServiceStatus::Paused,
         };
 
         Ok(Service {
             id: container_id,
             config,
-            state: State { status, started_at },
+            state: State {
+                status,
+                started_at,
+                replicas: None,
+            },
         })
     }
 }
@@ -1322,6 +2434,7 @@ impl TryFrom<ContainerInspectResponse> for Service {
 impl From<BollardError> for DockerInfrastructureError {
     fn from(err: BollardError) -> Self {
         match &err {
+            BollardError::RequestTimeoutError => return DockerInfrastructureError::Timeout,
             BollardError::DockerResponseServerError {
                 status_code,
                 message,
@@ -1359,9 +2472,9 @@ impl From<ServiceError> for DockerInfrastructureError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{Environment, EnvironmentVariable};
+    use crate::deployment::deployment_unit::DeploymentUnitBuilder;
+    use crate::models::{Environment, EnvironmentVariable, Healthcheck};
     use crate::sc;
-    use bollard::models::ContainerState;
     use bollard::models::ContainerStateStatusEnum;
     use bollard::models::NetworkSettings;
     use secstr::SecUtf8;
@@ -1491,6 +2604,7 @@ mod tests {
               "Image": "docker.io/library/mariadb:10.3.17",
               "Labels": {
                 "com.aixigo.preview.servant.app-name": "master",
+                "com.aixigo.preview.servant.config-hash": config.config_hash(),
                 "com.aixigo.preview.servant.container-type": "instance",
                 "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
                 "com.aixigo.preview.servant.service-name": "db",
@@ -1501,12 +2615,10 @@ mod tests {
     }
 
     #[test]
-    fn should_create_container_options_with_environment_variable() {
+    fn should_create_container_options_with_exposed_port() {
         let mut config = sc!("db", "mariadb:10.3.17");
-        config.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
-            String::from("MYSQL_ROOT_PASSWORD"),
-            SecUtf8::from("example"),
-        )])));
+        config.set_port(3306);
+        config.set_expose_port(true);
 
         let options = DockerInfrastructure::create_container_options(
             &String::from("master"),
@@ -1515,40 +2627,24 @@ mod tests {
             &Vec::new(),
         );
 
-        let json = serde_json::to_value(&options).unwrap();
-        assert_json_diff::assert_json_eq!(
-            json,
-            serde_json::json!({
-              "Env": [
-                "MYSQL_ROOT_PASSWORD=example"
-              ],
-              "HostConfig": {
-                "Binds": [],
-                "RestartPolicy": {
-                  "Name": "always"
-                }
-              },
-              "Image": "docker.io/library/mariadb:10.3.17",
-              "Labels": {
-                "com.aixigo.preview.servant.app-name": "master",
-                "com.aixigo.preview.servant.container-type": "instance",
-                "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
-                "com.aixigo.preview.servant.service-name": "db",
-                "traefik.frontend.rule": "PathPrefixStrip: /master/db/; PathPrefix:/master/db/;"
-              }
-            })
+        assert_eq!(
+            options.exposed_ports,
+            Some(HashMap::from([(String::from("3306/tcp"), HashMap::new())]))
+        );
+        assert_eq!(
+            options
+                .host_config
+                .and_then(|host_config| host_config.port_bindings),
+            Some(HashMap::from([(
+                String::from("3306/tcp"),
+                Some(vec![PortBinding::default()])
+            )]))
         );
     }
 
     #[test]
-    fn should_create_container_options_with_replicated_environment_variable() {
+    fn should_create_container_options_with_port_label_for_known_port() {
         let mut config = sc!("db", "mariadb:10.3.17");
-        config.set_env(Some(Environment::new(vec![
-            EnvironmentVariable::with_replicated(
-                String::from("MYSQL_ROOT_PASSWORD"),
-                SecUtf8::from("example"),
-            ),
-        ])));
 
         let options = DockerInfrastructure::create_container_options(
             &String::from("master"),
@@ -1556,34 +2652,300 @@ mod tests {
             &ContainerConfig::default(),
             &Vec::new(),
         );
+        assert_eq!(
+            options
+                .labels
+                .and_then(|labels| labels.get(CONTAINER_PORT_LABEL).cloned()),
+            None
+        );
 
-        let json = serde_json::to_value(&options).unwrap();
-        assert_json_diff::assert_json_eq!(
-            json,
-            serde_json::json!({
-              "Env": [
-                "MYSQL_ROOT_PASSWORD=example"
-              ],
-              "HostConfig": {
-                "Binds": [],
-                "RestartPolicy": {
-                  "Name": "always"
-                }
-              },
-              "Image": "docker.io/library/mariadb:10.3.17",
-              "Labels": {
-                "com.aixigo.preview.servant.app-name": "master",
-                "com.aixigo.preview.servant.container-type": "instance",
-                "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
-                "com.aixigo.preview.servant.replicated-env": serde_json::json!({
-                        "MYSQL_ROOT_PASSWORD": {
-                        "value": "example",
-                        "templated": false,
-                        "replicate": true,
-                        }
-                    }).to_string(),
-                "com.aixigo.preview.servant.service-name": "db",
-                "traefik.frontend.rule": "PathPrefixStrip: /master/db/; PathPrefix:/master/db/;"
+        config.set_port(3306);
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+        assert_eq!(
+            options
+                .labels
+                .and_then(|labels| labels.get(CONTAINER_PORT_LABEL).cloned()),
+            Some(String::from("3306"))
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_tmpfs() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_tmpfs(vec![std::path::PathBuf::from("/tmp")]);
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options
+                .host_config
+                .and_then(|host_config| host_config.tmpfs),
+            Some(HashMap::from([(String::from("/tmp"), String::new())]))
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_working_dir() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_working_dir(Some(std::path::PathBuf::from("/app")));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(options.working_dir, Some(String::from("/app")));
+    }
+
+    #[test]
+    fn should_create_container_options_with_metadata_labels() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_metadata(BTreeMap::from([(
+            String::from("team"),
+            String::from("foo"),
+        )]));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        let labels = options.labels.unwrap();
+        assert_eq!(
+            labels.get("com.aixigo.preview.metadata/team"),
+            Some(&String::from("foo"))
+        );
+        assert_eq!(
+            metadata_from_labels(&labels),
+            BTreeMap::from([(String::from("team"), String::from("foo"))])
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_cpu_limit() {
+        let config = sc!("db", "mariadb:10.3.17");
+        let container_config = crate::config_from_str!(
+            r#"
+            [containers]
+            cpu_limit = 1.5
+            "#
+        )
+        .container_config();
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &container_config,
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options
+                .host_config
+                .and_then(|host_config| host_config.nano_cpus),
+            Some(1_500_000_000)
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_pinned_image() {
+        let config = sc!(
+            "db",
+            "mariadb:10.3.17@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+        );
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options.image.as_deref(),
+            Some("docker.io/library/mariadb:10.3.17@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913")
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_healthcheck() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_healthcheck(Some(Healthcheck::new(
+            String::from("healthcheck.sh --connect"),
+            Duration::from_secs(10),
+            5,
+            Duration::from_secs(60),
+        )));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options.healthcheck,
+            Some(HealthConfig {
+                test: Some(vec![
+                    String::from("CMD-SHELL"),
+                    String::from("healthcheck.sh --connect")
+                ]),
+                interval: Some(10_000_000_000),
+                retries: Some(5),
+                start_period: Some(60_000_000_000),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_gpus() {
+        let mut config = sc!("model", "pytorch/pytorch:latest");
+        config.set_gpus(Some(2));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options
+                .host_config
+                .and_then(|host_config| host_config.device_requests),
+            Some(vec![DeviceRequest {
+                count: Some(2),
+                capabilities: Some(vec![vec![String::from("gpu")]]),
+                ..Default::default()
+            }])
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_restart_policy() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_restart_policy(ServiceRestartPolicy::OnFailure);
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        assert_eq!(
+            options
+                .host_config
+                .and_then(|host_config| host_config.restart_policy),
+            Some(RestartPolicy {
+                name: Some(RestartPolicyNameEnum::ON_FAILURE),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_environment_variable() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
+            String::from("MYSQL_ROOT_PASSWORD"),
+            SecUtf8::from("example"),
+        )])));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        let json = serde_json::to_value(&options).unwrap();
+        assert_json_diff::assert_json_eq!(
+            json,
+            serde_json::json!({
+              "Env": [
+                "MYSQL_ROOT_PASSWORD=example"
+              ],
+              "HostConfig": {
+                "Binds": [],
+                "RestartPolicy": {
+                  "Name": "always"
+                }
+              },
+              "Image": "docker.io/library/mariadb:10.3.17",
+              "Labels": {
+                "com.aixigo.preview.servant.app-name": "master",
+                "com.aixigo.preview.servant.config-hash": config.config_hash(),
+                "com.aixigo.preview.servant.container-type": "instance",
+                "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
+                "com.aixigo.preview.servant.service-name": "db",
+                "traefik.frontend.rule": "PathPrefixStrip: /master/db/; PathPrefix:/master/db/;"
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_replicated_environment_variable() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_replicated(
+                String::from("MYSQL_ROOT_PASSWORD"),
+                SecUtf8::from("example"),
+            ),
+        ])));
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+
+        let json = serde_json::to_value(&options).unwrap();
+        assert_json_diff::assert_json_eq!(
+            json,
+            serde_json::json!({
+              "Env": [
+                "MYSQL_ROOT_PASSWORD=example"
+              ],
+              "HostConfig": {
+                "Binds": [],
+                "RestartPolicy": {
+                  "Name": "always"
+                }
+              },
+              "Image": "docker.io/library/mariadb:10.3.17",
+              "Labels": {
+                "com.aixigo.preview.servant.app-name": "master",
+                "com.aixigo.preview.servant.config-hash": config.config_hash(),
+                "com.aixigo.preview.servant.container-type": "instance",
+                "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
+                "com.aixigo.preview.servant.replicated-env": serde_json::json!({
+                        "MYSQL_ROOT_PASSWORD": {
+                        "value": "example",
+                        "templated": false,
+                        "replicate": true,
+                        }
+                    }).to_string(),
+                "com.aixigo.preview.servant.service-name": "db",
+                "traefik.frontend.rule": "PathPrefixStrip: /master/db/; PathPrefix:/master/db/;"
               }
             })
         );
@@ -1609,6 +2971,168 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_calculate_cpu_cores_from_stats() {
+        let precpu_stats = CPUStats {
+            cpu_usage: bollard::container::CPUUsage {
+                percpu_usage: None,
+                usage_in_usermode: 0,
+                total_usage: 1_000,
+                usage_in_kernelmode: 0,
+            },
+            system_cpu_usage: Some(10_000),
+            online_cpus: Some(4),
+            throttling_data: bollard::container::ThrottlingData {
+                periods: 0,
+                throttled_periods: 0,
+                throttled_time: 0,
+            },
+        };
+        let cpu_stats = CPUStats {
+            cpu_usage: bollard::container::CPUUsage {
+                total_usage: 3_000,
+                ..precpu_stats.cpu_usage.clone()
+            },
+            system_cpu_usage: Some(18_000),
+            ..precpu_stats.clone()
+        };
+
+        assert_eq!(cpu_cores(&cpu_stats, &precpu_stats), 1.0);
+        assert_eq!(cpu_cores(&precpu_stats, &precpu_stats), 0.0);
+    }
+
+    #[test]
+    fn should_detect_stale_operation_lock() {
+        let now = Utc::now();
+        let container = ContainerSummary {
+            created: Some((now - chrono::Duration::minutes(31)).timestamp()),
+            ..Default::default()
+        };
+
+        assert!(is_stale_operation_lock(
+            &container,
+            Some(std::time::Duration::from_secs(30 * 60)),
+            now
+        ));
+        assert!(!is_stale_operation_lock(
+            &container,
+            Some(std::time::Duration::from_secs(60 * 60)),
+            now
+        ));
+        assert!(!is_stale_operation_lock(&container, None, now));
+    }
+
+    #[test]
+    fn should_create_service_config_from_container_details_with_exposed_port() {
+        let mut details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        details.network_settings = Some(bollard::service::NetworkSettings {
+            ports: Some(HashMap::from([
+                (
+                    String::from("443/tcp"),
+                    Some(vec![bollard::service::PortBinding {
+                        host_ip: Some(String::from("0.0.0.0")),
+                        host_port: Some(String::from("32769")),
+                    }]),
+                ),
+                (
+                    String::from("80/tcp"),
+                    Some(vec![bollard::service::PortBinding {
+                        host_ip: Some(String::from("0.0.0.0")),
+                        host_port: Some(String::from("32768")),
+                    }]),
+                ),
+                (String::from("8080/tcp"), None),
+            ])),
+            ..Default::default()
+        });
+
+        let service = Service::try_from(details).unwrap();
+
+        assert_eq!(service.config.exposed_host_port(), Some(32768));
+    }
+
+    #[test]
+    fn should_create_service_config_from_container_details_with_exposed_port_of_service() {
+        let mut details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+            String::from(CONTAINER_PORT_LABEL) => String::from("443")
+        );
+        details.network_settings = Some(bollard::service::NetworkSettings {
+            ports: Some(HashMap::from([
+                (
+                    String::from("443/tcp"),
+                    Some(vec![bollard::service::PortBinding {
+                        host_ip: Some(String::from("0.0.0.0")),
+                        host_port: Some(String::from("32769")),
+                    }]),
+                ),
+                (
+                    String::from("80/tcp"),
+                    Some(vec![bollard::service::PortBinding {
+                        host_ip: Some(String::from("0.0.0.0")),
+                        host_port: Some(String::from("32768")),
+                    }]),
+                ),
+                (String::from("8080/tcp"), None),
+            ])),
+            ..Default::default()
+        });
+
+        let service = Service::try_from(details).unwrap();
+
+        assert_eq!(service.config.exposed_host_port(), Some(32769));
+    }
+
+    #[test]
+    fn should_create_starting_service_from_container_details_with_starting_health() {
+        let mut details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        if let Some(state) = details.state.as_mut() {
+            state.health = Some(bollard::service::Health {
+                status: Some(HealthStatusEnum::STARTING),
+                ..Default::default()
+            });
+        }
+
+        let service = Service::try_from(details).unwrap();
+
+        assert_eq!(service.status(), &ServiceStatus::Starting);
+    }
+
+    #[test]
+    fn should_create_frozen_service_from_paused_container_details() {
+        let mut details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        if let Some(state) = details.state.as_mut() {
+            state.status = Some(ContainerStateStatusEnum::PAUSED);
+            state.paused = Some(true);
+        }
+
+        let service = Service::try_from(details).unwrap();
+
+        assert_eq!(service.status(), &ServiceStatus::Frozen);
+    }
+
     #[test]
     fn should_not_create_service_config_from_container_details_with_invalid_image_information() {
         let details = container_details!(
@@ -1672,6 +3196,224 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn should_fail_operation_exceeding_timeout() {
+        let result = with_timeout(
+            Some(Duration::from_millis(10)),
+            futures::future::pending::<Result<(), BollardError>>(),
+        )
+        .await;
+
+        let err = anyhow::Error::new(result.unwrap_err());
+        assert!(is_timeout(&err));
+        assert!(is_timeout(&anyhow::Error::new(
+            DockerInfrastructureError::from(BollardError::RequestTimeoutError)
+        )));
+    }
+
+    #[tokio::test]
+    async fn should_fail_waiting_for_service_not_ready_within_timeout() {
+        let result = wait_until_ready("db", Duration::from_millis(10), || async {
+            Ok(ContainerState {
+                status: Some(ContainerStateStatusEnum::CREATED),
+                ..Default::default()
+            })
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(DockerInfrastructureError::DependencyNotReady { service_name }) if service_name == "db"
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_service_becoming_ready() {
+        let mut states = vec![
+            ContainerState {
+                status: Some(ContainerStateStatusEnum::RUNNING),
+                health: Some(bollard::service::Health {
+                    status: Some(HealthStatusEnum::HEALTHY),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ContainerState {
+                status: Some(ContainerStateStatusEnum::RUNNING),
+                health: Some(bollard::service::Health {
+                    status: Some(HealthStatusEnum::STARTING),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ];
+
+        let result = wait_until_ready("db", Duration::from_secs(10), || {
+            let state = states.pop().unwrap();
+            async { Ok(state) }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert!(states.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_not_fail_operation_without_timeout() {
+        let result = with_timeout(None, async { Ok::<_, BollardError>(42) }).await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert!(!is_timeout(&anyhow::anyhow!("unexpected error")));
+    }
+
+    #[tokio::test]
+    async fn should_refuse_deployment_with_kubernetes_manifest_companions() {
+        let config = crate::config_from_str!(
+            r#"
+            [companions.redis-operator]
+            type = 'kubernetes-manifest'
+            manifest = '/etc/prevant/redis.yaml'
+            "#
+        );
+        let infrastructure = DockerInfrastructure::new(config);
+        let deployment_unit = DeploymentUnitBuilder::init(AppName::master(), vec![sc!("nginx")])
+            .extend_with_config(&Default::default())
+            .extend_with_templating_only_service_configs(Vec::new())
+            .extend_with_image_infos(HashMap::new())
+            .apply_templating(&None, None)
+            .unwrap()
+            .apply_hooks(&Default::default())
+            .await
+            .unwrap()
+            .build();
+
+        let err = infrastructure
+            .deploy_services("status-id", &deployment_unit, &ContainerConfig::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<DockerInfrastructureError>(),
+            Some(DockerInfrastructureError::UnsupportedKubernetesManifestCompanions { companion_names })
+                if companion_names == &vec![String::from("redis-operator")]
+        ));
+    }
+
+    #[test]
+    fn should_keep_timeout_of_rolled_back_deployment() {
+        let err = DockerInfrastructureError::RolledBack {
+            service_names: vec![String::from("db")],
+            err: Box::new(DockerInfrastructureError::Timeout),
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "The Docker daemon did not respond in time. Please, retry the operation. The started services db have been rolled back."
+        );
+        assert!(is_timeout(&anyhow::Error::new(err)));
+    }
+
+    #[test]
+    fn should_restore_replicated_env_from_container_labels() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_replicated(
+                String::from("MYSQL_ROOT_PASSWORD"),
+                SecUtf8::from("example"),
+            ),
+            EnvironmentVariable::new(String::from("LOG_LEVEL"), SecUtf8::from("debug")),
+        ])));
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &Vec::new(),
+        );
+        let replicated_env = options
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(REPLICATED_ENV_LABEL))
+            .unwrap()
+            .clone();
+
+        let details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("db")),
+            Some(String::from("mariadb")),
+            None,
+            String::from(REPLICATED_ENV_LABEL) => replicated_env
+        );
+        let service = Service::try_from(details).unwrap();
+
+        assert_eq!(
+            service.config.env(),
+            Some(&Environment::new(vec![
+                EnvironmentVariable::with_replicated(
+                    String::from("MYSQL_ROOT_PASSWORD"),
+                    SecUtf8::from("example"),
+                )
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_extract_live_environment_from_container_details() {
+        let mut details = container_details!(
+            "some-random-id".to_string(),
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        if let Some(config) = details.config.as_mut() {
+            config.env = Some(vec![
+                String::from("MYSQL_ROOT_PASSWORD=example"),
+                String::from("JAVA_OPTS=-Dkey=value"),
+            ]);
+        }
+
+        let (service_name, env) = live_environment(&details).unwrap();
+
+        assert_eq!(service_name, "nginx");
+        assert_eq!(
+            env,
+            Environment::new(vec![
+                EnvironmentVariable::new(
+                    String::from("MYSQL_ROOT_PASSWORD"),
+                    SecUtf8::from("example")
+                ),
+                EnvironmentVariable::new(String::from("JAVA_OPTS"), SecUtf8::from("-Dkey=value")),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_create_container_options_with_basic_auth() {
+        let mut config = sc!("adminer", "adminer:4.8.1");
+        config.set_basic_auth_users(vec![
+            SecUtf8::from("test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/"),
+            SecUtf8::from("test2:$apr1$d9hr9HBB$4HxwgUir3HP4EsggP/QNo0"),
+        ]);
+
+        let options = DockerInfrastructure::create_container_options(
+            &String::from("master"),
+            &config,
+            &ContainerConfig::default(),
+            &[],
+        );
+
+        assert_eq!(
+            options
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get("traefik.frontend.auth.basic.users")),
+            Some(&String::from(
+                "test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/,test2:$apr1$d9hr9HBB$4HxwgUir3HP4EsggP/QNo0"
+            ))
+        );
+    }
+
     #[test]
     fn should_create_container_options_with_host_config_binds() {
         let config = sc!("db", "mariadb:10.3.17");
@@ -1698,6 +3440,7 @@ mod tests {
               "Image": "docker.io/library/mariadb:10.3.17",
               "Labels": {
                 "com.aixigo.preview.servant.app-name": "master",
+                "com.aixigo.preview.servant.config-hash": config.config_hash(),
                 "com.aixigo.preview.servant.container-type": "instance",
                 "com.aixigo.preview.servant.image": "docker.io/library/mariadb:10.3.17",
                 "com.aixigo.preview.servant.service-name": "db",
@@ -1706,4 +3449,62 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn should_recreate_container_options_without_defaults_of_previous_image() {
+        let container_config = DockerContainerConfig {
+            image: Some(String::from("sha256:old")),
+            cmd: Some(vec![String::from("nginx")]),
+            env: Some(vec![
+                String::from("PATH=/usr/local/bin"),
+                String::from("VAR_1=abcd"),
+            ]),
+            labels: Some(HashMap::from([
+                (String::from("maintainer"), String::from("nginx")),
+                (String::from(SERVICE_NAME_LABEL), String::from("api")),
+            ])),
+            working_dir: Some(String::from("/")),
+            ..Default::default()
+        };
+        let previous_image_config = ImageConfig {
+            cmd: Some(vec![String::from("nginx")]),
+            env: Some(vec![String::from("PATH=/usr/local/bin")]),
+            labels: Some(HashMap::from([(
+                String::from("maintainer"),
+                String::from("nginx"),
+            )])),
+            working_dir: Some(String::from("/")),
+            ..Default::default()
+        };
+        let host_config = HostConfig {
+            restart_policy: Some(RestartPolicy {
+                name: Some(RestartPolicyNameEnum::ALWAYS),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let options = recreated_container_options(
+            container_config,
+            Some(host_config),
+            Some(previous_image_config),
+            String::from("sha256:new"),
+        );
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({
+              "Env": ["VAR_1=abcd"],
+              "HostConfig": {
+                "RestartPolicy": {
+                  "Name": "always"
+                }
+              },
+              "Image": "sha256:new",
+              "Labels": {
+                "com.aixigo.preview.servant.service-name": "api"
+              }
+            })
+        );
+    }
 }
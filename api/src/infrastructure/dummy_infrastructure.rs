@@ -25,29 +25,37 @@
  */
 
 use crate::config::ContainerConfig;
-use crate::deployment::deployment_unit::DeployableService;
+use crate::deployment::deployment_unit::{DeployableService, DeploymentStrategy};
 use crate::deployment::DeploymentUnit;
-use crate::infrastructure::Infrastructure;
-use crate::models::service::{Service, ServiceStatus, Services, State};
-use crate::models::{AppName, ServiceConfig};
+use crate::infrastructure::{Infrastructure, ScalingNotSupported};
+use crate::models::service::{Replicas, Service, ServiceStatus, Services, State};
+use crate::models::{AppName, ResourceUsage, ServiceConfig};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset, Utc};
 use futures::stream::{self, BoxStream};
 use multimap::MultiMap;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use super::TraefikIngressRoute;
 
+#[cfg(test)]
+type ReplicaCounts = HashMap<(AppName, String), u32>;
+
 #[cfg(test)]
 #[derive(Clone)]
 pub struct DummyInfrastructure {
     delay: Option<Duration>,
     services: Arc<Mutex<MultiMap<AppName, DeployableService>>>,
+    started_services: Arc<Mutex<Vec<String>>>,
+    pinned_apps: Arc<Mutex<HashSet<AppName>>>,
     base_ingress_route: Option<TraefikIngressRoute>,
+    failing_service: Option<usize>,
+    replicas: Option<Arc<Mutex<ReplicaCounts>>>,
 }
 
 #[cfg(test)]
@@ -56,7 +64,11 @@ impl DummyInfrastructure {
         Self {
             delay: None,
             services: Arc::new(Mutex::new(MultiMap::new())),
+            started_services: Arc::new(Mutex::new(Vec::new())),
+            pinned_apps: Arc::new(Mutex::new(HashSet::new())),
             base_ingress_route: None,
+            failing_service: None,
+            replicas: None,
         }
     }
 
@@ -64,7 +76,11 @@ impl DummyInfrastructure {
         Self {
             delay: Some(delay),
             services: Arc::new(Mutex::new(MultiMap::new())),
+            started_services: Arc::new(Mutex::new(Vec::new())),
+            pinned_apps: Arc::new(Mutex::new(HashSet::new())),
             base_ingress_route: None,
+            failing_service: None,
+            replicas: None,
         }
     }
 
@@ -72,7 +88,37 @@ impl DummyInfrastructure {
         Self {
             delay: None,
             services: Arc::new(Mutex::new(MultiMap::new())),
+            started_services: Arc::new(Mutex::new(Vec::new())),
+            pinned_apps: Arc::new(Mutex::new(HashSet::new())),
             base_ingress_route: Some(base_ingress_route),
+            failing_service: None,
+            replicas: None,
+        }
+    }
+
+    /// Creates an infrastructure that fails to start the `n`-th service (starting at 1) of every
+    /// deployment.
+    pub fn with_failing_service(n: usize) -> Self {
+        Self {
+            failing_service: Some(n),
+            ..Self::new()
+        }
+    }
+
+    /// Creates an infrastructure that supports scaling services to multiple replicas.
+    pub fn with_scaling() -> Self {
+        Self {
+            replicas: Some(Arc::new(Mutex::new(HashMap::new()))),
+            ..Self::new()
+        }
+    }
+
+    /// Returns an infrastructure that shares the state of this one but fails to start the `n`-th
+    /// service (starting at 1) of every deployment.
+    pub fn fail_service(&self, n: usize) -> Self {
+        Self {
+            failing_service: Some(n),
+            ..self.clone()
         }
     }
 
@@ -84,6 +130,11 @@ impl DummyInfrastructure {
             .flat_map(|(_, v)| v.iter().cloned())
             .collect::<Vec<_>>()
     }
+
+    /// The names of the services in the order in which they have been started.
+    pub fn started_services(&self) -> Vec<String> {
+        self.started_services.lock().unwrap().clone()
+    }
 }
 
 #[cfg(test)]
@@ -93,6 +144,18 @@ impl DummyInfrastructure {
             tokio::time::sleep(*delay).await;
         }
     }
+
+    fn replicas_of(&self, app_name: &AppName, service_name: &str) -> Option<Replicas> {
+        let replicas = self.replicas.as_ref()?.lock().unwrap();
+        let count = replicas
+            .get(&(app_name.clone(), service_name.to_string()))
+            .copied()
+            .unwrap_or(1);
+        Some(Replicas {
+            desired: count,
+            current: count,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -105,9 +168,12 @@ impl Infrastructure for DummyInfrastructure {
         for (app, configs) in services.iter_all() {
             let mut services = Vec::with_capacity(configs.len());
             for config in configs {
+                let mut service_config = ServiceConfig::clone(config);
+                service_config.set_deployed_config_hash(Some(config.config_hash()));
+
                 let service = Service {
                     id: config.service_name().clone(),
-                    config: ServiceConfig::clone(config),
+                    config: service_config,
                     state: State {
                         status: ServiceStatus::Running,
                         started_at: Some(
@@ -115,6 +181,7 @@ impl Infrastructure for DummyInfrastructure {
                                 .unwrap()
                                 .with_timezone(&Utc),
                         ),
+                        replicas: self.replicas_of(app, config.service_name()),
                     },
                 };
 
@@ -137,19 +204,49 @@ impl Infrastructure for DummyInfrastructure {
 
         let mut services = self.services.lock().unwrap();
         let app_name = deployment_unit.app_name();
-        let deployable_services = deployment_unit.services();
-        if let Some(running_services) = services.get_vec_mut(&app_name) {
-            let service_names = deployable_services
-                .iter()
-                .map(|c| c.service_name())
-                .collect::<HashSet<&String>>();
 
-            running_services.retain(|config| !service_names.contains(config.service_name()));
-        }
+        let mut started_services = self.started_services.lock().unwrap();
+        let mut created_in_deployment = Vec::new();
+        for (index, config) in deployment_unit
+            .deployment_stages()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            if self.failing_service == Some(index + 1) {
+                if !deployment_unit.rollback_on_failure() || created_in_deployment.is_empty() {
+                    anyhow::bail!("Cannot start {}", config.service_name());
+                }
+
+                if let Some(running_services) = services.get_vec_mut(app_name) {
+                    running_services
+                        .retain(|config| !created_in_deployment.contains(config.service_name()));
+                }
+                anyhow::bail!(
+                    "Cannot start {}. The started services {} have been rolled back.",
+                    config.service_name(),
+                    created_in_deployment.join(", ")
+                );
+            }
+
+            let existing = services.get_vec(app_name).is_some_and(|running_services| {
+                running_services
+                    .iter()
+                    .any(|running| running.service_name() == config.service_name())
+            });
+            if existing && matches!(config.strategy(), DeploymentStrategy::RedeployNever) {
+                continue;
+            }
+            if let Some(running_services) = services.get_vec_mut(app_name) {
+                running_services.retain(|running| running.service_name() != config.service_name());
+            }
+            if !existing {
+                created_in_deployment.push(config.service_name().clone());
+            }
 
-        for config in deployable_services {
             info!("started {} for {}.", config.service_name(), app_name);
             services.insert(app_name.clone(), config.clone());
+            started_services.push(config.service_name().clone());
         }
         Ok(services
             .get_vec(app_name)
@@ -165,32 +262,85 @@ impl Infrastructure for DummyInfrastructure {
                             .unwrap()
                             .with_timezone(&Utc),
                     ),
+                    replicas: None,
                 },
             })
             .collect::<Vec<_>>()
             .into())
     }
 
+    async fn update_images(
+        &self,
+        _status_id: &str,
+        app_name: &AppName,
+        image_digests: &HashMap<String, String>,
+    ) -> Result<Services> {
+        self.delay_if_configured().await;
+
+        {
+            let mut services = self.services.lock().unwrap();
+            let mut started_services = self.started_services.lock().unwrap();
+            for service in services.get_vec_mut(app_name).into_iter().flatten() {
+                let Some(digest) = image_digests.get(service.service_name()) else {
+                    continue;
+                };
+                let is_up_to_date = matches!(
+                    service.strategy(),
+                    DeploymentStrategy::RedeployOnImageUpdate(running) if running == digest
+                );
+                if is_up_to_date {
+                    continue;
+                }
+
+                info!(
+                    "updated image of {} for {}.",
+                    service.service_name(),
+                    app_name
+                );
+                *service = DeployableService::new(
+                    ServiceConfig::clone(service),
+                    DeploymentStrategy::RedeployOnImageUpdate(digest.clone()),
+                    service.ingress_route().clone(),
+                    service.declared_volumes().clone(),
+                );
+                started_services.push(service.service_name().clone());
+            }
+        }
+
+        Ok(self
+            .fetch_services()
+            .await?
+            .remove(app_name)
+            .unwrap_or_else(Services::empty))
+    }
+
     async fn stop_services(&self, _status_id: &str, app_name: &AppName) -> Result<Services> {
         self.delay_if_configured().await;
 
         let mut services = self.services.lock().unwrap();
+        self.pinned_apps.lock().unwrap().remove(app_name);
 
         match services.remove(&app_name) {
             Some(services) => Ok(Services::from(
                 services
                     .into_iter()
-                    .map(|sc| Service {
-                        id: sc.service_name().clone(),
-                        config: ServiceConfig::clone(&sc),
-                        state: State {
-                            status: ServiceStatus::Running,
-                            started_at: Some(
-                                DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z")
-                                    .unwrap()
-                                    .with_timezone(&Utc),
-                            ),
-                        },
+                    .map(|sc| {
+                        let mut config = ServiceConfig::clone(&sc);
+                        config.set_deployed_at(None);
+                        config.set_created_at(None);
+                        Service {
+                            id: sc.service_name().clone(),
+                            config,
+                            state: State {
+                                status: ServiceStatus::Running,
+                                started_at: Some(
+                                    DateTime::parse_from_rfc3339("2019-07-18T07:25:00.000000000Z")
+                                        .unwrap()
+                                        .with_timezone(&Utc),
+                                ),
+                                replicas: None,
+                            },
+                        }
                     })
                     .collect::<Vec<_>>(),
             )),
@@ -226,13 +376,106 @@ impl Infrastructure for DummyInfrastructure {
         ))
     }
 
+    async fn stop_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<Option<Service>> {
+        let mut services = self.services.lock().unwrap();
+        let Some(app_services) = services.get_vec_mut(app_name) else {
+            return Ok(None);
+        };
+        let Some(index) = app_services
+            .iter()
+            .position(|service| service.service_name() == service_name)
+        else {
+            return Ok(None);
+        };
+
+        let service = app_services.remove(index);
+        if app_services.is_empty() {
+            services.remove(app_name);
+        }
+
+        Ok(Some(Service {
+            id: service.service_name().clone(),
+            config: ServiceConfig::clone(&service),
+            state: State {
+                status: ServiceStatus::Paused,
+                started_at: None,
+                replicas: None,
+            },
+        }))
+    }
+
     async fn change_status(
         &self,
-        _app_name: &AppName,
-        _service_name: &str,
-        _status: ServiceStatus,
+        app_name: &AppName,
+        service_name: &str,
+        status: ServiceStatus,
     ) -> Result<Option<Service>> {
-        Ok(None)
+        let services = self.services.lock().unwrap();
+        Ok(services
+            .get_vec(app_name)
+            .and_then(|services| services.iter().find(|s| s.service_name() == service_name))
+            .map(|config| Service {
+                id: config.service_name().clone(),
+                config: ServiceConfig::clone(config),
+                state: State {
+                    status,
+                    started_at: None,
+                    replicas: None,
+                },
+            }))
+    }
+
+    async fn scale_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        replicas: NonZeroU32,
+    ) -> Result<Option<Service>> {
+        let Some(scaled_replicas) = &self.replicas else {
+            return Err(ScalingNotSupported.into());
+        };
+
+        let service = {
+            let services = self.services.lock().unwrap();
+            services
+                .get_vec(app_name)
+                .and_then(|services| services.iter().find(|s| s.service_name() == service_name))
+                .map(|config| Service {
+                    id: config.service_name().clone(),
+                    config: ServiceConfig::clone(config),
+                    state: State {
+                        status: ServiceStatus::Running,
+                        started_at: None,
+                        replicas: self.replicas_of(app_name, service_name),
+                    },
+                })
+        };
+
+        if service.is_some() {
+            scaled_replicas
+                .lock()
+                .unwrap()
+                .insert((app_name.clone(), service_name.to_string()), replicas.get());
+        }
+
+        Ok(service)
+    }
+
+    async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<Option<BTreeMap<String, ResourceUsage>>> {
+        let services = self.services.lock().unwrap();
+        Ok(services.get_vec(app_name).map(|configs| {
+            configs
+                .iter()
+                .map(|config| (config.service_name().clone(), ResourceUsage::new(0.1, 1024)))
+                .collect()
+        }))
     }
 
     async fn base_traefik_ingress_route(&self) -> Result<Option<TraefikIngressRoute>> {
@@ -243,6 +486,20 @@ impl Infrastructure for DummyInfrastructure {
         self
     }
 
+    async fn fetch_pinned_app_names(&self) -> Result<HashSet<AppName>> {
+        Ok(self.pinned_apps.lock().unwrap().clone())
+    }
+
+    async fn set_pinned(&self, app_name: &AppName, pinned: bool) -> Result<()> {
+        let mut pinned_apps = self.pinned_apps.lock().unwrap();
+        if pinned {
+            pinned_apps.insert(app_name.clone());
+        } else {
+            pinned_apps.remove(app_name);
+        }
+        Ok(())
+    }
+
     async fn http_forwarder(&self) -> Result<Box<dyn super::HttpForwarder>> {
         unimplemented!("Currently not supported by the dummy infra")
     }
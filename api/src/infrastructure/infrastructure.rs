@@ -28,13 +28,20 @@ use super::traefik::TraefikIngressRoute;
 use crate::config::ContainerConfig;
 use crate::deployment::DeploymentUnit;
 use crate::models::service::{Service, ServiceStatus, Services};
-use crate::models::{AppName, ContainerType, ServiceConfig, WebHostMeta};
+use crate::models::{
+    AppName, ContainerType, Environment, ResourceUsage, ServiceConfig, WebHostMeta,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use dyn_clone::DynClone;
 use futures::stream::BoxStream;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
+
+#[derive(Debug, thiserror::Error)]
+#[error("The infrastructure cannot run multiple replicas of a service.")]
+pub struct ScalingNotSupported;
 
 #[async_trait]
 pub trait Infrastructure: Send + Sync + DynClone {
@@ -64,16 +71,44 @@ pub trait Infrastructure: Send + Sync + DynClone {
         container_config: &ContainerConfig,
     ) -> Result<Services>;
 
+    /// Recreates the services of the app that are listed in `image_digests`, mapped by the service
+    /// name, if they do not run the given image digest yet, e.g. because a new image has been
+    /// pushed with the same tag. Unlike [`deploy_services`](Self::deploy_services), the services
+    /// keep the specification of their running container or deployment, including everything that
+    /// cannot be restored into a [`ServiceConfig`], such as environment variables, files and
+    /// secrets. Only the image is updated.
+    ///
+    /// Returns the services of the app.
+    async fn update_images(
+        &self,
+        status_id: &str,
+        app_name: &AppName,
+        image_digests: &HashMap<String, String>,
+    ) -> Result<Services>;
+
     async fn get_status_change(&self, _status_id: &str) -> Result<Option<Services>> {
         Ok(None)
     }
 
+    /// Releases the locks of operations that have been interrupted, e.g. because PREvant crashed
+    /// during a deployment, and returns the number of released locks. This will be called on
+    /// startup before this instance starts any operation, but other instances sharing the
+    /// infrastructure might still hold locks of operations in progress.
+    async fn release_orphaned_operation_locks(&self) -> Result<usize> {
+        Ok(0)
+    }
+
     /// Stops the services running for the given `app_name`
     ///
     /// The implementation must ensure that it returns the services that have been
     /// stopped.
     async fn stop_services(&self, status_id: &str, app_name: &AppName) -> Result<Services>;
 
+    /// Stops and removes a single service of the app, e.g. because it has been moved to another
+    /// runtime (see `[runtimes]`). Returns `None` if the app does not run the service.
+    async fn stop_service(&self, app_name: &AppName, service_name: &str)
+        -> Result<Option<Service>>;
+
     /// Streams the log lines with a the corresponding timestamps in it.
     async fn get_logs<'a>(
         &'a self,
@@ -92,6 +127,45 @@ pub trait Infrastructure: Send + Sync + DynClone {
         status: ServiceStatus,
     ) -> Result<Option<Service>>;
 
+    /// Changes the number of replicas of a service and returns the service as it was before the
+    /// change, or `None` if the service does not exist. Infrastructures that cannot run multiple
+    /// replicas of a service fail with [`ScalingNotSupported`].
+    async fn scale_service(
+        &self,
+        _app_name: &AppName,
+        _service_name: &str,
+        _replicas: NonZeroU32,
+    ) -> Result<Option<Service>> {
+        Err(ScalingNotSupported.into())
+    }
+
+    /// Returns the current resource usage of each service of the given app, mapped by the service
+    /// name, or `None` if the app does not exist.
+    async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<Option<BTreeMap<String, ResourceUsage>>>;
+
+    /// Returns the environment variables that the containers of the given app are currently
+    /// running with, mapped by the service name. In contrast to the replicated environment of the
+    /// service configurations, these values reflect changes that have been applied after the
+    /// deployment.
+    async fn fetch_live_environments(
+        &self,
+        _app_name: &AppName,
+    ) -> Result<HashMap<String, Environment>> {
+        Ok(HashMap::new())
+    }
+
+    /// Returns the names of the apps that are pinned, i.e. that must not be deleted
+    /// automatically.
+    async fn fetch_pinned_app_names(&self) -> Result<HashSet<AppName>>;
+
+    /// Pins or unpins the given app. The pin must be stored with the objects of the app on the
+    /// infrastructure so that it survives a restart of PREvant and so that it will be removed
+    /// together with the app.
+    async fn set_pinned(&self, app_name: &AppName, pinned: bool) -> Result<()>;
+
     async fn http_forwarder(&self) -> Result<Box<dyn HttpForwarder>>;
 
     /// Determines the [router rule](https://doc.traefik.io/traefik/routing/routers/) that points
@@ -682,6 +682,10 @@ impl K8sDeploymentUnit {
         images
     }
 
+    pub(super) fn apply_basic_auth_secret(&mut self, basic_auth_secret: Secret) {
+        self.secrets.push(basic_auth_secret);
+    }
+
     pub(super) fn apply_image_pull_secret(&mut self, image_pull_secret: Secret) {
         let pull_secret_reference = LocalObjectReference {
             name: image_pull_secret.metadata.name.clone().unwrap_or_default(),
@@ -1149,6 +1153,7 @@ mod tests {
             state: State {
                 status: crate::models::service::ServiceStatus::Running,
                 started_at: Some(Utc::now()),
+                replicas: None,
             },
         }));
 
@@ -1189,6 +1194,7 @@ mod tests {
             state: State {
                 status: crate::models::service::ServiceStatus::Running,
                 started_at: Some(Utc::now()),
+                replicas: None,
             },
         }));
 
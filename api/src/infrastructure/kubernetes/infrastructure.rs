@@ -24,23 +24,31 @@
  * =========================LICENSE_END==================================
  */
 use super::super::{
-    APP_NAME_LABEL, CONTAINER_TYPE_LABEL, IMAGE_LABEL, REPLICATED_ENV_LABEL, SERVICE_NAME_LABEL,
-    STORAGE_TYPE_LABEL,
+    metadata_from_labels, APP_NAME_LABEL, CONFIG_HASH_LABEL, CONTAINER_TYPE_LABEL,
+    CREATED_AT_LABEL, DEPLOYED_AT_LABEL, IMAGE_LABEL, PINNED_LABEL, REPLICATED_ENV_LABEL,
+    SERVICE_NAME_LABEL, STORAGE_TYPE_LABEL, USER_DEFINED_PARAMETERS_LABEL,
 };
 use super::deployment_unit::K8sDeploymentUnit;
 use super::payloads::{
-    deployment_payload, image_pull_secret_payload, ingress_route_payload, middleware_payload,
-    namespace_payload, persistent_volume_claim_payload, secrets_payload, service_payload,
-    IngressRoute, Middleware,
+    basic_auth_secret_payload, deployment_payload, image_pull_secret_payload,
+    ingress_route_payload, middleware_payload, namespace_payload, persistent_volume_claim_payload,
+    secrets_payload, service_payload, IngressRoute, Middleware, GPU_RESOURCE,
+    IMAGE_HASH_ANNOTATION,
+};
+use crate::config::{
+    Config as PREvantConfig, ContainerConfig, CredentialsProviderError, KubernetesClusterConfig,
+    Runtime,
 };
-use crate::config::{Config as PREvantConfig, ContainerConfig, Runtime};
 use crate::deployment::deployment_unit::{DeployableService, DeploymentUnit};
 use crate::infrastructure::traefik::{TraefikIngressRoute, TraefikMiddleware};
 use crate::infrastructure::{HttpForwarder, Infrastructure, TraefikRouterRule};
 use crate::models::service::{
-    ContainerType, Service, ServiceError, ServiceStatus, Services, State,
+    ContainerType, Replicas, Service, ServiceError, ServiceStatus, Services, State,
+};
+use crate::models::{
+    AppName, Environment, EnvironmentVariable, Image, ResourceUsage, RestartPolicy, ServiceConfig,
+    WebHostMeta,
 };
-use crate::models::{AppName, Environment, Image, ServiceConfig, WebHostMeta};
 use anyhow::Result;
 use async_stream::stream;
 use async_trait::async_trait;
@@ -54,22 +62,33 @@ use hyper_util::rt::TokioIo;
 use k8s_openapi::api::core::v1::PersistentVolumeClaim;
 use k8s_openapi::api::storage::v1::StorageClass;
 use k8s_openapi::api::{
-    apps::v1::Deployment as V1Deployment, core::v1::Namespace as V1Namespace,
+    apps::v1::Deployment as V1Deployment, core::v1::Namespace as V1Namespace, core::v1::Node,
     core::v1::Pod as V1Pod, core::v1::Secret as V1Secret, core::v1::Service as V1Service,
 };
-use kube::Resource;
+use kube::discovery::{pinned_kind, Scope};
 use kube::{
-    api::{Api, DeleteParams, ListParams, LogParams, Patch, PatchParams, PostParams},
+    api::{
+        Api, ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, LogParams,
+        Patch, PatchParams, PostParams,
+    },
     client::Client,
-    config::Config,
+    config::{
+        AuthInfo, Cluster, Config, Context, KubeConfigOptions, Kubeconfig, NamedAuthInfo,
+        NamedCluster, NamedContext,
+    },
     error::{Error as KubeError, ErrorResponse},
 };
+use kube::{Resource, ResourceExt};
 use log::{debug, warn};
 use secstr::SecUtf8;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::{From, TryFrom};
+use std::num::NonZeroU32;
 use std::str::FromStr;
 
+/// The annotation of a deployment that holds the replicas of a paused service.
+static PAUSED_REPLICAS_ANNOTATION: &str = "com.aixigo.preview.servant.paused-replicas";
+
 #[derive(Clone)]
 pub struct KubernetesInfrastructure {
     config: PREvantConfig,
@@ -93,6 +112,19 @@ pub enum KubernetesInfrastructureError {
     MissingDeploymentAnnotations,
     #[error("Bootstrap pod {pod_name} for {app_name} failed")]
     BootstrapContainerFailed { pod_name: String, app_name: AppName },
+    #[error("Invalid Kubernetes cluster configuration: {reason}")]
+    InvalidClusterConfig { reason: &'static str },
+    #[error("The services {} require GPUs but no node of the cluster provides {GPU_RESOURCE}.", service_names.join(", "))]
+    GpusNotSupported { service_names: Vec<String> },
+    #[error("The services {} request a restart policy other than always which is not supported by Kubernetes deployments.", service_names.join(", "))]
+    UnsupportedRestartPolicy { service_names: Vec<String> },
+    #[error("The manifest companion object {name} of kind {kind} is cluster-scoped but only namespaced objects are supported.")]
+    ClusterScopedManifestObject { kind: String, name: String },
+    #[error("Cannot fetch the credentials of registry {registry}: {err}")]
+    RegistryCredentialsUnavailable {
+        registry: String,
+        err: CredentialsProviderError,
+    },
 }
 
 impl KubernetesInfrastructure {
@@ -100,13 +132,25 @@ impl KubernetesInfrastructure {
         Self { config }
     }
 
+    /// Checks that the Kubernetes API server can be reached with the configured connection.
+    pub async fn verify_connection(&self) -> Result<(), KubernetesInfrastructureError> {
+        let version = self
+            .client()
+            .await?
+            .apiserver_version()
+            .await
+            .map_err(|err| KubernetesInfrastructureError::UnexpectedError {
+                err: anyhow::Error::new(err).context("Failed to connect to the API server"),
+            })?;
+        log::info!("Connected to Kubernetes {}", version.git_version);
+        Ok(())
+    }
+
     async fn client(&self) -> Result<Client, KubernetesInfrastructureError> {
-        let configuration = Config::infer().await.map_err(|err| {
-            KubernetesInfrastructureError::UnexpectedError {
-                err: anyhow::Error::new(err)
-                    .context("Failed to read Kube configuration from cluster env"),
-            }
-        })?;
+        let configuration = match self.config.runtime_config() {
+            Runtime::Kubernetes(k8s_config) => kube_config(k8s_config.cluster()).await?,
+            Runtime::Docker(_) => kube_config(&KubernetesClusterConfig::default()).await?,
+        };
 
         Client::try_from(configuration).map_err(|err| {
             KubernetesInfrastructureError::UnexpectedError {
@@ -181,12 +225,19 @@ impl KubernetesInfrastructure {
                 .list(&list_param)
                 .await
         };
+        let client_clone = client.clone();
         let pods = async {
-            Api::<V1Pod>::namespaced(client, &namespace)
+            Api::<V1Pod>::namespaced(client_clone, &namespace)
                 .list(&list_param)
                 .await
         };
-        let (deployments, mut pods) = futures::try_join!(deployments, pods)?;
+        let k8s_services = async {
+            Api::<V1Service>::namespaced(client, &namespace)
+                .list(&list_param)
+                .await
+        };
+        let (deployments, mut pods, k8s_services) =
+            futures::try_join!(deployments, pods, k8s_services)?;
 
         let mut services = Vec::with_capacity(deployments.items.len());
         for deployment in deployments.into_iter() {
@@ -213,7 +264,7 @@ impl KubernetesInfrastructure {
                 }
             };
 
-            let service = match Service::try_from((deployment, pod)) {
+            let mut service = match Service::try_from((deployment, pod)) {
                 Ok(service) => service,
                 Err(e) => {
                     debug!("Deployment does not provide required data: {:?}", e);
@@ -221,12 +272,117 @@ impl KubernetesInfrastructure {
                 }
             };
 
+            let node_port = k8s_services
+                .iter()
+                .find(|k8s_service| {
+                    k8s_service.metadata.name.as_ref() == Some(service.service_name())
+                })
+                .and_then(|k8s_service| k8s_service.spec.as_ref())
+                .filter(|spec| spec.type_.as_deref() == Some("NodePort"))
+                .and_then(|spec| spec.ports.as_ref()?.first()?.node_port)
+                .and_then(|node_port| u16::try_from(node_port).ok());
+            service.config.set_exposed_host_port(node_port);
+
             services.push(service);
         }
 
         Ok(services.into())
     }
 
+    /// Parses the objects of the rendered manifests of Kubernetes manifest companions and moves
+    /// them into the namespace of the app, labeled with the app name.
+    fn manifest_objects(
+        app_name: &AppName,
+        manifests: &[String],
+    ) -> Result<Vec<(GroupVersionKind, DynamicObject)>> {
+        let mut objects = Vec::new();
+        for manifest in manifests {
+            for doc in serde_yaml::Deserializer::from_str(manifest) {
+                let mut object = <DynamicObject as serde::Deserialize>::deserialize(doc)?;
+                let Some(types) = object.types.as_ref() else {
+                    anyhow::bail!(
+                        "The manifest object {:?} does not provide apiVersion and kind",
+                        object.metadata.name
+                    );
+                };
+                let gvk = GroupVersionKind::try_from(types)?;
+
+                object.metadata.namespace = Some(app_name.to_rfc1123_namespace_id());
+                object
+                    .labels_mut()
+                    .insert(APP_NAME_LABEL.to_string(), app_name.to_string());
+                objects.push((gvk, object));
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Applies the objects of the rendered manifests of Kubernetes manifest companions to the
+    /// namespace of the app. There is no need to track these objects for the teardown because
+    /// they will be deleted with the namespace. Cluster-scoped objects will be rejected because
+    /// they would outlive the namespace and might affect other apps.
+    async fn apply_manifests(
+        client: Client,
+        app_name: &AppName,
+        manifests: &[String],
+    ) -> Result<()> {
+        let objects = Self::manifest_objects(app_name, manifests)?;
+
+        let mut resolved_objects = Vec::with_capacity(objects.len());
+        for (gvk, object) in objects {
+            let (api_resource, capabilities) = pinned_kind(&client, &gvk).await?;
+            if capabilities.scope == Scope::Cluster {
+                return Err(KubernetesInfrastructureError::ClusterScopedManifestObject {
+                    kind: gvk.kind,
+                    name: object.name_any(),
+                }
+                .into());
+            }
+            resolved_objects.push((api_resource, object));
+        }
+
+        for (api_resource, object) in resolved_objects {
+            let name = object.name_any();
+            debug!(
+                "Applying {name} ({}, {}) of manifest companion to {app_name}.",
+                api_resource.api_version, api_resource.kind
+            );
+
+            Api::<DynamicObject>::namespaced_with(
+                client.clone(),
+                &app_name.to_rfc1123_namespace_id(),
+                &api_resource,
+            )
+            .patch(
+                &name,
+                &PatchParams::apply("prevant").force(),
+                &Patch::Apply(&object),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether any node of the cluster provides GPUs. If PREvant is not allowed to list the
+    /// nodes, the check will be skipped and the scheduler decides.
+    async fn supports_gpus(client: Client) -> Result<bool, KubernetesInfrastructureError> {
+        match Api::<Node>::all(client).list(&ListParams::default()).await {
+            Ok(nodes) => Ok(nodes.items.iter().any(|node| {
+                node.status
+                    .as_ref()
+                    .and_then(|status| status.allocatable.as_ref())
+                    .and_then(|allocatable| allocatable.get(GPU_RESOURCE))
+                    .is_some_and(|gpus| gpus.0 != "0")
+            })),
+            Err(KubeError::Api(ErrorResponse { code: 403, .. })) => {
+                debug!("Not allowed to list the nodes, cannot verify that GPUs are available.");
+                Ok(true)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     async fn create_namespace_if_necessary(
         &self,
         app_name: &AppName,
@@ -259,28 +415,49 @@ impl KubernetesInfrastructure {
         }
     }
 
-    fn image_pull_secret<'a, I>(&self, app_name: &AppName, images: I) -> Option<V1Secret>
+    async fn image_pull_secret<'a, I>(
+        &self,
+        app_name: &AppName,
+        images: I,
+    ) -> Result<Option<V1Secret>, KubernetesInfrastructureError>
     where
         I: Iterator<Item = &'a Image>,
     {
-        let registries_and_credentials: BTreeMap<String, (&str, &SecUtf8)> = images
-            .filter_map(|image| {
-                image.registry().and_then(|registry| {
-                    self.config
-                        .registry_credentials(&registry)
-                        .map(|(username, password)| (registry, (username, password)))
-                })
-            })
-            .collect();
+        let mut credentials = BTreeMap::new();
+        for registry in images.filter_map(|image| image.registry()) {
+            if credentials.contains_key(&registry) {
+                continue;
+            }
+            let registry_credentials =
+                self.config
+                    .registry_credentials(&registry)
+                    .await
+                    .map_err(|err| {
+                        KubernetesInfrastructureError::RegistryCredentialsUnavailable {
+                            registry: registry.clone(),
+                            err,
+                        }
+                    })?;
+            if let Some(registry_credentials) = registry_credentials {
+                credentials.insert(registry, registry_credentials);
+            }
+        }
 
-        if registries_and_credentials.is_empty() {
-            return None;
+        if credentials.is_empty() {
+            return Ok(None);
         }
 
-        Some(image_pull_secret_payload(
+        let registries_and_credentials: BTreeMap<String, (&str, &SecUtf8)> = credentials
+            .iter()
+            .map(|(registry, (username, password))| {
+                (registry.clone(), (username.as_str(), password))
+            })
+            .collect();
+
+        Ok(Some(image_pull_secret_payload(
             app_name,
             registries_and_credentials,
-        ))
+        )))
     }
 
     async fn create_payloads(
@@ -477,10 +654,42 @@ impl Infrastructure for KubernetesInfrastructure {
         container_config: &ContainerConfig,
     ) -> Result<Services> {
         let app_name = deployment_unit.app_name();
-        self.create_namespace_if_necessary(app_name).await?;
 
         let client = self.client().await?;
 
+        let gpu_service_names = deployment_unit
+            .services()
+            .iter()
+            .filter(|service| service.gpus().is_some())
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        if !gpu_service_names.is_empty() && !Self::supports_gpus(client.clone()).await? {
+            return Err(KubernetesInfrastructureError::GpusNotSupported {
+                service_names: gpu_service_names,
+            }
+            .into());
+        }
+
+        let unsupported_restart_policy_service_names = deployment_unit
+            .services()
+            .iter()
+            .filter(|service| {
+                !matches!(
+                    service.restart_policy(),
+                    RestartPolicy::Always | RestartPolicy::UnlessStopped
+                )
+            })
+            .map(|service| service.service_name().clone())
+            .collect::<Vec<_>>();
+        if !unsupported_restart_policy_service_names.is_empty() {
+            return Err(KubernetesInfrastructureError::UnsupportedRestartPolicy {
+                service_names: unsupported_restart_policy_service_names,
+            }
+            .into());
+        }
+
+        self.create_namespace_if_necessary(app_name).await?;
+
         let bootstrapping_containers = self.config.companion_bootstrapping_containers(
             app_name,
             &deployment_unit.app_base_route().to_url(),
@@ -490,10 +699,22 @@ impl Infrastructure for KubernetesInfrastructure {
             deployment_unit.user_defined_parameters(),
         )?;
 
-        let bootstrap_image_pull_secret = self.image_pull_secret(
+        let manifests = self.config.companion_kubernetes_manifests(
             app_name,
-            bootstrapping_containers.iter().map(|bc| bc.image()),
-        );
+            &deployment_unit.app_base_route().to_url(),
+            Some(serde_json::json!({
+                "namespace": app_name.to_rfc1123_namespace_id()
+            })),
+            deployment_unit.user_defined_parameters(),
+        )?;
+        Self::apply_manifests(client.clone(), app_name, &manifests).await?;
+
+        let bootstrap_image_pull_secret = self
+            .image_pull_secret(
+                app_name,
+                bootstrapping_containers.iter().map(|bc| bc.image()),
+            )
+            .await?;
         let mut k8s_deployment_unit = K8sDeploymentUnit::bootstrap(
             deployment_unit,
             client.clone(),
@@ -526,10 +747,16 @@ impl Infrastructure for KubernetesInfrastructure {
                 .await?;
 
             k8s_deployment_unit.merge(secret, service, deployment, ingress_route, middlewares);
+
+            if let Some(basic_auth_secret) = basic_auth_secret_payload(app_name, deployable_service)
+            {
+                k8s_deployment_unit.apply_basic_auth_secret(basic_auth_secret);
+            }
         }
 
-        if let Some(image_pull_secret) =
-            self.image_pull_secret(app_name, k8s_deployment_unit.images().iter())
+        if let Some(image_pull_secret) = self
+            .image_pull_secret(app_name, k8s_deployment_unit.images().iter())
+            .await?
         {
             k8s_deployment_unit.apply_image_pull_secret(image_pull_secret);
         }
@@ -614,6 +841,48 @@ impl Infrastructure for KubernetesInfrastructure {
         }.boxed()
     }
 
+    async fn stop_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+    ) -> Result<Option<Service>> {
+        let Some((deployment, pod)) = self.get_deployment_and_pod(app_name, service_name).await?
+        else {
+            return Ok(None);
+        };
+        let service = Service::try_from((deployment.clone(), pod))?;
+
+        let client = self.client().await?;
+        let namespace = app_name.to_rfc1123_namespace_id();
+        let delete_params = DeleteParams::default();
+
+        Api::<V1Deployment>::namespaced(client.clone(), &namespace)
+            .delete(&deployment.metadata.name.unwrap(), &delete_params)
+            .await?;
+        // The service and the ingress route do not exist if the deployment of the service failed
+        // halfway.
+        for result in [
+            Api::<V1Service>::namespaced(client.clone(), &namespace)
+                .delete(service_name, &delete_params)
+                .await
+                .map(|_| ()),
+            Api::<IngressRoute>::namespaced(client, &namespace)
+                .delete(
+                    &format!("{namespace}-{service_name}-ingress-route"),
+                    &delete_params,
+                )
+                .await
+                .map(|_| ()),
+        ] {
+            match result {
+                Ok(()) | Err(KubeError::Api(ErrorResponse { code: 404, .. })) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(Some(service))
+    }
+
     async fn change_status(
         &self,
         app_name: &AppName,
@@ -631,26 +900,230 @@ impl Infrastructure for KubernetesInfrastructure {
             return Ok(None);
         }
 
-        let Some(spec) = deployment.spec.as_mut() else {
+        // The service keeps running if it already runs with the replicas it has been scaled to.
+        if change_replicas_for_status(&mut deployment, &status) {
+            Api::<V1Deployment>::namespaced(
+                self.client().await?,
+                &app_name.to_rfc1123_namespace_id(),
+            )
+            .patch(
+                &deployment.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(serde_json::json!({
+                    "metadata": {
+                        "annotations": {
+                            PAUSED_REPLICAS_ANNOTATION: deployment
+                                .annotations()
+                                .get(PAUSED_REPLICAS_ANNOTATION)
+                        }
+                    },
+                    "spec": {
+                        "replicas": deployment.spec.as_ref().and_then(|spec| spec.replicas)
+                    }
+                })),
+            )
+            .await?;
+        }
+
+        Ok(Some(service))
+    }
+
+    async fn scale_service(
+        &self,
+        app_name: &AppName,
+        service_name: &str,
+        replicas: NonZeroU32,
+    ) -> Result<Option<Service>> {
+        let Some((deployment, pod)) = self.get_deployment_and_pod(app_name, service_name).await?
+        else {
             return Ok(None);
         };
 
-        spec.replicas = Some(match status {
-            ServiceStatus::Running => 1,
-            ServiceStatus::Paused => 0,
-        });
+        let service = Service::try_from((deployment.clone(), pod))?;
 
         Api::<V1Deployment>::namespaced(self.client().await?, &app_name.to_rfc1123_namespace_id())
             .patch(
-                &deployment.metadata.name.clone().unwrap(),
+                &deployment.metadata.name.unwrap(),
                 &PatchParams::default(),
-                &Patch::Merge(deployment),
+                &Patch::Merge(serde_json::json!({
+                    "spec": { "replicas": replicas.get() }
+                })),
             )
             .await?;
 
         Ok(Some(service))
     }
 
+    async fn update_images(
+        &self,
+        _status_id: &str,
+        app_name: &AppName,
+        image_digests: &HashMap<String, String>,
+    ) -> Result<Services> {
+        let deployments = Api::<V1Deployment>::namespaced(
+            self.client().await?,
+            &app_name.to_rfc1123_namespace_id(),
+        );
+
+        for deployment in deployments.list(&Default::default()).await? {
+            let Some(digest) = deployment
+                .metadata
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(SERVICE_NAME_LABEL))
+                .and_then(|service_name| image_digests.get(service_name))
+            else {
+                continue;
+            };
+
+            let running_digest = deployment
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.template.metadata.as_ref())
+                .and_then(|metadata| metadata.annotations.as_ref())
+                .and_then(|annotations| annotations.get(IMAGE_HASH_ANNOTATION));
+            if running_digest == Some(digest) {
+                continue;
+            }
+
+            // The containers pull their image always, so that changing the annotation of the pod
+            // template rolls out pods with the new image while the rest of the deployment, e.g.
+            // its secrets, stays as it is.
+            let name = deployment.name_any();
+            info!("Rolling out {name} of {app_name} with image {digest}");
+            deployments
+                .patch(
+                    &name,
+                    &PatchParams::default(),
+                    &Patch::Merge(serde_json::json!({
+                        "spec": {
+                            "template": {
+                                "metadata": {
+                                    "annotations": { IMAGE_HASH_ANNOTATION: digest }
+                                }
+                            }
+                        }
+                    })),
+                )
+                .await?;
+        }
+
+        Ok(self.get_services_of_app(app_name).await?)
+    }
+
+    async fn fetch_live_environments(
+        &self,
+        app_name: &AppName,
+    ) -> Result<HashMap<String, Environment>> {
+        let deployments = Api::<V1Deployment>::namespaced(
+            self.client().await?,
+            &app_name.to_rfc1123_namespace_id(),
+        )
+        .list(&Default::default())
+        .await?;
+
+        Ok(deployments.iter().filter_map(live_environment).collect())
+    }
+
+    async fn fetch_resource_usage(
+        &self,
+        app_name: &AppName,
+    ) -> Result<Option<BTreeMap<String, ResourceUsage>>> {
+        let services = self.get_services_of_app(app_name).await?;
+        if services.is_empty() {
+            return Ok(None);
+        }
+
+        let client = self.client().await?;
+        let namespace = app_name.to_rfc1123_namespace_id();
+
+        let pods = Api::<V1Pod>::namespaced(client.clone(), &namespace)
+            .list(&ListParams::default().labels(SERVICE_NAME_LABEL))
+            .await?;
+
+        // The pod metrics are provided by the metrics-server that must be installed in the
+        // cluster.
+        let pod_metrics_resource = ApiResource::from_gvk_with_plural(
+            &GroupVersionKind::gvk("metrics.k8s.io", "v1beta1", "PodMetrics"),
+            "pods",
+        );
+        let pod_metrics =
+            Api::<DynamicObject>::namespaced_with(client, &namespace, &pod_metrics_resource)
+                .list(&ListParams::default())
+                .await?;
+
+        let mut usages = services
+            .iter()
+            .map(|service| (service.service_name().clone(), ResourceUsage::default()))
+            .collect::<BTreeMap<_, _>>();
+
+        for pod in pods {
+            let Some(service_name) = pod
+                .metadata
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(SERVICE_NAME_LABEL))
+            else {
+                continue;
+            };
+
+            let Some(metrics) = pod_metrics
+                .items
+                .iter()
+                .find(|metrics| metrics.metadata.name == pod.metadata.name)
+            else {
+                continue;
+            };
+
+            let usage = usages.entry(service_name.clone()).or_default();
+            *usage = *usage + pod_resource_usage(metrics);
+        }
+
+        Ok(Some(usages))
+    }
+
+    async fn fetch_pinned_app_names(&self) -> Result<HashSet<AppName>> {
+        let client = self.client().await?;
+        Ok(Api::<V1Namespace>::all(client)
+            .list(&ListParams {
+                label_selector: Some(APP_NAME_LABEL.to_string()),
+                ..Default::default()
+            })
+            .await?
+            .iter()
+            .filter(|ns| {
+                ns.metadata
+                    .annotations
+                    .as_ref()
+                    .and_then(|annotations| annotations.get(PINNED_LABEL))
+                    .map(|pinned| pinned.as_str())
+                    == Some("true")
+            })
+            .filter_map(|ns| {
+                AppName::from_str(ns.metadata.labels.as_ref()?.get(APP_NAME_LABEL)?).ok()
+            })
+            .collect::<HashSet<_>>())
+    }
+
+    async fn set_pinned(&self, app_name: &AppName, pinned: bool) -> Result<()> {
+        // A null value removes the annotation with a JSON merge patch.
+        let pinned = pinned.then_some("true");
+        Api::<V1Namespace>::all(self.client().await?)
+            .patch(
+                &app_name.to_rfc1123_namespace_id(),
+                &PatchParams::default(),
+                &Patch::Merge(serde_json::json!({
+                    "metadata": {
+                        "annotations": {
+                            PINNED_LABEL: pinned
+                        }
+                    }
+                })),
+            )
+            .await?;
+        Ok(())
+    }
+
     async fn http_forwarder(&self) -> Result<Box<dyn HttpForwarder>> {
         let client = self.client().await?;
         Ok(Box::new(K8sHttpForwarder { client }))
@@ -841,6 +1314,24 @@ impl TryFrom<(V1Deployment, Option<V1Pod>)> for Service {
 
     fn try_from(deployment_and_pod: (V1Deployment, Option<V1Pod>)) -> Result<Self, Self::Error> {
         let service_config = ServiceConfig::try_from(&deployment_and_pod.0)?;
+        let waiting_for_readiness =
+            is_waiting_for_readiness(&deployment_and_pod.0, &deployment_and_pod.1);
+
+        let replicas = deployment_and_pod
+            .0
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.replicas)
+            .map(|desired| Replicas {
+                desired: u32::try_from(desired).unwrap_or_default(),
+                current: deployment_and_pod
+                    .0
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.ready_replicas)
+                    .and_then(|ready| u32::try_from(ready).ok())
+                    .unwrap_or_default(),
+            });
 
         let name = deployment_and_pod
             .0
@@ -855,6 +1346,7 @@ impl TryFrom<(V1Deployment, Option<V1Pod>)> for Service {
             .map(|spec| match spec.replicas {
                 None => ServiceStatus::Paused,
                 Some(replicas) if replicas <= 0 => ServiceStatus::Paused,
+                _ if waiting_for_readiness => ServiceStatus::Starting,
                 _ => ServiceStatus::Running,
             })
             .unwrap_or(ServiceStatus::Paused);
@@ -869,11 +1361,35 @@ impl TryFrom<(V1Deployment, Option<V1Pod>)> for Service {
         Ok(Service {
             id: name,
             config: service_config,
-            state: State { status, started_at },
+            state: State {
+                status,
+                started_at,
+                replicas,
+            },
         })
     }
 }
 
+/// Checks if the container of the deployment declares a readiness probe (see
+/// [`Healthcheck`](crate::models::Healthcheck)) that has not passed yet for the given pod.
+fn is_waiting_for_readiness(deployment: &V1Deployment, pod: &Option<V1Pod>) -> bool {
+    let has_readiness_probe = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.template.spec.as_ref())
+        .and_then(|pod_spec| pod_spec.containers.first())
+        .is_some_and(|container| container.readiness_probe.is_some());
+    if !has_readiness_probe {
+        return false;
+    }
+
+    pod.as_ref()
+        .and_then(|pod| pod.status.as_ref())
+        .and_then(|status| status.container_statuses.as_ref())
+        .and_then(|container_statuses| container_statuses.first())
+        .is_none_or(|container_status| !container_status.ready)
+}
+
 impl TryFrom<&V1Deployment> for ServiceConfig {
     type Error = KubernetesInfrastructureError;
 
@@ -922,6 +1438,26 @@ impl TryFrom<&V1Deployment> for ServiceConfig {
                 config.set_container_type(lb.parse::<ContainerType>()?);
             }
 
+            config.set_metadata(metadata_from_labels(annotations));
+            config.set_deployed_config_hash(annotations.get(CONFIG_HASH_LABEL).cloned());
+            config.set_deployed_at(
+                annotations
+                    .get(DEPLOYED_AT_LABEL)
+                    .and_then(|deployed_at| DateTime::parse_from_rfc3339(deployed_at).ok())
+                    .map(|deployed_at| deployed_at.with_timezone(&Utc)),
+            );
+            config.set_created_at(
+                annotations
+                    .get(CREATED_AT_LABEL)
+                    .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+                    .map(|created_at| created_at.with_timezone(&Utc)),
+            );
+            config.set_user_defined_parameters(
+                annotations
+                    .get(USER_DEFINED_PARAMETERS_LABEL)
+                    .and_then(|parameters| serde_json::from_str(parameters).ok()),
+            );
+
             Ok(config)
         } else {
             Err(KubernetesInfrastructureError::MissingDeploymentAnnotations)
@@ -929,6 +1465,97 @@ impl TryFrom<&V1Deployment> for ServiceConfig {
     }
 }
 
+/// Extracts the service name and the environment variables of the deployment's container. Variables
+/// that reference other sources, e.g. secrets, will be ignored.
+fn live_environment(deployment: &V1Deployment) -> Option<(String, Environment)> {
+    let service_name = deployment
+        .metadata
+        .labels
+        .as_ref()?
+        .get(SERVICE_NAME_LABEL)?
+        .clone();
+    let container = deployment
+        .spec
+        .as_ref()?
+        .template
+        .spec
+        .as_ref()?
+        .containers
+        .first()?;
+
+    let env = container
+        .env
+        .iter()
+        .flatten()
+        .filter_map(|variable| {
+            Some(EnvironmentVariable::new(
+                variable.name.clone(),
+                SecUtf8::from(variable.value.as_deref()?),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    Some((service_name, Environment::new(env)))
+}
+
+/// Sums up the usage of all containers of the [pod
+/// metrics](https://github.com/kubernetes/metrics/blob/master/pkg/apis/metrics/v1beta1/types.go).
+fn pod_resource_usage(pod_metrics: &DynamicObject) -> ResourceUsage {
+    pod_metrics.data["containers"]
+        .as_array()
+        .map(|containers| {
+            containers
+                .iter()
+                .map(|container| {
+                    let usage = &container["usage"];
+                    ResourceUsage::new(
+                        usage["cpu"]
+                            .as_str()
+                            .and_then(parse_quantity)
+                            .unwrap_or_default(),
+                        usage["memory"]
+                            .as_str()
+                            .and_then(parse_quantity)
+                            .unwrap_or_default() as u64,
+                    )
+                })
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+/// Parses a [quantity](https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/quantity/),
+/// e.g. `250m` or `128Mi`.
+fn parse_quantity(quantity: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 14] = [
+        ("Ki", 1024.0),
+        ("Mi", 1_048_576.0),
+        ("Gi", 1_073_741_824.0),
+        ("Ti", 1_099_511_627_776.0),
+        ("Pi", 1_125_899_906_842_624.0),
+        ("Ei", 1_152_921_504_606_846_976.0),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+    ];
+
+    let (number, factor) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, factor)| {
+            quantity
+                .strip_suffix(suffix)
+                .map(|number| (number, *factor))
+        })
+        .unwrap_or((quantity, 1.0));
+
+    number.parse::<f64>().ok().map(|number| number * factor)
+}
+
 impl From<KubeError> for KubernetesInfrastructureError {
     fn from(err: KubeError) -> Self {
         KubernetesInfrastructureError::UnexpectedError {
@@ -952,6 +1579,130 @@ impl From<ServiceError> for KubernetesInfrastructureError {
     }
 }
 
+async fn kube_config(
+    cluster: &KubernetesClusterConfig,
+) -> Result<Config, KubernetesInfrastructureError> {
+    let has_tls_settings = cluster.certificate_authority().is_some()
+        || cluster.client_certificate().is_some()
+        || cluster.client_key().is_some();
+
+    let configuration = match (cluster.url(), cluster.kubeconfig(), cluster.context()) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            return Err(KubernetesInfrastructureError::InvalidClusterConfig {
+                reason: "url cannot be combined with kubeconfig or context",
+            })
+        }
+        (None, _, _) if has_tls_settings => {
+            return Err(KubernetesInfrastructureError::InvalidClusterConfig {
+                reason: "certificateAuthority, clientCertificate, and clientKey require url",
+            })
+        }
+        (Some(url), None, None) => Config::from_custom_kubeconfig(
+            kubeconfig_of_cluster(url, cluster),
+            &KubeConfigOptions::default(),
+        )
+        .await
+        .map_err(anyhow::Error::new),
+        (None, Some(kubeconfig), context) => match Kubeconfig::read_from(kubeconfig) {
+            Ok(kubeconfig) => Config::from_custom_kubeconfig(
+                kubeconfig,
+                &KubeConfigOptions {
+                    context: context.cloned(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(anyhow::Error::new),
+            Err(err) => Err(anyhow::Error::new(err)),
+        },
+        (None, None, Some(context)) => Config::from_kubeconfig(&KubeConfigOptions {
+            context: Some(context.clone()),
+            ..Default::default()
+        })
+        .await
+        .map_err(anyhow::Error::new),
+        (None, None, None) => Config::infer().await.map_err(anyhow::Error::new),
+    };
+
+    configuration.map_err(|err| KubernetesInfrastructureError::UnexpectedError {
+        err: err.context("Failed to read Kube configuration"),
+    })
+}
+
+/// Builds a kubeconfig with a single context that points to the explicitly configured API server.
+fn kubeconfig_of_cluster(url: &url::Url, cluster: &KubernetesClusterConfig) -> Kubeconfig {
+    const NAME: &str = "prevant";
+    let path = |path: Option<&std::path::PathBuf>| path.map(|path| path.display().to_string());
+
+    Kubeconfig {
+        clusters: vec![NamedCluster {
+            name: NAME.to_string(),
+            cluster: Some(Cluster {
+                server: Some(url.as_str().trim_end_matches('/').to_string()),
+                certificate_authority: path(cluster.certificate_authority()),
+                ..Default::default()
+            }),
+        }],
+        auth_infos: vec![NamedAuthInfo {
+            name: NAME.to_string(),
+            auth_info: Some(AuthInfo {
+                client_certificate: path(cluster.client_certificate()),
+                client_key: path(cluster.client_key()),
+                ..Default::default()
+            }),
+        }],
+        contexts: vec![NamedContext {
+            name: NAME.to_string(),
+            context: Some(Context {
+                cluster: NAME.to_string(),
+                user: Some(NAME.to_string()),
+                ..Default::default()
+            }),
+        }],
+        current_context: Some(NAME.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Kubernetes cannot suspend the processes of a pod. Thus, freezing a service is approximated by
+/// scaling it down which does not preserve the process state. The replicas of the running service
+/// are kept in an annotation so that resuming the service restores them, e.g. after it has been
+/// scaled up. Returns `false` if the deployment already has replicas matching the status.
+fn change_replicas_for_status(deployment: &mut V1Deployment, status: &ServiceStatus) -> bool {
+    let Some(spec) = deployment.spec.as_mut() else {
+        return false;
+    };
+    let replicas = spec.replicas.unwrap_or(1);
+    let annotations = deployment
+        .metadata
+        .annotations
+        .get_or_insert_with(BTreeMap::new);
+
+    match status {
+        ServiceStatus::Running | ServiceStatus::Starting => {
+            if replicas > 0 {
+                return false;
+            }
+            spec.replicas = Some(
+                annotations
+                    .remove(PAUSED_REPLICAS_ANNOTATION)
+                    .and_then(|replicas| replicas.parse::<i32>().ok())
+                    .filter(|replicas| *replicas > 0)
+                    .unwrap_or(1),
+            );
+        }
+        ServiceStatus::Paused | ServiceStatus::Frozen => {
+            if replicas == 0 {
+                return false;
+            }
+            annotations.insert(PAUSED_REPLICAS_ANNOTATION.to_string(), replicas.to_string());
+            spec.replicas = Some(0);
+        }
+    }
+
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -993,6 +1744,40 @@ mod tests {
         }};
     }
 
+    #[test]
+    fn should_parse_quantities() {
+        assert_eq!(parse_quantity("250m"), Some(0.25));
+        assert_eq!(parse_quantity("2"), Some(2.0));
+        assert_eq!(parse_quantity("500000000n"), Some(0.5));
+        assert_eq!(parse_quantity("128Mi"), Some(134_217_728.0));
+        assert_eq!(parse_quantity("1k"), Some(1000.0));
+        assert_eq!(parse_quantity("abc"), None);
+    }
+
+    #[test]
+    fn should_sum_up_resource_usage_of_pod_containers() {
+        let pod_metrics = serde_json::from_value::<DynamicObject>(serde_json::json!({
+            "apiVersion": "metrics.k8s.io/v1beta1",
+            "kind": "PodMetrics",
+            "metadata": { "name": "nginx-7d8b49557c-5xk2w" },
+            "timestamp": "2024-01-01T00:00:00Z",
+            "window": "15s",
+            "containers": [{
+                "name": "nginx",
+                "usage": { "cpu": "250m", "memory": "1Ki" }
+            }, {
+                "name": "sidecar",
+                "usage": { "cpu": "500m", "memory": "1Ki" }
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            pod_resource_usage(&pod_metrics),
+            ResourceUsage::new(0.75, 2048)
+        );
+    }
+
     #[test]
     fn should_parse_service_from_deployment_spec() {
         let deployment = deployment_object!(
@@ -1008,6 +1793,75 @@ mod tests {
         assert_eq!(service.service_name(), &String::from("nginx"));
     }
 
+    #[test]
+    fn should_parse_starting_service_from_deployment_with_unready_pod() {
+        let mut deployment = deployment_object!(
+            "master-nginx",
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        deployment.spec = Some(
+            serde_json::from_value(serde_json::json!({
+                "replicas": 1,
+                "selector": {},
+                "template": {
+                    "spec": {
+                        "containers": [{
+                            "name": "nginx",
+                            "readinessProbe": { "exec": { "command": ["true"] } }
+                        }]
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+        let pod = serde_json::from_value::<V1Pod>(serde_json::json!({
+            "status": {
+                "containerStatuses": [{
+                    "name": "nginx",
+                    "image": "nginx",
+                    "imageID": "",
+                    "ready": false,
+                    "restartCount": 0
+                }]
+            }
+        }))
+        .unwrap();
+
+        let service = Service::try_from((deployment, Some(pod))).unwrap();
+
+        assert_eq!(service.status(), &ServiceStatus::Starting);
+    }
+
+    #[test]
+    fn should_parse_replicas_from_deployment() {
+        let mut deployment = deployment_object!(
+            "master-nginx",
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        deployment.spec = Some(DeploymentSpec {
+            replicas: Some(3),
+            ..Default::default()
+        });
+        deployment.status =
+            Some(serde_json::from_value(serde_json::json!({ "readyReplicas": 2 })).unwrap());
+
+        let service = Service::try_from((deployment, None)).unwrap();
+
+        assert_eq!(
+            service.state.replicas,
+            Some(Replicas {
+                desired: 3,
+                current: 2
+            })
+        );
+    }
+
     #[test]
     fn should_parse_service_from_deployment_spec_with_replicated_env() {
         let deployment = deployment_object!(
@@ -1110,4 +1964,192 @@ mod tests {
             } if deployment_name == "master-nginx".to_string()
         ));
     }
+
+    #[test]
+    fn should_restore_scaled_replicas_when_resuming_paused_service() {
+        let mut deployment = deployment_object!(
+            "master-nginx",
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        deployment.spec = Some(DeploymentSpec {
+            replicas: Some(3),
+            ..Default::default()
+        });
+
+        assert!(change_replicas_for_status(
+            &mut deployment,
+            &ServiceStatus::Paused
+        ));
+        assert_eq!(deployment.spec.as_ref().unwrap().replicas, Some(0));
+
+        assert!(change_replicas_for_status(
+            &mut deployment,
+            &ServiceStatus::Running
+        ));
+        assert_eq!(deployment.spec.as_ref().unwrap().replicas, Some(3));
+        assert_eq!(
+            deployment.annotations().get(PAUSED_REPLICAS_ANNOTATION),
+            None
+        );
+    }
+
+    #[test]
+    fn should_keep_replicas_of_running_service() {
+        let mut deployment = deployment_object!(
+            "master-nginx",
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        deployment.spec = Some(DeploymentSpec {
+            replicas: Some(3),
+            ..Default::default()
+        });
+
+        assert!(!change_replicas_for_status(
+            &mut deployment,
+            &ServiceStatus::Running
+        ));
+        assert_eq!(deployment.spec.as_ref().unwrap().replicas, Some(3));
+    }
+
+    #[test]
+    fn should_resume_paused_service_without_scaled_replicas_with_one_replica() {
+        let mut deployment = deployment_object!(
+            "master-nginx",
+            Some(String::from("master")),
+            Some(String::from("nginx")),
+            Some(String::from("nginx")),
+            None,
+        );
+        deployment.spec = Some(DeploymentSpec {
+            replicas: Some(0),
+            ..Default::default()
+        });
+
+        assert!(change_replicas_for_status(
+            &mut deployment,
+            &ServiceStatus::Running
+        ));
+        assert_eq!(deployment.spec.as_ref().unwrap().replicas, Some(1));
+    }
+
+    fn cluster_config(cluster_toml: &str) -> KubernetesClusterConfig {
+        let Runtime::Kubernetes(runtime) =
+            toml::from_str::<Runtime>(&format!("type = 'Kubernetes'\n[cluster]\n{cluster_toml}"))
+                .unwrap()
+        else {
+            panic!("Need a K8s config")
+        };
+        runtime.cluster().clone()
+    }
+
+    #[tokio::test]
+    async fn should_create_kube_config_for_cluster_url() {
+        let cluster = cluster_config("url = 'https://k8s.example.com:6443'");
+
+        let config = kube_config(&cluster).await.unwrap();
+
+        assert_eq!(
+            config.cluster_url.to_string(),
+            "https://k8s.example.com:6443/"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reject_cluster_url_with_kubeconfig() {
+        let cluster = cluster_config(
+            r#"
+            url = 'https://k8s.example.com:6443'
+            kubeconfig = '/home/prevant/.kube/config'
+            "#,
+        );
+
+        assert!(matches!(
+            kube_config(&cluster).await,
+            Err(KubernetesInfrastructureError::InvalidClusterConfig { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_reject_client_certificate_without_cluster_url() {
+        let cluster = cluster_config(
+            r#"
+            clientCertificate = '/etc/prevant/client.crt'
+            clientKey = '/etc/prevant/client.key'
+            "#,
+        );
+
+        assert!(matches!(
+            kube_config(&cluster).await,
+            Err(KubernetesInfrastructureError::InvalidClusterConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn should_prepare_manifest_objects_for_namespace_of_app() {
+        let app_name = AppName::from_str("master").unwrap();
+        let manifests = vec![String::from(
+            r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: redis-config
+  namespace: default
+  labels:
+    app: redis
+---
+apiVersion: databases.spotahome.com/v1
+kind: RedisFailover
+metadata:
+  name: redis
+"#,
+        )];
+
+        let objects = KubernetesInfrastructure::manifest_objects(&app_name, &manifests).unwrap();
+
+        assert_eq!(
+            objects
+                .iter()
+                .map(|(gvk, object)| (
+                    gvk.clone(),
+                    object.name_any(),
+                    object.namespace(),
+                    object.labels().clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    GroupVersionKind::gvk("", "v1", "ConfigMap"),
+                    String::from("redis-config"),
+                    Some(String::from("master")),
+                    BTreeMap::from([
+                        (String::from("app"), String::from("redis")),
+                        (String::from(APP_NAME_LABEL), String::from("master")),
+                    ])
+                ),
+                (
+                    GroupVersionKind::gvk("databases.spotahome.com", "v1", "RedisFailover"),
+                    String::from("redis"),
+                    Some(String::from("master")),
+                    BTreeMap::from([(String::from(APP_NAME_LABEL), String::from("master"))])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reject_manifest_objects_without_kind() {
+        let app_name = AppName::from_str("master").unwrap();
+        let manifests = vec![String::from(
+            r#"metadata:
+  name: redis-config
+"#,
+        )];
+
+        assert!(KubernetesInfrastructure::manifest_objects(&app_name, &manifests).is_err());
+    }
 }
@@ -24,21 +24,22 @@
  * =========================LICENSE_END==================================
  */
 use super::super::{
-    APP_NAME_LABEL, CONTAINER_TYPE_LABEL, IMAGE_LABEL, REPLICATED_ENV_LABEL, SERVICE_NAME_LABEL,
-    STORAGE_TYPE_LABEL,
+    metadata_to_labels, APP_NAME_LABEL, CONFIG_HASH_LABEL, CONTAINER_TYPE_LABEL, CREATED_AT_LABEL,
+    DEPLOYED_AT_LABEL, IMAGE_LABEL, REPLICATED_ENV_LABEL, SERVICE_NAME_LABEL, STORAGE_TYPE_LABEL,
+    USER_DEFINED_PARAMETERS_LABEL,
 };
 use crate::config::{Config, ContainerConfig};
 use crate::deployment::deployment_unit::{DeployableService, DeploymentStrategy};
 use crate::infrastructure::{TraefikIngressRoute, TraefikRouterRule};
-use crate::models::{AppName, ServiceConfig};
+use crate::models::{AppName, HttpProbe, RestartPolicy, ServiceConfig};
 use base64::{engine::general_purpose, Engine};
 use bytesize::ByteSize;
 use chrono::Utc;
 use k8s_openapi::api::apps::v1::DeploymentSpec;
 use k8s_openapi::api::core::v1::{
-    Container, ContainerPort, EnvVar, KeyToPath, PersistentVolumeClaim, PersistentVolumeClaimSpec,
-    PersistentVolumeClaimVolumeSource, PodSpec, PodTemplateSpec, ResourceRequirements,
-    SecretVolumeSource, Volume, VolumeMount,
+    Container, ContainerPort, EmptyDirVolumeSource, EnvVar, ExecAction, HTTPGetAction, KeyToPath,
+    PersistentVolumeClaim, PersistentVolumeClaimSpec, PersistentVolumeClaimVolumeSource, PodSpec,
+    PodTemplateSpec, Probe, ResourceRequirements, SecretVolumeSource, Volume, VolumeMount,
 };
 use k8s_openapi::api::networking::v1::Ingress;
 use k8s_openapi::api::{
@@ -47,6 +48,7 @@ use k8s_openapi::api::{
 };
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use k8s_openapi::ByteString;
 use kube::core::ObjectMeta;
 use kube::CustomResource;
@@ -312,7 +314,7 @@ pub fn convert_k8s_ingress_to_traefik_ingress(
 /// Namespaces](https://kubernetes.io/docs/tasks/administer-cluster/namespaces/)
 pub fn namespace_payload(app_name: &AppName, config: &Config) -> V1Namespace {
     let annotations = match config.runtime_config() {
-        crate::config::Runtime::Docker => None,
+        crate::config::Runtime::Docker(_) => None,
         crate::config::Runtime::Kubernetes(runtime) => {
             let annotations = runtime.annotations().namespace();
 
@@ -345,6 +347,14 @@ impl AppName {
     }
 }
 
+/// The [extended resource](https://kubernetes.io/docs/tasks/manage-gpus/scheduling-gpus/) that
+/// nodes with NVIDIA GPUs advertise.
+pub const GPU_RESOURCE: &str = "nvidia.com/gpu";
+
+/// The annotation of the pod template that holds the image digest of a service deployed with
+/// [`DeploymentStrategy::RedeployOnImageUpdate`]. Changing it rolls out new pods.
+pub const IMAGE_HASH_ANNOTATION: &str = "imageHash";
+
 /// Creates a JSON payload suitable for [Kubernetes'
 /// Deployments](https://kubernetes.io/docs/concepts/workloads/controllers/deployment/)
 pub fn deployment_payload(
@@ -363,7 +373,7 @@ pub fn deployment_payload(
             .collect()
     });
 
-    let annotations = if let Some(replicated_env) = service
+    let mut annotations = if let Some(replicated_env) = service
         .env()
         .and_then(super::super::replicated_environment_variable_to_json)
     {
@@ -374,6 +384,20 @@ pub fn deployment_payload(
     } else {
         BTreeMap::from([(IMAGE_LABEL.to_string(), service.image().to_string())])
     };
+    annotations.insert(CONFIG_HASH_LABEL.to_string(), service.config_hash());
+    if let Some(deployed_at) = service.deployed_at() {
+        annotations.insert(DEPLOYED_AT_LABEL.to_string(), deployed_at.to_rfc3339());
+    }
+    if let Some(created_at) = service.created_at() {
+        annotations.insert(CREATED_AT_LABEL.to_string(), created_at.to_rfc3339());
+    }
+    if let Some(user_defined_parameters) = service.user_defined_parameters() {
+        annotations.insert(
+            USER_DEFINED_PARAMETERS_LABEL.to_string(),
+            user_defined_parameters.to_string(),
+        );
+    }
+    annotations.extend(metadata_to_labels(service.metadata()));
 
     let volume_mounts = service.files().map(|files| {
         let parent_paths = files
@@ -451,15 +475,47 @@ pub fn deployment_payload(
         None => volumes,
     };
 
-    let resources = container_config
-        .memory_limit()
-        .map(|mem_limit| ResourceRequirements {
-            limits: Some(BTreeMap::from([(
-                String::from("memory"),
-                Quantity(format!("{}", mem_limit.as_u64())),
-            )])),
-            ..Default::default()
-        });
+    let (volume_mounts, volumes) = if service.tmpfs().is_empty() {
+        (volume_mounts, volumes)
+    } else {
+        let mut mounts = volume_mounts.unwrap_or_default();
+        let mut vols = volumes.unwrap_or_default();
+        for path in service.tmpfs() {
+            let name = format!("tmpfs-{}", secret_name_from_path!(path));
+            mounts.push(VolumeMount {
+                name: name.clone(),
+                mount_path: path.to_string_lossy().to_string(),
+                ..Default::default()
+            });
+            vols.push(Volume {
+                name,
+                empty_dir: Some(EmptyDirVolumeSource {
+                    medium: Some(String::from("Memory")),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        }
+        (Some(mounts), Some(vols))
+    };
+
+    let mut limits = BTreeMap::new();
+    if let Some(mem_limit) = container_config.memory_limit() {
+        limits.insert(
+            String::from("memory"),
+            Quantity(format!("{}", mem_limit.as_u64())),
+        );
+    }
+    if let Some(cpu_limit) = container_config.cpu_limit_in_millicores() {
+        limits.insert(String::from("cpu"), Quantity(cpu_limit));
+    }
+    if let Some(gpus) = service.gpus() {
+        limits.insert(String::from(GPU_RESOURCE), Quantity(gpus.to_string()));
+    }
+    let resources = (!limits.is_empty()).then(|| ResourceRequirements {
+        limits: Some(limits),
+        ..Default::default()
+    });
 
     let labels = BTreeMap::from([
         (APP_NAME_LABEL.to_string(), app_name.to_string()),
@@ -510,8 +566,41 @@ pub fn deployment_payload(
                             ..Default::default()
                         }]),
                         resources,
+                        working_dir: service
+                            .working_dir()
+                            .map(|working_dir| working_dir.to_string_lossy().to_string()),
+                        readiness_probe: service
+                            .probes()
+                            .and_then(|probes| probes.readiness())
+                            .map(|probe| http_probe(probe, service.port()))
+                            .or_else(|| {
+                                service.healthcheck().map(|healthcheck| Probe {
+                                    exec: Some(ExecAction {
+                                        command: Some(vec![
+                                            String::from("sh"),
+                                            String::from("-c"),
+                                            healthcheck.command().to_string(),
+                                        ]),
+                                    }),
+                                    period_seconds: Some(healthcheck.interval().as_secs() as i32),
+                                    failure_threshold: Some(healthcheck.retries() as i32),
+                                    initial_delay_seconds: Some(
+                                        healthcheck.start_period().as_secs() as i32,
+                                    ),
+                                    ..Default::default()
+                                })
+                            }),
+                        liveness_probe: service
+                            .probes()
+                            .and_then(|probes| probes.liveness())
+                            .map(|probe| http_probe(probe, service.port())),
                         ..Default::default()
                     }],
+                    restart_policy: Some(String::from(match service.restart_policy() {
+                        RestartPolicy::Always | RestartPolicy::UnlessStopped => "Always",
+                        RestartPolicy::OnFailure => "OnFailure",
+                        RestartPolicy::No => "Never",
+                    })),
                     ..Default::default()
                 }),
             },
@@ -521,6 +610,22 @@ pub fn deployment_payload(
     }
 }
 
+/// Creates a probe that sends an HTTP `GET` request to the port of the probe, or to `service_port`
+/// if the probe does not specify a port.
+fn http_probe(probe: &HttpProbe, service_port: u16) -> Probe {
+    Probe {
+        http_get: Some(HTTPGetAction {
+            path: Some(probe.path().to_string()),
+            port: IntOrString::Int(i32::from(probe.port().unwrap_or(service_port))),
+            ..Default::default()
+        }),
+        period_seconds: Some(probe.interval().as_secs() as i32),
+        failure_threshold: Some(probe.retries() as i32),
+        initial_delay_seconds: Some(probe.start_period().as_secs() as i32),
+        ..Default::default()
+    }
+}
+
 /// Creates the value of an [annotations object](https://kubernetes.io/docs/concepts/overview/working-with-objects/annotations/)
 /// so that the underlying pod will be deployed according to its [deployment strategy](`DeploymentStrategy`).
 ///
@@ -530,7 +635,7 @@ pub fn deployment_payload(
 fn deployment_annotations(strategy: &DeploymentStrategy) -> BTreeMap<String, String> {
     match strategy {
         DeploymentStrategy::RedeployOnImageUpdate(image_id) => {
-            BTreeMap::from([(String::from("imageHash"), image_id.clone())])
+            BTreeMap::from([(String::from(IMAGE_HASH_ANNOTATION), image_id.clone())])
         }
         DeploymentStrategy::RedeployNever => BTreeMap::new(),
         DeploymentStrategy::RedeployAlways => {
@@ -571,6 +676,42 @@ pub fn secrets_payload(
     .expect("Cannot convert value to core/v1/Secret")
 }
 
+/// Creates the secret that holds the htpasswd entries of the users that are allowed to access a
+/// service protected by basic authentication. The secret is referenced by the service's basic auth
+/// middleware.
+pub fn basic_auth_secret_payload(
+    app_name: &AppName,
+    service: &DeployableService,
+) -> Option<V1Secret> {
+    let users = service
+        .basic_auth_users()?
+        .iter()
+        .map(|user| user.unsecure())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(V1Secret {
+        metadata: ObjectMeta {
+            name: Some(service.basic_auth_secret_name()),
+            namespace: Some(app_name.to_rfc1123_namespace_id()),
+            labels: Some(BTreeMap::from([
+                (APP_NAME_LABEL.to_string(), app_name.to_string()),
+                (
+                    SERVICE_NAME_LABEL.to_string(),
+                    service.service_name().to_string(),
+                ),
+            ])),
+            ..Default::default()
+        },
+        type_: Some(String::from("Opaque")),
+        data: Some(BTreeMap::from([(
+            String::from("users"),
+            ByteString(users.into_bytes()),
+        )])),
+        ..Default::default()
+    })
+}
+
 pub fn image_pull_secret_payload(
     app_name: &AppName,
     registries_and_credentials: BTreeMap<String, (&str, &SecUtf8)>,
@@ -636,6 +777,7 @@ pub fn service_payload(app_name: &AppName, service_config: &ServiceConfig) -> V1
         CONTAINER_TYPE_LABEL: service_config.container_type().to_string()
       },
       "spec": {
+        "type": if service_config.expose_port() { "NodePort" } else { "ClusterIP" },
         "ports": [
           {
             "name": service_config.service_name(),
@@ -849,7 +991,7 @@ mod tests {
     use super::*;
     use crate::infrastructure::traefik::TraefikMiddleware;
     use crate::infrastructure::{TraefikIngressRoute, TraefikRouterRule};
-    use crate::models::{AppName, Environment, EnvironmentVariable};
+    use crate::models::{AppName, Environment, EnvironmentVariable, Healthcheck};
     use crate::sc;
     use std::str::FromStr;
 
@@ -1432,6 +1574,311 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_create_deployment_with_tmpfs() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_tmpfs(vec![PathBuf::from("/tmp")]);
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "db",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "containers": [{
+                      "volumeMounts": [{
+                        "mountPath": "/tmp",
+                        "name": "tmpfs-tmp"
+                      }]
+                    }],
+                    "volumes": [{
+                      "name": "tmpfs-tmp",
+                      "emptyDir": {
+                        "medium": "Memory"
+                      }
+                    }]
+                  }
+                }
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_working_dir() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_working_dir(Some(PathBuf::from("/app")));
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "db",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "containers": [{
+                      "workingDir": "/app"
+                    }]
+                  }
+                }
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_cpu_limit() {
+        let config = sc!("db", "mariadb:10.3.17");
+        let container_config = crate::config_from_str!(
+            r#"
+            [containers]
+            cpu_limit = 0.5
+            "#
+        )
+        .container_config();
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "db",
+                ])),
+                Vec::new(),
+            ),
+            &container_config,
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "containers": [{
+                      "resources": {
+                        "limits": {
+                          "cpu": "500m"
+                        }
+                      }
+                    }]
+                  }
+                }
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_readiness_probe() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_healthcheck(Some(Healthcheck::new(
+            String::from("healthcheck.sh --connect"),
+            std::time::Duration::from_secs(10),
+            5,
+            std::time::Duration::from_secs(60),
+        )));
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "db",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "containers": [{
+                      "readinessProbe": {
+                        "exec": {
+                          "command": ["sh", "-c", "healthcheck.sh --connect"]
+                        },
+                        "periodSeconds": 10,
+                        "failureThreshold": 5,
+                        "initialDelaySeconds": 60
+                      }
+                    }]
+                  }
+                }
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_http_probes() {
+        let mut config = sc!("api", "nginx:latest");
+        config.set_healthcheck(Some(Healthcheck::new(
+            String::from("curl -f http://localhost"),
+            std::time::Duration::from_secs(10),
+            5,
+            std::time::Duration::from_secs(60),
+        )));
+        config.set_probes(Some(
+            serde_json::from_value(serde_json::json!({
+                "readiness": { "path": "/health/ready", "startPeriod": "30s" },
+                "liveness": { "path": "/health/live", "port": 8081, "retries": 5 }
+            }))
+            .unwrap(),
+        ));
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "api",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        let container = &payload.spec.unwrap().template.spec.unwrap().containers[0];
+        assert_eq!(
+            container.readiness_probe,
+            Some(Probe {
+                http_get: Some(HTTPGetAction {
+                    path: Some(String::from("/health/ready")),
+                    port: IntOrString::Int(80),
+                    ..Default::default()
+                }),
+                period_seconds: Some(10),
+                failure_threshold: Some(3),
+                initial_delay_seconds: Some(30),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            container.liveness_probe,
+            Some(Probe {
+                http_get: Some(HTTPGetAction {
+                    path: Some(String::from("/health/live")),
+                    port: IntOrString::Int(8081),
+                    ..Default::default()
+                }),
+                period_seconds: Some(10),
+                failure_threshold: Some(5),
+                initial_delay_seconds: Some(0),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_gpus() {
+        let mut config = sc!("model", "pytorch/pytorch:latest");
+        config.set_gpus(Some(1));
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "model",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "containers": [{
+                      "resources": {
+                        "limits": {
+                          "nvidia.com/gpu": "1"
+                        }
+                      }
+                    }]
+                  }
+                }
+              }
+            })
+        );
+    }
+
+    #[test]
+    fn should_create_deployment_with_restart_policy() {
+        let mut config = sc!("db", "mariadb:10.3.17");
+        config.set_restart_policy(RestartPolicy::UnlessStopped);
+
+        let payload = deployment_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::with_rule(TraefikRouterRule::path_prefix_rule(&[
+                    "master", "db",
+                ])),
+                Vec::new(),
+            ),
+            &ContainerConfig::default(),
+            &None,
+        );
+
+        assert_json_diff::assert_json_include!(
+            actual: payload,
+            expected: serde_json::json!({
+              "spec": {
+                "template": {
+                  "spec": {
+                    "restartPolicy": "Always"
+                  }
+                }
+              }
+            })
+        );
+    }
+
     #[test]
     fn should_create_deployment_for_config_containing_file_data() {
         let mut config = sc!("db", "mariadb:10.3.17");
@@ -1630,6 +2077,70 @@ mod tests {
         )
     }
 
+    #[test]
+    fn create_basic_auth_secret() {
+        let mut config = sc!("adminer", "adminer:4.8.1");
+        config.set_basic_auth_users(vec![
+            SecUtf8::from("test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/"),
+            SecUtf8::from("test2:$apr1$d9hr9HBB$4HxwgUir3HP4EsggP/QNo0"),
+        ]);
+
+        let payload = basic_auth_secret_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                config,
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::empty(),
+                Vec::new(),
+            ),
+        );
+
+        assert_eq!(
+            payload,
+            Some(V1Secret {
+                metadata: ObjectMeta {
+                    name: Some(String::from("adminer-basic-auth")),
+                    namespace: Some(String::from("master")),
+                    labels: Some(BTreeMap::from([
+                        (
+                            String::from("com.aixigo.preview.servant.app-name"),
+                            String::from("master")
+                        ),
+                        (
+                            String::from("com.aixigo.preview.servant.service-name"),
+                            String::from("adminer")
+                        )
+                    ])),
+                    ..Default::default()
+                },
+                data: Some(BTreeMap::from([(
+                    String::from("users"),
+                    ByteString(
+                        b"test:$apr1$H6uskkkW$IgXLP6ewTrSuBkTrqE8wj/\ntest2:$apr1$d9hr9HBB$4HxwgUir3HP4EsggP/QNo0"
+                            .to_vec()
+                    )
+                )])),
+                type_: Some(String::from("Opaque")),
+                ..Default::default()
+            })
+        )
+    }
+
+    #[test]
+    fn should_not_create_basic_auth_secret_for_unprotected_service() {
+        let payload = basic_auth_secret_payload(
+            &AppName::master(),
+            &DeployableService::new(
+                sc!("adminer", "adminer:4.8.1"),
+                DeploymentStrategy::RedeployAlways,
+                TraefikIngressRoute::empty(),
+                Vec::new(),
+            ),
+        );
+
+        assert_eq!(payload, None);
+    }
+
     #[test]
     fn convert_k8s_ingress_to_traefik_ingress() {
         let (route, middlewares) = super::convert_k8s_ingress_to_traefik_ingress(
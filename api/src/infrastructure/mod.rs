@@ -25,14 +25,18 @@
  */
 
 use crate::models::Environment;
+pub use composite::CompositeInfrastructure;
+pub use docker::is_timeout;
 pub use docker::DockerInfrastructure as Docker;
 #[cfg(test)]
 pub use dummy_infrastructure::DummyInfrastructure as Dummy;
-pub use infrastructure::{HttpForwarder, Infrastructure};
+pub use infrastructure::{HttpForwarder, Infrastructure, ScalingNotSupported};
 pub use kubernetes::KubernetesInfrastructure as Kubernetes;
 use serde_json::{map::Map, Value};
+use std::collections::BTreeMap;
 pub use traefik::{TraefikIngressRoute, TraefikMiddleware, TraefikRouterRule};
 
+mod composite;
 mod docker;
 #[cfg(test)]
 mod dummy_infrastructure;
@@ -47,6 +51,37 @@ static REPLICATED_ENV_LABEL: &str = "com.aixigo.preview.servant.replicated-env";
 static IMAGE_LABEL: &str = "com.aixigo.preview.servant.image";
 static STATUS_ID: &str = "com.aixigo.preview.servant.status-id";
 static STORAGE_TYPE_LABEL: &str = "com.aixigo.preview.servant.storage-type";
+static CONFIG_HASH_LABEL: &str = "com.aixigo.preview.servant.config-hash";
+static DEPLOYED_AT_LABEL: &str = "com.aixigo.preview.servant.deployed-at";
+static CREATED_AT_LABEL: &str = "com.aixigo.preview.servant.created-at";
+static PINNED_LABEL: &str = "com.aixigo.preview.servant.pinned";
+static USER_DEFINED_PARAMETERS_LABEL: &str = "com.aixigo.preview.servant.user-defined-parameters";
+/// Prefixes the metadata of a deployment request so that it cannot clash with the labels above.
+static METADATA_LABEL_PREFIX: &str = "com.aixigo.preview.metadata/";
+
+/// Adds the metadata of the service as labels (or annotations) that are prefixed with
+/// [`METADATA_LABEL_PREFIX`].
+fn metadata_to_labels(
+    metadata: &BTreeMap<String, String>,
+) -> impl Iterator<Item = (String, String)> + '_ {
+    metadata
+        .iter()
+        .map(|(key, value)| (format!("{METADATA_LABEL_PREFIX}{key}"), value.clone()))
+}
+
+/// Collects the metadata of a deployment request from the labels (or annotations) of a service.
+fn metadata_from_labels<'a, I>(labels: I) -> BTreeMap<String, String>
+where
+    I: IntoIterator<Item = (&'a String, &'a String)>,
+{
+    labels
+        .into_iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix(METADATA_LABEL_PREFIX)
+                .map(|key| (key.to_string(), value.clone()))
+        })
+        .collect()
+}
 
 /// This function converts the environment variables and adds all variables, that
 /// must be replicated, into a JSON object. This function should be used by implementations
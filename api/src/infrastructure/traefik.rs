@@ -128,6 +128,13 @@ impl TraefikIngressRoute {
         }
     }
 
+    /// Adds the middleware to every route of this ingress route.
+    pub fn add_middleware(&mut self, middleware: TraefikMiddleware) {
+        for route in self.routes.iter_mut() {
+            route.middlewares.push(middleware.clone());
+        }
+    }
+
     pub fn merge_with(&mut self, other: Self) {
         self.entry_points.extend(other.entry_points);
 
@@ -156,6 +163,19 @@ impl TraefikIngressRoute {
         };
     }
 
+    /// The path prefix under which the first route is reachable, e.g. `/master/whoami/`.
+    pub fn path_prefix(&self) -> Option<&str> {
+        self.routes
+            .first()?
+            .rule
+            .matches
+            .iter()
+            .find_map(|m| match m {
+                Matcher::PathPrefix { paths } => paths.first().map(String::as_str),
+                _ => None,
+            })
+    }
+
     pub fn to_url(&self) -> Option<Url> {
         let mut domain = None;
         let mut path = None;
@@ -917,6 +937,13 @@ mod test {
         );
     }
 
+    #[test]
+    fn path_prefix_of_defaults() {
+        let route = TraefikIngressRoute::with_defaults(&AppName::master(), "whoami");
+
+        assert_eq!(route.path_prefix(), Some("/master/whoami/"));
+    }
+
     mod to_url {
         use super::*;
 
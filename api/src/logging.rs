@@ -0,0 +1,76 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+//! Configures the log output of PREvant, either as plain text or as JSON lines so that log
+//! aggregators, such as ELK or Loki, can process the logs without parsing them.
+
+use crate::config::{Config, LogFormat};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// Installs the global logger with the configured format. The log level can be set through the
+/// environment variable `RUST_LOG` and defaults to `info`.
+pub fn init(config: &Config) {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+
+    if config.log_format() == LogFormat::Json {
+        builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+    }
+
+    builder.init();
+}
+
+fn json_line(record: &log::Record) -> Value {
+    json!({
+        "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_record_as_json() {
+        let line = json_line(
+            &log::Record::builder()
+                .args(format_args!("Deployed {}", "master"))
+                .level(log::Level::Info)
+                .target("prevant::apps")
+                .build(),
+        );
+
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "prevant::apps");
+        assert_eq!(line["message"], "Deployed master");
+        assert!(line["timestamp"].is_string());
+    }
+}
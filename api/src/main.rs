@@ -34,10 +34,11 @@ extern crate serde_derive;
 use crate::apps::host_meta_crawling;
 use crate::apps::Apps;
 use crate::config::{Config, Runtime};
-use crate::infrastructure::{Docker, Infrastructure, Kubernetes};
+use crate::infrastructure::{CompositeInfrastructure, Docker, Infrastructure, Kubernetes};
 use crate::models::request_info::RequestInfo;
 use clap::Parser;
 use rocket::fs::{FileServer, Options};
+use rocket::serde::json::Json;
 use serde_yaml::{from_reader, to_string, Value};
 use std::fs::File;
 use std::path::Path;
@@ -48,8 +49,10 @@ mod config;
 mod deployment;
 mod http_result;
 mod infrastructure;
+mod logging;
 mod models;
 mod registry;
+mod telemetry;
 mod tickets;
 mod webhooks;
 
@@ -73,30 +76,88 @@ fn openapi(request_info: RequestInfo) -> Option<String> {
     Some(to_string(&v).unwrap())
 }
 
-fn create_infrastructure(config: &Config) -> Box<dyn Infrastructure> {
+/// Points clients to the API and its documentation if the frontend is disabled.
+#[get("/")]
+fn api_index(request_info: RequestInfo) -> Json<serde_json::Value> {
+    let mut api_url = request_info.get_base_url().clone();
+    api_url.set_path("/api");
+    let mut openapi_url = request_info.get_base_url().clone();
+    openapi_url.set_path("/openapi.yaml");
+
+    Json(serde_json::json!({
+        "api": api_url,
+        "openapi": openapi_url,
+    }))
+}
+
+async fn create_infrastructure(config: &Config) -> Result<Box<dyn Infrastructure>, StartUpError> {
+    let default = create_runtime_infrastructure(config).await?;
+    if config.additional_runtimes().is_empty() {
+        return Ok(default);
+    }
+
+    let mut runtimes = std::collections::BTreeMap::new();
+    for (name, runtime) in config.additional_runtimes() {
+        log::info!("Adding runtime {name}");
+        let runtime_config = config.with_runtime(runtime.clone());
+        runtimes.insert(
+            name.clone(),
+            create_runtime_infrastructure(&runtime_config).await?,
+        );
+    }
+
+    Ok(Box::new(CompositeInfrastructure::new(default, runtimes)))
+}
+
+async fn create_runtime_infrastructure(
+    config: &Config,
+) -> Result<Box<dyn Infrastructure>, StartUpError> {
     match config.runtime_config() {
-        Runtime::Docker => {
+        Runtime::Docker(_config) => {
             log::info!("Using Docker backend");
-            Box::new(Docker::new(config.clone()))
+            Ok(Box::new(Docker::new(config.clone())))
         }
         Runtime::Kubernetes(_config) => {
             log::info!("Using Kubernetes backend");
-            Box::new(Kubernetes::new(config.clone()))
+            let kubernetes = Kubernetes::new(config.clone());
+            kubernetes.verify_connection().await.map_err(|err| {
+                StartUpError::CannotConnectToInfrastructure {
+                    err: err.to_string(),
+                }
+            })?;
+            Ok(Box::new(kubernetes))
         }
     }
 }
 
 #[rocket::main]
 async fn main() -> Result<(), StartUpError> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
     let cli = crate::config::CliArgs::parse();
 
-    let config = Config::from_figment(&cli).map_err(|err| StartUpError::InvalidConfiguration {
-        err: err.to_string(),
-    })?;
+    let mut config =
+        Config::from_figment(&cli).map_err(|err| StartUpError::InvalidConfiguration {
+            err: err.to_string(),
+        })?;
+    logging::init(&config);
+
+    config
+        .include_remote_parts()
+        .await
+        .map_err(|err| StartUpError::InvalidConfiguration {
+            err: err.to_string(),
+        })?;
+
+    let tracer_provider =
+        telemetry::init(&config).map_err(|err| StartUpError::CannotInitializeTracing {
+            err: err.to_string(),
+        })?;
 
-    let infrastructure = create_infrastructure(&config);
+    let infrastructure = create_infrastructure(&config).await?;
+    match infrastructure.release_orphaned_operation_locks().await {
+        Ok(0) => {}
+        Ok(released) => log::info!("Released {released} orphaned operation lock(s)"),
+        Err(err) => log::error!("Cannot release orphaned operation locks: {err}"),
+    }
     let apps = Apps::new(config.clone(), infrastructure)
         .map_err(|e| StartUpError::CannotCreateApps { err: e.to_string() })?;
 
@@ -107,25 +168,66 @@ async fn main() -> Result<(), StartUpError> {
 
     let app_updates = apps.app_updates().await;
 
-    let (host_meta_cache, host_meta_crawler) = host_meta_crawling();
+    {
+        let apps = apps.clone();
+        tokio::spawn(async move {
+            if let Err(err) = apps.ensure_default_app().await {
+                log::error!("Cannot deploy default app: {err}");
+            }
+        });
+    }
+
+    if config.app_ttl().is_some() {
+        let apps = apps.clone();
+        let interval = config.app_ttl_check_interval();
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = apps.delete_expired_apps().await {
+                    log::error!("Cannot delete expired apps: {err}");
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    let (host_meta_cache, host_meta_crawler) = host_meta_crawling(config.host_meta_config());
     host_meta_crawler.spawn(apps.clone(), app_updates.clone());
 
-    let _rocket = rocket::build()
+    let rocket = if config.is_frontend_enabled() {
+        rocket::build().mount(
+            "/",
+            FileServer::new(Path::new("frontend"), Options::Index | Options::Missing),
+        )
+    } else {
+        log::info!("The frontend is disabled, serving the API only");
+        rocket::build().mount("/", routes![api_index])
+    };
+
+    let create_app_rate_limiter =
+        crate::apps::CreateAppRateLimiter::new(config.create_app_rate_limit());
+
+    let _rocket = rocket
+        .manage(create_app_rate_limiter)
         .manage(config)
         .manage(apps)
         .manage(host_meta_cache)
         .manage(app_updates)
-        .mount(
-            "/",
-            FileServer::new(Path::new("frontend"), Options::Index | Options::Missing),
-        )
+        .manage(crate::apps::graphql_schema())
         .mount("/openapi.yaml", routes![openapi])
         .mount("/api/apps", crate::apps::apps_routes())
+        .mount("/api/companions", crate::apps::companions_routes())
+        .mount("/graphql", crate::apps::graphql_routes())
         .mount("/api", routes![tickets::tickets])
         .mount("/api", routes![webhooks::webhooks])
         .launch()
         .await?;
 
+    if let Some(tracer_provider) = tracer_provider {
+        if let Err(err) = tracer_provider.shutdown() {
+            log::error!("Cannot flush pending spans: {err}");
+        }
+    }
+
     Ok(())
 }
 
@@ -133,10 +235,14 @@ async fn main() -> Result<(), StartUpError> {
 enum StartUpError {
     #[error("Cannot read configuration: {err}")]
     InvalidConfiguration { err: String },
+    #[error("Cannot initialize tracing: {err}")]
+    CannotInitializeTracing { err: String },
     #[error("Cannot start HTTP server: {err}")]
     CannotStartWebServer { err: String },
     #[error("Cannot create apps service: {err}")]
     CannotCreateApps { err: String },
+    #[error("Cannot connect to infrastructure backend: {err}")]
+    CannotConnectToInfrastructure { err: String },
 }
 
 impl std::convert::From<rocket::Error> for StartUpError {
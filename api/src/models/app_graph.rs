@@ -0,0 +1,200 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use super::service::{ContainerType, Services};
+use super::Environment;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The services of an app and their dependencies, e.g. to render the app as a graph.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AppGraph {
+    nodes: Vec<AppGraphNode>,
+    edges: Vec<AppGraphEdge>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct AppGraphNode {
+    name: String,
+    #[serde(rename = "type")]
+    container_type: ContainerType,
+    image: String,
+}
+
+/// A dependency of the service `from` on the service `to`. The dependency is inferred from the
+/// environment variables of `from` whose values reference `to`, e.g. `DB_HOST=db`.
+#[derive(Debug, PartialEq, Serialize)]
+struct AppGraphEdge {
+    from: String,
+    to: String,
+    variables: BTreeSet<String>,
+}
+
+impl AppGraph {
+    /// Builds the graph from the services of an app. The environment of a service is taken from
+    /// `live_environments` if available because the variables that are injected during the
+    /// deployment, e.g. for links, are not part of the service configuration.
+    pub fn new(services: &Services, live_environments: &HashMap<String, Environment>) -> Self {
+        let mut nodes = services
+            .iter()
+            .map(|service| AppGraphNode {
+                name: service.service_name().clone(),
+                container_type: service.config.container_type().clone(),
+                image: service.config.image().to_string(),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+        let references = nodes
+            .iter()
+            .map(|node| {
+                let pattern = format!(r"(^|[^\w-]){}([^\w-]|$)", regex::escape(&node.name));
+                (&node.name, Regex::new(&pattern).unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        let mut edges = BTreeMap::<(String, String), BTreeSet<String>>::new();
+        for service in services.iter() {
+            let Some(env) = live_environments
+                .get(service.service_name())
+                .or(service.config.env())
+            else {
+                continue;
+            };
+
+            for variable in env.iter() {
+                for (name, reference) in references.iter() {
+                    if *name != service.service_name()
+                        && reference.is_match(variable.value().unsecure())
+                    {
+                        edges
+                            .entry((service.service_name().clone(), (*name).clone()))
+                            .or_default()
+                            .insert(variable.key().clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            edges: edges
+                .into_iter()
+                .map(|((from, to), variables)| AppGraphEdge {
+                    from,
+                    to,
+                    variables,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::service::{Service, State};
+    use crate::models::{EnvironmentVariable, ServiceConfig};
+    use crate::sc;
+    use secstr::SecUtf8;
+
+    fn service(config: ServiceConfig) -> Service {
+        Service {
+            id: config.service_name().clone(),
+            state: State {
+                status: crate::models::service::ServiceStatus::Running,
+                started_at: None,
+                replicas: None,
+            },
+            config,
+        }
+    }
+
+    fn env(variables: &[(&str, &str)]) -> Environment {
+        Environment::new(
+            variables
+                .iter()
+                .map(|(key, value)| {
+                    EnvironmentVariable::new(key.to_string(), SecUtf8::from(*value))
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn should_infer_edges_from_environment() {
+        let mut wordpress = sc!("wordpress", "quay.io/example/wordpress:latest");
+        wordpress.set_env(Some(env(&[
+            ("WORDPRESS_DB_HOST", "db:3306"),
+            ("WORDPRESS_DB_NAME", "db"),
+            ("WORDPRESS_URL", "http://wordpress-db.example.com"),
+        ])));
+        let services = Services::from(vec![
+            service(wordpress),
+            service(sc!("db", "quay.io/example/mariadb:latest")),
+        ]);
+
+        let graph = AppGraph::new(&services, &HashMap::new());
+
+        assert_eq!(
+            serde_json::to_value(&graph).unwrap(),
+            serde_json::json!({
+                "nodes": [
+                    { "name": "db", "type": "instance", "image": "quay.io/example/mariadb:latest" },
+                    { "name": "wordpress", "type": "instance", "image": "quay.io/example/wordpress:latest" }
+                ],
+                "edges": [
+                    { "from": "wordpress", "to": "db", "variables": [ "WORDPRESS_DB_HOST", "WORDPRESS_DB_NAME" ] }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn should_prefer_live_environment() {
+        let mut wordpress = sc!("wordpress", "quay.io/example/wordpress:latest");
+        wordpress.set_env(Some(env(&[("LOG_LEVEL", "debug")])));
+        let services = Services::from(vec![
+            service(wordpress),
+            service(sc!("db", "quay.io/example/mariadb:latest")),
+        ]);
+
+        let graph = AppGraph::new(
+            &services,
+            &HashMap::from([(String::from("wordpress"), env(&[("DB_HOST", "db")]))]),
+        );
+
+        assert_eq!(
+            graph.edges,
+            vec![AppGraphEdge {
+                from: String::from("wordpress"),
+                to: String::from("db"),
+                variables: BTreeSet::from([String::from("DB_HOST")]),
+            }]
+        );
+    }
+}
@@ -0,0 +1,692 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+use super::{Environment, EnvironmentVariable, Image, RestartPolicy, ServiceConfig};
+use secstr::SecUtf8;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Keys of a [Compose file](https://compose-spec.io/) that PREvant ignores because it takes care of
+/// them itself, e.g. the containers are named after the app and the service.
+const IGNORED_SERVICE_KEYS: [&str; 2] = ["container_name", "networks"];
+
+/// Top-level keys of a Compose file that PREvant ignores. Networks are ignored like the networks of
+/// the services and the declarations of volumes are irrelevant because the volumes that services
+/// mount are checked per service.
+const IGNORED_KEYS: [&str; 4] = ["version", "name", "networks", "volumes"];
+
+/// Translates the services of a Compose file into the service configurations of a deployment
+/// request. Features of the Compose file that cannot be mapped onto PREvant's service
+/// configurations, e.g. volumes that refer to files of the client, are rejected instead of being
+/// silently dropped. Only volumes of type `tmpfs` can be mapped.
+pub fn parse_compose_file(content: &str) -> Result<Vec<ServiceConfig>, ComposeError> {
+    let compose_file =
+        serde_yaml::from_str::<ComposeFile>(content).map_err(|err| ComposeError::InvalidYaml {
+            err: err.to_string(),
+        })?;
+
+    if let Some(key) = compose_file
+        .other
+        .keys()
+        .find(|key| !is_extension(key) && !IGNORED_KEYS.contains(&key.as_str()))
+    {
+        return Err(ComposeError::UnsupportedKey { key: key.clone() });
+    }
+
+    compose_file
+        .services
+        .iter()
+        .map(|(service_name, service)| {
+            service.to_service_config(service_name, &compose_file.configs)
+        })
+        .collect()
+}
+
+fn is_extension(key: &str) -> bool {
+    key.starts_with("x-")
+}
+
+#[derive(Deserialize)]
+struct ComposeFile {
+    services: BTreeMap<String, ComposeService>,
+    #[serde(default)]
+    configs: BTreeMap<String, ComposeConfig>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Deserialize)]
+struct ComposeService {
+    image: Option<String>,
+    #[serde(default)]
+    environment: Option<ComposeEnvironment>,
+    #[serde(default)]
+    configs: Vec<ServiceConfigReference>,
+    #[serde(default)]
+    depends_on: Option<DependsOn>,
+    #[serde(default)]
+    tmpfs: Option<StringOrList>,
+    #[serde(default)]
+    volumes: Vec<ComposeVolume>,
+    working_dir: Option<PathBuf>,
+    restart: Option<String>,
+    #[serde(default)]
+    ports: Vec<ComposePort>,
+    #[serde(default)]
+    expose: Vec<ComposePort>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeEnvironment {
+    Map(BTreeMap<String, serde_yaml::Value>),
+    List(Vec<String>),
+}
+
+/// A config of the service, either as the name of the config that will be mounted at `/<name>`
+/// or with an explicit target path.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ServiceConfigReference {
+    Short(String),
+    Long {
+        source: String,
+        target: Option<PathBuf>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DependsOn {
+    List(Vec<String>),
+    Map(BTreeMap<String, serde_yaml::Value>),
+}
+
+/// A volume that the service mounts, e.g. `./nginx.conf:/etc/nginx/nginx.conf` or
+/// `{ type: tmpfs, target: /cache }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeVolume {
+    Short(String),
+    Long {
+        #[serde(rename = "type")]
+        volume_type: String,
+        source: Option<String>,
+        target: PathBuf,
+    },
+}
+
+impl std::fmt::Display for ComposeVolume {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComposeVolume::Short(volume) => write!(f, "{volume}"),
+            ComposeVolume::Long {
+                source: Some(source),
+                target,
+                ..
+            } => write!(f, "{source}:{}", target.display()),
+            ComposeVolume::Long { target, .. } => write!(f, "{}", target.display()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+/// A published or exposed port of the service, e.g. `8080`, `"127.0.0.1:8080:80/tcp"`, or
+/// `{ target: 80, published: 8080 }`. Only the port of the container is relevant to PREvant
+/// because the service is reachable through the reverse proxy.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposePort {
+    Number(u16),
+    Short(String),
+    Long { target: u16 },
+}
+
+#[derive(Deserialize)]
+struct ComposeConfig {
+    content: Option<String>,
+}
+
+impl ComposeService {
+    fn to_service_config(
+        &self,
+        service_name: &str,
+        configs: &BTreeMap<String, ComposeConfig>,
+    ) -> Result<ServiceConfig, ComposeError> {
+        let image = self
+            .image
+            .as_ref()
+            .ok_or_else(|| ComposeError::MissingImage {
+                service_name: service_name.to_string(),
+            })?;
+
+        if let Some(key) = self
+            .other
+            .keys()
+            .find(|key| !is_extension(key) && !IGNORED_SERVICE_KEYS.contains(&key.as_str()))
+        {
+            return Err(ComposeError::UnsupportedServiceKey {
+                service_name: service_name.to_string(),
+                key: key.clone(),
+            });
+        }
+
+        let image = Image::from_str(image).map_err(|err| ComposeError::InvalidService {
+            service_name: service_name.to_string(),
+            err: err.to_string(),
+        })?;
+
+        let mut config = ServiceConfig::new(service_name.to_string(), image);
+        if let Some(environment) = &self.environment {
+            config.set_env(Some(environment.to_environment(service_name)?));
+        }
+        if !self.configs.is_empty() {
+            config.set_files(Some(self.files(service_name, configs)?));
+        }
+        if let Some(depends_on) = &self.depends_on {
+            config.set_depends_on(match depends_on {
+                DependsOn::List(service_names) => service_names.clone(),
+                DependsOn::Map(service_names) => service_names.keys().cloned().collect(),
+            });
+        }
+        let mut tmpfs = match &self.tmpfs {
+            Some(StringOrList::String(path)) => vec![PathBuf::from(path)],
+            Some(StringOrList::List(paths)) => paths.iter().map(PathBuf::from).collect(),
+            None => Vec::new(),
+        };
+        for volume in &self.volumes {
+            match volume {
+                ComposeVolume::Long {
+                    volume_type,
+                    target,
+                    ..
+                } if volume_type == "tmpfs" => tmpfs.push(target.clone()),
+                volume => {
+                    return Err(ComposeError::UnsupportedVolume {
+                        service_name: service_name.to_string(),
+                        volume: volume.to_string(),
+                    })
+                }
+            }
+        }
+        if !tmpfs.is_empty() {
+            config.set_tmpfs(tmpfs);
+        }
+        config.set_working_dir(self.working_dir.clone());
+        if let Some(port) = self.ports.iter().chain(self.expose.iter()).next() {
+            config.set_port(port.container_port(service_name)?);
+        }
+        if let Some(restart) = &self.restart {
+            config.set_restart_policy(
+                RestartPolicy::deserialize(serde_yaml::Value::from(restart.as_str())).map_err(
+                    |_| ComposeError::InvalidService {
+                        service_name: service_name.to_string(),
+                        err: format!("unsupported restart policy {restart}"),
+                    },
+                )?,
+            );
+        }
+
+        Ok(config)
+    }
+
+    fn files(
+        &self,
+        service_name: &str,
+        configs: &BTreeMap<String, ComposeConfig>,
+    ) -> Result<BTreeMap<PathBuf, SecUtf8>, ComposeError> {
+        let mut files = BTreeMap::new();
+        for reference in &self.configs {
+            let (source, target) = match reference {
+                ServiceConfigReference::Short(source) => (source, None),
+                ServiceConfigReference::Long { source, target } => (source, target.clone()),
+            };
+
+            let content = configs
+                .get(source)
+                .and_then(|config| config.content.as_ref())
+                .ok_or_else(|| ComposeError::InvalidService {
+                    service_name: service_name.to_string(),
+                    err: format!("config {source} must be defined with inline content"),
+                })?;
+
+            files.insert(
+                target.unwrap_or_else(|| PathBuf::from("/").join(source)),
+                SecUtf8::from(content.as_str()),
+            );
+        }
+        Ok(files)
+    }
+}
+
+impl ComposePort {
+    fn container_port(&self, service_name: &str) -> Result<u16, ComposeError> {
+        match self {
+            ComposePort::Number(port) | ComposePort::Long { target: port } => Ok(*port),
+            ComposePort::Short(port) => {
+                let without_protocol = port.split_once('/').map_or(port.as_str(), |(port, _)| port);
+                let container_port = without_protocol
+                    .rsplit_once(':')
+                    .map_or(without_protocol, |(_, container_port)| container_port);
+
+                container_port
+                    .parse::<u16>()
+                    .map_err(|_| ComposeError::InvalidService {
+                        service_name: service_name.to_string(),
+                        err: format!("unsupported port {port}"),
+                    })
+            }
+        }
+    }
+}
+
+impl ComposeEnvironment {
+    fn to_environment(&self, service_name: &str) -> Result<Environment, ComposeError> {
+        let without_value = |key: &str| ComposeError::InvalidService {
+            service_name: service_name.to_string(),
+            err: format!("environment variable {key} must have a value"),
+        };
+
+        let variables = match self {
+            ComposeEnvironment::Map(variables) => variables
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_yaml::Value::String(value) => value.clone(),
+                        serde_yaml::Value::Number(value) => value.to_string(),
+                        serde_yaml::Value::Bool(value) => value.to_string(),
+                        _ => return Err(without_value(key)),
+                    };
+                    Ok(EnvironmentVariable::new(key.clone(), SecUtf8::from(value)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            ComposeEnvironment::List(variables) => variables
+                .iter()
+                .map(|variable| match variable.split_once('=') {
+                    Some((key, value)) => Ok(EnvironmentVariable::new(
+                        key.to_string(),
+                        SecUtf8::from(value),
+                    )),
+                    None => Err(without_value(variable)),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        Ok(Environment::new(variables))
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ComposeError {
+    #[error("Invalid Compose file: {err}")]
+    InvalidYaml { err: String },
+    #[error("The Compose file uses {key}, which is not supported")]
+    UnsupportedKey { key: String },
+    #[error("Service {service_name} uses {key}, which is not supported")]
+    UnsupportedServiceKey { service_name: String, key: String },
+    #[error("Service {service_name} mounts {volume}, which is not supported; provide files as configs with inline content")]
+    UnsupportedVolume {
+        service_name: String,
+        volume: String,
+    },
+    #[error("Service {service_name} must provide an image because PREvant cannot build images")]
+    MissingImage { service_name: String },
+    #[error("Service {service_name} is invalid: {err}")]
+    InvalidService { service_name: String, err: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_services() {
+        let services = parse_compose_file(
+            r#"
+            version: '3.8'
+            services:
+              db:
+                image: mariadb:10.11
+                environment:
+                  MARIADB_USER: example-user
+                  MARIADB_PORT: 3306
+                restart: unless-stopped
+              blog:
+                image: wordpress
+                environment:
+                  - WORDPRESS_DB_HOST=db
+                depends_on:
+                  - db
+                ports:
+                  - "8080:80"
+            "#,
+        )
+        .unwrap();
+
+        let mut db = crate::sc!("db", "mariadb:10.11");
+        db.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::new(String::from("MARIADB_PORT"), SecUtf8::from("3306")),
+            EnvironmentVariable::new(String::from("MARIADB_USER"), SecUtf8::from("example-user")),
+        ])));
+        db.set_restart_policy(RestartPolicy::UnlessStopped);
+        let mut blog = crate::sc!("blog", "wordpress");
+        blog.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
+            String::from("WORDPRESS_DB_HOST"),
+            SecUtf8::from("db"),
+        )])));
+        blog.set_depends_on(vec![String::from("db")]);
+        blog.set_port(80);
+        assert_eq!(services, vec![blog, db]);
+    }
+
+    #[test]
+    fn should_parse_container_port_of_ports_and_expose() {
+        let services = parse_compose_file(
+            r#"
+            services:
+              api:
+                image: api
+                ports:
+                  - "127.0.0.1:8080:8081/tcp"
+              db:
+                image: postgres
+                expose:
+                  - 5432
+              frontend:
+                image: frontend
+                ports:
+                  - target: 3000
+                    published: 80
+              nginx:
+                image: nginx
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            services
+                .iter()
+                .map(|service| (service.service_name().as_str(), service.port()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("api", 8081),
+                ("db", 5432),
+                ("frontend", 3000),
+                ("nginx", 80)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reject_port_ranges() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              api:
+                image: api
+                ports:
+                  - "8000-8010:8000-8010"
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::InvalidService {
+                service_name: String::from("api"),
+                err: String::from("unsupported port 8000-8010:8000-8010"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_configs_with_content_as_files() {
+        let services = parse_compose_file(
+            r#"
+            services:
+              nginx:
+                image: nginx
+                configs:
+                  - source: nginx-conf
+                    target: /etc/nginx/conf.d/default.conf
+                  - index.html
+            configs:
+              nginx-conf:
+                content: |
+                  server {}
+              index.html:
+                content: Hello
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            services[0].files(),
+            Some(&BTreeMap::from([
+                (
+                    PathBuf::from("/etc/nginx/conf.d/default.conf"),
+                    SecUtf8::from("server {}\n")
+                ),
+                (PathBuf::from("/index.html"), SecUtf8::from("Hello")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_reject_volumes() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              nginx:
+                image: nginx
+                volumes:
+                  - ./nginx.conf:/etc/nginx/nginx.conf
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::UnsupportedVolume {
+                service_name: String::from("nginx"),
+                volume: String::from("./nginx.conf:/etc/nginx/nginx.conf"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_named_volumes() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              db:
+                image: mariadb
+                volumes:
+                  - type: volume
+                    source: db-data
+                    target: /var/lib/mysql
+            volumes:
+              db-data: {}
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::UnsupportedVolume {
+                service_name: String::from("db"),
+                volume: String::from("db-data:/var/lib/mysql"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_tmpfs_volumes() {
+        let services = parse_compose_file(
+            r#"
+            services:
+              nginx:
+                image: nginx
+                tmpfs: /run
+                volumes:
+                  - type: tmpfs
+                    target: /var/cache/nginx
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            services[0].tmpfs(),
+            &[PathBuf::from("/run"), PathBuf::from("/var/cache/nginx")]
+        );
+    }
+
+    #[test]
+    fn should_reject_services_to_be_built() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              api:
+                build: .
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::MissingImage {
+                service_name: String::from("api"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_configs_from_files() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              nginx:
+                image: nginx
+                configs:
+                  - nginx-conf
+            configs:
+              nginx-conf:
+                file: ./nginx.conf
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::InvalidService {
+                service_name: String::from("nginx"),
+                err: String::from("config nginx-conf must be defined with inline content"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_environment_variables_without_value() {
+        let err = parse_compose_file(
+            r#"
+            services:
+              db:
+                image: mariadb
+                environment:
+                  - MARIADB_PASSWORD
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::InvalidService {
+                service_name: String::from("db"),
+                err: String::from("environment variable MARIADB_PASSWORD must have a value"),
+            }
+        );
+    }
+
+    #[test]
+    fn should_ignore_named_networks() {
+        let services = parse_compose_file(
+            r#"
+            services:
+              db:
+                image: mariadb
+                networks:
+                  - backend
+              blog:
+                image: wordpress
+                networks:
+                  - backend
+                  - frontend
+            networks:
+              backend: {}
+              frontend:
+                driver: bridge
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            services
+                .iter()
+                .map(|service| service.service_name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["blog", "db"]
+        );
+    }
+
+    #[test]
+    fn should_reject_unsupported_top_level_keys() {
+        let err = parse_compose_file(
+            r#"
+            services: {}
+            secrets:
+              db-password:
+                file: ./db-password.txt
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ComposeError::UnsupportedKey {
+                key: String::from("secrets"),
+            }
+        );
+    }
+}
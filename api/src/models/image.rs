@@ -38,6 +38,8 @@ pub enum Image {
         registry: Option<String>,
         image_user: Option<String>,
         image_tag: Option<String>,
+        /// The digest that pins the image to an exact manifest, e.g. `nginx:1.27@sha256:…`.
+        image_digest: Option<String>,
     },
     Digest {
         hash: String,
@@ -52,6 +54,7 @@ impl Hash for Image {
                 registry,
                 image_user,
                 image_tag,
+                image_digest,
             } => {
                 state.write(
                     registry
@@ -72,6 +75,7 @@ impl Hash for Image {
                         .map_or("latest", |image_tag| image_tag.as_str())
                         .as_bytes(),
                 );
+                state.write(image_digest.as_deref().unwrap_or_default().as_bytes());
             }
             Image::Digest { hash } => {
                 state.write(hash.as_bytes());
@@ -90,15 +94,18 @@ impl PartialEq for Image {
                     registry,
                     image_user,
                     image_tag,
+                    image_digest,
                 },
                 Named {
                     image_repository: image_repository_other,
                     registry: registry_other,
                     image_user: image_user_other,
                     image_tag: image_tag_other,
+                    image_digest: image_digest_other,
                 },
             ) => {
-                if image_repository != image_repository_other {
+                if image_repository != image_repository_other || image_digest != image_digest_other
+                {
                     return false;
                 }
 
@@ -126,31 +133,39 @@ impl PartialEq for Image {
 }
 
 impl Image {
-    #[cfg(test)]
+    /// The tag of the image. Images that are pinned by their digest only, e.g.
+    /// `nginx@sha256:…`, do not have a tag.
     pub fn tag(&self) -> Option<String> {
         match &self {
             Image::Digest { .. } => None,
             Image::Named {
-                image_repository: _,
-                registry: _,
-                image_user: _,
                 image_tag,
-            } => match &image_tag {
-                None => Some(String::from("latest")),
-                Some(tag) => Some(tag.clone()),
+                image_digest,
+                ..
+            } => match (&image_tag, image_digest) {
+                (None, Some(_)) => None,
+                (None, None) => Some(String::from("latest")),
+                (Some(tag), _) => Some(tag.clone()),
             },
         }
     }
 
-    #[cfg(test)]
+    /// The digest that identifies the image content, either the image id or the digest that
+    /// pins a named image, e.g. `nginx:1.27@sha256:…`.
+    pub fn digest(&self) -> Option<&str> {
+        match &self {
+            Image::Digest { hash } => Some(hash),
+            Image::Named { image_digest, .. } => image_digest.as_deref(),
+        }
+    }
+
     pub fn name(&self) -> Option<String> {
         match &self {
             Image::Digest { .. } => None,
             Image::Named {
                 image_repository,
-                registry: _,
                 image_user,
-                image_tag: _,
+                ..
             } => {
                 let user = match &image_user {
                     None => String::from("library"),
@@ -165,12 +180,7 @@ impl Image {
     pub fn registry(&self) -> Option<String> {
         match &self {
             Image::Digest { .. } => None,
-            Image::Named {
-                image_repository: _,
-                registry,
-                image_user: _,
-                image_tag: _,
-            } => Some(
+            Image::Named { registry, .. } => Some(
                 registry
                     .clone()
                     .unwrap_or_else(|| String::from("docker.io")),
@@ -192,7 +202,7 @@ impl FromStr for Image {
         }
 
         regex = Regex::new(
-            r"^(((?P<registry>([\w\.-]|:)+)/)?(?P<user>[\w/-]+)/)?(?P<repo>[\w-]+)(:(?P<tag>[\w\.-]+))?$",
+            r"^(((?P<registry>([\w\.-]|:)+)/)?(?P<user>[\w/-]+)/)?(?P<repo>[\w-]+)(:(?P<tag>[\w\.-]+))?(@(?P<digest>[a-z0-9]+([\+\._-][a-z0-9]+)*:[a-fA-F0-9]{32,}))?$",
         )
         .unwrap();
         let captures = match regex.captures(s) {
@@ -211,12 +221,14 @@ impl FromStr for Image {
         let registry = captures.name("registry").map(|m| String::from(m.as_str()));
         let user = captures.name("user").map(|m| String::from(m.as_str()));
         let tag = captures.name("tag").map(|m| String::from(m.as_str()));
+        let digest = captures.name("digest").map(|m| String::from(m.as_str()));
 
         let named = Image::Named {
             image_repository: repo,
             registry,
             image_user: user,
             image_tag: tag,
+            image_digest: digest,
         };
 
         // FIXME: eventually replace Image with oci_client::Reference
@@ -243,6 +255,7 @@ impl Display for Image {
                 registry,
                 image_user,
                 image_tag,
+                image_digest,
             } => {
                 let registry = match &registry {
                     None => String::from("docker.io"),
@@ -254,12 +267,16 @@ impl Display for Image {
                     Some(user) => user.clone(),
                 };
 
-                let tag = match &image_tag {
-                    None => "latest".to_owned(),
-                    Some(tag) => tag.clone(),
-                };
-
-                write!(f, "{}/{}/{}:{}", registry, user, image_repository, tag)
+                write!(f, "{}/{}/{}", registry, user, image_repository)?;
+                match (&image_tag, &image_digest) {
+                    (Some(tag), _) => write!(f, ":{}", tag)?,
+                    (None, None) => write!(f, ":latest")?,
+                    (None, Some(_)) => {}
+                }
+                match &image_digest {
+                    Some(digest) => write!(f, "@{}", digest),
+                    None => Ok(()),
+                }
             }
         }
     }
@@ -431,6 +448,70 @@ mod tests {
         assert_eq!(&image.tag().unwrap(), "latest");
     }
 
+    #[test]
+    fn should_parse_image_with_tag_and_digest() {
+        let image = Image::from_str(
+            "nginx:1.27@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913",
+        )
+        .unwrap();
+
+        assert_eq!(&image.name().unwrap(), "library/nginx");
+        assert_eq!(&image.tag().unwrap(), "1.27");
+        assert_eq!(
+            image.digest(),
+            Some("sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913")
+        );
+        assert_eq!(
+            &image.to_string(),
+            "docker.io/library/nginx:1.27@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+        );
+        assert_eq!(Image::from_str(&image.to_string()).unwrap(), image);
+    }
+
+    #[test]
+    fn should_parse_image_with_digest_only() {
+        let image = Image::from_str(
+            "localhost:5000/library/nginx@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913",
+        )
+        .unwrap();
+
+        assert_eq!(&image.registry().unwrap(), "localhost:5000");
+        assert_eq!(image.tag(), None);
+        assert_eq!(
+            &image.to_string(),
+            "localhost:5000/library/nginx@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+        );
+        assert_eq!(Image::from_str(&image.to_string()).unwrap(), image);
+    }
+
+    #[test]
+    fn should_compare_pinned_images() {
+        let pinned = Image::from_str(
+            "nginx:1.27@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913",
+        )
+        .unwrap();
+        let pinned_differently = Image::from_str(
+            "nginx:1.27@sha256:0000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        let unpinned = Image::from_str("nginx:1.27").unwrap();
+
+        assert_eq!(
+            pinned,
+            Image::from_str(
+                "docker.io/library/nginx:1.27@sha256:9895c9b90b58c9490471b877f6bb6a90e6bdc154da7fbb526a0322ea242fc913"
+            )
+            .unwrap()
+        );
+        assert_ne!(pinned, pinned_differently);
+        assert_ne!(pinned, unpinned);
+        assert_ne!(unpinned, pinned);
+
+        let map = HashMap::from([(pinned.clone(), "Hello Image")]);
+        assert_eq!(map.get(&pinned), Some(&"Hello Image"));
+        assert_eq!(map.get(&unpinned), None);
+    }
+
     #[test]
     fn fail() {
         assert_eq!(
@@ -445,5 +526,11 @@ mod tests {
                 invalid_string: String::from("private-registry.example.com/_/postgres")
             })
         );
+        assert_eq!(
+            Image::from_str("nginx:1.27@sha256:xyz"),
+            Err(ServiceError::InvalidImageString {
+                invalid_string: String::from("nginx:1.27@sha256:xyz")
+            })
+        );
     }
 }
@@ -24,6 +24,7 @@
  * =========================LICENSE_END==================================
  */
 use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
 use std::convert::From;
 
 pub struct LogChunk {
@@ -76,3 +77,20 @@ impl From<Vec<(DateTime<FixedOffset>, String)>> for LogChunk {
         })
     }
 }
+
+/// Selects the log lines that match a regular expression or, if inverted, the log lines that do
+/// not match it.
+pub struct LogFilter {
+    regex: Regex,
+    invert: bool,
+}
+
+impl LogFilter {
+    pub fn new(regex: Regex, invert: bool) -> Self {
+        Self { regex, invert }
+    }
+
+    pub fn matches(&self, log_line: &str) -> bool {
+        self.regex.is_match(log_line) != self.invert
+    }
+}
@@ -24,20 +24,29 @@
  * =========================LICENSE_END==================================
  */
 
+pub use app_graph::AppGraph;
 pub use app_name::{AppName, AppNameError};
 pub use app_status_change_id::{AppStatusChangeId, AppStatusChangeIdError};
+pub use compose::parse_compose_file;
 pub use image::Image;
-pub use logs_chunks::LogChunk;
+pub use logs_chunks::{LogChunk, LogFilter};
 pub use request_info::RequestInfo;
+pub use resource_usage::{AppResourceUsage, ResourceUsage};
 pub use service::ContainerType;
-pub use service_config::{Environment, EnvironmentVariable, ServiceConfig};
+pub use service_config::{
+    Environment, EnvironmentVariable, Healthcheck, HttpProbe, Probes, RestartPolicy, ServiceConfig,
+    ServicePeer,
+};
 pub use web_host_meta::WebHostMeta;
 
+mod app_graph;
 mod app_name;
 mod app_status_change_id;
+mod compose;
 mod image;
 mod logs_chunks;
 pub mod request_info;
+mod resource_usage;
 #[cfg_attr(test, macro_use)]
 pub mod service;
 mod service_config;
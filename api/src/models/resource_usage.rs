@@ -0,0 +1,112 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::iter::Sum;
+use std::ops::Add;
+
+/// The CPU and memory consumption of a service or of a whole app.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    /// The consumed CPU in cores, e.g. `0.5` means half of a core.
+    cpu_cores: f64,
+    memory_bytes: u64,
+}
+
+impl ResourceUsage {
+    pub fn new(cpu_cores: f64, memory_bytes: u64) -> Self {
+        Self {
+            cpu_cores,
+            memory_bytes,
+        }
+    }
+}
+
+impl Add for ResourceUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            cpu_cores: self.cpu_cores + other.cpu_cores,
+            memory_bytes: self.memory_bytes + other.memory_bytes,
+        }
+    }
+}
+
+impl Sum for ResourceUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// The resource usage per service of an app and the total usage of the app.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AppResourceUsage {
+    services: BTreeMap<String, ResourceUsage>,
+    total: ResourceUsage,
+}
+
+impl From<BTreeMap<String, ResourceUsage>> for AppResourceUsage {
+    fn from(services: BTreeMap<String, ResourceUsage>) -> Self {
+        let total = services.values().copied().sum();
+        Self { services, total }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_sum_up_resource_usage_of_services() {
+        let usage = AppResourceUsage::from(BTreeMap::from([
+            (String::from("db"), ResourceUsage::new(0.25, 512)),
+            (String::from("web"), ResourceUsage::new(0.5, 1024)),
+        ]));
+
+        assert_eq!(usage.total, ResourceUsage::new(0.75, 1536));
+    }
+
+    #[test]
+    fn should_serialize_app_resource_usage() {
+        let usage = AppResourceUsage::from(BTreeMap::from([(
+            String::from("db"),
+            ResourceUsage::new(0.25, 512),
+        )]));
+
+        assert_eq!(
+            serde_json::to_value(&usage).unwrap(),
+            serde_json::json!({
+                "services": {
+                    "db": { "cpuCores": 0.25, "memoryBytes": 512 }
+                },
+                "total": { "cpuCores": 0.25, "memoryBytes": 512 }
+            })
+        );
+    }
+}
@@ -24,10 +24,12 @@
  * =========================LICENSE_END==================================
  */
 
+use crate::config::ServiceOrder;
 use crate::models::{web_host_meta::WebHostMeta, AppName, ServiceConfig};
 use chrono::{DateTime, Utc};
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::str::FromStr;
 use url::Url;
@@ -55,6 +57,35 @@ impl Services {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    /// The time of the last deployment request that deployed one of the services.
+    pub fn last_deployment(&self) -> Option<&DateTime<Utc>> {
+        self.iter()
+            .filter_map(|service| service.config.deployed_at())
+            .max()
+    }
+
+    /// The time of the first deployment request that created one of the services. Services that
+    /// have been deployed before PREvant stored their creation time fall back to their last
+    /// deployment.
+    pub fn creation(&self) -> Option<&DateTime<Utc>> {
+        self.iter()
+            .filter_map(|service| service.config.created_at().or(service.config.deployed_at()))
+            .min()
+    }
+
+    /// Sorts the services so that API responses are stable across calls.
+    pub fn sorted_by(mut self, order: ServiceOrder) -> Self {
+        match order {
+            ServiceOrder::Name => self.0.sort_by(|a, b| {
+                (a.service_name(), a.container_type()).cmp(&(b.service_name(), b.container_type()))
+            }),
+            ServiceOrder::Type => self.0.sort_by(|a, b| {
+                (a.container_type(), a.service_name()).cmp(&(b.container_type(), b.service_name()))
+            }),
+        }
+        self
+    }
 }
 
 impl Serialize for Services {
@@ -78,9 +109,7 @@ impl From<Vec<Service>> for Services {
             return Self::empty();
         }
 
-        let mut services = services;
-        services.sort_by_key(|service| service.config.service_name().clone());
-        Self(services)
+        Self(services).sorted_by(ServiceOrder::Name)
     }
 }
 
@@ -98,13 +127,31 @@ pub struct State {
     pub status: ServiceStatus,
     #[serde(skip)]
     pub started_at: Option<DateTime<Utc>>,
+    /// The number of replicas of a service whose infrastructure supports scaling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<Replicas>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct Replicas {
+    /// The number of replicas that have been requested.
+    pub desired: u32,
+    /// The number of replicas that are ready.
+    pub current: u32,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ServiceStatus {
     Running,
+    /// The service is running but its healthcheck has not passed yet, e.g. because it is still
+    /// booting. Clients cannot request this status.
+    #[serde(skip_deserializing)]
+    Starting,
+    /// The service has been stopped and its process state is lost.
     Paused,
+    /// The processes of the service are suspended but their state (e.g. memory) is preserved.
+    Frozen,
 }
 
 impl Service {
@@ -180,6 +227,22 @@ impl ServiceWithHostMeta {
             config: service.config,
         }
     }
+
+    pub fn config(&self) -> &ServiceConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn service_url(&self) -> Option<&Url> {
+        self.service_url.as_ref()
+    }
+
+    pub fn web_host_meta(&self) -> &WebHostMeta {
+        &self.web_host_meta
+    }
 }
 
 impl Serialize for ServiceWithHostMeta {
@@ -202,6 +265,14 @@ impl Serialize for ServiceWithHostMeta {
             #[serde(skip_serializing_if = "Option::is_none")]
             async_api_url: Option<&'a Url>,
             state: &'a State,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            exposed_port: Option<u16>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            deployed_at: Option<&'a DateTime<Utc>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            created_at: Option<&'a DateTime<Utc>>,
+            #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+            metadata: &'a BTreeMap<String, String>,
         }
 
         #[derive(Serialize)]
@@ -234,6 +305,10 @@ impl Serialize for ServiceWithHostMeta {
             open_api_url,
             async_api_url: self.web_host_meta.asyncapi(),
             state: &self.state,
+            exposed_port: self.config.exposed_host_port(),
+            deployed_at: self.config.deployed_at(),
+            created_at: self.config.created_at(),
+            metadata: self.config.metadata(),
         };
 
         s.serialize(serializer)
@@ -258,15 +333,32 @@ impl Serialize for ServicesWithHostMeta {
     }
 }
 
+impl ServicesWithHostMeta {
+    /// Keeps the order of the given services, e.g. because they have been sorted already (see
+    /// [`Services::sorted_by`]).
+    pub fn in_given_order(services: Vec<ServiceWithHostMeta>) -> Self {
+        Self(services)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ServiceWithHostMeta> {
+        self.0.iter()
+    }
+}
+
 impl From<Vec<ServiceWithHostMeta>> for ServicesWithHostMeta {
     fn from(services: Vec<ServiceWithHostMeta>) -> Self {
         let mut services = services;
-        services.sort_by_key(|service| service.config.service_name().clone());
+        services.sort_by(|a, b| {
+            (a.config.service_name(), a.config.container_type())
+                .cmp(&(b.config.service_name(), b.config.container_type()))
+        });
         Self(services)
     }
 }
 
-#[derive(Debug, Default, Deserialize, Clone, Eq, Hash, PartialEq, Serialize)]
+/// The variants are declared in the order in which services are listed if they are sorted by their
+/// type (see [`ServiceOrder::Type`]).
+#[derive(Debug, Default, Deserialize, Clone, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum ContainerType {
     #[serde(rename = "instance")]
     #[default]
@@ -335,6 +427,7 @@ mod tests {
                 state: State {
                     status: ServiceStatus::Running,
                     started_at: Some(Utc::now()),
+                    replicas: None,
                 },
                 config: crate::sc!("mariadb", "mariadb:latest")
             })
@@ -364,6 +457,7 @@ mod tests {
                     state: State {
                         status: ServiceStatus::Running,
                         started_at: Some(Utc::now()),
+                        replicas: None,
                     },
                     config: crate::sc!("postgres", "postgres:latest")
                 },
@@ -372,6 +466,7 @@ mod tests {
                     state: State {
                         status: ServiceStatus::Running,
                         started_at: Some(Utc::now()),
+                        replicas: None,
                     },
                     config: crate::sc!("mariadb", "mariadb:latest")
                 }
@@ -410,6 +505,7 @@ mod tests {
                         state: State {
                             status: ServiceStatus::Running,
                             started_at: Some(Utc::now()),
+                            replicas: None,
                         },
                         config: crate::sc!("postgres", "postgres:latest")
                     },
@@ -423,6 +519,7 @@ mod tests {
                         state: State {
                             status: ServiceStatus::Running,
                             started_at: Some(Utc::now()),
+                            replicas: None,
                         },
                         config: crate::sc!("mariadb", "mariadb:latest")
                     },
@@ -434,4 +531,203 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn serialize_service_with_deployment_time() {
+        let deployed_at = DateTime::parse_from_rfc3339("2024-05-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let created_at = DateTime::parse_from_rfc3339("2024-04-01T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut config = crate::sc!("mariadb", "mariadb:latest");
+        config.set_deployed_at(Some(deployed_at));
+        config.set_created_at(Some(created_at));
+
+        assert_json_eq!(
+            serde_json::json!({
+                "name": "mariadb",
+                "type": "instance",
+                "state": {
+                    "status": "running"
+                },
+                "deployedAt": "2024-05-01T12:30:00Z",
+                "createdAt": "2024-04-01T08:00:00Z"
+            }),
+            serde_json::to_value(ServiceWithHostMeta::from_service_and_web_host_meta(
+                Service {
+                    id: String::from("some id"),
+                    state: State {
+                        status: ServiceStatus::Running,
+                        started_at: Some(Utc::now()),
+                        replicas: None,
+                    },
+                    config,
+                },
+                WebHostMeta::invalid(),
+                Url::from_str("http://prevant.example.com").unwrap(),
+                &AppName::master()
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_service_with_metadata() {
+        let mut config = crate::sc!("mariadb", "mariadb:latest");
+        config.set_metadata(BTreeMap::from([
+            (String::from("gitSha"), String::from("5d8f6a2")),
+            (String::from("prNumber"), String::from("1337")),
+        ]));
+
+        assert_json_eq!(
+            serde_json::json!({
+                "name": "mariadb",
+                "type": "instance",
+                "state": {
+                    "status": "running"
+                },
+                "metadata": {
+                    "gitSha": "5d8f6a2",
+                    "prNumber": "1337"
+                }
+            }),
+            serde_json::to_value(ServiceWithHostMeta::from_service_and_web_host_meta(
+                Service {
+                    id: String::from("some id"),
+                    state: State {
+                        status: ServiceStatus::Running,
+                        started_at: Some(Utc::now()),
+                        replicas: None,
+                    },
+                    config,
+                },
+                WebHostMeta::invalid(),
+                Url::from_str("http://prevant.example.com").unwrap(),
+                &AppName::master()
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn should_return_last_deployment_of_services() {
+        let deployed_at = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let mut db = service("db", ContainerType::Instance);
+        db.config
+            .set_deployed_at(Some(deployed_at("2024-05-01T12:30:00Z")));
+        let mut api = service("api", ContainerType::Instance);
+        api.config
+            .set_deployed_at(Some(deployed_at("2024-05-02T08:00:00Z")));
+        let openid = service("openid", ContainerType::ApplicationCompanion);
+
+        let services = Services::from(vec![db, api, openid]);
+
+        assert_eq!(
+            services.last_deployment(),
+            Some(&deployed_at("2024-05-02T08:00:00Z"))
+        );
+        assert_eq!(Services::empty().last_deployment(), None);
+    }
+
+    #[test]
+    fn should_return_creation_of_services() {
+        let time = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let mut db = service("db", ContainerType::Instance);
+        db.config.set_created_at(Some(time("2024-04-01T08:00:00Z")));
+        db.config
+            .set_deployed_at(Some(time("2024-05-02T08:00:00Z")));
+        let mut api = service("api", ContainerType::Instance);
+        api.config
+            .set_deployed_at(Some(time("2024-03-01T08:00:00Z")));
+        let mut openid = service("openid", ContainerType::ApplicationCompanion);
+        openid
+            .config
+            .set_created_at(Some(time("2024-04-15T08:00:00Z")));
+
+        let services = Services::from(vec![db, openid]);
+        assert_eq!(services.creation(), Some(&time("2024-04-01T08:00:00Z")));
+
+        let services = Services::from(vec![services.iter().next().unwrap().clone(), api]);
+        assert_eq!(services.creation(), Some(&time("2024-03-01T08:00:00Z")));
+        assert_eq!(Services::empty().creation(), None);
+    }
+
+    fn service(name: &str, container_type: ContainerType) -> Service {
+        let id = format!("{name}-{container_type}");
+        let mut config = crate::sc!(name, "nginx:latest");
+        config.set_container_type(container_type);
+        Service {
+            id,
+            state: State {
+                status: ServiceStatus::Running,
+                started_at: None,
+                replicas: None,
+            },
+            config,
+        }
+    }
+
+    fn names_and_types(services: &Services) -> Vec<(String, ContainerType)> {
+        services
+            .iter()
+            .map(|service| {
+                (
+                    service.service_name().clone(),
+                    service.container_type().clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sort_services_by_name_and_type() {
+        let services = Services::from(vec![
+            service("openid", ContainerType::ApplicationCompanion),
+            service("db", ContainerType::ServiceCompanion),
+            service("db", ContainerType::Instance),
+            service("api", ContainerType::Replica),
+        ]);
+
+        assert_eq!(
+            names_and_types(&services),
+            vec![
+                (String::from("api"), ContainerType::Replica),
+                (String::from("db"), ContainerType::Instance),
+                (String::from("db"), ContainerType::ServiceCompanion),
+                (String::from("openid"), ContainerType::ApplicationCompanion),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_services_by_type_and_name() {
+        let services = Services::from(vec![
+            service("openid", ContainerType::ApplicationCompanion),
+            service("db", ContainerType::ServiceCompanion),
+            service("web", ContainerType::Instance),
+            service("api", ContainerType::Replica),
+            service("app", ContainerType::Instance),
+        ])
+        .sorted_by(ServiceOrder::Type);
+
+        assert_eq!(
+            names_and_types(&services),
+            vec![
+                (String::from("app"), ContainerType::Instance),
+                (String::from("web"), ContainerType::Instance),
+                (String::from("api"), ContainerType::Replica),
+                (String::from("openid"), ContainerType::ApplicationCompanion),
+                (String::from("db"), ContainerType::ServiceCompanion),
+            ]
+        );
+    }
 }
@@ -0,0 +1,139 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2020 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use serde::{de, Deserialize, Deserializer};
+use std::time::Duration;
+
+/// Describes how the infrastructure checks whether a service is ready to receive traffic, e.g.
+/// to distinguish a booting service from a running one.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Healthcheck {
+    command: String,
+    #[serde(
+        default = "Healthcheck::default_interval",
+        deserialize_with = "parse_duration"
+    )]
+    interval: Duration,
+    #[serde(default = "Healthcheck::default_retries")]
+    retries: u32,
+    #[serde(default, deserialize_with = "parse_duration")]
+    start_period: Duration,
+}
+
+impl Healthcheck {
+    #[cfg(test)]
+    pub fn new(command: String, interval: Duration, retries: u32, start_period: Duration) -> Self {
+        Self {
+            command,
+            interval,
+            retries,
+            start_period,
+        }
+    }
+
+    fn default_interval() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn default_retries() -> u32 {
+        3
+    }
+
+    /// The command that will be run by a shell inside the container. The service is healthy if
+    /// the command exits with zero.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// The time between two checks.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The number of consecutive failed checks until the service is considered as unhealthy.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// The time the service needs to boot. Failed checks do not count during this period.
+    pub fn start_period(&self) -> Duration {
+        self.start_period
+    }
+}
+
+pub(super) fn parse_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let duration = String::deserialize(deserializer)?;
+    humantime::parse_duration(&duration).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::from_value;
+
+    #[test]
+    fn should_parse_healthcheck() {
+        let healthcheck = from_value::<Healthcheck>(serde_json::json!({
+            "command": "curl -f http://localhost/health",
+            "interval": "10s",
+            "retries": 5,
+            "startPeriod": "1m"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            healthcheck,
+            Healthcheck::new(
+                String::from("curl -f http://localhost/health"),
+                Duration::from_secs(10),
+                5,
+                Duration::from_secs(60)
+            )
+        );
+    }
+
+    #[test]
+    fn should_parse_healthcheck_with_defaults() {
+        let healthcheck = from_value::<Healthcheck>(serde_json::json!({
+            "command": "pg_isready"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            healthcheck,
+            Healthcheck::new(
+                String::from("pg_isready"),
+                Duration::from_secs(30),
+                3,
+                Duration::ZERO
+            )
+        );
+    }
+}
@@ -26,15 +26,33 @@
 use crate::config::Routing;
 use crate::models::service::ContainerType;
 use crate::models::Image;
+use chrono::{DateTime, Utc};
 pub use environment::{Environment, EnvironmentVariable};
+pub use healthcheck::Healthcheck;
+pub use probes::{HttpProbe, Probes};
 use secstr::SecUtf8;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
+pub use templating::ServicePeer;
 
 mod environment;
+mod healthcheck;
+mod probes;
 mod templating;
 
+/// The restart policy of a service's container, mirroring the [restart policies of
+/// Docker](https://docs.docker.com/engine/containers/start-containers-automatically/).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    No,
+    UnlessStopped,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ServiceConfig {
@@ -44,13 +62,45 @@ pub struct ServiceConfig {
     #[serde(alias = "volumes", alias = "files", default)]
     files: Option<BTreeMap<PathBuf, SecUtf8>>,
     #[serde(skip)]
-    labels: Option<BTreeMap<String, String>>,
+    custom_labels: BTreeMap<String, String>,
     #[serde(skip, default = "ContainerType::default")]
     container_type: ContainerType,
     #[serde(skip)]
-    port: u16,
+    port: Option<u16>,
     #[serde(skip)]
     routing: Option<Routing>,
+    #[serde(skip)]
+    basic_auth_users: Option<Vec<SecUtf8>>,
+    #[serde(skip)]
+    gpus: Option<u32>,
+    #[serde(skip)]
+    condition: Option<String>,
+    #[serde(skip)]
+    metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    links: Vec<String>,
+    #[serde(default)]
+    tmpfs: Vec<PathBuf>,
+    working_dir: Option<PathBuf>,
+    #[serde(default)]
+    expose_port: bool,
+    runtime: Option<String>,
+    restart_policy: Option<RestartPolicy>,
+    healthcheck: Option<Healthcheck>,
+    #[serde(skip)]
+    probes: Option<Probes>,
+    #[serde(skip)]
+    depends_on: Vec<String>,
+    #[serde(skip)]
+    exposed_host_port: Option<u16>,
+    #[serde(skip)]
+    deployed_config_hash: Option<String>,
+    #[serde(skip)]
+    deployed_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    user_defined_parameters: Option<serde_json::Value>,
 }
 
 impl ServiceConfig {
@@ -60,10 +110,28 @@ impl ServiceConfig {
             image,
             env: None,
             files: None,
-            labels: None,
+            custom_labels: BTreeMap::new(),
             container_type: ContainerType::Instance,
-            port: 80,
+            port: None,
             routing: None,
+            basic_auth_users: None,
+            gpus: None,
+            condition: None,
+            metadata: BTreeMap::new(),
+            links: Vec::new(),
+            tmpfs: Vec::new(),
+            working_dir: None,
+            expose_port: false,
+            runtime: None,
+            restart_policy: None,
+            healthcheck: None,
+            probes: None,
+            depends_on: Vec::new(),
+            exposed_host_port: None,
+            deployed_config_hash: None,
+            deployed_at: None,
+            created_at: None,
+            user_defined_parameters: None,
         }
     }
 
@@ -99,19 +167,33 @@ impl ServiceConfig {
         }
     }
 
-    #[deprecated]
+    #[deprecated(note = "use `set_custom_labels` for custom labels and `set_routing` for routing")]
+    #[allow(dead_code)]
     pub fn set_labels(&mut self, labels: Option<BTreeMap<String, String>>) {
-        self.labels = labels;
+        self.custom_labels = labels.unwrap_or_default();
     }
 
-    #[deprecated]
+    #[deprecated(note = "use `custom_labels` for custom labels and `routing` for routing")]
+    #[allow(dead_code)]
     pub fn labels<'a, 'b: 'a>(&'b self) -> Option<&'a BTreeMap<String, String>> {
-        match &self.labels {
-            None => None,
-            Some(labels) => Some(labels),
+        if self.custom_labels.is_empty() {
+            None
+        } else {
+            Some(&self.custom_labels)
         }
     }
 
+    /// Sets the labels that are attached as-is to the container (or pod) of the service. Routing
+    /// related configuration, e.g. Traefik rules, should be provided through [`Self::set_routing`]
+    /// instead because the infrastructure derives its own routing labels.
+    pub fn set_custom_labels(&mut self, custom_labels: BTreeMap<String, String>) {
+        self.custom_labels = custom_labels;
+    }
+
+    pub fn custom_labels(&self) -> &BTreeMap<String, String> {
+        &self.custom_labels
+    }
+
     pub fn add_file(&mut self, path: PathBuf, data: SecUtf8) {
         if let Some(ref mut files) = self.files {
             files.insert(path, data);
@@ -134,11 +216,17 @@ impl ServiceConfig {
     }
 
     pub fn set_port(&mut self, port: u16) {
-        self.port = port;
+        self.port = Some(port);
     }
 
+    /// The port the service listens on. Defaults to 80 if the port could not be determined.
     pub fn port(&self) -> u16 {
-        self.port
+        self.port.unwrap_or(80)
+    }
+
+    /// Whether the port has been determined, e.g. from the image, instead of defaulting to 80.
+    pub fn is_port_known(&self) -> bool {
+        self.port.is_some()
     }
 
     pub fn set_routing(&mut self, routing: Routing) {
@@ -149,8 +237,234 @@ impl ServiceConfig {
         self.routing.as_ref()
     }
 
-    /// Copy labels, envs and files from other into self.
-    /// If something is defined in self and other, self has precedence.
+    pub fn set_basic_auth_users(&mut self, users: Vec<SecUtf8>) {
+        self.basic_auth_users = Some(users);
+    }
+
+    /// The htpasswd entries of the users that may access the service through the reverse proxy.
+    /// If present, the route of the service is protected by basic authentication.
+    pub fn basic_auth_users(&self) -> Option<&[SecUtf8]> {
+        self.basic_auth_users.as_deref()
+    }
+
+    pub fn set_gpus(&mut self, gpus: Option<u32>) {
+        self.gpus = gpus;
+    }
+
+    /// The number of GPUs the container of the service requires.
+    pub fn gpus(&self) -> Option<u32> {
+        self.gpus
+    }
+
+    pub fn set_condition(&mut self, condition: String) {
+        self.condition = Some(condition);
+    }
+
+    /// Whether the companion should be deployed according to its condition. The condition is a
+    /// handlebars template, thus this must be called on a templated configuration: the condition
+    /// is met if it has not been set or if it has not been rendered to an empty string or `false`.
+    pub fn is_condition_met(&self) -> bool {
+        match self.condition.as_deref().map(str::trim) {
+            None => true,
+            Some(condition) => !condition.is_empty() && condition != "false",
+        }
+    }
+
+    #[cfg(test)]
+    pub fn set_links(&mut self, links: Vec<String>) {
+        self.links = links;
+    }
+
+    /// Names of the services this service links to. For each linked service the environment
+    /// variables `<NAME>_HOST` and `<NAME>_PORT` will be injected during the deployment.
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+
+    pub fn set_tmpfs(&mut self, tmpfs: Vec<PathBuf>) {
+        self.tmpfs = tmpfs;
+    }
+
+    /// Paths inside the container that will be backed by memory instead of the container layer.
+    pub fn tmpfs(&self) -> &[PathBuf] {
+        &self.tmpfs
+    }
+
+    pub fn set_working_dir(&mut self, working_dir: Option<PathBuf>) {
+        self.working_dir = working_dir;
+    }
+
+    /// The working directory of the container, overriding the working directory of the image.
+    pub fn working_dir(&self) -> Option<&PathBuf> {
+        self.working_dir.as_ref()
+    }
+
+    pub fn set_runtime(&mut self, runtime: Option<String>) {
+        self.runtime = runtime;
+    }
+
+    /// The name of the runtime (see `[runtimes]`) that will run the service, or `None` if the
+    /// service will be run by the default runtime.
+    pub fn runtime(&self) -> Option<&String> {
+        self.runtime.as_ref()
+    }
+
+    pub fn set_restart_policy(&mut self, restart_policy: RestartPolicy) {
+        self.restart_policy = Some(restart_policy);
+    }
+
+    /// Determines if the infrastructure restarts the service's container when it exits. Defaults
+    /// to [`RestartPolicy::Always`] if the service does not define a restart policy.
+    pub fn restart_policy(&self) -> &RestartPolicy {
+        self.restart_policy
+            .as_ref()
+            .unwrap_or(&RestartPolicy::Always)
+    }
+
+    pub fn set_healthcheck(&mut self, healthcheck: Option<Healthcheck>) {
+        self.healthcheck = healthcheck;
+    }
+
+    /// The check that tells whether the service is still booting or ready to receive traffic.
+    pub fn healthcheck(&self) -> Option<&Healthcheck> {
+        self.healthcheck.as_ref()
+    }
+
+    pub fn set_probes(&mut self, probes: Option<Probes>) {
+        self.probes = probes;
+    }
+
+    /// The HTTP probes of the service that are used by Kubernetes.
+    pub fn probes(&self) -> Option<&Probes> {
+        self.probes.as_ref()
+    }
+
+    pub fn set_depends_on(&mut self, depends_on: Vec<String>) {
+        self.depends_on = depends_on;
+    }
+
+    /// The names of the services that must be ready before this service will be started.
+    pub fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    /// Sets the metadata of the deployment request, e.g. the team or cost center, that will be
+    /// stored alongside the service.
+    pub fn set_metadata(&mut self, metadata: BTreeMap<String, String>) {
+        self.metadata = metadata;
+    }
+
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    #[cfg(test)]
+    pub fn set_expose_port(&mut self, expose_port: bool) {
+        self.expose_port = expose_port;
+    }
+
+    /// Whether the port of the service should be published on the host (Docker) or on the nodes
+    /// (Kubernetes) so that the service can be reached without the reverse proxy, e.g. for
+    /// debugging.
+    pub fn expose_port(&self) -> bool {
+        self.expose_port
+    }
+
+    pub fn set_exposed_host_port(&mut self, exposed_host_port: Option<u16>) {
+        self.exposed_host_port = exposed_host_port;
+    }
+
+    /// The port that the infrastructure has assigned to the service if its port is exposed.
+    pub fn exposed_host_port(&self) -> Option<u16> {
+        self.exposed_host_port
+    }
+
+    /// Computes a hash over everything that determines how the service will be deployed, so that
+    /// PREvant can tell whether a running service has to be redeployed. The metadata and the
+    /// information the infrastructure assigns after the deployment are not part of the hash.
+    pub fn config_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.service_name.as_bytes());
+        hasher.update(self.image.to_string().as_bytes());
+        hasher.update(self.container_type.to_string().as_bytes());
+        for variable in self.env.iter().flat_map(|env| env.iter()) {
+            hasher.update(variable.key().as_bytes());
+            hasher.update(variable.value().unsecure().as_bytes());
+        }
+        for (path, content) in self.files.iter().flatten() {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(content.unsecure().as_bytes());
+        }
+        for (key, value) in &self.custom_labels {
+            hasher.update(key.as_bytes());
+            hasher.update(value.as_bytes());
+        }
+        hasher.update(format!("{:?}", self.routing).as_bytes());
+        hasher.update(self.port.map(|port| port.to_be_bytes()).unwrap_or_default());
+        hasher.update(self.gpus.map(|gpus| gpus.to_be_bytes()).unwrap_or_default());
+        for link in &self.links {
+            hasher.update(link.as_bytes());
+        }
+        for path in self.tmpfs.iter().chain(self.working_dir.iter()) {
+            hasher.update(path.to_string_lossy().as_bytes());
+        }
+        hasher.update([self.expose_port as u8]);
+        hasher.update(self.runtime.as_deref().unwrap_or_default().as_bytes());
+        hasher.update(format!("{:?}", self.restart_policy()).as_bytes());
+        hasher.update(format!("{:?}", self.healthcheck).as_bytes());
+        hasher.update(format!("{:?}", self.probes).as_bytes());
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub fn set_deployed_config_hash(&mut self, deployed_config_hash: Option<String>) {
+        self.deployed_config_hash = deployed_config_hash;
+    }
+
+    /// The [`config_hash`](Self::config_hash) that the infrastructure has stored alongside the
+    /// running service, or `None` if the service has been deployed by an older version of PREvant.
+    pub fn deployed_config_hash(&self) -> Option<&String> {
+        self.deployed_config_hash.as_ref()
+    }
+
+    pub fn set_deployed_at(&mut self, deployed_at: Option<DateTime<Utc>>) {
+        self.deployed_at = deployed_at;
+    }
+
+    /// The time of the last deployment request that deployed the service, as stored by the
+    /// infrastructure. Reconciliations and redeployments with updated images keep that time.
+    pub fn deployed_at(&self) -> Option<&DateTime<Utc>> {
+        self.deployed_at.as_ref()
+    }
+
+    pub fn set_created_at(&mut self, created_at: Option<DateTime<Utc>>) {
+        self.created_at = created_at;
+    }
+
+    /// The time of the first deployment request that deployed the service, as stored by the
+    /// infrastructure. Later deployments of the same service keep that time.
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
+        self.created_at.as_ref()
+    }
+
+    pub fn set_user_defined_parameters(
+        &mut self,
+        user_defined_parameters: Option<serde_json::Value>,
+    ) {
+        self.user_defined_parameters = user_defined_parameters;
+    }
+
+    /// The user-defined parameters of the deployment request that deployed the service, as stored
+    /// by the infrastructure, so that the companions can be templated again without the request.
+    pub fn user_defined_parameters(&self) -> Option<&serde_json::Value> {
+        self.user_defined_parameters.as_ref()
+    }
+
+    /// Copy custom labels, routing, envs, files, the working directory, the restart policy, the
+    /// healthcheck, the probes, the dependencies, the links and the tmpfs mounts from other into
+    /// self. If something is defined in self and other, self has precedence.
     pub fn merge_with(&mut self, other: &Self) {
         if let Some(env) = &other.env {
             self.env = match self.env.take() {
@@ -171,9 +485,61 @@ impl ServiceConfig {
         files.extend(self.files.as_ref().cloned().unwrap_or_default());
         self.files = Some(files);
 
-        let mut labels = other.labels.as_ref().cloned().unwrap_or_default();
-        labels.extend(self.labels.as_ref().cloned().unwrap_or_default());
-        self.labels = Some(labels);
+        let mut custom_labels = other.custom_labels.clone();
+        custom_labels.append(&mut self.custom_labels);
+        self.custom_labels = custom_labels;
+
+        if self.routing.is_none() {
+            self.routing = other.routing.clone();
+        }
+
+        if self.working_dir.is_none() {
+            self.working_dir = other.working_dir.clone();
+        }
+
+        if self.restart_policy.is_none() {
+            self.restart_policy = other.restart_policy.clone();
+        }
+
+        if self.healthcheck.is_none() {
+            self.healthcheck = other.healthcheck.clone();
+        }
+
+        if self.probes.is_none() {
+            self.probes = other.probes.clone();
+        }
+
+        if self.depends_on.is_empty() {
+            self.depends_on = other.depends_on.clone();
+        }
+
+        if self.links.is_empty() {
+            self.links = other.links.clone();
+        }
+
+        if self.tmpfs.is_empty() {
+            self.tmpfs = other.tmpfs.clone();
+        }
+    }
+
+    /// Copy custom labels, routing, envs, files, the working directory, the restart policy, the
+    /// healthcheck, the probes, the dependencies, the links and the tmpfs mounts from other into
+    /// self. If something is defined in self and other, other has precedence.
+    pub fn merge_with_precedence_of(&mut self, other: &Self) {
+        let mut merged = other.clone();
+        merged.merge_with(self);
+
+        self.env = merged.env;
+        self.files = merged.files;
+        self.custom_labels = merged.custom_labels;
+        self.routing = merged.routing;
+        self.working_dir = merged.working_dir;
+        self.healthcheck = merged.healthcheck;
+        self.probes = merged.probes;
+        self.depends_on = merged.depends_on;
+        self.links = merged.links;
+        self.tmpfs = merged.tmpfs;
+        self.restart_policy = merged.restart_policy;
     }
 }
 
@@ -210,7 +576,7 @@ macro_rules! sc {
 
         let mut _labels = std::collections::BTreeMap::new();
         $( _labels.insert(String::from($l_key), String::from($l_value)); )*
-        config.set_labels(Some(_labels));
+        config.set_custom_labels(_labels);
 
         let mut _files = std::collections::BTreeMap::new();
         $( _files.insert(std::path::PathBuf::from($v_key), String::from($v_value)); )*
@@ -247,7 +613,7 @@ macro_rules! sc {
 
         let mut _labels = std::collections::BTreeMap::new();
         $( _labels.insert(String::from($l_key), String::from($l_value)); )*
-        config.set_labels(Some(_labels));
+        config.set_custom_labels(_labels);
 
         let mut _files = std::collections::BTreeMap::new();
         $( _files.insert(std::path::PathBuf::from($v_key), SecUtf8::from($v_value)); )*
@@ -289,6 +655,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_service_config_json_with_links() {
+        let config = from_value::<ServiceConfig>(serde_json::json!({
+            "serviceName": "wordpress",
+            "image": "wordpress:latest",
+            "links": [ "db" ]
+        }))
+        .unwrap();
+
+        assert_eq!(config.links(), &[String::from("db")]);
+    }
+
+    #[test]
+    fn should_parse_service_config_json_with_tmpfs() {
+        let config = from_value::<ServiceConfig>(serde_json::json!({
+            "serviceName": "wordpress",
+            "image": "wordpress:latest",
+            "tmpfs": [ "/tmp" ]
+        }))
+        .unwrap();
+
+        assert_eq!(config.tmpfs(), &[PathBuf::from("/tmp")]);
+    }
+
     #[test]
     fn should_merge_service_configs_labels() {
         let mut config = sc!(
@@ -308,21 +698,59 @@ mod tests {
 
         config.merge_with(&config2);
 
-        assert_eq!(config.labels().unwrap().len(), 3);
+        assert_eq!(config.custom_labels().len(), 3);
         assert_eq!(
-            config.labels().unwrap().get("priority"),
+            config.custom_labels().get("priority"),
             Some(&String::from("1000"))
         );
         assert_eq!(
-            config.labels().unwrap().get("rule"),
+            config.custom_labels().get("rule"),
             Some(&String::from("some_string"))
         );
         assert_eq!(
-            config.labels().unwrap().get("test_label"),
+            config.custom_labels().get("test_label"),
             Some(&String::from("other_string"))
         );
     }
 
+    #[test]
+    fn should_merge_service_configs_routing() {
+        let mut config = sc!("proxy", "nginx");
+        let mut config2 = sc!("proxy", "nginx");
+        config2.set_routing(Routing {
+            rule: Some(String::from("PathPrefix(`/proxy`)")),
+            additional_middlewares: BTreeMap::new(),
+        });
+
+        config.merge_with(&config2);
+
+        assert_eq!(
+            config.routing().and_then(|routing| routing.rule.as_deref()),
+            Some("PathPrefix(`/proxy`)")
+        );
+    }
+
+    #[test]
+    fn should_merge_service_configs_routing_with_precedence_of_other() {
+        let mut config = sc!("proxy", "nginx");
+        config.set_routing(Routing {
+            rule: Some(String::from("PathPrefix(`/service`)")),
+            additional_middlewares: BTreeMap::new(),
+        });
+        let mut config2 = sc!("proxy", "nginx");
+        config2.set_routing(Routing {
+            rule: Some(String::from("PathPrefix(`/companion`)")),
+            additional_middlewares: BTreeMap::new(),
+        });
+
+        config.merge_with_precedence_of(&config2);
+
+        assert_eq!(
+            config.routing().and_then(|routing| routing.rule.as_deref()),
+            Some("PathPrefix(`/companion`)")
+        );
+    }
+
     #[test]
     fn should_merge_service_configs_envs() {
         let mut config = sc!(
@@ -364,6 +792,167 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_merge_service_configs_envs_with_precedence_of_other() {
+        let mut config = sc!(
+            "proxy",
+            "nginx",
+            env = ("VAR_1" => "abcd", "VAR_2" => "1234")
+        );
+
+        let config2 = sc!(
+            "proxy",
+            "nginx",
+            env = ("VAR_1" => "efgh", "VAR_3" => "1234")
+        );
+
+        config.merge_with_precedence_of(&config2);
+
+        let env = config.env().unwrap();
+        assert_eq!(env.iter().count(), 3);
+        assert_eq!(
+            env.variable("VAR_1"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_1"),
+                SecUtf8::from("efgh")
+            ))
+        );
+        assert_eq!(
+            env.variable("VAR_2"),
+            Some(&EnvironmentVariable::new(
+                String::from("VAR_2"),
+                SecUtf8::from("1234")
+            ))
+        );
+    }
+
+    #[test]
+    fn should_parse_service_config_json_with_working_dir() {
+        let config = from_value::<ServiceConfig>(serde_json::json!({
+            "serviceName": "wordpress",
+            "image": "wordpress:latest",
+            "workingDir": "/app"
+        }))
+        .unwrap();
+
+        assert_eq!(config.working_dir(), Some(&PathBuf::from("/app")));
+    }
+
+    #[test]
+    fn should_parse_service_config_json_with_restart_policy() {
+        let config = from_value::<ServiceConfig>(serde_json::json!({
+            "serviceName": "migration",
+            "image": "flyway/flyway:latest",
+            "restartPolicy": "on-failure"
+        }))
+        .unwrap();
+
+        assert_eq!(config.restart_policy(), &RestartPolicy::OnFailure);
+    }
+
+    #[test]
+    fn should_parse_service_config_json_with_default_restart_policy() {
+        let config = from_value::<ServiceConfig>(serde_json::json!({
+            "serviceName": "wordpress",
+            "image": "wordpress:latest"
+        }))
+        .unwrap();
+
+        assert_eq!(config.restart_policy(), &RestartPolicy::Always);
+    }
+
+    #[test]
+    fn should_compute_same_config_hash_for_equal_configs() {
+        let mut config = sc!("proxy", "nginx");
+        config.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
+            String::from("VAR_1"),
+            SecUtf8::from("value"),
+        )])));
+
+        assert_eq!(config.config_hash(), config.clone().config_hash());
+    }
+
+    #[test]
+    fn should_compute_different_config_hash_for_changed_env() {
+        let config = sc!("proxy", "nginx");
+        let mut changed = config.clone();
+        changed.set_env(Some(Environment::new(vec![EnvironmentVariable::new(
+            String::from("VAR_1"),
+            SecUtf8::from("value"),
+        )])));
+
+        assert_ne!(config.config_hash(), changed.config_hash());
+    }
+
+    #[test]
+    fn should_merge_service_configs_working_dir() {
+        let mut config = sc!("proxy", "nginx");
+        config.set_working_dir(Some(PathBuf::from("/app")));
+        let mut companion = sc!("proxy", "nginx");
+        companion.set_working_dir(Some(PathBuf::from("/opt")));
+
+        let mut merged = config.clone();
+        merged.merge_with(&companion);
+        assert_eq!(merged.working_dir(), Some(&PathBuf::from("/app")));
+
+        let mut merged = config.clone();
+        merged.merge_with_precedence_of(&companion);
+        assert_eq!(merged.working_dir(), Some(&PathBuf::from("/opt")));
+
+        let mut merged = sc!("proxy", "nginx");
+        merged.merge_with(&companion);
+        assert_eq!(merged.working_dir(), Some(&PathBuf::from("/opt")));
+    }
+
+    #[test]
+    fn should_merge_service_configs_restart_policy() {
+        let mut config = sc!("migration", "flyway");
+        config.set_restart_policy(RestartPolicy::No);
+        let mut companion = sc!("migration", "flyway");
+        companion.set_restart_policy(RestartPolicy::OnFailure);
+
+        let mut merged = config.clone();
+        merged.merge_with(&companion);
+        assert_eq!(merged.restart_policy(), &RestartPolicy::No);
+
+        let mut merged = config.clone();
+        merged.merge_with_precedence_of(&companion);
+        assert_eq!(merged.restart_policy(), &RestartPolicy::OnFailure);
+
+        let mut merged = sc!("migration", "flyway");
+        merged.merge_with(&companion);
+        assert_eq!(merged.restart_policy(), &RestartPolicy::OnFailure);
+
+        let mut merged = companion.clone();
+        merged.merge_with_precedence_of(&sc!("migration", "flyway"));
+        assert_eq!(merged.restart_policy(), &RestartPolicy::OnFailure);
+    }
+
+    #[test]
+    fn should_merge_service_configs_links_and_tmpfs() {
+        let mut config = sc!("wordpress", "wordpress");
+        config.set_links(vec![String::from("db")]);
+        config.set_tmpfs(vec![PathBuf::from("/tmp")]);
+        let mut companion = sc!("wordpress", "wordpress");
+        companion.set_links(vec![String::from("mariadb")]);
+        companion.set_tmpfs(vec![PathBuf::from("/run")]);
+
+        let mut merged = config.clone();
+        merged.merge_with(&companion);
+        assert_eq!(merged.links(), &[String::from("db")]);
+        assert_eq!(merged.tmpfs(), &[PathBuf::from("/tmp")]);
+
+        let mut merged = config.clone();
+        merged.merge_with_precedence_of(&companion);
+        assert_eq!(merged.links(), &[String::from("mariadb")]);
+        assert_eq!(merged.tmpfs(), &[PathBuf::from("/run")]);
+
+        let mut merged = sc!("wordpress", "wordpress");
+        merged.merge_with(&companion);
+        assert_eq!(merged.links(), &[String::from("mariadb")]);
+        assert_eq!(merged.tmpfs(), &[PathBuf::from("/run")]);
+    }
+
     #[test]
     fn should_merge_service_configs_volumes() {
         let mut config = sc!(
@@ -0,0 +1,138 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2020 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+use super::healthcheck::parse_duration;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// The HTTP probes that Kubernetes uses to check whether a service is ready to receive traffic
+/// and whether it is still alive.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Probes {
+    readiness: Option<HttpProbe>,
+    liveness: Option<HttpProbe>,
+}
+
+/// Describes an HTTP `GET` request against the service. The probe succeeds if the service
+/// responds with a status code between 200 and 399.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpProbe {
+    path: String,
+    port: Option<u16>,
+    #[serde(
+        default = "HttpProbe::default_interval",
+        deserialize_with = "parse_duration"
+    )]
+    interval: Duration,
+    #[serde(default = "HttpProbe::default_retries")]
+    retries: u32,
+    #[serde(default, deserialize_with = "parse_duration")]
+    start_period: Duration,
+}
+
+impl Probes {
+    /// The probe that tells whether the service is still booting or ready to receive traffic.
+    pub fn readiness(&self) -> Option<&HttpProbe> {
+        self.readiness.as_ref()
+    }
+
+    /// The probe that tells whether the service has to be restarted.
+    pub fn liveness(&self) -> Option<&HttpProbe> {
+        self.liveness.as_ref()
+    }
+}
+
+impl HttpProbe {
+    fn default_interval() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    fn default_retries() -> u32 {
+        3
+    }
+
+    /// The path of the HTTP request, e.g. `/health/ready`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The port of the HTTP request. If unspecified, the port of the service will be used.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The time between two probes.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The number of consecutive failed probes until the probe is considered as failed.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// The time the service needs to boot before the first probe.
+    pub fn start_period(&self) -> Duration {
+        self.start_period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::from_value;
+
+    #[test]
+    fn should_parse_probes() {
+        let probes = from_value::<Probes>(serde_json::json!({
+            "readiness": {
+                "path": "/health/ready",
+                "port": 8080,
+                "interval": "5s",
+                "retries": 5,
+                "startPeriod": "1m"
+            },
+            "liveness": {
+                "path": "/health/live"
+            }
+        }))
+        .unwrap();
+
+        let readiness = probes.readiness().unwrap();
+        assert_eq!(readiness.path(), "/health/ready");
+        assert_eq!(readiness.port(), Some(8080));
+        assert_eq!(readiness.interval(), Duration::from_secs(5));
+        assert_eq!(readiness.retries(), 5);
+        assert_eq!(readiness.start_period(), Duration::from_secs(60));
+
+        let liveness = probes.liveness().unwrap();
+        assert_eq!(liveness.path(), "/health/live");
+        assert_eq!(liveness.port(), None);
+        assert_eq!(liveness.interval(), Duration::from_secs(10));
+        assert_eq!(liveness.retries(), 3);
+        assert_eq!(liveness.start_period(), Duration::ZERO);
+    }
+}
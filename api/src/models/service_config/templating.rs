@@ -27,7 +27,7 @@
 use crate::config::Routing;
 use crate::models::service::ContainerType;
 use crate::models::user_defined_parameters::UserDefinedParameters;
-use crate::models::{AppName, Environment, EnvironmentVariable, ServiceConfig};
+use crate::models::{AppName, Environment, EnvironmentVariable, Image, ServiceConfig};
 use handlebars::{
     Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
     RenderErrorReason, Renderable,
@@ -43,6 +43,7 @@ impl ServiceConfig {
         &self,
         app_name: &AppName,
         base_url: &Option<Url>,
+        peers: &[ServicePeer],
         user_defined_parameters: &Option<UserDefinedParameters>,
     ) -> Result<Self, RenderError> {
         let parameters = TemplateParameters {
@@ -50,12 +51,8 @@ impl ServiceConfig {
                 name: app_name,
                 base_url,
             },
-            services: None,
-            service: Some(ServiceTemplateParameter {
-                name: self.service_name(),
-                container_type: self.container_type().clone(),
-                port: self.port(),
-            }),
+            services: Some(ServicesTemplateParameter::by_name(peers, base_url)),
+            service: Some(ServiceTemplateParameter::from(self)),
             user_defined_parameters,
         };
 
@@ -67,6 +64,7 @@ impl ServiceConfig {
         app_name: &AppName,
         base_url: &Option<Url>,
         service_config: &Self,
+        peers: &[ServicePeer],
         user_defined_parameters: &Option<UserDefinedParameters>,
     ) -> Result<Self, RenderError> {
         let parameters = TemplateParameters {
@@ -74,12 +72,8 @@ impl ServiceConfig {
                 name: app_name,
                 base_url,
             },
-            services: None,
-            service: Some(ServiceTemplateParameter {
-                name: service_config.service_name(),
-                container_type: service_config.container_type().clone(),
-                port: service_config.port(),
-            }),
+            services: Some(ServicesTemplateParameter::by_name(peers, base_url)),
+            service: Some(ServiceTemplateParameter::from(service_config)),
             user_defined_parameters,
         };
 
@@ -98,16 +92,12 @@ impl ServiceConfig {
                 name: app_name,
                 base_url,
             },
-            services: Some(
+            services: Some(ServicesTemplateParameter::List(
                 service_configs
                     .iter()
-                    .map(|c| ServiceTemplateParameter {
-                        name: c.service_name(),
-                        container_type: c.container_type().clone(),
-                        port: c.port(),
-                    })
+                    .map(ServiceTemplateParameter::from)
                     .collect(),
-            ),
+            )),
             service: None,
             user_defined_parameters,
         };
@@ -132,8 +122,25 @@ impl ServiceConfig {
                 .set_files(Some(apply_templates_with_secrets(&reg, parameters, files)?));
         }
 
-        if let Some(labels) = self.labels() {
-            templated_config.set_labels(Some(apply_templates(&reg, parameters, labels)?));
+        if !self.custom_labels().is_empty() {
+            templated_config.set_custom_labels(apply_templates(
+                &reg,
+                parameters,
+                self.custom_labels(),
+            )?);
+        }
+
+        if let Some(condition) = &self.condition {
+            templated_config.set_condition(reg.render_template(condition, &parameters)?);
+        }
+
+        if !self.depends_on.is_empty() {
+            templated_config.set_depends_on(
+                self.depends_on
+                    .iter()
+                    .map(|service_name| reg.render_template(service_name, &parameters))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
         }
 
         if let Some(routing) = self.routing() {
@@ -323,10 +330,23 @@ fn apply_templating_to_middleware_value(
     }
 }
 
+/// Another service of the application that a service can refer to while templating, e.g. with
+/// `{{services.api.url}}`, together with the path under which the reverse proxy routes to it.
+pub struct ServicePeer<'a> {
+    config: &'a ServiceConfig,
+    path: Option<String>,
+}
+
+impl<'a> ServicePeer<'a> {
+    pub fn new(config: &'a ServiceConfig, path: Option<String>) -> Self {
+        Self { config, path }
+    }
+}
+
 #[derive(Serialize)]
 struct TemplateParameters<'a, 'b> {
     application: ApplicationTemplateParameter<'a>,
-    services: Option<Vec<ServiceTemplateParameter<'a>>>,
+    services: Option<ServicesTemplateParameter<'a>>,
     service: Option<ServiceTemplateParameter<'a>>,
     #[serde(rename = "userDefined")]
     user_defined_parameters: &'b Option<UserDefinedParameters>,
@@ -340,12 +360,85 @@ struct ApplicationTemplateParameter<'a> {
     base_url: &'a Option<Url>,
 }
 
+/// The services of the application: application companions iterate over a list of all services
+/// while services and their companions look up other services by name.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ServicesTemplateParameter<'a> {
+    List(Vec<ServiceTemplateParameter<'a>>),
+    ByName(BTreeMap<&'a str, ServiceTemplateParameter<'a>>),
+}
+
+impl<'a> ServicesTemplateParameter<'a> {
+    fn by_name(peers: &'a [ServicePeer], base_url: &Option<Url>) -> Self {
+        Self::ByName(
+            peers
+                .iter()
+                .map(|peer| {
+                    let mut parameter = ServiceTemplateParameter::from(peer.config);
+                    parameter.url = match (base_url, &peer.path) {
+                        (Some(base_url), Some(path)) => base_url.join(path).ok(),
+                        _ => None,
+                    };
+                    parameter.path = peer.path.as_deref();
+                    (peer.config.service_name().as_str(), parameter)
+                })
+                .collect(),
+        )
+    }
+}
+
 #[derive(Serialize)]
 struct ServiceTemplateParameter<'a> {
     name: &'a str,
+    host: &'a str,
     port: u16,
     #[serde(rename = "type")]
     container_type: ContainerType,
+    image: ImageTemplateParameter,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Url>,
+}
+
+impl<'a> From<&'a ServiceConfig> for ServiceTemplateParameter<'a> {
+    fn from(config: &'a ServiceConfig) -> Self {
+        Self {
+            name: config.service_name(),
+            host: config.service_name(),
+            port: config.port(),
+            container_type: config.container_type().clone(),
+            image: ImageTemplateParameter::from(config.image()),
+            path: None,
+            url: None,
+        }
+    }
+}
+
+/// The image of a service, e.g. to render the versions of the services of an app. Images that are
+/// referenced by digest provide only the digest.
+#[derive(Serialize)]
+struct ImageTemplateParameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest: Option<String>,
+}
+
+impl From<&Image> for ImageTemplateParameter {
+    fn from(image: &Image) -> Self {
+        Self {
+            registry: image.registry(),
+            name: image.name(),
+            tag: image.tag(),
+            digest: image.digest().map(String::from),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -353,7 +446,6 @@ mod tests {
 
     use super::*;
     use crate::config::Routing;
-    use crate::models::Image;
     use crate::sc;
     use std::path::PathBuf;
 
@@ -418,6 +510,49 @@ mod tests {
         assert_eq!(env.value().unsecure(), "service-a,service-b,");
     }
 
+    #[test]
+    fn should_apply_app_companion_templating_with_image_tags() {
+        let mut config = ServiceConfig::new(
+            String::from("version-banner"),
+            Image::from_str("nginx").unwrap(),
+        );
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_templating(
+                "VERSIONS".to_string(),
+                SecUtf8::from(
+                    r#"{{~#each services~}}
+                    {{~name}}={{image.name}}:{{image.tag}}{{image.digest~}},
+                {{~/each~}}"#,
+                ),
+            ),
+        ])));
+
+        let service_configs = vec![
+            ServiceConfig::new(
+                String::from("service-a"),
+                Image::from_str("quay.io/example/service-a:1.2.3").unwrap(),
+            ),
+            ServiceConfig::new(
+                String::from("service-b"),
+                Image::from_str("service").unwrap(),
+            ),
+        ];
+        let templated_config = config
+            .apply_templating_for_application_companion(
+                &AppName::master(),
+                &None,
+                &service_configs,
+                &None,
+            )
+            .unwrap();
+
+        let env = templated_config.env().unwrap().get(0).unwrap();
+        assert_eq!(
+            env.value().unsecure(),
+            "service-a=example/service-a:1.2.3,service-b=library/service:latest,"
+        );
+    }
+
     #[test]
     fn should_apply_app_companion_templating_with_labels() {
         let mut config = ServiceConfig::new(
@@ -430,7 +565,7 @@ mod tests {
             String::from("com.foo.bar"),
             String::from("app-{{application.name}}"),
         );
-        config.set_labels(Some(labels));
+        config.set_custom_labels(labels);
 
         let service_configs = vec![
             ServiceConfig::new(
@@ -451,7 +586,7 @@ mod tests {
             )
             .unwrap();
 
-        for (k, v) in templated_config.labels().unwrap().iter() {
+        for (k, v) in templated_config.custom_labels().iter() {
             assert_eq!(k, "com.foo.bar");
             assert_eq!(v, "app-master");
         }
@@ -797,6 +932,7 @@ location /service-d {
                 &AppName::master(),
                 &None,
                 &sc!("wordpress", "wordpress:alpine"),
+                &[],
                 &None,
             )
             .unwrap();
@@ -821,6 +957,7 @@ location /service-d {
                 &AppName::master(),
                 &None,
                 &sc!("wordpress", "wordpress:alpine"),
+                &[],
                 &None,
             )
             .unwrap();
@@ -830,6 +967,50 @@ location /service-d {
         assert_eq!(env.value().unsecure(), "admin-wordpress");
     }
 
+    #[test]
+    fn should_apply_templating_for_environment_with_other_services() {
+        let mut config = sc!("frontend", "nginx");
+        config.set_env(Some(Environment::new(vec![
+            EnvironmentVariable::with_templating(
+                String::from("API_URL"),
+                SecUtf8::from("{{services.api.url}}"),
+            ),
+            EnvironmentVariable::with_templating(
+                String::from("API_ADDRESS"),
+                SecUtf8::from("{{services.api.host}}:{{services.api.port}}"),
+            ),
+            EnvironmentVariable::with_templating(
+                String::from("API_PATH"),
+                SecUtf8::from("{{services.api.path}}"),
+            ),
+        ])));
+        let mut api = sc!("api", "api:latest");
+        api.set_port(8080);
+
+        let config = config
+            .apply_templating(
+                &AppName::master(),
+                &Url::parse("https://prevant.example.com").ok(),
+                &[ServicePeer::new(&api, Some(String::from("/master/api/")))],
+                &None,
+            )
+            .unwrap();
+
+        let env = config.env().unwrap();
+        assert_eq!(
+            env.variable("API_URL").unwrap().value().unsecure(),
+            "https://prevant.example.com/master/api/"
+        );
+        assert_eq!(
+            env.variable("API_ADDRESS").unwrap().value().unsecure(),
+            "api:8080"
+        );
+        assert_eq!(
+            env.variable("API_PATH").unwrap().value().unsecure(),
+            "/master/api/"
+        );
+    }
+
     #[test]
     fn should_keep_original_environment_variable() {
         let mut config = sc!("db", "maria-db");
@@ -845,6 +1026,7 @@ location /service-d {
                 &AppName::master(),
                 &None,
                 &sc!("wordpress", "wordpress:alpine"),
+                &[],
                 &None,
             )
             .unwrap();
@@ -874,6 +1056,7 @@ location /service-d {
                 &AppName::master(),
                 &None,
                 &sc!("wordpress", "wordpress:alpine"),
+                &[],
                 &Some(
                     UserDefinedParameters::new(
                         serde_json::json!({
@@ -34,6 +34,16 @@ pub struct TicketInfo {
     status: String,
 }
 
+impl TicketInfo {
+    pub fn new(link: Url, summary: String, status: String) -> Self {
+        TicketInfo {
+            link,
+            summary,
+            status,
+        }
+    }
+}
+
 impl From<Issue> for TicketInfo {
     fn from(issue: Issue) -> Self {
         let summary = issue.fields.summary;
@@ -2,7 +2,7 @@ use jsonschema::Validator;
 use serde::Serializer;
 use serde_json::Value;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct UserDefinedParameters {
     data: Value,
 }
@@ -25,6 +25,10 @@ impl UserDefinedParameters {
 
         Ok(Self { data })
     }
+
+    pub fn data(&self) -> &Value {
+        &self.data
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -24,7 +24,7 @@
  * =========================LICENSE_END==================================
  */
 
-use crate::config::Config;
+use crate::config::{Config, CredentialsProviderError};
 use crate::models::Image;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
@@ -33,8 +33,7 @@ use oci_client::errors::OciDistributionError;
 use oci_client::secrets::RegistryAuth;
 use oci_client::{Client, Reference};
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::convert::From;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 
 pub struct Registry<'a> {
@@ -63,39 +62,27 @@ impl<'a> Registry<'a> {
 
         let mut image_infos = HashMap::new();
         while let Some(result) = resolve_image_info_futures.next().await {
-            match result {
-                Ok((image, image_info)) => {
-                    image_infos.insert(image.clone(), image_info);
-                }
-                Err((image, err)) => {
-                    return Err(match err {
-                        OciDistributionError::AuthenticationFailure(err) => {
-                            RegistryError::AuthenticationFailure {
-                                image: image.to_string(),
-                                failure: err,
-                            }
-                        }
-                        OciDistributionError::ImageManifestNotFoundError(_) => {
-                            RegistryError::ImageNotFound {
-                                image: image.to_string(),
-                            }
-                        }
-                        err => RegistryError::UnexpectedError {
-                            image: image.to_string(),
-                            err: anyhow::Error::new(err),
-                        },
-                    });
-                }
-            }
+            let (image, image_info) = result?;
+            image_infos.insert(image.clone(), image_info);
         }
 
         Ok(image_infos)
     }
 
+    /// Returns the images whose tags are considered as mutable (see `[images] mutableTags`), i.e.
+    /// the images might change unexpectedly between deployments.
+    pub fn mutable_images(&self, images: &HashSet<Image>) -> HashSet<Image> {
+        images
+            .iter()
+            .filter(|image| self.config.is_mutable_image(image))
+            .cloned()
+            .collect()
+    }
+
     async fn resolve_image_info<'i>(
         config: &Config,
         image: &'i Image,
-    ) -> Result<(&'i Image, ImageInfo), (&'i Image, OciDistributionError)> {
+    ) -> Result<(&'i Image, ImageInfo), RegistryError> {
         debug!("Resolve image manifest for {:?}", image);
 
         let client = Client::new(ClientConfig {
@@ -125,10 +112,20 @@ impl<'a> Registry<'a> {
             reference.set_mirror_registry(mirror.to_string());
         }
 
+        let auth = Self::registry_auth(config, &reference)
+            .await
+            .map_err(|err| RegistryError::CredentialsUnavailable {
+                image: image.to_string(),
+                err,
+            })?;
         let (_manifest, digest, config) = client
-            .pull_manifest_and_config(&reference, &Self::registry_auth(config, &reference))
+            .pull_manifest_and_config(&reference, &auth)
             .await
-            .map_err(|err| (image, err))?;
+            .map_err(|err| RegistryError::from_oci_error(image, err))?;
+        // The registry might resolve an image index to the manifest of a specific platform. Thus,
+        // pinned images keep the digest they have been pinned to, so that the deployment strategy
+        // `redeploy-on-image-update` compares the same digest on each deployment.
+        let digest = image.digest().map(String::from).unwrap_or(digest);
 
         let blob = match serde_json::from_str::<ImageBlob>(&config) {
             Ok(blob) => ImageInfo {
@@ -144,13 +141,25 @@ impl<'a> Registry<'a> {
         Ok((image, blob))
     }
 
-    fn registry_auth(config: &Config, reference: &Reference) -> RegistryAuth {
-        match config.registry_credentials(reference.registry()) {
+    /// Returns the credentials of the registry's mirror if the image will be pulled through a
+    /// mirror and the credentials of the registry otherwise.
+    async fn registry_auth(
+        config: &Config,
+        reference: &Reference,
+    ) -> Result<RegistryAuth, CredentialsProviderError> {
+        let credentials = if config.registry_mirror(reference.registry()).is_some() {
+            config
+                .registry_mirror_credentials(reference.registry())
+                .await?
+        } else {
+            config.registry_credentials(reference.registry()).await?
+        };
+        Ok(match credentials {
             Some((username, password)) => {
-                RegistryAuth::Basic(String::from(username), password.unsecure().to_string())
+                RegistryAuth::Basic(username, password.unsecure().to_string())
             }
             None => RegistryAuth::Anonymous,
-        }
+        })
     }
 }
 
@@ -175,6 +184,26 @@ impl ImageInfo {
             None => Vec::new(),
         }
     }
+
+    #[cfg(test)]
+    pub fn with_exposed_port(digest: &str, port: u16) -> Self {
+        let blob = serde_json::from_str::<ImageBlob>(&format!(
+            r#"{{ "config": {{ "ExposedPorts": {{ "{port}/tcp": {{}} }} }} }}"#
+        ))
+        .unwrap();
+        ImageInfo {
+            blob: Some(blob),
+            digest: digest.to_string(),
+        }
+    }
+
+    /// The labels of the image config, e.g. `org.opencontainers.image.revision`.
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        self.blob
+            .as_ref()
+            .and_then(|blob| blob.config.labels.clone())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -198,6 +227,8 @@ struct ImageConfig {
     exposed_ports: Option<HashMap<String, serde_json::Value>>,
     #[serde(rename = "Volumes")]
     declared_volumes: Option<HashMap<String, serde_json::Value>>,
+    #[serde(rename = "Labels")]
+    labels: Option<BTreeMap<String, String>>,
 }
 
 impl ImageConfig {
@@ -235,12 +266,57 @@ pub enum RegistryError {
     AuthenticationFailure { image: String, failure: String },
     #[error("Cannot find image {image}")]
     ImageNotFound { image: String },
+    #[error("Cannot fetch the registry credentials to resolve image {image}: {err}")]
+    CredentialsUnavailable {
+        image: String,
+        err: CredentialsProviderError,
+    },
+}
+
+impl RegistryError {
+    fn from_oci_error(image: &Image, err: OciDistributionError) -> Self {
+        match err {
+            OciDistributionError::AuthenticationFailure(err) => {
+                RegistryError::AuthenticationFailure {
+                    image: image.to_string(),
+                    failure: err,
+                }
+            }
+            OciDistributionError::ImageManifestNotFoundError(_) => RegistryError::ImageNotFound {
+                image: image.to_string(),
+            },
+            err => RegistryError::UnexpectedError {
+                image: image.to_string(),
+                err: anyhow::Error::new(err),
+            },
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn should_return_mutable_images() {
+        let config = Config::default();
+        let images = HashSet::from([
+            Image::from_str("nginx").unwrap(),
+            Image::from_str("nginx:1.27").unwrap(),
+            Image::from_str("private.example.com/library/openid:latest").unwrap(),
+        ]);
+
+        let mutable_images = Registry::new(&config).mutable_images(&images);
+
+        assert_eq!(
+            mutable_images,
+            HashSet::from([
+                Image::from_str("nginx").unwrap(),
+                Image::from_str("private.example.com/library/openid:latest").unwrap(),
+            ])
+        );
+    }
+
     #[test]
     fn should_return_exposed_port() {
         let blob = serde_json::from_str::<ImageBlob>(
@@ -306,6 +382,32 @@ mod tests {
         assert_eq!(blob.declared_volumes(), vec!["var/lib/data"]);
     }
 
+    #[test]
+    fn should_return_labels() {
+        let blob = serde_json::from_str::<ImageBlob>(
+            r#"{
+                "config": {
+                    "Hostname": "837a64dcc771",
+                    "Labels": {
+                        "org.opencontainers.image.revision": "a1b2c3d"
+                    }
+                } }"#,
+        )
+        .unwrap();
+        let info = ImageInfo {
+            blob: Some(blob),
+            digest: String::from("sha256:4711"),
+        };
+
+        assert_eq!(
+            info.labels(),
+            BTreeMap::from([(
+                String::from("org.opencontainers.image.revision"),
+                String::from("a1b2c3d")
+            )])
+        );
+    }
+
     #[test]
     fn should_return_none_if_no_declared_volumes() {
         let blob = serde_json::from_str::<ImageBlob>(
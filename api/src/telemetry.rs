@@ -0,0 +1,75 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+//! Optional export of deployment phases as [OpenTelemetry](https://opentelemetry.io/) spans.
+//!
+//! If `[tracing] otlpEndpoint` is not configured, the global tracer of OpenTelemetry stays a
+//! no-op tracer and the spans created via [`in_span`] are discarded without being recorded.
+
+use crate::config::Config;
+use opentelemetry::trace::{FutureExt, TraceContextExt, TraceError, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use std::future::Future;
+
+/// Installs a global tracer provider that exports spans to the configured OTLP endpoint (HTTP
+/// with protobuf encoding). The returned provider should be shut down before PREvant exits so
+/// that pending spans will be flushed.
+pub fn init(config: &Config) -> Result<Option<TracerProvider>, TraceError> {
+    let Some(endpoint) = config.otlp_endpoint() else {
+        return Ok(None);
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint.to_string())
+        .build()?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new("service.name", "PREvant")]))
+        .build();
+
+    global::set_tracer_provider(provider.clone());
+
+    Ok(Some(provider))
+}
+
+/// Runs the future within a span with the given name. Spans that are started while the future is
+/// running become children of this span.
+pub async fn in_span<F>(name: &'static str, attributes: Vec<KeyValue>, future: F) -> F::Output
+where
+    F: Future,
+{
+    let tracer = global::tracer("prevant");
+    let span = tracer
+        .span_builder(name)
+        .with_attributes(attributes)
+        .start(&tracer);
+
+    future.with_context(Context::current_with_span(span)).await
+}
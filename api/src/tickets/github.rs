@@ -0,0 +1,261 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use super::{ListTicketsError, TicketTracker};
+use crate::config::GitHubConfig;
+use crate::models::ticket_info::TicketInfo;
+use crate::models::AppName;
+use async_trait::async_trait;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use regex::Regex;
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use url::Url;
+
+/// Bounds the time that requesting the tickets of the apps waits for GitHub.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolves the GitHub issues and pull requests whose numbers are referenced by the app names,
+/// e.g. `pr-123`, `issue-123`, or `123`.
+pub(super) struct GitHubTracker {
+    config: GitHubConfig,
+    client: reqwest::Client,
+}
+
+impl GitHubTracker {
+    pub(super) fn new(config: GitHubConfig) -> Self {
+        Self {
+            config,
+            // like reqwest::Client::new(), this only fails if the TLS backend cannot be
+            // initialized
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("The HTTP client for GitHub should be buildable"),
+        }
+    }
+
+    fn issue_number(app_name: &AppName) -> Option<u64> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^(?:[A-Za-z]+-)?(\d+)$").unwrap();
+        }
+
+        RE.captures(app_name.as_str())?
+            .get(1)?
+            .as_str()
+            .parse()
+            .ok()
+    }
+
+    async fn issue(&self, number: u64) -> Result<Option<GitHubIssue>, reqwest::Error> {
+        let url = format!(
+            "{}/repos/{}/issues/{number}",
+            self.config.host().trim_end_matches('/'),
+            self.config.repository()
+        );
+
+        let mut request = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .header(header::USER_AGENT, "PREvant");
+        if let Some(token) = self.config.token() {
+            request = request.bearer_auth(token.unsecure());
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        response
+            .error_for_status()?
+            .json::<GitHubIssue>()
+            .await
+            .map(Some)
+    }
+}
+
+#[async_trait]
+impl TicketTracker for GitHubTracker {
+    async fn tickets(
+        &self,
+        app_names: &HashSet<AppName>,
+    ) -> Result<HashMap<String, TicketInfo>, ListTicketsError> {
+        let mut tickets = HashMap::new();
+
+        let mut futures = app_names
+            .iter()
+            .filter_map(|app_name| Some((app_name, Self::issue_number(app_name)?)))
+            .map(|(app_name, number)| async move { (app_name, self.issue(number).await) })
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some((app_name, r)) = futures.next().await {
+            match r {
+                Ok(Some(issue)) => {
+                    tickets.insert(app_name.to_string(), TicketInfo::from(issue));
+                }
+                Ok(None) => {
+                    debug!("Cannot find GitHub issue or pull request for {app_name}");
+                }
+                Err(err) => {
+                    return Err(ListTicketsError::UnexpectedError {
+                        err: anyhow::Error::new(err),
+                    });
+                }
+            }
+        }
+
+        Ok(tickets)
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    html_url: Url,
+    title: String,
+    state: String,
+    pull_request: Option<GitHubPullRequest>,
+}
+
+#[derive(Deserialize)]
+struct GitHubPullRequest {
+    merged_at: Option<String>,
+}
+
+impl From<GitHubIssue> for TicketInfo {
+    fn from(issue: GitHubIssue) -> Self {
+        let status = match issue.pull_request {
+            Some(GitHubPullRequest { merged_at: Some(_) }) => String::from("merged"),
+            _ => issue.state,
+        };
+
+        TicketInfo::new(issue.html_url, issue.title, status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Serves the given issues by their number like the GitHub REST API of the repository
+    /// `aixigo/PREvant` and responds with 404 to any other request.
+    async fn mock_github(issues: HashMap<u64, serde_json::Value>) -> MockServer {
+        let server = MockServer::start().await;
+        for (number, issue) in issues {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/aixigo/PREvant/issues/{number}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    fn tracker(host: String) -> GitHubTracker {
+        let config = crate::config_from_str!(&format!(
+            r#"
+            [github]
+            host = '{host}'
+            repository = 'aixigo/PREvant'
+            "#
+        ));
+        GitHubTracker::new(config.github_config().unwrap())
+    }
+
+    #[test]
+    fn should_extract_issue_numbers_from_app_names() {
+        assert_eq!(
+            GitHubTracker::issue_number(&AppName::from_str("pr-123").unwrap()),
+            Some(123)
+        );
+        assert_eq!(
+            GitHubTracker::issue_number(&AppName::from_str("issue-7").unwrap()),
+            Some(7)
+        );
+        assert_eq!(
+            GitHubTracker::issue_number(&AppName::from_str("42").unwrap()),
+            Some(42)
+        );
+        assert_eq!(GitHubTracker::issue_number(&AppName::master()), None);
+    }
+
+    #[tokio::test]
+    async fn should_resolve_pull_requests_and_issues() {
+        let github = mock_github(HashMap::from([
+            (
+                123,
+                serde_json::json!({
+                    "html_url": "https://github.com/aixigo/PREvant/pull/123",
+                    "title": "Support GitHub issues",
+                    "state": "closed",
+                    "pull_request": { "merged_at": "2024-01-01T12:00:00Z" }
+                }),
+            ),
+            (
+                7,
+                serde_json::json!({
+                    "html_url": "https://github.com/aixigo/PREvant/issues/7",
+                    "title": "Tickets only support Jira",
+                    "state": "open"
+                }),
+            ),
+        ]))
+        .await;
+
+        let tickets = tracker(github.uri())
+            .tickets(&HashSet::from([
+                AppName::from_str("pr-123").unwrap(),
+                AppName::from_str("issue-7").unwrap(),
+                AppName::from_str("pr-404").unwrap(),
+                AppName::master(),
+            ]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(tickets).unwrap(),
+            serde_json::json!({
+                "pr-123": {
+                    "link": "https://github.com/aixigo/PREvant/pull/123",
+                    "summary": "Support GitHub issues",
+                    "status": "merged"
+                },
+                "issue-7": {
+                    "link": "https://github.com/aixigo/PREvant/issues/7",
+                    "summary": "Tickets only support Jira",
+                    "status": "open"
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,94 @@
+/*-
+ * ========================LICENSE_START=================================
+ * PREvant REST API
+ * %%
+ * Copyright (C) 2018 - 2019 aixigo AG
+ * %%
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ * THE SOFTWARE.
+ * =========================LICENSE_END==================================
+ */
+
+use super::{ListTicketsError, TicketTracker};
+use crate::config::{JiraAuth, JiraConfig};
+use crate::models::ticket_info::TicketInfo;
+use crate::models::AppName;
+use async_trait::async_trait;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use jira_query::{JiraInstance, JiraQueryError};
+use std::collections::{HashMap, HashSet};
+
+/// Resolves the Jira issues whose keys match the app names, e.g. `PREVANT-42`.
+pub(super) struct JiraTracker {
+    jira: JiraInstance,
+}
+
+impl JiraTracker {
+    pub(super) fn new(jira_config: JiraConfig) -> Self {
+        let jira = JiraInstance::at(jira_config.host().clone())
+            .unwrap()
+            .authenticate(match jira_config.auth() {
+                JiraAuth::Basic { user, password } => jira_query::Auth::Basic {
+                    user: user.clone(),
+                    password: password.unsecure().to_string(),
+                },
+                JiraAuth::ApiKey { api_key } => {
+                    jira_query::Auth::ApiKey(api_key.unsecure().to_string())
+                }
+            });
+
+        Self { jira }
+    }
+}
+
+#[async_trait]
+impl TicketTracker for JiraTracker {
+    async fn tickets(
+        &self,
+        app_names: &HashSet<AppName>,
+    ) -> Result<HashMap<String, TicketInfo>, ListTicketsError> {
+        let mut tickets = HashMap::new();
+
+        let mut futures = app_names
+            .iter()
+            .map(|app_name| self.jira.issue(app_name))
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some(r) = futures.next().await {
+            match r {
+                Ok(issue) => {
+                    tickets.insert(issue.key.clone(), TicketInfo::from(issue));
+                }
+                Err(JiraQueryError::MissingIssues(issues)) => {
+                    debug!("Cannot query issue information for {issues:?}");
+                }
+                Err(JiraQueryError::Request(err)) if err.is_decode() => {
+                    debug!("Cannot deserialize issue, assuming it cannot be decoded due to issue not being found: {err}");
+                }
+                Err(err) => {
+                    return Err(ListTicketsError::UnexpectedError {
+                        err: anyhow::Error::new(err),
+                    });
+                }
+            }
+        }
+
+        Ok(tickets)
+    }
+}
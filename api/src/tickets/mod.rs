@@ -28,16 +28,43 @@ use crate::apps::Apps;
 use crate::config::Config;
 use crate::http_result::{HttpApiError, HttpResult};
 use crate::models::ticket_info::TicketInfo;
-use futures::stream::FuturesUnordered;
-use futures::StreamExt;
+use crate::models::AppName;
+use async_trait::async_trait;
+use github::GitHubTracker;
 use http_api_problem::{HttpApiProblem, StatusCode};
-use jira_query::{JiraInstance, JiraQueryError};
+use jira::JiraTracker;
 use rocket::serde::json::Json;
 use rocket::State;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::sync::Arc;
 
+mod github;
+mod jira;
+
+/// Resolves the tickets, e.g. Jira issues or GitHub pull requests, that are referenced by the
+/// names of the apps.
+#[async_trait]
+pub trait TicketTracker: Send + Sync {
+    /// Returns a map of the ticket key and the corresponding `TicketInfo`. App names that do not
+    /// reference a ticket are omitted.
+    async fn tickets(
+        &self,
+        app_names: &HashSet<AppName>,
+    ) -> Result<HashMap<String, TicketInfo>, ListTicketsError>;
+}
+
+/// Creates the ticket tracker of the configuration. If Jira and GitHub are configured, Jira will
+/// be used.
+fn ticket_tracker(config: &Config) -> Option<Box<dyn TicketTracker>> {
+    if let Some(jira_config) = config.jira_config() {
+        return Some(Box::new(JiraTracker::new(jira_config)));
+    }
+    config
+        .github_config()
+        .map(|github_config| Box::new(GitHubTracker::new(github_config)) as Box<dyn TicketTracker>)
+}
+
 /// Analyzes running containers and returns a map of `review-app-name` with the
 /// corresponding `TicketInfo`.
 #[get("/apps/tickets", format = "application/json")]
@@ -45,58 +72,16 @@ pub async fn tickets(
     config_state: &State<Config>,
     apps_service: &State<Arc<Apps>>,
 ) -> HttpResult<Json<HashMap<String, TicketInfo>>> {
-    let mut tickets: HashMap<String, TicketInfo> = HashMap::new();
-
-    match config_state.jira_config() {
-        None => {
-            return Err(ListTicketsError::MissingIssueTrackingConfiguration.into());
-        }
-        Some(jira_config) => {
-            let app_names = apps_service.fetch_app_names().await?;
-            if app_names.is_empty() {
-                return Ok(Json(tickets));
-            }
-
-            let jira = JiraInstance::at(jira_config.host().clone())
-                .unwrap()
-                .authenticate(match jira_config.auth() {
-                    crate::config::JiraAuth::Basic { user, password } => jira_query::Auth::Basic {
-                        user: user.clone(),
-                        password: password.unsecure().to_string(),
-                    },
-                    crate::config::JiraAuth::ApiKey { api_key } => {
-                        jira_query::Auth::ApiKey(api_key.unsecure().to_string())
-                    }
-                });
-
-            let mut futures = app_names
-                .iter()
-                .map(|app_name| jira.issue(app_name))
-                .collect::<FuturesUnordered<_>>();
-
-            while let Some(r) = futures.next().await {
-                match r {
-                    Ok(issue) => {
-                        tickets.insert(issue.key.clone(), TicketInfo::from(issue));
-                    }
-                    Err(JiraQueryError::MissingIssues(issues)) => {
-                        debug!("Cannot query issue information for {issues:?}");
-                    }
-                    Err(JiraQueryError::Request(err)) if err.is_decode() => {
-                        debug!("Cannot deserialize issue, assuming it cannot be decoded due to issue not being found: {err}");
-                    }
-                    Err(err) => {
-                        return Err(ListTicketsError::UnexpectedError {
-                            err: anyhow::Error::new(err),
-                        }
-                        .into());
-                    }
-                }
-            }
-        }
+    let Some(tracker) = ticket_tracker(config_state) else {
+        return Err(ListTicketsError::MissingIssueTrackingConfiguration.into());
     };
 
-    Ok(Json(tickets))
+    let app_names = apps_service.fetch_app_names().await?;
+    if app_names.is_empty() {
+        return Ok(Json(HashMap::new()));
+    }
+
+    Ok(Json(tracker.tickets(&app_names).await?))
 }
 
 #[derive(Debug, thiserror::Error)]